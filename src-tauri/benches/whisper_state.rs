@@ -0,0 +1,37 @@
+//! Transcribing the bundled speech clip with the engine's reused decoder state against creating a
+//! state for every call, as it was done before. Needs a downloaded model: the one at
+//! HYPERGRANOLA_BENCH_MODEL, or the tiny English model in the app's model dir.
+//!
+//! cargo bench --features bench --bench whisper_state
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use hypergranola_lib::whisper::{self, ModelSize, WhisperEngine};
+use std::path::{Path, PathBuf};
+
+fn transcribe(c: &mut Criterion) {
+    let path = match std::env::var_os("HYPERGRANOLA_BENCH_MODEL") {
+        Some(path) => PathBuf::from(path),
+        None => whisper::get_model_path(ModelSize::Tiny).unwrap(),
+    };
+    if !path.exists() {
+        eprintln!("No model at {}, download one or set HYPERGRANOLA_BENCH_MODEL", path.display());
+        return;
+    }
+    let engine = WhisperEngine::new(&path, false).unwrap();
+    let clip = Path::new(env!("CARGO_MANIFEST_DIR")).join(whisper::BENCHMARK_CLIP);
+    let samples = whisper::benchmark_audio(Some(&clip));
+
+    // Each run takes seconds on a CPU, so fewer samples than Criterion's default
+    let mut group = c.benchmark_group("transcribe");
+    group.sample_size(10);
+    group.bench_function("reused_state", |b| {
+        b.iter(|| engine.transcribe(&samples, Some("en"), None, None).unwrap())
+    });
+    group.bench_function("new_state_per_call", |b| {
+        b.iter(|| engine.transcribe_in_new_state(&samples, Some("en"), None, None).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, transcribe);
+criterion_main!(benches);
@@ -0,0 +1,514 @@
+//! Meeting assistant
+//! Prompts the LLM for meeting facilitation, and answers questions asked during the meeting
+
+use crate::chunking;
+use crate::diarization::{QuestionDetected, SharedDiarizationState};
+use crate::events::{emit_event, report_error, ErrorSource};
+use crate::llm::{ChatMessage, CompletionOptions, LlmClient, StreamError};
+use crate::meeting_context::{MeetingContext, MeetingContextManager};
+use crate::settings::{AppSettings, SharedSettings};
+use crate::transcript::{SharedTranscriptStore, TranscriptStore};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Transcript segments sent along with an automatically answered question
+const QUESTION_CONTEXT_SEGMENTS: usize = 20;
+/// Time into the session after which present participants who haven't spoken are flagged
+const SILENT_PARTICIPANT_AFTER: Duration = Duration::from_secs(5 * 60);
+/// Longest summary of the earlier transcript, when it's too long to send as is
+const TRANSCRIPT_SUMMARY_MAX_TOKENS: u32 = 600;
+/// Pieces of new discussion sent as is before they're folded into the rolling summary
+const SUMMARIZE_EVERY_DELTAS: usize = 5;
+
+/// What the assistant has read of the transcript so far. Only new text is sent each time,
+/// along with a rolling summary of what came before.
+#[derive(Debug, Default)]
+pub struct AssistantMemory {
+    /// Position in the session transcript read up to, see `TranscriptStore::text_since`
+    position: u64,
+    /// Summary of the discussion before `recent`
+    summary: String,
+    /// New text of the latest calls, oldest first, not yet in the summary
+    recent: Vec<String>,
+}
+
+pub type SharedAssistantMemory = Arc<Mutex<AssistantMemory>>;
+
+/// The new discussion to send, with the summary of what came before
+#[derive(Debug, Clone)]
+pub struct AssistantInput {
+    /// Text not seen before this call
+    pub delta: String,
+    /// Recent new text not yet summarized, `delta` included
+    pub discussion: String,
+    pub summary: Option<String>,
+}
+
+impl AssistantMemory {
+    /// The transcript spoken since the last call, or None when nothing was. Marks it as read.
+    pub fn take_delta(&mut self, transcript: &TranscriptStore) -> Option<String> {
+        let (text, position) = transcript.text_since(self.position);
+        self.position = position;
+        Some(text).filter(|text| !text.is_empty())
+    }
+
+    /// Recent text to fold into the summary, once enough pieces or tokens of it piled up
+    fn take_for_summary(&mut self, max_tokens: usize) -> Option<Vec<String>> {
+        let tokens: usize = self.recent.iter().map(|text| chunking::estimate_tokens(text)).sum();
+        if self.recent.len() < SUMMARIZE_EVERY_DELTAS && tokens <= max_tokens {
+            return None;
+        }
+        Some(std::mem::take(&mut self.recent))
+    }
+
+    /// The rolling summary of the discussion so far, None before there is one
+    pub fn summary(&self) -> Option<String> {
+        Some(self.summary.clone()).filter(|summary| !summary.is_empty())
+    }
+
+    /// Forget everything processed, so the next call starts over
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Take `text`, or without it the transcript spoken since the last call, for the assistant,
+/// folding older new text into the rolling summary with a short LLM call every few pieces.
+/// None when there's nothing new.
+pub async fn next_input(
+    llm: &LlmClient,
+    memory: &SharedAssistantMemory,
+    transcript: &SharedTranscriptStore,
+    text: Option<&str>,
+) -> Result<Option<AssistantInput>, String> {
+    let max_tokens = llm.context_window() / 4;
+    let (delta, earlier, summary) = {
+        let mut memory = memory.lock().map_err(|e| e.to_string())?;
+        let delta = match text {
+            // Text passed in is new, e.g. typed or heard by the browser's speech recognition
+            Some(text) => Some(text.trim().to_string()).filter(|text| !text.is_empty()),
+            None => memory.take_delta(&*transcript.lock().map_err(|e| e.to_string())?),
+        };
+        let Some(delta) = delta else {
+            return Ok(None);
+        };
+        let earlier = memory.take_for_summary(max_tokens);
+        memory.recent.push(delta.clone());
+        (delta, earlier, memory.summary.clone())
+    };
+
+    if let Some(earlier) = earlier {
+        let result = match llm.check_budget() {
+            Ok(()) => update_summary(llm, &summary, &earlier.join("\n"), max_tokens).await,
+            Err(e) => Err(e),
+        };
+        let mut memory = memory.lock().map_err(|e| e.to_string())?;
+        match result {
+            Ok(updated) => memory.summary = updated,
+            Err(e) => {
+                // Keep the text as new discussion and try again next time
+                eprintln!("Failed to update the assistant's summary: {}", e);
+                memory.recent.splice(0..0, earlier);
+            }
+        }
+    }
+
+    let memory = memory.lock().map_err(|e| e.to_string())?;
+    Ok(Some(AssistantInput {
+        delta,
+        discussion: memory.recent.join("\n"),
+        summary: memory.summary(),
+    }))
+}
+
+/// Payload of the `meeting_assistant_done` event, sent after the last `meeting_assistant_chunk`
+#[derive(Debug, Clone, Serialize)]
+pub struct AssistantDone {
+    /// The whole response, or as much as arrived before the stream broke off
+    pub message: String,
+    /// The response is incomplete
+    pub interrupted: bool,
+    pub error: Option<String>,
+    /// Written in privacy mode, by a local LLM without web search
+    pub offline: bool,
+}
+
+/// Participants marked present who haven't been heard from yet, once the session is
+/// SILENT_PARTICIPANT_AFTER old. Needs diarization, to know who has spoken.
+fn silent_participants(app_handle: &AppHandle, meeting_context: &MeetingContext) -> Vec<String> {
+    let Some(state) = app_handle.try_state::<SharedDiarizationState>() else {
+        return Vec::new();
+    };
+    let Ok(engine) = state.lock() else {
+        return Vec::new();
+    };
+    let Some(engine) = engine.as_ref() else {
+        return Vec::new();
+    };
+    if engine.session_elapsed() < SILENT_PARTICIPANT_AFTER {
+        return Vec::new();
+    }
+
+    let speakers = engine.speakers();
+    meeting_context
+        .participants
+        .iter()
+        .filter(|participant| participant.is_present)
+        .filter(|participant| {
+            !speakers.iter().any(|speaker| {
+                speaker.message_count > 0
+                    && (speaker.participant.as_deref() == Some(participant.name.as_str())
+                        || speaker.label.eq_ignore_ascii_case(&participant.name))
+            })
+        })
+        .map(|participant| participant.name.clone())
+        .collect()
+}
+
+/// Fold `text` into `summary` a chunk at a time, starting a summary if it's empty
+async fn update_summary(llm: &LlmClient, summary: &str, text: &str, chunk_tokens: usize) -> Result<String, String> {
+    let mut summary = summary.to_string();
+    for chunk in chunking::chunk_text(text, chunk_tokens) {
+        let prompt = if summary.is_empty() {
+            format!(
+                "Summarize this part of a meeting transcript: topics discussed, decisions, action items with owners, and open questions. Return ONLY the summary as short bullet points.\n\nTranscript:\n{}",
+                chunk
+            )
+        } else {
+            format!(
+                "Update this running summary of a meeting with the next part of its transcript: topics discussed, decisions, action items with owners, and open questions. Return ONLY the updated summary as short bullet points.\n\nSummary so far:\n{}\n\nNext part of the transcript:\n{}",
+                summary, chunk
+            )
+        };
+        let options = CompletionOptions {
+            max_tokens: Some(TRANSCRIPT_SUMMARY_MAX_TOKENS),
+            temperature: Some(0.2),
+            ..Default::default()
+        };
+        if let Some(content) = llm.complete(&[ChatMessage::user(prompt)], options).await? {
+            summary = content.trim().to_string();
+        }
+    }
+    Ok(summary)
+}
+
+/// The transcript section of the prompt under `heading`. A transcript too long for half the
+/// context window is sent as a summary of its earlier part followed by the most recent part as is.
+async fn transcript_section(llm: &LlmClient, heading: &str, transcript: &str) -> Result<String, String> {
+    let context_window = llm.context_window();
+    if chunking::estimate_tokens(transcript) <= context_window / 2 {
+        return Ok(format!("{}:\n{}", heading, transcript));
+    }
+
+    let (earlier, recent) = chunking::split_recent(transcript, context_window / 4);
+    println!(
+        "Transcript too long for the context window, summarizing {} earlier tokens",
+        chunking::estimate_tokens(earlier)
+    );
+    let summary = update_summary(llm, "", earlier, context_window / 4).await?;
+    Ok(format!(
+        "Summary of the Earlier Part:\n{}\n\n{} (most recent part):\n{}",
+        summary, heading, recent
+    ))
+}
+
+/// Ask the assistant about `transcript`. With a `summary` of the discussion before it, the
+/// transcript is sent as the new discussion.
+pub async fn ask_meeting_assistant(
+    app_handle: &tauri::AppHandle,
+    llm: &LlmClient,
+    transcript: &str,
+    summary: Option<&str>,
+    search_context: &str,
+    meeting_context: Option<&MeetingContext>,
+) -> Result<String, String> {
+    println!("Asking Meeting Assistant via: {} (Model: {})", llm.api_url(), llm.model());
+
+    // Build context-aware prompt
+    let mut prompt_parts = Vec::new();
+    let settings: AppSettings = app_handle
+        .try_state::<SharedSettings>()
+        .and_then(|settings| settings.lock().ok().map(|settings| settings.clone()))
+        .unwrap_or_default();
+
+    // Add domain-specific role
+    if let Some(context) = meeting_context {
+        prompt_parts.push(context.prompt_prefix(&settings));
+        prompt_parts.push(format!("\n\nMeeting Context:\n{}", context.get_context_summary()));
+        let silent = silent_participants(app_handle, context);
+        if !silent.is_empty() {
+            prompt_parts.push(format!(
+                "Awaiting input from {}, who hasn't spoken yet. Suggest inviting them in.",
+                silent.join(", ")
+            ));
+        }
+    } else {
+        prompt_parts.push("You are an expert AI Meeting Assistant specializing in productive meetings, clear communication, and effective decision-making.".to_string());
+        if let Some(prefix) = &settings.default_prompt_prefix {
+            prompt_parts.push(prefix.clone());
+        }
+        if let Some(instructions) = &settings.default_instructions {
+            prompt_parts.push(format!("Instructions from the organizer, always follow these:\n{}", instructions));
+        }
+    }
+
+    if settings.privacy_mode {
+        prompt_parts.push("You are running offline in privacy mode, without web search. Leave out the Search Context section.".to_string());
+    }
+
+    // Add search context if available
+    if !search_context.is_empty() {
+        prompt_parts.push(format!("Context from Live Search:\n{}", search_context));
+    }
+
+    // Add transcript, after the summary of what came before it. Its earlier part is summarized
+    // too if it's still too long.
+    match summary {
+        Some(summary) => {
+            prompt_parts.push(format!("Summary So Far:\n{}", summary));
+            prompt_parts.push(transcript_section(llm, "New Discussion", transcript).await?);
+        }
+        None => prompt_parts.push(transcript_section(llm, "Current Meeting Transcript", transcript).await?),
+    }
+
+    // Add meeting assistance instructions
+    prompt_parts.push(r#"
+IMPORTANT: You are a MEETING FACILITATOR, not a chatbot. Provide STRUCTURED, ACTIONABLE HELP only.
+
+Format your response using MARKDOWN with clear sections:
+- Use ## for main sections
+- Use - for bullet points
+- Use **bold** for emphasis
+- Include numbers for prioritized lists
+
+NEVER:
+- Make small talk or casual chat
+- Ask conversational follow-ups like "How does that sound?"
+- Give generic advice
+- Respond with opinion or chat
+
+ALWAYS:
+- Extract concrete ACTION ITEMS with ownership
+- List KEY DECISIONS made
+- Highlight RISKS or CONCERNS
+- Provide WEB SEARCH context when relevant (clearly labeled)
+- Use domain-specific terminology for this meeting type
+- Focus on what NEEDS TO HAPPEN NEXT
+
+Structure your response exactly like this:
+
+## Action Items
+- [Clear action] - Owner: [person], Due: [timeframe]
+
+## Key Decisions
+- Decision and reasoning
+
+## Discussion Summary
+- Main points covered
+
+## Risks/Concerns
+- Potential issues to address
+
+## Search Context (if relevant)
+- [Only if needed based on transcript]
+
+Keep each section CONCISE and ACTIONABLE. No fluff."#.to_string());
+
+    let prompt = prompt_parts.join("\n\n");
+
+    let result = llm
+        .complete_stream(&[ChatMessage::user(prompt)], CompletionOptions::default(), |text| {
+            emit_event(app_handle, "meeting_assistant_chunk", text);
+        })
+        .await;
+    let done = match &result {
+        Ok(message) => AssistantDone {
+            message: message.clone(),
+            interrupted: false,
+            error: None,
+            offline: settings.privacy_mode,
+        },
+        Err(StreamError { partial, message }) => AssistantDone {
+            message: partial.clone(),
+            interrupted: true,
+            error: Some(message.clone()),
+            offline: settings.privacy_mode,
+        },
+    };
+    emit_event(app_handle, "meeting_assistant_done", done);
+    result.map_err(|e| e.message)
+}
+
+/// Answers questions detected in the live transcript, one at a time
+#[derive(Default)]
+pub struct AutoAnswer {
+    enabled: AtomicBool,
+    /// Set while an answer is being written
+    busy: AtomicBool,
+    /// Question asked meanwhile, only the latest is kept
+    pending: Mutex<Option<QuestionDetected>>,
+}
+
+impl AutoAnswer {
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    fn take_pending(&self) -> Option<QuestionDetected> {
+        self.pending.lock().ok().and_then(|mut pending| pending.take())
+    }
+}
+
+/// Answer a question detected in the transcript, if auto-answering is on.
+/// Questions asked while an answer is being written wait for it, and only the latest is answered.
+pub fn answer_question(app_handle: &AppHandle, question: QuestionDetected) {
+    let Some(auto_answer) = app_handle.try_state::<AutoAnswer>() else {
+        return;
+    };
+    if !auto_answer.enabled.load(Ordering::SeqCst) {
+        return;
+    }
+    if let Err(e) = app_handle.state::<LlmClient>().check_budget() {
+        eprintln!("Not answering question: {}", e);
+        return;
+    }
+    match auto_answer.pending.lock() {
+        Ok(mut pending) => *pending = Some(question),
+        Err(_) => return,
+    }
+    // The task already answering picks the question up when it's done
+    if auto_answer.busy.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let auto_answer = app_handle.state::<AutoAnswer>();
+        loop {
+            if let Some(question) = auto_answer.take_pending() {
+                if let Err(e) = answer(&app_handle, &question).await {
+                    report_error(&app_handle, ErrorSource::Llm, format!("Failed to answer question: {}", e));
+                }
+                continue;
+            }
+            auto_answer.busy.store(false, Ordering::SeqCst);
+            // A question may have come in between checking and clearing the flag
+            let missed = auto_answer.pending.lock().is_ok_and(|pending| pending.is_some());
+            if !missed || auto_answer.busy.swap(true, Ordering::SeqCst) {
+                break;
+            }
+        }
+    });
+}
+
+/// Ask the assistant about a question, with the recent transcript as context
+async fn answer(app_handle: &AppHandle, question: &QuestionDetected) -> Result<(), String> {
+    let recent = app_handle
+        .try_state::<SharedTranscriptStore>()
+        .and_then(|store| store.lock().ok().map(|store| store.recent_text(QUESTION_CONTEXT_SEGMENTS)))
+        .unwrap_or_default();
+    let meeting_context = app_handle
+        .try_state::<Arc<Mutex<MeetingContextManager>>>()
+        .and_then(|manager| manager.lock().ok().and_then(|manager| manager.get_current_context().cloned()));
+    let transcript = format!(
+        "{}\n\nQuestion just asked by {}: {}",
+        recent, question.speaker_label, question.question
+    );
+
+    let summary = app_handle
+        .try_state::<SharedAssistantMemory>()
+        .and_then(|memory| memory.lock().ok().and_then(|memory| memory.summary()));
+
+    let llm = app_handle.state::<LlmClient>();
+    let answer = ask_meeting_assistant(
+        app_handle,
+        &llm,
+        &transcript,
+        summary.as_deref(),
+        "",
+        meeting_context.as_ref(),
+    )
+    .await?;
+    emit_event(app_handle, "meeting_assistant_response", &answer);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::whisper::TranscriptSegment;
+
+    fn said(transcript: &mut TranscriptStore, text: &str) {
+        let segment = TranscriptSegment {
+            text: text.to_string(),
+            start_ms: 0,
+            end_ms: 1000,
+            words: Vec::new(),
+            confidence: 1.0,
+            no_speech_prob: 0.0,
+        };
+        transcript.append(0, &[segment], None);
+    }
+
+    #[test]
+    fn transcript_is_taken_from_where_the_last_call_left_off() {
+        let mut memory = AssistantMemory::default();
+        let mut transcript = TranscriptStore::default();
+        assert_eq!(memory.take_delta(&transcript), None);
+
+        said(&mut transcript, "Hello everyone, let's start.");
+        assert_eq!(memory.take_delta(&transcript).as_deref(), Some("Hello everyone, let's start."));
+        assert_eq!(memory.take_delta(&transcript), None);
+
+        said(&mut transcript, "Ben: first item is the budget.");
+        transcript.mark_gap(2000, 5);
+        said(&mut transcript, "Ana: any questions?");
+        assert_eq!(memory.take_delta(&transcript).as_deref(), Some("Ben: first item is the budget. Ana: any questions?"));
+
+        // The same short answer said twice is new both times
+        said(&mut transcript, "Yes.");
+        assert_eq!(memory.take_delta(&transcript).as_deref(), Some("Yes."));
+        said(&mut transcript, "Yes.");
+        assert_eq!(memory.take_delta(&transcript).as_deref(), Some("Yes."));
+        assert_eq!(memory.take_delta(&transcript), None);
+
+        // A cleared transcript carries on from its new segments
+        transcript.clear();
+        said(&mut transcript, "About the roadmap.");
+        assert_eq!(memory.take_delta(&transcript).as_deref(), Some("About the roadmap."));
+    }
+
+    #[test]
+    fn reset_memory_takes_everything_again() {
+        let mut memory = AssistantMemory::default();
+        let mut transcript = TranscriptStore::default();
+        said(&mut transcript, "We agreed on Friday.");
+        memory.take_delta(&transcript);
+        memory.summary = "Launch planning".to_string();
+        memory.recent.push("We agreed on Friday.".to_string());
+
+        memory.reset();
+        assert_eq!(memory.summary(), None);
+        assert!(memory.recent.is_empty());
+        assert_eq!(memory.take_delta(&transcript).as_deref(), Some("We agreed on Friday."));
+    }
+
+    #[test]
+    fn recent_text_is_summarized_every_few_pieces_or_when_long() {
+        let mut memory = AssistantMemory::default();
+        for i in 0..SUMMARIZE_EVERY_DELTAS {
+            assert_eq!(memory.take_for_summary(1000), None);
+            memory.recent.push(format!("Piece {}", i));
+        }
+        let earlier = memory.take_for_summary(1000).unwrap();
+        assert_eq!(earlier.len(), SUMMARIZE_EVERY_DELTAS);
+        assert!(memory.recent.is_empty());
+
+        memory.recent.push("word ".repeat(500));
+        assert_eq!(memory.take_for_summary(1000), None);
+        assert_eq!(memory.take_for_summary(100).map(|earlier| earlier.len()), Some(1));
+    }
+}
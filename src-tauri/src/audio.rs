@@ -1,156 +1,580 @@
 //! Audio capture module using cpal
-//! Captures microphone input and buffers it for transcription
+//! Captures microphone and/or system audio and buffers it for transcription
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{SampleFormat, Stream, StreamConfig};
 use ringbuf::{HeapRb, HeapCons, HeapProd};
 use ringbuf::traits::{Split, Consumer, Producer, Observer};
 use cpal::Sample;
-use std::sync::atomic::{AtomicBool, Ordering};
+use rubato::{FftFixedIn, Resampler};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 pub const WHISPER_SAMPLE_RATE: u32 = 16000;
+/// Default length of the capture ring buffer
+pub const DEFAULT_BUFFER_SECONDS: usize = 30;
+
+/// Whether a device captures or plays audio
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceDirection {
+    Input,
+    Output,
+}
+
+/// Standard sample rates reported to the frontend when a device supports them
+const COMMON_SAMPLE_RATES: [u32; 9] = [8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000];
+
+/// Audio device description for device selection
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AudioDeviceInfo {
+    pub name: String,
+    pub direction: DeviceDirection,
+    pub is_default: bool,
+    pub supported_sample_rates: Vec<u32>,
+    pub channels: Vec<u16>,
+}
+
+/// List the input devices of the default host
+pub fn list_input_devices() -> Result<Vec<AudioDeviceInfo>, String> {
+    let host = cpal::default_host();
+    let default_input = host.default_input_device().and_then(|d| d.name().ok());
+
+    let inputs = host
+        .input_devices()
+        .map_err(|e| format!("Failed to enumerate input devices: {}", e))?;
+
+    Ok(inputs
+        .enumerate()
+        .map(|(i, device)| describe_device(&device, i, DeviceDirection::Input, default_input.as_deref()))
+        .collect())
+}
+
+/// List the input and output devices of the default host
+pub fn list_devices() -> Result<Vec<AudioDeviceInfo>, String> {
+    let host = cpal::default_host();
+    let default_output = host.default_output_device().and_then(|d| d.name().ok());
+
+    let mut devices = list_input_devices()?;
+
+    let outputs = host
+        .output_devices()
+        .map_err(|e| format!("Failed to enumerate output devices: {}", e))?;
+    devices.extend(
+        outputs
+            .enumerate()
+            .map(|(i, device)| describe_device(&device, i, DeviceDirection::Output, default_output.as_deref())),
+    );
+
+    Ok(devices)
+}
+
+/// Summarize the supported configs of a device.
+/// Devices whose name can't be read are labeled by their position in the list.
+fn describe_device(
+    device: &cpal::Device,
+    index: usize,
+    direction: DeviceDirection,
+    default_name: Option<&str>,
+) -> AudioDeviceInfo {
+    let name = device.name().ok();
+    let configs: Vec<cpal::SupportedStreamConfigRange> = match direction {
+        DeviceDirection::Input => device.supported_input_configs().map(|c| c.collect::<Vec<_>>()),
+        DeviceDirection::Output => device.supported_output_configs().map(|c| c.collect::<Vec<_>>()),
+    }
+    .unwrap_or_default();
+
+    let supported_sample_rates = COMMON_SAMPLE_RATES
+        .iter()
+        .copied()
+        .filter(|rate| {
+            configs
+                .iter()
+                .any(|c| c.min_sample_rate().0 <= *rate && *rate <= c.max_sample_rate().0)
+        })
+        .collect();
+    let mut channels: Vec<u16> = configs.iter().map(|c| c.channels()).collect();
+    channels.sort_unstable();
+    channels.dedup();
+
+    AudioDeviceInfo {
+        is_default: name.is_some() && name.as_deref() == default_name,
+        name: name.unwrap_or_else(|| format!("Unknown Device {}", index + 1)),
+        direction,
+        supported_sample_rates,
+        channels,
+    }
+}
+
+/// Which audio sources are captured for transcription
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureSource {
+    Microphone,
+    System,
+    Both,
+}
+
+impl std::str::FromStr for CaptureSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "microphone" => Ok(CaptureSource::Microphone),
+            "system" => Ok(CaptureSource::System),
+            "both" => Ok(CaptureSource::Both),
+            other => Err(format!("Unknown capture source '{}', expected microphone, system or both", other)),
+        }
+    }
+}
+
+/// Capture source selection and per-source gain
+#[derive(Debug, Clone)]
+pub struct CaptureSettings {
+    pub source: CaptureSource,
+    pub input_device: Option<String>,
+    pub microphone_gain: f32,
+    pub system_gain: f32,
+}
+
+impl Default for CaptureSettings {
+    fn default() -> Self {
+        Self {
+            source: CaptureSource::Microphone,
+            input_device: None,
+            microphone_gain: 1.0,
+            system_gain: 1.0,
+        }
+    }
+}
+
+/// Find the device carrying system (loopback) audio.
+///
+/// On Windows this is the default output device, which WASAPI can open as a loopback input.
+/// Other platforms have no native loopback in cpal: Linux exposes PulseAudio/PipeWire
+/// "Monitor of ..." sources as inputs, and macOS needs a virtual device such as BlackHole.
+fn find_system_device(host: &cpal::Host) -> Result<cpal::Device, String> {
+    #[cfg(target_os = "windows")]
+    {
+        host.default_output_device()
+            .ok_or_else(|| "No output device available for loopback capture".to_string())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        host.input_devices()
+            .map_err(|e| format!("Failed to enumerate input devices: {}", e))?
+            .find(|d| {
+                d.name()
+                    .map(|n| {
+                        let n = n.to_lowercase();
+                        n.contains("monitor") || n.contains("blackhole")
+                    })
+                    .unwrap_or(false)
+            })
+            .ok_or_else(|| {
+                "System audio capture needs a loopback device (a PulseAudio monitor source on Linux, BlackHole on macOS)".to_string()
+            })
+    }
+}
+
+/// Error message for a device that was unplugged or otherwise went away
+fn device_unavailable(name: &str) -> String {
+    format!("Audio device '{}' is no longer available, was it unplugged?", name)
+}
+
+/// Find an input device by its name
+fn find_input_device(host: &cpal::Host, name: &str) -> Option<cpal::Device> {
+    host.input_devices()
+        .ok()?
+        .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+}
+
+/// Number of input frames handed to the resampler per call
+const RESAMPLER_CHUNK_SIZE: usize = 1024;
+
+/// Streaming mono resampler converting the device rate to WHISPER_SAMPLE_RATE.
+/// Uses a fixed integer rate ratio, so it doesn't drift over long sessions.
+pub struct MonoResampler {
+    resampler: Option<FftFixedIn<f32>>,
+    pending: Vec<f32>,
+    output: Vec<Vec<f32>>,
+}
+
+impl MonoResampler {
+    /// Create a resampler for the given input rate (passthrough when it already matches)
+    pub fn new(input_sample_rate: u32) -> Result<Self, String> {
+        if input_sample_rate == WHISPER_SAMPLE_RATE {
+            return Ok(Self {
+                resampler: None,
+                pending: Vec::new(),
+                output: Vec::new(),
+            });
+        }
+
+        let resampler = FftFixedIn::<f32>::new(
+            input_sample_rate as usize,
+            WHISPER_SAMPLE_RATE as usize,
+            RESAMPLER_CHUNK_SIZE,
+            2,
+            1,
+        )
+        .map_err(|e| format!("Failed to create resampler: {}", e))?;
+        let output = vec![vec![0.0; resampler.output_frames_max()]];
+
+        Ok(Self {
+            resampler: Some(resampler),
+            pending: Vec::with_capacity(RESAMPLER_CHUNK_SIZE * 2),
+            output,
+        })
+    }
+
+    /// Feed mono samples at the input rate and pass every resampled sample to `emit`
+    pub fn process(&mut self, samples: &[f32], mut emit: impl FnMut(f32)) {
+        let resampler = match &mut self.resampler {
+            Some(resampler) => resampler,
+            None => {
+                samples.iter().for_each(|&s| emit(s));
+                return;
+            }
+        };
+
+        self.pending.extend_from_slice(samples);
+
+        let mut consumed = 0;
+        while self.pending.len() - consumed >= resampler.input_frames_next() {
+            let needed = resampler.input_frames_next();
+            let input = [&self.pending[consumed..consumed + needed]];
+            match resampler.process_into_buffer(&input, &mut self.output, None) {
+                Ok((frames_in, frames_out)) => {
+                    consumed += frames_in;
+                    self.output[0][..frames_out].iter().for_each(|&s| emit(s));
+                }
+                Err(e) => {
+                    eprintln!("Resampling error: {}", e);
+                    consumed += needed;
+                }
+            }
+        }
+        self.pending.drain(..consumed);
+    }
+}
+
+/// Seconds of system audio buffered while waiting to be mixed with the microphone
+const MIX_BUFFER_SECONDS: usize = 2;
+
+/// Capture buffer health counters
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct CaptureStats {
+    /// Samples discarded because the ring buffer was full
+    pub dropped_samples: u64,
+    /// Samples waiting to be read
+    pub buffered_samples: usize,
+}
+
+/// Shared handle for reading the input level from outside the audio thread
+#[derive(Clone, Default)]
+pub struct LevelMeter {
+    peak_bits: Arc<AtomicU32>,
+    is_recording: Arc<AtomicBool>,
+}
+
+impl LevelMeter {
+    /// Peak level (0.0 to 1.0) since the last call
+    pub fn take_peak(&self) -> f32 {
+        f32::from_bits(self.peak_bits.swap(0, Ordering::Relaxed)).min(1.0)
+    }
+
+    /// Whether the capture this meter belongs to is still running
+    pub fn is_active(&self) -> bool {
+        self.is_recording.load(Ordering::SeqCst)
+    }
+}
+
+/// Backoff schedule for reopening an audio device that disappeared
+#[derive(Debug, Clone, Copy)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub max_attempts: u32,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+            max_attempts: 10,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before the given attempt (counting from 0), or None once attempts run out
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let factor = 2u32.saturating_pow(attempt);
+        Some(self.initial_delay.saturating_mul(factor).min(self.max_delay))
+    }
+}
+
+/// Called from the audio thread when a stream reports an error
+pub type ErrorHandler = Arc<dyn Fn(String) + Send + Sync>;
 
 /// Audio capture state
 pub struct AudioCapture {
-    stream: Option<Stream>,
+    streams: Vec<Stream>,
+    /// Write side of the buffer, handed to the stream callbacks by `start`
+    producer: Option<HeapProd<f32>>,
     consumer: HeapCons<f32>,
     is_recording: Arc<AtomicBool>,
+    is_paused: Arc<AtomicBool>,
+    dropped_samples: Arc<AtomicU64>,
+    peak_bits: Arc<AtomicU32>,
+    on_error: Option<ErrorHandler>,
+    /// Set when a stream's device is unplugged or otherwise goes away
+    device_lost: Arc<AtomicBool>,
 }
 
 impl AudioCapture {
-    /// Create a new audio capture instance
-    pub fn new() -> Result<(Self, HeapProd<f32>), String> {
-        // Create a ring buffer for audio samples (30 seconds at 16kHz)
-        let buffer_size = WHISPER_SAMPLE_RATE as usize * 30;
+    /// Create a new audio capture instance buffering up to `buffer_seconds` of audio
+    pub fn new(buffer_seconds: usize) -> Result<Self, String> {
+        if buffer_seconds == 0 {
+            return Err("Audio buffer duration must be at least 1 second".to_string());
+        }
+
+        // Create a ring buffer for audio samples at 16kHz
+        let buffer_size = WHISPER_SAMPLE_RATE as usize * buffer_seconds;
         let rb = HeapRb::<f32>::new(buffer_size);
         let (producer, consumer) = rb.split();
 
-        Ok((
-            Self {
-                stream: None,
-                consumer,
-                is_recording: Arc::new(AtomicBool::new(false)),
-            },
-            producer,
-        ))
+        Ok(Self {
+            streams: Vec::new(),
+            producer: Some(producer),
+            consumer,
+            is_recording: Arc::new(AtomicBool::new(false)),
+            is_paused: Arc::new(AtomicBool::new(false)),
+            dropped_samples: Arc::new(AtomicU64::new(0)),
+            peak_bits: Arc::new(AtomicU32::new(0)),
+            on_error: None,
+            device_lost: Arc::new(AtomicBool::new(false)),
+        })
     }
 
-    /// Start recording from the default input device
-    pub fn start(&mut self, producer: HeapProd<f32>) -> Result<(), String> {
+    /// Report stream errors to `handler` as well as the log. Set before `start`.
+    pub fn set_error_handler(&mut self, handler: ErrorHandler) {
+        self.on_error = Some(handler);
+    }
+
+    /// Start recording from the configured sources.
+    ///
+    /// The microphone is the named input device, or the default one if it isn't found.
+    /// Returns the name of the microphone actually used, if one is captured.
+    /// A capture can only be started once, create a new one to capture again.
+    pub fn start(&mut self, settings: &CaptureSettings) -> Result<Option<String>, String> {
+        let mut producer = self
+            .producer
+            .take()
+            .ok_or("Audio capture has already been started")?;
         let host = cpal::default_host();
-        
-        let device = host
-            .default_input_device()
-            .ok_or("No input device available")?;
+        self.is_recording.store(true, Ordering::SeqCst);
+
+        let mic_gain = settings.microphone_gain;
+        let system_gain = settings.system_gain;
+        let dropped = self.dropped_samples.clone();
+
+        let mut mic_name = None;
+        match settings.source {
+            CaptureSource::Microphone => {
+                let device = self.microphone_device(&host, settings.input_device.as_deref())?;
+                mic_name = Some(device.name().unwrap_or_default());
+                let stream = self.open_stream(&device, false, move |sample| {
+                    if producer.try_push(sample * mic_gain).is_err() {
+                        dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                })?;
+                self.streams.push(stream);
+            }
+            CaptureSource::System => {
+                let device = find_system_device(&host)?;
+                let stream = self.open_stream(&device, true, move |sample| {
+                    if producer.try_push(sample * system_gain).is_err() {
+                        dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                })?;
+                self.streams.push(stream);
+            }
+            CaptureSource::Both => {
+                // System audio goes through an intermediate buffer and is mixed in
+                // sample-by-sample as microphone audio arrives
+                let mix_rb = HeapRb::<f32>::new(WHISPER_SAMPLE_RATE as usize * MIX_BUFFER_SECONDS);
+                let (mut system_producer, mut system_consumer) = mix_rb.split();
+
+                let system_device = find_system_device(&host)?;
+                let system_stream = self.open_stream(&system_device, true, move |sample| {
+                    let _ = system_producer.try_push(sample);
+                })?;
 
-        println!("Using input device: {}", device.name().unwrap_or_default());
+                let device = self.microphone_device(&host, settings.input_device.as_deref())?;
+                mic_name = Some(device.name().unwrap_or_default());
+                let mic_stream = self.open_stream(&device, false, move |sample| {
+                    let system = system_consumer.try_pop().unwrap_or(0.0);
+                    let mixed = (sample * mic_gain + system * system_gain).clamp(-1.0, 1.0);
+                    if producer.try_push(mixed).is_err() {
+                        dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                })?;
+
+                self.streams.push(system_stream);
+                self.streams.push(mic_stream);
+            }
+        }
+
+        println!("Audio capture started ({:?})", settings.source);
+        Ok(mic_name)
+    }
+
+    /// Resolve the microphone, falling back to the default input device
+    fn microphone_device(&self, host: &cpal::Host, device_name: Option<&str>) -> Result<cpal::Device, String> {
+        match device_name.and_then(|name| find_input_device(host, name)) {
+            Some(device) => Ok(device),
+            None => host
+                .default_input_device()
+                .ok_or_else(|| "No input device available".to_string()),
+        }
+    }
+
+    /// Open and start a stream on a device, passing each 16kHz mono sample to `on_sample`.
+    /// `loopback` opens an output device as an input (WASAPI loopback).
+    fn open_stream<F>(&self, device: &cpal::Device, loopback: bool, on_sample: F) -> Result<Stream, String>
+    where
+        F: FnMut(f32) + Send + 'static,
+    {
+        let name = device.name().unwrap_or_default();
+        println!("Using {} device: {}", if loopback { "loopback" } else { "input" }, name);
 
         // Get supported config
-        let supported_config = device
-            .default_input_config()
-            .map_err(|e| format!("Failed to get default input config: {}", e))?;
+        let supported_config = if loopback {
+            device.default_output_config()
+        } else {
+            device.default_input_config()
+        }
+        .map_err(|e| match e {
+            cpal::DefaultStreamConfigError::DeviceNotAvailable => device_unavailable(&name),
+            e => format!("Failed to get default config for '{}': {}", name, e),
+        })?;
 
-        println!("Default input config: {:?}", supported_config);
+        println!("Default config: {:?}", supported_config);
 
         let sample_format = supported_config.sample_format();
         let config: StreamConfig = supported_config.into();
         let input_sample_rate = config.sample_rate.0;
         let channels = config.channels as usize;
 
-        let is_recording = self.is_recording.clone();
-        is_recording.store(true, Ordering::SeqCst);
-
         // Build the input stream
         let stream = match sample_format {
-            SampleFormat::F32 => self.build_stream::<f32>(
-                &device,
-                &config,
-                producer,
-                input_sample_rate,
-                channels,
-            )?,
-            SampleFormat::I16 => self.build_stream::<i16>(
-                &device,
-                &config,
-                producer,
-                input_sample_rate,
-                channels,
-            )?,
-            SampleFormat::U16 => self.build_stream::<u16>(
-                &device,
-                &config,
-                producer,
-                input_sample_rate,
-                channels,
-            )?,
+            SampleFormat::F32 => self.build_stream::<f32, F>(device, &config, on_sample, input_sample_rate, channels)?,
+            SampleFormat::I16 => self.build_stream::<i16, F>(device, &config, on_sample, input_sample_rate, channels)?,
+            SampleFormat::U16 => self.build_stream::<u16, F>(device, &config, on_sample, input_sample_rate, channels)?,
             _ => return Err(format!("Unsupported sample format: {:?}", sample_format)),
         };
 
-        stream.play().map_err(|e| format!("Failed to play stream: {}", e))?;
-        self.stream = Some(stream);
-
-        println!("Audio capture started");
-        Ok(())
+        stream.play().map_err(|e| match e {
+            cpal::PlayStreamError::DeviceNotAvailable => device_unavailable(&name),
+            e => format!("Failed to play stream: {}", e),
+        })?;
+        Ok(stream)
     }
 
-    fn build_stream<T: cpal::Sample + cpal::SizedSample>(
+    fn build_stream<T, F>(
         &self,
         device: &cpal::Device,
         config: &StreamConfig,
-        mut producer: HeapProd<f32>,
+        mut on_sample: F,
         input_sample_rate: u32,
         channels: usize,
     ) -> Result<Stream, String>
     where
+        T: cpal::Sample + cpal::SizedSample,
         f32: cpal::FromSample<T>,
+        F: FnMut(f32) + Send + 'static,
     {
         let is_recording = self.is_recording.clone();
-        let resample_ratio = WHISPER_SAMPLE_RATE as f64 / input_sample_rate as f64;
+        let is_paused = self.is_paused.clone();
+        let peak_bits = self.peak_bits.clone();
+        let on_error = self.on_error.clone();
+        let device_lost = self.device_lost.clone();
+        let mut resampler = MonoResampler::new(input_sample_rate)?;
+        let mut mono = Vec::new();
 
         let stream = device
             .build_input_stream(
                 config,
                 move |data: &[T], _: &cpal::InputCallbackInfo| {
-                    if !is_recording.load(Ordering::SeqCst) {
+                    if !is_recording.load(Ordering::SeqCst) || is_paused.load(Ordering::SeqCst) {
                         return;
                     }
 
-                    // Convert to f32 and mono, then resample to 16kHz
-                    for (i, frame) in data.chunks(channels).enumerate() {
-                        // Mix to mono
-                        let sample: f32 = frame
+                    // Mix to mono f32
+                    mono.clear();
+                    mono.extend(data.chunks(channels).map(|frame| {
+                        frame
                             .iter()
                             .map(|s| f32::from_sample(*s))
                             .sum::<f32>()
-                            / channels as f32;
+                            / channels as f32
+                    }));
 
-                        // Simple resampling (for better quality, use a proper resampler)
-                        let target_idx = (i as f64 * resample_ratio) as usize;
-                        if target_idx < producer.vacant_len() {
-                            let _ = producer.try_push(sample);
-                        }
+                    // Non-negative f32 bit patterns order like the floats, so fetch_max keeps the peak
+                    let peak = mono.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+                    peak_bits.fetch_max(peak.to_bits(), Ordering::Relaxed);
+
+                    // Resample to 16kHz before buffering
+                    resampler.process(&mono, &mut on_sample);
+                },
+                move |err| {
+                    eprintln!("Audio stream error: {}", err);
+                    if let cpal::StreamError::DeviceNotAvailable = err {
+                        device_lost.store(true, Ordering::SeqCst);
+                    }
+                    if let Some(on_error) = &on_error {
+                        on_error(err.to_string());
                     }
                 },
-                |err| eprintln!("Audio stream error: {}", err),
                 None,
             )
-            .map_err(|e| format!("Failed to build input stream: {}", e))?;
+            .map_err(|e| {
+                let name = device.name().unwrap_or_default();
+                match e {
+                    cpal::BuildStreamError::DeviceNotAvailable => device_unavailable(&name),
+                    e => format!("Failed to build input stream on '{}': {}", name, e),
+                }
+            })?;
 
         Ok(stream)
     }
 
-    /// Stop recording
+    /// Stop recording, closing the device streams
     pub fn stop(&mut self) {
         self.is_recording.store(false, Ordering::SeqCst);
-        self.stream = None;
-        println!("Audio capture stopped");
+        // Dropping a cpal stream releases the OS device
+        let closed = std::mem::take(&mut self.streams).len();
+        println!("Audio capture stopped, closed {} stream(s)", closed);
     }
 
-    /// Check if currently recording
-    #[allow(dead_code)]
-    pub fn is_recording(&self) -> bool {
-        self.is_recording.load(Ordering::SeqCst)
+    /// Stop buffering audio while keeping the streams open
+    pub fn pause(&mut self) {
+        self.is_paused.store(true, Ordering::SeqCst);
+        println!("Audio capture paused");
+    }
+
+    /// Resume buffering, anything left over from before the pause stays buffered
+    pub fn resume(&mut self) {
+        self.is_paused.store(false, Ordering::SeqCst);
+        println!("Audio capture resumed");
     }
 
     /// Get available samples from buffer
@@ -165,10 +589,144 @@ impl AudioCapture {
         samples
     }
 
+    /// Check whether a captured device has gone away, leaving its stream dead
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::SeqCst)
+    }
+
+    /// Get a handle for polling the input level
+    pub fn level_meter(&self) -> LevelMeter {
+        LevelMeter {
+            peak_bits: self.peak_bits.clone(),
+            is_recording: self.is_recording.clone(),
+        }
+    }
+
+    /// Get dropped and buffered sample counts
+    pub fn get_capture_stats(&self) -> CaptureStats {
+        CaptureStats {
+            dropped_samples: self.dropped_samples.load(Ordering::Relaxed),
+            buffered_samples: self.consumer.occupied_len(),
+        }
+    }
+
     /// Clear the audio buffer
-    #[allow(dead_code)]
     pub fn clear_buffer(&mut self) {
         while self.consumer.try_pop().is_some() {}
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    /// Magnitude of `frequency` in `samples` at `rate`, a single DFT bin
+    fn magnitude(samples: &[f32], rate: u32, frequency: f32) -> f32 {
+        let (mut re, mut im) = (0.0f32, 0.0f32);
+        for (i, &s) in samples.iter().enumerate() {
+            let phase = TAU * frequency * i as f32 / rate as f32;
+            re += s * phase.cos();
+            im += s * phase.sin();
+        }
+        (re * re + im * im).sqrt() / samples.len() as f32
+    }
+
+    #[test]
+    fn resampling_48khz_keeps_a_sine_at_its_frequency() {
+        let input_rate = 48_000;
+        let sine: Vec<f32> = (0..input_rate)
+            .map(|i| 0.5 * (TAU * 1000.0 * i as f32 / input_rate as f32).sin())
+            .collect();
+
+        // Fed in 10ms blocks like a device callback
+        let mut resampler = MonoResampler::new(input_rate).unwrap();
+        let mut output = Vec::new();
+        for block in sine.chunks(480) {
+            resampler.process(block, |s| output.push(s));
+        }
+
+        // One second in gives a second out, less the partial chunk still pending and the
+        // resampler's delay, together under 32ms
+        let expected = WHISPER_SAMPLE_RATE as usize;
+        assert!(output.len() <= expected, "{} samples", output.len());
+        assert!(output.len() > expected - RESAMPLER_CHUNK_SIZE / 2, "{} samples", output.len());
+
+        // Skip the resampler's start-up delay, then find the strongest frequency in 50 Hz steps
+        let steady = &output[output.len() / 4..];
+        let dominant = (1..160)
+            .map(|step| step as f32 * 50.0)
+            .max_by(|a, b| magnitude(steady, WHISPER_SAMPLE_RATE, *a).total_cmp(&magnitude(steady, WHISPER_SAMPLE_RATE, *b)))
+            .unwrap();
+        assert_eq!(dominant, 1000.0);
+        assert!(magnitude(steady, WHISPER_SAMPLE_RATE, 1000.0) > 0.2);
+    }
+
+    #[test]
+    fn resampling_44_1khz_stays_in_step_over_a_long_stream() {
+        let input_rate = 44_100;
+        let seconds = 12;
+        let sine: Vec<f32> = (0..input_rate * seconds)
+            .map(|i| (0.5 * (std::f64::consts::TAU * 1000.0 * i as f64 / input_rate as f64).sin()) as f32)
+            .collect();
+
+        // Fed in 10ms blocks like a device callback
+        let mut resampler = MonoResampler::new(input_rate).unwrap();
+        let mut output = Vec::new();
+        for block in sine.chunks(441) {
+            resampler.process(block, |s| output.push(s));
+        }
+
+        // 441 doesn't divide into 160, so a ratio that slipped would show up as missing or extra audio
+        let expected = (seconds * WHISPER_SAMPLE_RATE) as usize;
+        assert!(output.len().abs_diff(expected) <= RESAMPLER_CHUNK_SIZE, "{} samples", output.len());
+
+        // The last second is still at 1000 Hz, searched in 5 Hz steps
+        let end = &output[output.len() - WHISPER_SAMPLE_RATE as usize..];
+        let dominant = (180..=220)
+            .map(|step| step as f32 * 5.0)
+            .max_by(|a, b| magnitude(end, WHISPER_SAMPLE_RATE, *a).total_cmp(&magnitude(end, WHISPER_SAMPLE_RATE, *b)))
+            .unwrap();
+        assert_eq!(dominant, 1000.0);
+        assert!(magnitude(end, WHISPER_SAMPLE_RATE, 1000.0) > 0.2);
+    }
+
+    #[test]
+    fn matching_rate_passes_samples_through() {
+        let samples = [0.1, -0.2, 0.3];
+        let mut resampler = MonoResampler::new(WHISPER_SAMPLE_RATE).unwrap();
+        let mut output = Vec::new();
+        resampler.process(&samples, |s| output.push(s));
+        assert_eq!(output, samples);
+    }
+
+    #[test]
+    fn reconnect_delay_doubles_up_to_the_cap() {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(3),
+            max_attempts: 5,
+        };
+        let delays: Vec<Option<Duration>> = (0..6).map(|attempt| policy.delay(attempt)).collect();
+        assert_eq!(
+            delays,
+            [
+                Some(Duration::from_millis(500)),
+                Some(Duration::from_secs(1)),
+                Some(Duration::from_secs(2)),
+                Some(Duration::from_secs(3)),
+                Some(Duration::from_secs(3)),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn reconnect_delay_doesnt_overflow() {
+        let policy = ReconnectPolicy {
+            max_attempts: u32::MAX,
+            ..ReconnectPolicy::default()
+        };
+        assert_eq!(policy.delay(1000), Some(policy.max_delay));
+    }
+}
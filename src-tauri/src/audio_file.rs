@@ -0,0 +1,201 @@
+//! Audio file import
+//! Decodes recorded meetings into 16kHz mono samples for transcription
+
+use crate::audio::{MonoResampler, WHISPER_SAMPLE_RATE};
+use hound::{SampleFormat, WavReader};
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Silence fed through the resampler to flush out its last samples
+const RESAMPLER_FLUSH_SAMPLES: usize = 4096;
+
+/// Decode an audio file to 16kHz mono, refusing files longer than `max_seconds`.
+/// WAV is read directly, other formats (mp3, m4a, ...) go through symphonia.
+pub fn decode_file(path: &Path, max_seconds: u64) -> Result<Vec<f32>, String> {
+    let is_wav = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"));
+
+    let (samples, sample_rate) = if is_wav {
+        decode_wav(path, max_seconds)?
+    } else {
+        decode_compressed(path, max_seconds)?
+    };
+    to_whisper_rate(&samples, sample_rate)
+}
+
+fn check_duration(seconds: f64, max_seconds: u64) -> Result<(), String> {
+    if seconds > max_seconds as f64 {
+        return Err(format!(
+            "Audio file is too long ({:.0}s), the import limit is {}s",
+            seconds, max_seconds
+        ));
+    }
+    Ok(())
+}
+
+/// Average interleaved frames down to one channel
+fn downmix(interleaved: &[f32], channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return interleaved.to_vec();
+    }
+    interleaved
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+fn decode_wav(path: &Path, max_seconds: u64) -> Result<(Vec<f32>, u32), String> {
+    let mut reader = WavReader::open(path).map_err(|e| format!("Failed to open WAV file: {}", e))?;
+    let spec = reader.spec();
+    check_duration(reader.duration() as f64 / spec.sample_rate as f64, max_seconds)?;
+
+    let interleaved = match spec.sample_format {
+        SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>(),
+        SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|s| s as f32 * scale))
+                .collect::<Result<Vec<_>, _>>()
+        }
+    }
+    .map_err(|e| format!("Failed to read WAV file: {}", e))?;
+
+    Ok((downmix(&interleaved, spec.channels as usize), spec.sample_rate))
+}
+
+fn decode_compressed(path: &Path, max_seconds: u64) -> Result<(Vec<f32>, u32), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| format!("Unsupported audio file: {}", e))?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or("No audio track found")?;
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate.ok_or("Unknown sample rate")?;
+    if let Some(frames) = track.codec_params.n_frames {
+        check_duration(frames as f64 / sample_rate as f64, max_seconds)?;
+    }
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported audio codec: {}", e))?;
+
+    let mut samples = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(format!("Failed to read audio file: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt packet only loses a few milliseconds
+            Err(SymphoniaError::DecodeError(e)) => {
+                eprintln!("Skipping undecodable audio packet: {}", e);
+                continue;
+            }
+            Err(e) => return Err(format!("Failed to decode audio file: {}", e)),
+        };
+        let spec = *decoded.spec();
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend(downmix(buffer.samples(), spec.channels.count()));
+
+        // The length isn't always in the header, so keep checking while decoding
+        check_duration(samples.len() as f64 / sample_rate as f64, max_seconds)?;
+    }
+
+    Ok((samples, sample_rate))
+}
+
+/// Resample mono audio to WHISPER_SAMPLE_RATE
+fn to_whisper_rate(samples: &[f32], sample_rate: u32) -> Result<Vec<f32>, String> {
+    if sample_rate == WHISPER_SAMPLE_RATE {
+        return Ok(samples.to_vec());
+    }
+
+    let expected = (samples.len() as u64 * WHISPER_SAMPLE_RATE as u64 / sample_rate as u64) as usize;
+    let mut resampler = MonoResampler::new(sample_rate)?;
+    let mut output = Vec::with_capacity(expected + RESAMPLER_FLUSH_SAMPLES);
+    resampler.process(samples, |s| output.push(s));
+    resampler.process(&[0.0; RESAMPLER_FLUSH_SAMPLES], |s| output.push(s));
+    output.truncate(expected);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Half a second of a 440 Hz tone at half scale, 16-bit stereo at 8kHz, both channels alike
+    fn tone_fixture() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tone-440hz-8khz-stereo.wav")
+    }
+
+    /// Strength of `frequency` in 16kHz samples, a single DFT bin
+    fn magnitude(samples: &[f32], frequency: f32) -> f32 {
+        let (re, im) = samples.iter().enumerate().fold((0.0f32, 0.0f32), |(re, im), (i, &s)| {
+            let phase = std::f32::consts::TAU * frequency * i as f32 / WHISPER_SAMPLE_RATE as f32;
+            (re + s * phase.cos(), im + s * phase.sin())
+        });
+        (re * re + im * im).sqrt() / samples.len() as f32
+    }
+
+    #[test]
+    fn wav_is_decoded_to_16khz_mono() {
+        let samples = decode_file(&tone_fixture(), 60).unwrap();
+        assert_eq!(samples.len(), WHISPER_SAMPLE_RATE as usize / 2);
+
+        // Past the resampler's start-up delay, the tone is intact at its level
+        let steady = &samples[samples.len() / 4..];
+        let peak = steady.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!((0.45..0.55).contains(&peak), "peak {}", peak);
+        assert!(magnitude(steady, 440.0) > 10.0 * magnitude(steady, 880.0));
+    }
+
+    #[test]
+    fn long_file_is_rejected() {
+        let error = decode_file(&tone_fixture(), 0).unwrap_err();
+        assert!(error.contains("too long"), "{}", error);
+    }
+
+    #[test]
+    fn file_that_isnt_audio_is_an_error() {
+        let path = std::env::temp_dir().join(format!("hypergranola-{}-not-audio.wav", std::process::id()));
+        std::fs::write(&path, b"not a wav file").unwrap();
+        assert!(decode_file(&path, 60).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn channels_are_averaged() {
+        assert_eq!(downmix(&[0.5, -0.5, 1.0, 0.0], 2), [0.0, 0.5]);
+        assert_eq!(downmix(&[0.25, 0.5], 1), [0.25, 0.5]);
+    }
+}
+
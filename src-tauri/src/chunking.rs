@@ -0,0 +1,228 @@
+//! Transcript chunking
+//! Splits long text into windows that fit the LLM's context, breaking after sentences where it can
+
+/// Rough characters per token, close enough for English text with most tokenizers
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Estimated tokens in `text`
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Ends a sentence when followed by whitespace
+fn is_sentence_end(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…')
+}
+
+/// Closes a quote or aside after a sentence's end, like `."` or `?)`
+fn is_closing(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '”' | '’')
+}
+
+/// Split `text` into contiguous parts, each with its trailing whitespace. With `sentences`,
+/// parts end after sentences and lines, otherwise after every word.
+fn split_parts(text: &str, sentences: bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut last = None;
+    let mut in_space = false;
+    let mut newline = false;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            in_space = true;
+            newline |= c == '\n';
+            continue;
+        }
+        if in_space && i > start && (!sentences || newline || last.is_some_and(is_sentence_end)) {
+            parts.push(&text[start..i]);
+            start = i;
+        }
+        in_space = false;
+        newline = false;
+        if !is_closing(c) {
+            last = Some(c);
+        }
+    }
+    if start < text.len() {
+        parts.push(&text[start..]);
+    }
+    parts
+}
+
+/// Split `text` every `max_chars` characters, for words too long to fit on their own
+fn split_chars(text: &str, max_chars: usize) -> Vec<&str> {
+    let mut cuts: Vec<usize> = text.char_indices().map(|(i, _)| i).step_by(max_chars).collect();
+    cuts.push(text.len());
+    cuts.windows(2).map(|cut| &text[cut[0]..cut[1]]).collect()
+}
+
+/// Contiguous pieces of `text` of at most `max_chars` characters, not counting trailing
+/// whitespace. Sentences are split between words only when too long, and words by character.
+fn pieces(text: &str, max_chars: usize) -> Vec<&str> {
+    let fits = |piece: &str| piece.trim_end().chars().count() <= max_chars;
+    let mut pieces = Vec::new();
+    for sentence in split_parts(text, true) {
+        if fits(sentence) {
+            pieces.push(sentence);
+            continue;
+        }
+        for word in split_parts(sentence, false) {
+            if fits(word) {
+                pieces.push(word);
+            } else {
+                pieces.extend(split_chars(word, max_chars));
+            }
+        }
+    }
+    pieces
+}
+
+/// Split `text` into chunks of about `max_tokens` tokens at most, in order, breaking after
+/// sentences where possible. Text without punctuation is broken between words instead.
+pub fn chunk_text(text: &str, max_tokens: usize) -> Vec<String> {
+    let max_chars = max_tokens.max(1) * CHARS_PER_TOKEN;
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_chars = 0;
+    for piece in pieces(text, max_chars) {
+        if current_chars + piece.trim_end().chars().count() > max_chars && !current.trim().is_empty() {
+            chunks.push(current.trim().to_string());
+            current.clear();
+            current_chars = 0;
+        }
+        current.push_str(piece);
+        current_chars += piece.chars().count();
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current.trim().to_string());
+    }
+    chunks
+}
+
+/// Split `text` into what came before and its most recent part of about `max_tokens` tokens at
+/// most, breaking after a sentence where possible. The earlier part is empty when all of it fits.
+pub fn split_recent(text: &str, max_tokens: usize) -> (&str, &str) {
+    let max_chars = max_tokens.max(1) * CHARS_PER_TOKEN;
+    let pieces = pieces(text, max_chars);
+    let mut recent_chars = 0;
+    let mut split = pieces.len();
+    while split > 0 {
+        let piece = pieces[split - 1];
+        let chars = if split == pieces.len() {
+            piece.trim_end().chars().count()
+        } else {
+            piece.chars().count()
+        };
+        if recent_chars + chars > max_chars {
+            break;
+        }
+        recent_chars += chars;
+        split -= 1;
+    }
+    let offset: usize = pieces[..split].iter().map(|piece| piece.len()).sum();
+    (text[..offset].trim(), text[offset..].trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A long meeting's transcript: numbered sentences on a few topics, some asking questions
+    fn transcript(sentences: usize) -> String {
+        let topics = ["the roadmap", "hiring", "the Q3 budget", "the launch"];
+        (0..sentences)
+            .map(|i| match i % 3 {
+                0 => format!("Sentence {} is about {}.", i, topics[i % topics.len()]),
+                1 => format!("Should we revisit {} next week?", topics[i % topics.len()]),
+                _ => format!("He said \"that's fine for {}.\"", topics[i % topics.len()]),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Whether `text` starts where one of `transcript`'s sentences does
+    fn starts_sentence(text: &str) -> bool {
+        ["Sentence", "Should", "He said"].iter().any(|start| text.starts_with(start))
+    }
+
+    #[test]
+    fn tokens_are_estimated_from_characters() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        // Characters, not bytes
+        assert_eq!(estimate_tokens("éééé"), 1);
+    }
+
+    #[test]
+    fn long_transcript_is_split_after_sentences() {
+        let text = transcript(600);
+        let chunks = chunk_text(&text, 200);
+
+        assert!(chunks.len() > 10);
+        for chunk in &chunks {
+            assert!(estimate_tokens(chunk) <= 200, "{} tokens", estimate_tokens(chunk));
+            assert!(starts_sentence(chunk), "{}", chunk);
+            assert!(chunk.ends_with('.') || chunk.ends_with('?') || chunk.ends_with(".\""), "{}", chunk);
+        }
+        // Nothing is lost, reordered or duplicated
+        assert_eq!(chunks.join(" "), text);
+    }
+
+    #[test]
+    fn text_without_punctuation_is_split_between_words() {
+        let text = (0..5000).map(|i| format!("word{}", i)).collect::<Vec<_>>().join(" ");
+        let chunks = chunk_text(&text, 100);
+
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= 400);
+            assert!(chunk.split(' ').all(|word| word.starts_with("word")), "{}", chunk);
+        }
+        assert_eq!(chunks.join(" "), text);
+    }
+
+    #[test]
+    fn lines_without_punctuation_are_split_between_lines() {
+        let text = "Ana: so the plan\nBen: right the plan\nAna: ok then\n".repeat(20);
+        let chunks = chunk_text(&text, 20);
+
+        for chunk in &chunks {
+            assert!(chunk.starts_with("Ana:") || chunk.starts_with("Ben:"), "{}", chunk);
+            assert!(chunk.chars().count() <= 80);
+        }
+        assert_eq!(chunks.join("\n"), text.trim_end());
+    }
+
+    #[test]
+    fn words_longer_than_a_chunk_are_cut() {
+        let word = "ä".repeat(100);
+        let chunks = chunk_text(&format!("short {} end", word), 10);
+        // The rest of it fits with what follows
+        let rest = format!("{} end", "ä".repeat(20));
+        assert_eq!(chunks, ["short", &"ä".repeat(40), &"ä".repeat(40), &rest]);
+    }
+
+    #[test]
+    fn short_or_empty_text_is_one_chunk_or_none() {
+        assert_eq!(chunk_text("  Just one sentence.  ", 100), ["Just one sentence."]);
+        assert!(chunk_text("", 100).is_empty());
+        assert!(chunk_text(" \n\t ", 100).is_empty());
+        // A limit of 0 is treated as 1
+        assert_eq!(chunk_text("Hi. Bye.", 0), ["Hi.", "Bye."]);
+    }
+
+    #[test]
+    fn recent_part_is_split_off_after_a_sentence() {
+        let text = transcript(300);
+        let (earlier, recent) = split_recent(&text, 150);
+
+        assert!(estimate_tokens(recent) <= 150);
+        assert!(estimate_tokens(recent) > 100, "the recent part is about as long as allowed");
+        assert!(starts_sentence(recent), "{}", recent);
+        assert!(recent.ends_with(&text[text.len() - 20..]));
+        assert_eq!(format!("{} {}", earlier, recent), text);
+
+        assert_eq!(split_recent("All of it fits.", 100), ("", "All of it fits."));
+        assert_eq!(split_recent("", 100), ("", ""));
+    }
+}
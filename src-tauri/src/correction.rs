@@ -0,0 +1,78 @@
+//! Transcript correction
+//! Fixes speech-to-text mistakes with the LLM, using the preceding transcript as context
+
+use crate::events::{emit_event, report_error, ErrorSource};
+use crate::llm::{ChatMessage, CompletionOptions, LlmClient};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+/// Payload of the `corrected_transcript` event
+#[derive(Debug, Clone, Serialize)]
+pub struct CorrectedTranscript {
+    /// Offset of the transcribed audio from the start of the session, as in `transcript_segment`
+    pub offset_ms: u64,
+    pub original: String,
+    pub corrected: String,
+}
+
+/// Correct `text` as spoken after `context`, returning it unchanged if the LLM gives nothing back
+pub async fn correct_text(llm: &LlmClient, text: &str, context: Option<&str>) -> Result<String, String> {
+    println!("Correcting transcript with context via: {} (Model: {})", llm.api_url(), llm.model());
+
+    let prompt = match context.filter(|context| !context.trim().is_empty()) {
+        Some(ctx) => format!(
+            "You are correcting speech-to-text transcriptions in real-time. Use the conversation context to improve accuracy.
+
+Previous conversation context:
+{}
+
+Current spoken text to correct: \"{}\"
+
+Return ONLY the corrected version of the spoken text. Do not include any explanations, coaching tips, or additional formatting. Just the corrected text:",
+            ctx, text
+        ),
+        None => format!(
+            "Correct this spoken text to make it more coherent and grammatically correct. Return ONLY the corrected text, nothing else: \"{}\"",
+            text
+        ),
+    };
+
+    let options = CompletionOptions {
+        max_tokens: Some(200),
+        temperature: Some(0.3),
+        ..Default::default()
+    };
+    match llm.complete(&[ChatMessage::user(prompt)], options).await? {
+        Some(content) => Ok(content.trim().to_string()),
+        None => {
+            // Fallback - return original text if correction fails
+            println!("Correction failed, returning original text");
+            Ok(text.to_string())
+        }
+    }
+}
+
+/// Correct a finalized segment in the background and emit it as `corrected_transcript`
+pub fn spawn_correction(app_handle: &AppHandle, offset_ms: u64, text: String, context: String) {
+    if app_handle.state::<LlmClient>().check_budget().is_err() {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let llm = app_handle.state::<LlmClient>();
+        match correct_text(&llm, &text, Some(&context)).await {
+            Ok(corrected) => {
+                emit_event(
+                    &app_handle,
+                    "corrected_transcript",
+                    CorrectedTranscript {
+                        offset_ms,
+                        original: text,
+                        corrected,
+                    },
+                );
+            }
+            Err(e) => report_error(&app_handle, ErrorSource::Llm, format!("Failed to correct transcript: {}", e)),
+        }
+    });
+}
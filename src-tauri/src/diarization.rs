@@ -1,11 +1,29 @@
-/// Speaker Diarization Module
-/// Simplified speaker identification and segmentation
+//! Speaker diarization
+//! Tells speakers apart by voice and attributes transcribed text to them
+//!
+//! Voices are compared by the coarse spectral fingerprint from `speaker_profiles`, not a trained
+//! speaker embedding such as pyannote's. It has only been checked against synthetic voices, so
+//! real speakers with similar voices may be folded together.
 
-use std::time::Duration;
+use crate::events::emit_event;
+use crate::speaker_profiles::{self, cosine_similarity, SharedSpeakerProfiles, VoiceCentroid, DEFAULT_RECOGNITION_THRESHOLD};
+use crate::transcript::{SharedTranscriptStore, TranscriptStore};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use serde::{Serialize, Deserialize};
+use tauri::Manager;
+
+/// Voice similarity above which two speakers are taken to be the same person
+const DEFAULT_MERGE_THRESHOLD: f32 = 0.95;
+/// Voice similarity needed to attribute speech to a speaker already heard this session,
+/// below it a new speaker is started
+const DEFAULT_CLUSTER_THRESHOLD: f32 = 0.9;
+/// Share of a turn that must overlap the previous speaker's for it to count as an interruption
+const DEFAULT_OVERLAP_THRESHOLD: f32 = 0.3;
 
 /// Speaker information with audio characteristics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct Speaker {
     pub id: String,
     pub label: String,
@@ -13,69 +31,563 @@ pub struct Speaker {
     pub first_detected: Duration,
     pub last_active: Duration,
     pub message_count: usize,
+    /// Meeting participant the user linked this speaker to
+    pub participant: Option<String>,
 }
 
 /// Enhanced transcription with speaker attribution
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct SpeakerAttributedText {
     pub speaker: Speaker,
     pub text: String,
+    /// Start of the speech, from the start of the session
     pub timestamp: Duration,
+    pub end: Duration,
+    /// Whisper's confidence in the text, 0.0 to 1.0
     pub confidence: f32,
     pub is_question: bool,
+    /// Speaker still talking when this speech started, if they overlap by more than overlap_threshold
+    pub overlaps_with: Option<Speaker>,
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+/// Speaker as sent to the frontend, with times in milliseconds since the session start
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerInfo {
+    pub id: String,
+    pub label: String,
+    pub characteristics: Vec<String>,
+    pub first_detected_ms: u64,
+    pub last_active_ms: u64,
+    pub message_count: usize,
+    #[serde(default)]
+    pub participant: Option<String>,
+}
+
+impl From<&Speaker> for SpeakerInfo {
+    fn from(speaker: &Speaker) -> Self {
+        Self {
+            id: speaker.id.clone(),
+            label: speaker.label.clone(),
+            characteristics: speaker.characteristics.clone(),
+            first_detected_ms: millis(speaker.first_detected),
+            last_active_ms: millis(speaker.last_active),
+            message_count: speaker.message_count,
+            participant: speaker.participant.clone(),
+        }
+    }
+}
+
+/// Payload of the `speaker_transcript` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerTranscript {
+    pub speaker_id: String,
+    pub speaker_label: String,
+    pub text: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub confidence: f32,
+    pub is_question: bool,
+    /// Speaker talking over this speech, crosstalk that may be partly theirs
+    #[serde(default)]
+    pub overlapping_speaker_id: Option<String>,
+    #[serde(default)]
+    pub overlapping_speaker_label: Option<String>,
+}
+
+impl From<&SpeakerAttributedText> for SpeakerTranscript {
+    fn from(attributed: &SpeakerAttributedText) -> Self {
+        Self {
+            speaker_id: attributed.speaker.id.clone(),
+            speaker_label: attributed.speaker.label.clone(),
+            text: attributed.text.clone(),
+            start_ms: millis(attributed.timestamp),
+            end_ms: millis(attributed.end),
+            confidence: attributed.confidence,
+            is_question: attributed.is_question,
+            overlapping_speaker_id: attributed.overlaps_with.as_ref().map(|speaker| speaker.id.clone()),
+            overlapping_speaker_label: attributed.overlaps_with.as_ref().map(|speaker| speaker.label.clone()),
+        }
+    }
+}
+
+/// Payload of `process_audio_diarization`, audio attributed to a speaker without a transcript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerSegment {
+    pub speaker_id: String,
+    pub speaker_label: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// Speaker still talking when this speech started
+    #[serde(default)]
+    pub overlapping_speaker_id: Option<String>,
+}
+
+/// Payload of the `speaker_change` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerChange {
+    pub speaker_id: String,
+    pub speaker_label: String,
+    /// When the new speaker started, from the start of the session
+    pub at_ms: u64,
+}
+
+/// Payload of the `question_detected` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionDetected {
+    pub speaker_id: String,
+    pub speaker_label: String,
+    pub question: String,
+    pub at_ms: u64,
+}
+
+/// A speaker folded into another one, found to be the same person
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerMerge {
+    pub from: String,
+    pub into: String,
+    /// Label of the speaker merged into
+    pub label: String,
+}
+
+/// Talk time of one speaker
+#[derive(Debug, Clone, Default)]
+struct TalkTime {
+    talk_time: Duration,
+    turns: usize,
+    interruptions: usize,
+}
+
+/// Who talked how much, built up from attributed segments
+#[derive(Debug, Default)]
+pub struct TalkTimeStats {
+    speakers: HashMap<String, TalkTime>,
+    /// Speaker and end of the latest segment
+    last_segment: Option<(String, Duration)>,
+}
+
+impl TalkTimeStats {
+    /// The previous speaker, if a segment by `speaker_id` from `start` to `end` overlaps their
+    /// latest speech by more than `overlap_threshold` of its length
+    pub fn overlapped_speaker(&self, speaker_id: &str, start: Duration, end: Duration, overlap_threshold: f32) -> Option<&str> {
+        let (previous, previous_end) = self.last_segment.as_ref()?;
+        if previous == speaker_id {
+            return None;
+        }
+        let length = end.saturating_sub(start);
+        let overlap = previous_end.saturating_sub(start).min(length);
+        (!overlap.is_zero() && overlap.as_secs_f32() > overlap_threshold * length.as_secs_f32()).then_some(previous.as_str())
+    }
+
+    /// Count a segment. A change of speaker starts a turn, and a turn that overlaps the
+    /// previous speaker's by more than `overlap_threshold` of its length is an interruption.
+    pub fn record(&mut self, speaker_id: &str, start: Duration, end: Duration, overlap_threshold: f32) {
+        let interrupted = self.overlapped_speaker(speaker_id, start, end, overlap_threshold).is_some();
+        let new_turn = self.last_segment.as_ref().is_none_or(|(previous, _)| previous != speaker_id);
+        let speaker = self.speakers.entry(speaker_id.to_string()).or_default();
+        speaker.talk_time += end.saturating_sub(start);
+        if new_turn {
+            speaker.turns += 1;
+        }
+        if interrupted {
+            speaker.interruptions += 1;
+        }
+
+        let latest_end = match &self.last_segment {
+            Some((previous, previous_end)) if previous == speaker_id => end.max(*previous_end),
+            _ => end,
+        };
+        self.last_segment = Some((speaker_id.to_string(), latest_end));
+    }
+
+    /// Count a merged speaker's talk towards the speaker they were merged into
+    pub fn merge(&mut self, from: &str, into: &str) {
+        if let Some(merged) = self.speakers.remove(from) {
+            let speaker = self.speakers.entry(into.to_string()).or_default();
+            speaker.talk_time += merged.talk_time;
+            speaker.turns += merged.turns;
+            speaker.interruptions += merged.interruptions;
+        }
+        if let Some((speaker, _)) = &mut self.last_segment {
+            if speaker == from {
+                *speaker = into.to_string();
+            }
+        }
+    }
+
+    /// Report per speaker, labelled from `speakers` and busiest first
+    pub fn report(&self, speakers: &[Speaker]) -> SpeakerStatsReport {
+        let total: Duration = self.speakers.values().map(|speaker| speaker.talk_time).sum();
+        let mut stats: Vec<SpeakerStats> = self
+            .speakers
+            .iter()
+            .map(|(id, talk)| SpeakerStats {
+                speaker_id: id.clone(),
+                speaker_label: speakers
+                    .iter()
+                    .find(|speaker| &speaker.id == id)
+                    .map(|speaker| speaker.label.clone())
+                    .unwrap_or_else(|| id.clone()),
+                talk_time_ms: millis(talk.talk_time),
+                talk_share: if total.is_zero() { 0.0 } else { talk.talk_time.as_secs_f32() / total.as_secs_f32() },
+                turn_count: talk.turns,
+                average_turn_ms: if talk.turns == 0 { 0 } else { millis(talk.talk_time) / talk.turns as u64 },
+                interruptions: talk.interruptions,
+            })
+            .collect();
+        stats.sort_by_key(|speaker| std::cmp::Reverse(speaker.talk_time_ms));
+
+        SpeakerStatsReport {
+            total_talk_time_ms: millis(total),
+            speakers: stats,
+        }
+    }
+}
+
+/// Talk time statistics of one speaker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerStats {
+    pub speaker_id: String,
+    pub speaker_label: String,
+    pub talk_time_ms: u64,
+    /// Share (0-1) of all talk time this session
+    pub talk_share: f32,
+    pub turn_count: usize,
+    pub average_turn_ms: u64,
+    /// Turns started before the previous speaker had finished
+    pub interruptions: usize,
+}
+
+/// Payload of `get_speaker_stats` and the `speaker_stats` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerStatsReport {
+    pub total_talk_time_ms: u64,
+    pub speakers: Vec<SpeakerStats>,
 }
 
 /// Diarization engine state
 pub struct DiarizationEngine {
     config: DiarizationConfig,
     active_speakers: Vec<Speaker>,
+    /// Speaker of the latest attributed speech
     current_speaker: Option<Speaker>,
-    last_speaker_change: Duration,
+    /// When the session started, for timing audio that arrives without an offset
+    session_start: Instant,
+    /// Voices saved from earlier meetings
+    profiles: SharedSpeakerProfiles,
+    /// Voice heard from each speaker this session, by speaker id
+    voices: HashMap<String, VoiceCentroid>,
+    talk_time: TalkTimeStats,
+    /// Speakers created this session, for numbering new ones
+    speakers_created: usize,
+    /// Merges not yet applied to the stored transcript, see `take_merges`
+    merges: Vec<SpeakerMerge>,
 }
 
 /// Speaker diarization configuration
 #[derive(Debug, Clone)]
 pub struct DiarizationConfig {
-    #[allow(dead_code)]
+    /// Speech shorter than this doesn't start a new speaker
     pub min_speaker_duration: Duration,
     pub max_speakers: usize,
+    /// Share (0-1) of a turn that must overlap the previous speaker's to count as an interruption
+    pub overlap_threshold: f32,
     pub voice_activity_threshold: f32,
-    #[allow(dead_code)]
-    pub silence_threshold: f32,
+    /// Voice similarity (0-1) needed to recognize a known speaker
+    pub recognition_threshold: f32,
+    /// Voice similarity (0-1) needed to attribute speech to a speaker heard this session
+    pub cluster_threshold: f32,
+    /// Voice similarity (0-1) above which two speakers are merged
+    pub merge_threshold: f32,
+    /// Number of people in the meeting, if the user knows it
+    pub expected_speakers: Option<usize>,
+}
+
+impl Default for DiarizationConfig {
+    fn default() -> Self {
+        Self {
+            min_speaker_duration: Duration::from_millis(500),
+            max_speakers: 10,
+            overlap_threshold: DEFAULT_OVERLAP_THRESHOLD,
+            voice_activity_threshold: 0.01,
+            recognition_threshold: DEFAULT_RECOGNITION_THRESHOLD,
+            cluster_threshold: DEFAULT_CLUSTER_THRESHOLD,
+            merge_threshold: DEFAULT_MERGE_THRESHOLD,
+            expected_speakers: None,
+        }
+    }
 }
 
+/// Changes to the diarization settings, each left as it is when None
+#[derive(Debug, Clone, Default)]
+pub struct DiarizationConfigUpdate {
+    pub max_speakers: Option<usize>,
+    pub min_speaker_duration_ms: Option<u64>,
+    pub overlap_threshold: Option<f32>,
+}
+
+impl DiarizationConfig {
+    /// Apply the settings of `update` after checking them all, so a bad one changes nothing
+    pub fn apply(&mut self, update: &DiarizationConfigUpdate) -> Result<(), String> {
+        if update.max_speakers == Some(0) {
+            return Err("Max speakers must be at least 1".to_string());
+        }
+        if update.overlap_threshold.is_some_and(|threshold| !(0.0..=1.0).contains(&threshold)) {
+            return Err("Overlap threshold must be between 0 and 1".to_string());
+        }
+        if let Some(max_speakers) = update.max_speakers {
+            self.max_speakers = max_speakers;
+        }
+        if let Some(ms) = update.min_speaker_duration_ms {
+            self.min_speaker_duration = Duration::from_millis(ms);
+        }
+        if let Some(threshold) = update.overlap_threshold {
+            self.overlap_threshold = threshold;
+        }
+        Ok(())
+    }
+}
+
+/// Relabel a speaker's earlier transcript segments after `DiarizationEngine::relabel_speaker`,
+/// along with those of the speakers it unlinked from the participant
+pub fn relabel_transcript(transcript: &mut TranscriptStore, speaker_id: &str, label: &str, unlinked: &[Speaker]) {
+    transcript.relabel_speaker(speaker_id, label);
+    for speaker in unlinked {
+        transcript.relabel_speaker(&speaker.id, &speaker.label);
+    }
+}
+
+/// The label a speaker gets when detected, e.g. "Speaker 2" for `speaker_2`
+fn numbered_label(speaker_id: &str) -> String {
+    match speaker_id.strip_prefix("speaker_") {
+        Some(number) => format!("Speaker {}", number),
+        None => speaker_id.to_string(),
+    }
+}
+
+/// The engine set up by `initialize_diarization_engine`, shared between commands
+pub type SharedDiarizationState = Arc<Mutex<Option<DiarizationEngine>>>;
+
 impl DiarizationEngine {
     /// Create a new diarization engine
-    pub async fn new(config: DiarizationConfig) -> Result<Self, String> {
-        Ok(Self {
+    pub fn new(config: DiarizationConfig, profiles: SharedSpeakerProfiles) -> Self {
+        Self {
             config,
             active_speakers: Vec::new(),
             current_speaker: None,
-            last_speaker_change: Duration::from_secs(0),
-        })
+            session_start: Instant::now(),
+            profiles,
+            voices: HashMap::new(),
+            talk_time: TalkTimeStats::default(),
+            speakers_created: 0,
+            merges: Vec::new(),
+        }
     }
 
-    /// Process audio samples and return speaker-attributed text
-    pub async fn process_audio(
-        &mut self,
-        audio_samples: &[f32],
-        _sample_rate: u32,
-    ) -> Result<Vec<SpeakerAttributedText>, String> {
-        // Detect voice activity
-        let voice_activity = self.detect_voice_activity(audio_samples)?;
+    /// Start timing a new session from now, e.g. when transcription starts.
+    /// Speakers and their names are kept, the talk time statistics start over.
+    pub fn start_session(&mut self) {
+        self.session_start = Instant::now();
+        self.talk_time = TalkTimeStats::default();
+    }
 
-        if !voice_activity {
-            return Ok(Vec::new());
+    /// Time since the session started
+    pub fn session_elapsed(&self) -> Duration {
+        self.session_start.elapsed()
+    }
+
+    /// Speakers detected so far this session
+    pub fn speakers(&self) -> Vec<SpeakerInfo> {
+        self.active_speakers.iter().map(SpeakerInfo::from).collect()
+    }
+
+    /// Talk time statistics of the session so far
+    pub fn speaker_stats(&self) -> SpeakerStatsReport {
+        self.talk_time.report(&self.active_speakers)
+    }
+
+    /// Change the settings mid-session. Speakers over a lowered max_speakers are merged right away.
+    pub fn update_config(&mut self, update: &DiarizationConfigUpdate) -> Result<(), String> {
+        self.config.apply(update)?;
+        self.merge_speakers();
+        Ok(())
+    }
+
+    /// Set how many people are in the meeting, None to allow up to max_speakers.
+    /// Speakers over the new count are merged right away.
+    pub fn set_expected_speakers(&mut self, expected: Option<usize>) {
+        self.config.expected_speakers = expected;
+        self.merge_speakers();
+    }
+
+    /// Merges since the last call, to apply to the stored transcript
+    pub fn take_merges(&mut self) -> Vec<SpeakerMerge> {
+        std::mem::take(&mut self.merges)
+    }
+
+    /// Most speakers told apart: the expected count if known, never more than max_speakers
+    fn speaker_limit(&self) -> usize {
+        self.config
+            .expected_speakers
+            .map_or(self.config.max_speakers, |expected| expected.clamp(1, self.config.max_speakers))
+    }
+
+    /// Merge speakers that are likely one person split up by noise: any two whose voices are
+    /// closer than merge_threshold, then the closest pairs until there are no more than the limit
+    fn merge_speakers(&mut self) {
+        while let Some((similarity, from, into)) = self.closest_speakers() {
+            if similarity < self.config.merge_threshold && self.active_speakers.len() <= self.speaker_limit() {
+                break;
+            }
+            self.merge_speaker(&from, &into);
+        }
+    }
+
+    /// The two speakers with the most similar voices, as (similarity, from, into).
+    /// The one heard for longer is merged into.
+    fn closest_speakers(&self) -> Option<(f32, String, String)> {
+        let voices: Vec<(&str, Vec<f32>, f32)> = self
+            .active_speakers
+            .iter()
+            .filter_map(|speaker| {
+                let voice = self.voices.get(&speaker.id)?;
+                Some((speaker.id.as_str(), voice.embedding()?, voice.seconds))
+            })
+            .collect();
+
+        let mut closest: Option<(f32, usize, usize)> = None;
+        for a in 0..voices.len() {
+            for b in a + 1..voices.len() {
+                let similarity = cosine_similarity(&voices[a].1, &voices[b].1);
+                if closest.is_none_or(|(best, _, _)| similarity > best) {
+                    closest = Some((similarity, a, b));
+                }
+            }
         }
+        let (similarity, a, b) = closest?;
+        let (from, into) = if voices[a].2 > voices[b].2 { (b, a) } else { (a, b) };
+        Some((similarity, voices[from].0.to_string(), voices[into].0.to_string()))
+    }
 
-        // For now, use a placeholder transcription
-        // In a full implementation, this would integrate with whisper
-        let transcription = "Speech detected".to_string();
+    /// Fold one speaker into another. A name the user linked to a participant wins over one that isn't.
+    fn merge_speaker(&mut self, from: &str, into: &str) {
+        let Some(index) = self.active_speakers.iter().position(|speaker| speaker.id == from) else {
+            return;
+        };
+        let merged = self.active_speakers.remove(index);
+        let Some(speaker) = self.active_speakers.iter_mut().find(|speaker| speaker.id == into) else {
+            self.active_speakers.insert(index, merged);
+            return;
+        };
+        speaker.first_detected = speaker.first_detected.min(merged.first_detected);
+        speaker.last_active = speaker.last_active.max(merged.last_active);
+        speaker.message_count += merged.message_count;
+        if speaker.participant.is_none() && merged.participant.is_some() {
+            speaker.label = merged.label;
+            speaker.participant = merged.participant;
+        }
+        let speaker = speaker.clone();
+
+        if let Some(voice) = self.voices.remove(from) {
+            self.voices.entry(into.to_string()).or_default().merge(&voice);
+        }
+        self.talk_time.merge(from, into);
+        if self.current_speaker.as_ref().is_some_and(|current| current.id == from || current.id == into) {
+            self.current_speaker = Some(speaker.clone());
+        }
+        self.merges.push(SpeakerMerge {
+            from: from.to_string(),
+            into: into.to_string(),
+            label: speaker.label,
+        });
+    }
+
+    /// Change a speaker's label, and optionally the participant it's linked to.
+    /// Text attributed from now on carries the new label.
+    /// A participant belongs to one speaker at a time, so any other speaker linked to them goes back
+    /// to its numbered label. Returns those speakers, whose earlier text needs relabeling too.
+    pub fn relabel_speaker(&mut self, speaker_id: &str, label: &str, participant: Option<&str>) -> Result<Vec<Speaker>, String> {
+        if !self.active_speakers.iter().chain(self.current_speaker.as_ref()).any(|speaker| speaker.id == speaker_id) {
+            return Err(format!("Unknown speaker: {}", speaker_id));
+        }
 
-        // Determine speaker (simplified approach)
-        let speaker = self.determine_speaker().await;
+        let mut unlinked = Vec::new();
+        let speakers = self.active_speakers.iter_mut().chain(self.current_speaker.as_mut());
+        for speaker in speakers {
+            if speaker.id == speaker_id {
+                speaker.label = label.to_string();
+                if let Some(participant) = participant {
+                    speaker.participant = Some(participant.to_string());
+                }
+            } else if participant.is_some_and(|participant| {
+                speaker.participant.as_deref().is_some_and(|linked| linked.eq_ignore_ascii_case(participant))
+            }) {
+                speaker.participant = None;
+                speaker.label = numbered_label(&speaker.id);
+                if !unlinked.iter().any(|other: &Speaker| other.id == speaker.id) {
+                    unlinked.push(speaker.clone());
+                }
+            }
+        }
+
+        // Remember the voice under its new name for later meetings
+        if let Some(voice) = self.voices.get(speaker_id) {
+            if let Some(embedding) = voice.embedding() {
+                let mut profiles = self.profiles.lock().map_err(|e| e.to_string())?;
+                profiles.upsert(label, &embedding, voice.seconds);
+                if let Err(e) = profiles.save() {
+                    eprintln!("{}", e);
+                }
+            }
+        }
+        Ok(unlinked)
+    }
+
+    /// Attribute audio that was just captured to a speaker, without transcribing it.
+    /// Returns None when the audio holds no speech.
+    pub fn process_audio(&mut self, audio_samples: &[f32], sample_rate: u32) -> Result<Option<SpeakerSegment>, String> {
+        if sample_rate == 0 {
+            return Err("Sample rate must be positive".to_string());
+        }
+        // The audio is taken to have just been captured, so it ends now on the session clock
+        let duration = Duration::from_secs_f64(audio_samples.len() as f64 / sample_rate as f64);
+        let start = self.session_start.elapsed().saturating_sub(duration);
+
+        let Some((speaker, overlaps_with)) = self.attribute_audio(audio_samples, sample_rate, start)? else {
+            return Ok(None);
+        };
+        Ok(Some(SpeakerSegment {
+            speaker_id: speaker.id,
+            speaker_label: speaker.label,
+            start_ms: millis(start),
+            end_ms: millis(start + duration),
+            overlapping_speaker_id: overlaps_with.map(|speaker| speaker.id),
+        }))
+    }
+
+    /// Attribute already transcribed text, with Whisper's `confidence` in it, to the speaker of its audio,
+    /// which starts at `start` into the session
+    pub fn attribute_text(
+        &mut self,
+        audio_samples: &[f32],
+        sample_rate: u32,
+        text: &str,
+        confidence: f32,
+        start: Duration,
+    ) -> Result<Vec<SpeakerAttributedText>, String> {
+        if sample_rate == 0 {
+            return Err("Sample rate must be positive".to_string());
+        }
+        let end = start + Duration::from_secs_f64(audio_samples.len() as f64 / sample_rate as f64);
+        let Some((speaker, overlaps_with)) = self.attribute_audio(audio_samples, sample_rate, start)? else {
+            return Ok(Vec::new());
+        };
 
         // Analyze transcription
+        let transcription = text.to_string();
         let is_question = self.detect_question(&transcription);
         let _characteristics = self.detect_speaker_characteristics(&transcription);
 
@@ -83,14 +595,75 @@ impl DiarizationEngine {
         let result = SpeakerAttributedText {
             speaker,
             text: transcription,
-            timestamp: Duration::from_secs(0),
-            confidence: 0.9,
+            timestamp: start,
+            end,
+            confidence,
             is_question,
+            overlaps_with,
         };
 
         Ok(vec![result])
     }
 
+    /// Attribute speech starting at `start` into the session to a speaker by its voice, counting it
+    /// towards their talk time. Returns the speaker and whoever they talked over, or None without voice activity.
+    fn attribute_audio(
+        &mut self,
+        audio_samples: &[f32],
+        sample_rate: u32,
+        start: Duration,
+    ) -> Result<Option<(Speaker, Option<Speaker>)>, String> {
+        let end = start + Duration::from_secs_f64(audio_samples.len() as f64 / sample_rate as f64);
+
+        // Detect voice activity
+        let voice_activity = self.detect_voice_activity(audio_samples)?;
+
+        if !voice_activity {
+            return Ok(None);
+        }
+
+        // Known voices are named right away, others are matched to the voices heard this session.
+        // Speech too short to tell voices apart only goes to speakers already heard.
+        let may_add = end - start >= self.config.min_speaker_duration;
+        let embedding = speaker_profiles::voice_embedding(audio_samples, sample_rate);
+        let speaker = match embedding.as_deref().and_then(|embedding| self.recognize_speaker(embedding, start, may_add)) {
+            Some(speaker) => speaker,
+            None => self.cluster_speaker(embedding.as_deref(), start, may_add),
+        };
+        if let Some(embedding) = &embedding {
+            let seconds = audio_samples.len() as f32 / sample_rate as f32;
+            self.voices.entry(speaker.id.clone()).or_default().add(embedding, seconds);
+        }
+
+        // Crosstalk: the speech may be partly the previous speaker's, so they're noted with it
+        let mut overlapped = self
+            .talk_time
+            .overlapped_speaker(&speaker.id, start, end, self.config.overlap_threshold)
+            .map(str::to_string);
+        let mut speaker = self.record_segment(speaker, start, end);
+
+        // The new voice may show two speakers to be one person
+        let merged_from = self.merges.len();
+        self.merge_speakers();
+        for merge in &self.merges[merged_from..] {
+            if merge.from == speaker.id {
+                speaker.id = merge.into.clone();
+            }
+            if overlapped.as_deref() == Some(merge.from.as_str()) {
+                overlapped = Some(merge.into.clone());
+            }
+        }
+        if let Some(merged) = self.active_speakers.iter().find(|active| active.id == speaker.id) {
+            speaker = merged.clone();
+        }
+        let overlaps_with = overlapped
+            .filter(|id| *id != speaker.id)
+            .and_then(|id| self.active_speakers.iter().find(|active| active.id == id).cloned());
+        self.current_speaker = Some(speaker.clone());
+
+        Ok(Some((speaker, overlaps_with)))
+    }
+
     /// Detect voice activity in audio samples
     fn detect_voice_activity(&self, samples: &[f32]) -> Result<bool, String> {
         if samples.is_empty() {
@@ -107,50 +680,90 @@ impl DiarizationEngine {
         Ok(has_voice)
     }
 
-    /// Determine current speaker (simplified approach)
-    async fn determine_speaker(&mut self) -> Speaker {
-        let now = Duration::from_secs(0);
-
-        // Check if we need to switch speakers based on time
-        let time_since_last_change = now - self.last_speaker_change;
-        let should_switch_speaker = time_since_last_change > Duration::from_secs(5);
-
-        if should_switch_speaker && self.active_speakers.len() < self.config.max_speakers {
-            // Create new speaker
-            let new_speaker = Speaker {
-                id: format!("speaker_{}", self.active_speakers.len() + 1),
-                label: format!("Speaker {}", self.active_speakers.len() + 1),
-                characteristics: Vec::new(),
-                first_detected: now,
-                last_active: now,
-                message_count: 0,
-            };
+    /// Match a voice against the known speakers, returning the session speaker with that name.
+    /// A known speaker not heard yet this session is only added if `may_add`.
+    fn recognize_speaker(&mut self, embedding: &[f32], now: Duration, may_add: bool) -> Option<Speaker> {
+        let name = {
+            let profiles = self.profiles.lock().ok()?;
+            profiles.recognize(embedding, self.config.recognition_threshold)?.name.clone()
+        };
+        if let Some(speaker) = self.active_speakers.iter().find(|speaker| speaker.label == name) {
+            return Some(speaker.clone());
+        }
+        if !may_add || self.active_speakers.len() >= self.speaker_limit() {
+            return None;
+        }
+        Some(self.new_speaker(Some(name), now))
+    }
 
-            self.active_speakers.push(new_speaker.clone());
-            self.current_speaker = Some(new_speaker.clone());
-            self.last_speaker_change = now;
+    /// Attribute a voice that isn't a known speaker to the speaker heard this session whose voice
+    /// is closest, or start a new speaker when none is within cluster_threshold. Speech too short
+    /// for a fingerprint goes to whoever spoke last.
+    fn cluster_speaker(&mut self, embedding: Option<&[f32]>, now: Duration, may_add: bool) -> Speaker {
+        let closest = embedding.and_then(|embedding| self.closest_voice(embedding));
+        if let Some((similarity, speaker)) = &closest {
+            if *similarity >= self.config.cluster_threshold {
+                return speaker.clone();
+            }
+        }
+        // A speaker first heard in speech too short to fingerprint gets the next voice heard
+        if let Some(current) = &self.current_speaker {
+            if embedding.is_some() && !self.voices.contains_key(&current.id) {
+                return current.clone();
+            }
+        }
+        if embedding.is_some() && may_add && self.active_speakers.len() < self.speaker_limit() {
+            return self.new_speaker(None, now);
+        }
 
-            return new_speaker;
+        // With everyone accounted for, or too little to go on, the speech goes to the closest voice
+        // or else whoever spoke last
+        match closest.map(|(_, speaker)| speaker).or_else(|| self.current_speaker.clone()) {
+            Some(speaker) => speaker,
+            None => self.new_speaker(None, now),
         }
+    }
 
-        // Use current speaker or create first one
-        if let Some(speaker) = &self.current_speaker {
-            return speaker.clone();
-        } else {
-            let new_speaker = Speaker {
-                id: "speaker_1".to_string(),
-                label: "Speaker 1".to_string(),
-                characteristics: Vec::new(),
-                first_detected: now,
-                last_active: now,
-                message_count: 0,
-            };
+    /// The speaker heard this session whose voice is most like `embedding`, with the similarity
+    fn closest_voice(&self, embedding: &[f32]) -> Option<(f32, Speaker)> {
+        self.active_speakers
+            .iter()
+            .filter_map(|speaker| {
+                let voice = self.voices.get(&speaker.id)?.embedding()?;
+                Some((cosine_similarity(&voice, embedding), speaker))
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(similarity, speaker)| (similarity, speaker.clone()))
+    }
 
-            self.active_speakers.push(new_speaker.clone());
-            self.current_speaker = Some(new_speaker.clone());
-            self.last_speaker_change = now;
+    /// Add a speaker first heard at `now`, labelled "Speaker N" unless named.
+    /// Numbers aren't reused, so merged speakers' ids never come back.
+    fn new_speaker(&mut self, label: Option<String>, now: Duration) -> Speaker {
+        self.speakers_created += 1;
+        let id = format!("speaker_{}", self.speakers_created);
+        let speaker = Speaker {
+            id: id.clone(),
+            label: label.unwrap_or_else(|| numbered_label(&id)),
+            characteristics: Vec::new(),
+            first_detected: now,
+            last_active: now,
+            message_count: 0,
+            participant: None,
+        };
+        self.active_speakers.push(speaker.clone());
+        speaker
+    }
 
-            return new_speaker;
+    /// Count a segment from `start` to `end` towards a speaker, returning the updated speaker
+    fn record_segment(&mut self, speaker: Speaker, start: Duration, end: Duration) -> Speaker {
+        self.talk_time.record(&speaker.id, start, end, self.config.overlap_threshold);
+        match self.active_speakers.iter_mut().find(|active| active.id == speaker.id) {
+            Some(active) => {
+                active.last_active = active.last_active.max(end);
+                active.message_count += 1;
+                active.clone()
+            }
+            None => speaker,
         }
     }
 
@@ -203,41 +816,129 @@ impl DiarizationEngine {
     }
 }
 
-/// Initialize diarization engine
+/// Initialize diarization engine, starting over with no detected speakers.
+/// `recognition_threshold` is the voice similarity (0-1) needed to recognize a known speaker.
+/// `max_speakers`, `min_speaker_duration_ms` and `overlap_threshold` default to DiarizationConfig's,
+/// e.g. a max of 2 for a 1:1 call keeps one voice from being split up.
 #[tauri::command]
-pub async fn initialize_diarization_engine() -> Result<String, String> {
-    let config = DiarizationConfig {
-        min_speaker_duration: Duration::from_millis(500),
-        max_speakers: 10,
-        voice_activity_threshold: 0.01,
-        silence_threshold: 0.001,
-    };
-
-    let _engine = DiarizationEngine::new(config).await?;
+pub fn initialize_diarization_engine(
+    recognition_threshold: Option<f32>,
+    max_speakers: Option<usize>,
+    min_speaker_duration_ms: Option<u64>,
+    overlap_threshold: Option<f32>,
+    state: tauri::State<'_, SharedDiarizationState>,
+    profiles: tauri::State<'_, SharedSpeakerProfiles>,
+) -> Result<String, String> {
+    let mut engine = state.lock().map_err(|e| e.to_string())?;
+    let mut config = DiarizationConfig::default();
+    if let Some(threshold) = recognition_threshold {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err("Recognition threshold must be between 0 and 1".to_string());
+        }
+        config.recognition_threshold = threshold;
+    }
+    config.apply(&DiarizationConfigUpdate {
+        max_speakers,
+        min_speaker_duration_ms,
+        overlap_threshold,
+    })?;
+    *engine = Some(DiarizationEngine::new(config, profiles.inner().clone()));
     Ok("Diarization engine initialized successfully".to_string())
 }
 
-/// Process audio with diarization (simplified version)
+/// Attribute captured audio to a speaker by voice, without transcribing it.
+/// Returns None when the audio holds no speech.
 #[tauri::command]
-pub async fn process_audio_diarization(
+pub fn process_audio_diarization(
+    app_handle: tauri::AppHandle,
     audio_samples: Vec<f32>,
     sample_rate: u32,
-) -> Result<Vec<SpeakerAttributedText>, String> {
-    let config = DiarizationConfig {
-        min_speaker_duration: Duration::from_millis(500),
-        max_speakers: 10,
-        voice_activity_threshold: 0.01,
-        silence_threshold: 0.001,
-    };
+    state: tauri::State<'_, SharedDiarizationState>,
+) -> Result<Option<SpeakerSegment>, String> {
+    let mut engine = state.lock().map_err(|e| e.to_string())?;
+    let engine = engine.as_mut().ok_or("Diarization engine not initialized")?;
+    let segment = engine.process_audio(&audio_samples, sample_rate)?;
+    apply_speaker_merges(&app_handle, &engine.take_merges(), &engine.speakers());
+    Ok(segment)
+}
 
-    let mut engine = DiarizationEngine::new(config).await?;
-    engine.process_audio(&audio_samples, sample_rate).await
+/// Move the stored transcript of merged speakers to the speakers they were merged into,
+/// then emit `speakers_updated`
+pub fn apply_speaker_merges(app_handle: &tauri::AppHandle, merges: &[SpeakerMerge], speakers: &[SpeakerInfo]) {
+    if merges.is_empty() {
+        return;
+    }
+    if let Some(store) = app_handle.try_state::<SharedTranscriptStore>() {
+        if let Ok(mut store) = store.lock() {
+            for merge in merges {
+                store.merge_speaker(&merge.from, &merge.into, &merge.label);
+            }
+        }
+    }
+    emit_event(app_handle, "speakers_updated", speakers);
+}
+
+/// Change the max speakers, minimum speaker duration or overlap threshold mid-session,
+/// leaving the ones not given as they are. Returns the speakers, merged down to a lowered max.
+#[tauri::command]
+pub fn update_diarization_config(
+    app_handle: tauri::AppHandle,
+    max_speakers: Option<usize>,
+    min_speaker_duration_ms: Option<u64>,
+    overlap_threshold: Option<f32>,
+    state: tauri::State<'_, SharedDiarizationState>,
+) -> Result<Vec<SpeakerInfo>, String> {
+    let mut engine = state.lock().map_err(|e| e.to_string())?;
+    let engine = engine.as_mut().ok_or("Diarization engine not initialized")?;
+    engine.update_config(&DiarizationConfigUpdate {
+        max_speakers,
+        min_speaker_duration_ms,
+        overlap_threshold,
+    })?;
+    let speakers = engine.speakers();
+    apply_speaker_merges(&app_handle, &engine.take_merges(), &speakers);
+    Ok(speakers)
+}
+
+/// Tell the diarizer how many people are in the meeting, e.g. from the meeting's participants,
+/// so one voice isn't split into several speakers. None goes back to up to max_speakers.
+#[tauri::command]
+pub fn set_expected_speaker_count(
+    app_handle: tauri::AppHandle,
+    n: Option<usize>,
+    state: tauri::State<'_, SharedDiarizationState>,
+) -> Result<Vec<SpeakerInfo>, String> {
+    if n == Some(0) {
+        return Err("Expected speaker count must be at least 1".to_string());
+    }
+    let mut engine = state.lock().map_err(|e| e.to_string())?;
+    let engine = engine.as_mut().ok_or("Diarization engine not initialized")?;
+    engine.set_expected_speakers(n);
+    let speakers = engine.speakers();
+    apply_speaker_merges(&app_handle, &engine.take_merges(), &speakers);
+    Ok(speakers)
+}
+
+/// Speakers detected so far this session
+#[tauri::command]
+pub fn get_current_speakers(state: tauri::State<'_, SharedDiarizationState>) -> Result<Vec<SpeakerInfo>, String> {
+    let engine = state.lock().map_err(|e| e.to_string())?;
+    let engine = engine.as_ref().ok_or("Diarization engine not initialized")?;
+    Ok(engine.speakers())
+}
+
+/// Talk time, turns and interruptions per speaker this session
+#[tauri::command]
+pub fn get_speaker_stats(state: tauri::State<'_, SharedDiarizationState>) -> Result<SpeakerStatsReport, String> {
+    let engine = state.lock().map_err(|e| e.to_string())?;
+    let engine = engine.as_ref().ok_or("Diarization engine not initialized")?;
+    Ok(engine.speaker_stats())
 }
 
 /// Get example speaker data
 #[tauri::command]
-pub fn get_example_speakers() -> Vec<Speaker> {
-    vec![
+pub fn get_example_speakers() -> Vec<SpeakerInfo> {
+    let speakers = [
         Speaker {
             id: "speaker_1".to_string(),
             label: "Speaker 1".to_string(),
@@ -245,6 +946,7 @@ pub fn get_example_speakers() -> Vec<Speaker> {
             first_detected: Duration::from_secs(0),
             last_active: Duration::from_secs(0),
             message_count: 0,
+            participant: None,
         },
         Speaker {
             id: "speaker_2".to_string(),
@@ -253,6 +955,479 @@ pub fn get_example_speakers() -> Vec<Speaker> {
             first_detected: Duration::from_secs(0),
             last_active: Duration::from_secs(0),
             message_count: 0,
+            participant: None,
+        }
+    ];
+    speakers.iter().map(SpeakerInfo::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The synthetic voices below check the clustering and bookkeeping, not how well the
+    // fingerprint separates real speakers
+    const RATE: u32 = 16_000;
+    /// First two formants of a few vowels, for an adult male vocal tract
+    const VOWELS: [[f32; 2]; 5] = [[700.0, 1200.0], [400.0, 2000.0], [550.0, 1700.0], [300.0, 900.0], [500.0, 1000.0]];
+
+    /// A voice as its pitch and how much shorter its vocal tract is, which raises the formants
+    #[derive(Clone, Copy)]
+    struct Voice {
+        pitch: f32,
+        formant_scale: f32,
+    }
+
+    const LOW_VOICE: Voice = Voice { pitch: 110.0, formant_scale: 1.0 };
+    const HIGH_VOICE: Voice = Voice { pitch: 220.0, formant_scale: 1.2 };
+
+    /// A vowel held for `seconds`: the voice's harmonics with slight vibrato, shaped by the formants
+    fn vowel(voice: Voice, formants: [f32; 2], seconds: f32) -> Vec<f32> {
+        let formants = formants.map(|formant| formant * voice.formant_scale);
+        (0..(seconds * RATE as f32) as usize)
+            .map(|i| {
+                let t = i as f32 / RATE as f32;
+                let pitch = voice.pitch * (1.0 + 0.02 * (t * 5.0 * std::f32::consts::TAU).sin());
+                let harmonics = (4000.0 / pitch) as usize;
+                let sample: f32 = (1..=harmonics)
+                    .map(|h| {
+                        let frequency = pitch * h as f32;
+                        let gain: f32 = formants.iter().map(|formant| 1.0 / (1.0 + ((frequency - formant) / 120.0).powi(2))).sum();
+                        gain * (t * frequency * std::f32::consts::TAU).sin() / h as f32
+                    })
+                    .sum();
+                0.6 * sample
+            })
+            .collect()
+    }
+
+    /// About 2.4 seconds of speech: short vowels in an order picked by `seed`
+    fn utterance(voice: Voice, seed: u32) -> Vec<f32> {
+        let mut state = seed.wrapping_mul(747_796_405).wrapping_add(2_891_336_453);
+        (0..12)
+            .flat_map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                vowel(voice, VOWELS[state as usize % VOWELS.len()], 0.2)
+            })
+            .collect()
+    }
+
+    fn engine(config: DiarizationConfig) -> DiarizationEngine {
+        DiarizationEngine::new(config, SharedSpeakerProfiles::default())
+    }
+
+    /// Attribute one utterance per voice in turn, half a second apart, returning each one's speaker id
+    fn diarize(engine: &mut DiarizationEngine, voices: &[Voice]) -> Vec<String> {
+        let mut start = Duration::ZERO;
+        voices
+            .iter()
+            .enumerate()
+            .map(|(i, voice)| {
+                let samples = utterance(*voice, i as u32 + 1);
+                let attributed = engine.attribute_text(&samples, RATE, "Sounds good.", 0.8, start).unwrap();
+                assert_eq!(attributed[0].confidence, 0.8, "the transcript's confidence is passed on");
+                start += Duration::from_secs_f64(samples.len() as f64 / RATE as f64 + 0.5);
+                attributed[0].speaker.id.clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn two_voices_become_two_speakers() {
+        let mut engine = engine(DiarizationConfig::default());
+        let ids = diarize(&mut engine, &[LOW_VOICE, HIGH_VOICE, LOW_VOICE, HIGH_VOICE, LOW_VOICE, HIGH_VOICE]);
+
+        assert_eq!(engine.speakers().len(), 2);
+        assert_ne!(ids[0], ids[1]);
+        for (i, id) in ids.iter().enumerate() {
+            assert_eq!(id, &ids[i % 2], "turn {} went to the wrong speaker", i);
+        }
+        let stats = engine.speaker_stats();
+        assert_eq!(stats.speakers.len(), 2);
+        assert!(stats.speakers.iter().all(|speaker| speaker.turn_count == 3));
+    }
+
+    #[test]
+    fn speaker_times_follow_the_session_offsets() {
+        let mut engine = engine(DiarizationConfig::default());
+        let mut attribute = |voice: Voice, seed: u32, start: Duration| {
+            let samples = utterance(voice, seed);
+            let attributed = engine.attribute_text(&samples, RATE, "Sounds good.", 0.8, start).unwrap();
+            (attributed[0].timestamp, attributed[0].end)
+        };
+        // Each utterance is 2.4 seconds long
+        assert_eq!(attribute(LOW_VOICE, 1, Duration::ZERO), (Duration::ZERO, Duration::from_millis(2400)));
+        attribute(HIGH_VOICE, 2, Duration::from_secs(3));
+        // A second batch a minute later
+        let minute = Duration::from_secs(60);
+        assert_eq!(attribute(LOW_VOICE, 3, minute), (minute, Duration::from_millis(62_400)));
+        attribute(HIGH_VOICE, 4, Duration::from_secs(63));
+
+        let times: Vec<(u64, u64, usize)> = engine
+            .speakers()
+            .iter()
+            .map(|speaker| (speaker.first_detected_ms, speaker.last_active_ms, speaker.message_count))
+            .collect();
+        assert_eq!(times, [(0, 62_400, 2), (3000, 65_400, 2)]);
+        let stats = engine.speaker_stats();
+        assert_eq!(stats.total_talk_time_ms, 9600);
+        assert!(stats.speakers.iter().all(|speaker| speaker.turn_count == 2));
+    }
+
+    #[test]
+    fn one_voice_stays_one_speaker() {
+        let mut engine = engine(DiarizationConfig::default());
+        let ids = diarize(&mut engine, &[LOW_VOICE; 5]);
+
+        assert_eq!(engine.speakers().len(), 1);
+        assert!(ids.iter().all(|id| id == &ids[0]));
+    }
+
+    #[test]
+    fn expected_speaker_count_caps_new_speakers() {
+        let mut engine = engine(DiarizationConfig {
+            expected_speakers: Some(1),
+            ..DiarizationConfig::default()
+        });
+        diarize(&mut engine, &[LOW_VOICE, HIGH_VOICE, LOW_VOICE]);
+
+        assert_eq!(engine.speakers().len(), 1);
+    }
+
+    /// Add a speaker heard from `start_secs` for `seconds` with the voice `embedding`, returning its id
+    fn heard(engine: &mut DiarizationEngine, embedding: &[f32], start_secs: u64, seconds: u64) -> String {
+        let start = Duration::from_secs(start_secs);
+        let speaker = engine.new_speaker(None, start);
+        engine.voices.entry(speaker.id.clone()).or_default().add(embedding, seconds as f32);
+        engine.record_segment(speaker, start, start + Duration::from_secs(seconds)).id
+    }
+
+    #[test]
+    fn close_voices_are_merged_into_the_one_heard_longer() {
+        let mut engine = engine(DiarizationConfig::default());
+        let short = heard(&mut engine, &[1.0, 0.0, 0.0], 0, 2);
+        let long = heard(&mut engine, &[0.99, 0.1, 0.0], 5, 6);
+        let other = heard(&mut engine, &[0.0, 1.0, 0.0], 20, 3);
+        engine.merge_speakers();
+
+        let merges = engine.take_merges();
+        assert_eq!(merges.len(), 1);
+        assert_eq!((merges[0].from.as_str(), merges[0].into.as_str()), (short.as_str(), long.as_str()));
+        assert_eq!(merges[0].label, "Speaker 2");
+        assert!(engine.take_merges().is_empty(), "merges are handed out once");
+
+        let speakers = engine.speakers();
+        let ids: Vec<&str> = speakers.iter().map(|speaker| speaker.id.as_str()).collect();
+        assert_eq!(ids, [long.as_str(), other.as_str()]);
+        assert_eq!((speakers[0].first_detected_ms, speakers[0].last_active_ms), (0, 11_000));
+        assert_eq!(speakers[0].message_count, 2);
+        let stats = engine.speaker_stats();
+        let merged = stats.speakers.iter().find(|speaker| speaker.speaker_id == long).unwrap();
+        assert_eq!(merged.talk_time_ms, 8000);
+        assert!(!engine.voices.contains_key(&short));
+        assert_eq!(engine.voices[&long].seconds, 8.0);
+    }
+
+    #[test]
+    fn speakers_over_the_limit_merge_the_closest_pair() {
+        let mut engine = engine(DiarizationConfig::default());
+        let first = heard(&mut engine, &[1.0, 0.0, 0.0], 0, 4);
+        let second = heard(&mut engine, &[0.8, 0.6, 0.0], 5, 2);
+        let third = heard(&mut engine, &[0.0, 0.0, 1.0], 10, 3);
+        // Different enough voices stay apart...
+        engine.merge_speakers();
+        assert_eq!(engine.speakers().len(), 3);
+
+        // ...until there are more than the meeting has people
+        engine.set_expected_speakers(Some(2));
+        let merges = engine.take_merges();
+        assert_eq!(merges.len(), 1);
+        assert_eq!((merges[0].from.as_str(), merges[0].into.as_str()), (second.as_str(), first.as_str()));
+        let ids: Vec<String> = engine.speakers().into_iter().map(|speaker| speaker.id).collect();
+        assert_eq!(ids, [first, third]);
+        // Numbers of merged speakers aren't handed out again
+        assert_eq!(heard(&mut engine, &[0.0, 1.0, 0.0], 20, 1), "speaker_4");
+    }
+
+    #[test]
+    fn merged_speaker_keeps_the_participant_name() {
+        let mut engine = engine(DiarizationConfig::default());
+        let named = heard(&mut engine, &[1.0, 0.0], 0, 1);
+        let unnamed = heard(&mut engine, &[0.99, 0.05], 2, 5);
+        // Linked directly, as relabeling would save the voice to the known speakers file
+        let speaker = engine.active_speakers.iter_mut().find(|speaker| speaker.id == named).unwrap();
+        (speaker.label, speaker.participant) = ("Dana".to_string(), Some("Dana".to_string()));
+        engine.merge_speakers();
+
+        let speakers = engine.speakers();
+        assert_eq!(speakers.len(), 1);
+        assert_eq!(speakers[0].id, unnamed);
+        assert_eq!((speakers[0].label.as_str(), speakers[0].participant.as_deref()), ("Dana", Some("Dana")));
+        assert_eq!(engine.take_merges()[0].label, "Dana");
+    }
+
+    /// Have `speaker`, or a new one, say `text` from `start_secs` for `seconds`, with the line in
+    /// `transcript`. Without a voice, as relabeling would save it to the known speakers file.
+    fn spoke(
+        engine: &mut DiarizationEngine,
+        transcript: &mut TranscriptStore,
+        speaker: Option<&str>,
+        text: &str,
+        start_secs: u64,
+        seconds: u64,
+    ) -> String {
+        let (start, end) = (Duration::from_secs(start_secs), Duration::from_secs(start_secs + seconds));
+        let speaker = match speaker {
+            Some(id) => engine.active_speakers.iter().find(|speaker| speaker.id == id).unwrap().clone(),
+            None => engine.new_speaker(None, start),
+        };
+        let speaker = engine.record_segment(speaker, start, end);
+        let segment = crate::whisper::TranscriptSegment {
+            text: text.to_string(),
+            start_ms: 0,
+            end_ms: seconds as i64 * 1000,
+            words: Vec::new(),
+            confidence: 0.9,
+            no_speech_prob: 0.0,
+        };
+        transcript.append(millis(start), &[segment], None);
+        transcript.assign_speaker(millis(start), millis(end), &speaker, None);
+        speaker.id
+    }
+
+    /// Relabel a speaker as the commands do, in the engine and the transcript
+    fn relabel(engine: &mut DiarizationEngine, transcript: &mut TranscriptStore, speaker_id: &str, name: &str, participant: Option<&str>) -> Vec<Speaker> {
+        let unlinked = engine.relabel_speaker(speaker_id, name, participant).unwrap();
+        relabel_transcript(transcript, speaker_id, name, &unlinked);
+        unlinked
+    }
+
+    fn transcript_labels(transcript: &TranscriptStore) -> Vec<Option<String>> {
+        transcript.segments().into_iter().map(|segment| segment.speaker_label).collect()
+    }
+
+    #[test]
+    fn participant_assigned_to_another_speaker_moves_to_it() {
+        let mut engine = engine(DiarizationConfig::default());
+        let mut transcript = TranscriptStore::default();
+        let first = spoke(&mut engine, &mut transcript, None, "I'll take the notes.", 0, 2);
+        let second = spoke(&mut engine, &mut transcript, None, "Thanks Dana.", 3, 2);
+        spoke(&mut engine, &mut transcript, Some(&first), "Sending them after.", 6, 2);
+
+        assert!(relabel(&mut engine, &mut transcript, &first, "Dana", Some("Dana")).is_empty());
+        assert_eq!(transcript_labels(&transcript), [Some("Dana".into()), Some("Speaker 2".into()), Some("Dana".into())]);
+
+        // Dana was the second speaker all along
+        let unlinked = relabel(&mut engine, &mut transcript, &second, "Dana", Some("Dana"));
+        assert_eq!(unlinked.len(), 1);
+        assert_eq!((unlinked[0].id.as_str(), unlinked[0].label.as_str()), (first.as_str(), "Speaker 1"));
+        assert_eq!(unlinked[0].participant, None);
+
+        let speakers: Vec<_> = engine
+            .speakers()
+            .into_iter()
+            .map(|speaker| (speaker.id, speaker.label, speaker.participant))
+            .collect();
+        assert_eq!(
+            speakers,
+            [
+                (first.clone(), "Speaker 1".to_string(), None),
+                (second.clone(), "Dana".to_string(), Some("Dana".to_string())),
+            ]
+        );
+        // Earlier lines follow both speakers
+        assert_eq!(
+            transcript_labels(&transcript),
+            [Some("Speaker 1".into()), Some("Dana".into()), Some("Speaker 1".into())]
+        );
+
+        // A rename without a participant leaves the link alone
+        assert!(relabel(&mut engine, &mut transcript, &first, "Ben", None).is_empty());
+        assert_eq!(engine.speakers()[1].participant.as_deref(), Some("Dana"));
+        assert_eq!(transcript_labels(&transcript)[0].as_deref(), Some("Ben"));
+        assert!(engine.relabel_speaker("speaker_9", "Ana", Some("Ana")).is_err());
+    }
+
+    #[test]
+    fn characteristics_are_tagged_from_the_text() {
+        let engine = engine(DiarizationConfig::default());
+        let long = "we went through the whole rollout plan again today ".repeat(4);
+        let cases: [(&str, &[&str]); 6] = [
+            ("How do we roll this back?", &["short_messages", "asks_questions", "how_questions"]),
+            ("What is the deadline?", &["short_messages", "asks_questions", "what_questions"]),
+            ("Why did the deploy fail?", &["short_messages", "asks_questions", "why_questions"]),
+            ("Thanks, please send me the notes!", &["short_messages", "expressive", "polite"]),
+            (
+                "We should move the launch to next week so the team can finish testing",
+                &["medium_messages"],
+            ),
+            (&long, &["long_messages"]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(engine.detect_speaker_characteristics(text), expected, "for {:?}", text);
         }
-    ]
-}
\ No newline at end of file
+    }
+
+    #[test]
+    fn questions_are_detected() {
+        let engine = engine(DiarizationConfig::default());
+        assert!(engine.detect_question("Can you share the doc?"));
+        assert!(engine.detect_question("When does the sprint end"));
+        assert!(engine.detect_question("  where is the staging link?  "));
+        assert!(!engine.detect_question("I'll share the doc after the call."));
+    }
+
+    #[test]
+    fn silence_is_not_attributed() {
+        let mut engine = engine(DiarizationConfig::default());
+        let attributed = engine.attribute_text(&vec![0.0; RATE as usize], RATE, "", 0.8, Duration::ZERO).unwrap();
+
+        assert!(attributed.is_empty());
+        assert!(engine.speakers().is_empty());
+    }
+
+    #[test]
+    fn raw_audio_is_attributed_without_a_transcript() {
+        let mut engine = engine(DiarizationConfig::default());
+        let segment = engine.process_audio(&utterance(LOW_VOICE, 1), RATE).unwrap().unwrap();
+
+        assert_eq!(segment.speaker_id, "speaker_1");
+        assert_eq!(segment.speaker_label, "Speaker 1");
+        assert!(segment.end_ms > segment.start_ms);
+    }
+
+    fn speaker(id: &str, label: &str) -> Speaker {
+        Speaker {
+            id: id.to_string(),
+            label: label.to_string(),
+            characteristics: vec!["questioning".to_string()],
+            first_detected: Duration::from_millis(1500),
+            last_active: Duration::from_millis(9250),
+            message_count: 3,
+            participant: Some("Dana".to_string()),
+        }
+    }
+
+    #[test]
+    fn speaker_transcript_is_sent_with_millisecond_times() {
+        let attributed = SpeakerAttributedText {
+            speaker: speaker("speaker_1", "Speaker 1"),
+            text: "Can we ship on Friday?".to_string(),
+            timestamp: Duration::from_millis(61_250),
+            end: Duration::from_millis(63_900),
+            confidence: 0.75,
+            is_question: true,
+            overlaps_with: Some(speaker("speaker_2", "Speaker 2")),
+        };
+        let json = serde_json::to_value(SpeakerTranscript::from(&attributed)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "speaker_id": "speaker_1",
+                "speaker_label": "Speaker 1",
+                "text": "Can we ship on Friday?",
+                "start_ms": 61_250,
+                "end_ms": 63_900,
+                "confidence": 0.75,
+                "is_question": true,
+                "overlapping_speaker_id": "speaker_2",
+                "overlapping_speaker_label": "Speaker 2",
+            })
+        );
+    }
+
+    #[test]
+    fn speaker_info_is_sent_with_millisecond_times() {
+        let json = serde_json::to_value(SpeakerInfo::from(&speaker("speaker_1", "Speaker 1"))).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "id": "speaker_1",
+                "label": "Speaker 1",
+                "characteristics": ["questioning"],
+                "first_detected_ms": 1500,
+                "last_active_ms": 9250,
+                "message_count": 3,
+                "participant": "Dana",
+            })
+        );
+    }
+
+    #[test]
+    fn payloads_without_optional_fields_still_parse() {
+        let transcript: SpeakerTranscript = serde_json::from_value(serde_json::json!({
+            "speaker_id": "speaker_1",
+            "speaker_label": "Speaker 1",
+            "text": "Yes.",
+            "start_ms": 0,
+            "end_ms": 400,
+            "confidence": 0.5,
+            "is_question": false,
+        }))
+        .unwrap();
+        assert_eq!(transcript.overlapping_speaker_id, None);
+
+        let info: SpeakerInfo = serde_json::from_value(serde_json::json!({
+            "id": "speaker_1",
+            "label": "Speaker 1",
+            "characteristics": [],
+            "first_detected_ms": 0,
+            "last_active_ms": 0,
+            "message_count": 0,
+        }))
+        .unwrap();
+        assert_eq!(info.participant, None);
+    }
+
+    fn secs(seconds: f32) -> Duration {
+        Duration::from_secs_f32(seconds)
+    }
+
+    #[test]
+    fn back_to_back_turns_add_up() {
+        let mut stats = TalkTimeStats::default();
+        stats.record("a", secs(0.0), secs(4.0), DEFAULT_OVERLAP_THRESHOLD);
+        stats.record("b", secs(4.0), secs(6.0), DEFAULT_OVERLAP_THRESHOLD);
+        stats.record("a", secs(6.0), secs(10.0), DEFAULT_OVERLAP_THRESHOLD);
+        // Still the same turn
+        stats.record("a", secs(10.5), secs(11.5), DEFAULT_OVERLAP_THRESHOLD);
+
+        let report = stats.report(&[speaker("a", "Ana"), speaker("b", "Ben")]);
+        assert_eq!(report.total_talk_time_ms, 11_000);
+        let (ana, ben) = (&report.speakers[0], &report.speakers[1]);
+        assert_eq!((ana.speaker_label.as_str(), ana.talk_time_ms, ana.turn_count), ("Ana", 9000, 2));
+        assert_eq!(ana.average_turn_ms, 4500);
+        assert!((ana.talk_share - 9.0 / 11.0).abs() < 1e-6);
+        assert_eq!((ben.speaker_label.as_str(), ben.talk_time_ms, ben.turn_count), ("Ben", 2000, 1));
+        assert_eq!(ana.interruptions + ben.interruptions, 0);
+    }
+
+    #[test]
+    fn talking_over_the_previous_speaker_is_an_interruption() {
+        let mut stats = TalkTimeStats::default();
+        stats.record("a", secs(0.0), secs(5.0), DEFAULT_OVERLAP_THRESHOLD);
+        // Half of the turn overlaps
+        assert_eq!(stats.overlapped_speaker("b", secs(4.0), secs(6.0), DEFAULT_OVERLAP_THRESHOLD), Some("a"));
+        stats.record("b", secs(4.0), secs(6.0), DEFAULT_OVERLAP_THRESHOLD);
+        // A brief overlap at the hand-over is not
+        stats.record("a", secs(5.9), secs(9.0), DEFAULT_OVERLAP_THRESHOLD);
+        // Nor is continuing one's own turn
+        stats.record("a", secs(8.5), secs(10.0), DEFAULT_OVERLAP_THRESHOLD);
+
+        let report = stats.report(&[]);
+        let interruptions = |id: &str| report.speakers.iter().find(|s| s.speaker_id == id).unwrap().interruptions;
+        assert_eq!(interruptions("b"), 1);
+        assert_eq!(interruptions("a"), 0);
+        // Speakers without a label are reported by id
+        assert_eq!(report.speakers[0].speaker_label, "a");
+    }
+
+    #[test]
+    fn no_speech_reports_nothing() {
+        let report = TalkTimeStats::default().report(&[]);
+        assert_eq!(report.total_talk_time_ms, 0);
+        assert!(report.speakers.is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+//! Events to the UI
+//! Sends events without failing the caller, and reports backend failures as `app_error`
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// Part of the backend a failure came from
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorSource {
+    Llm,
+    Search,
+    /// Loading a Whisper model
+    Model,
+    Diarization,
+    /// Saving and loading files such as recordings and saved meetings
+    Storage,
+}
+
+/// Payload of the `app_error` event, a failure the user should hear about
+#[derive(Debug, Clone, Serialize)]
+pub struct AppError {
+    pub source: ErrorSource,
+    pub message: String,
+    pub at: chrono::DateTime<chrono::Local>,
+}
+
+/// Send `event` to the UI, logging rather than failing if it can't be sent
+pub fn emit_event<S: Serialize + Clone>(app_handle: &AppHandle, event: &str, payload: S) {
+    if let Err(e) = app_handle.emit(event, payload) {
+        eprintln!("Failed to emit {}: {}", event, e);
+    }
+}
+
+/// Log a failure that no command returns and tell the UI with an `app_error` event
+pub fn report_error(app_handle: &AppHandle, source: ErrorSource, message: impl Into<String>) {
+    let message = message.into();
+    eprintln!("{:?} error: {}", source, message);
+    emit_event(
+        app_handle,
+        "app_error",
+        AppError {
+            source,
+            message,
+            at: chrono::Local::now(),
+        },
+    );
+}
@@ -0,0 +1,545 @@
+//! Meeting export
+//! Writes the meeting's details, summary and transcript to a Markdown or PDF file
+
+use crate::diarization::SpeakerStatsReport;
+use crate::insights::{MeetingInsights, MeetingSummary};
+use crate::meeting_context::{ActionItem, MeetingContext};
+use crate::transcript::StoredSegment;
+use chrono::{DateTime, Local};
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// A4, in millimetres
+const PAGE_WIDTH_MM: f32 = 210.0;
+const PAGE_HEIGHT_MM: f32 = 297.0;
+const MARGIN_MM: f32 = 20.0;
+const PT_TO_MM: f32 = 0.3528;
+const LINE_SPACING: f32 = 1.4;
+/// Rough width of a Helvetica character relative to the font size, for wrapping
+const CHAR_WIDTH_EM: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Pdf,
+}
+
+/// Everything that goes into an exported meeting
+#[derive(Debug, Default)]
+pub struct SummaryData {
+    pub context: Option<MeetingContext>,
+    /// From `generate_meeting_summary`
+    pub summary: Option<MeetingSummary>,
+    /// The latest from `get_meeting_insights`, used when there's no summary
+    pub insights: Option<MeetingInsights>,
+    pub segments: Vec<StoredSegment>,
+    pub talk_time: Option<SpeakerStatsReport>,
+    /// The assistant's rolling summary of the discussion, if it has one
+    pub assistant_summary: Option<String>,
+    /// When the export was made
+    pub exported_at: DateTime<Local>,
+}
+
+fn format_ms(ms: u64) -> String {
+    let seconds = ms / 1000;
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// The action items to export: the ones tracked on the meeting, or the summary's or insights'
+/// when there's no meeting
+fn action_items(data: &SummaryData) -> &[ActionItem] {
+    match (&data.context, &data.summary, &data.insights) {
+        (Some(context), _, _) => &context.action_items,
+        (None, Some(summary), _) => &summary.action_items,
+        (None, None, Some(insights)) => &insights.action_items,
+        (None, None, None) => &[],
+    }
+}
+
+/// The meeting as a Markdown document: a header with its details, then the summary (or the
+/// latest insights), action items, the assistant's running summary, key point coverage and talk
+/// time, then the transcript with speakers and times
+pub fn meeting_markdown(data: &SummaryData) -> String {
+    let mut markdown = String::new();
+    match &data.context {
+        Some(context) => {
+            markdown.push_str(&format!("# {}\n\n", context.title));
+            if let Some(description) = &context.description {
+                markdown.push_str(&format!("{}\n\n", description));
+            }
+            let created = context.created_at.with_timezone(&Local);
+            markdown.push_str(&format!("**Date:** {}\n", created.format("%Y-%m-%d %H:%M")));
+            markdown.push_str(&format!("**Domain:** {:?}\n", context.domain));
+            markdown.push_str(&format!("**Planned duration:** {} minutes\n", context.duration_estimate_minutes));
+
+            if !context.participants.is_empty() {
+                markdown.push_str("\n## Participants\n");
+                for participant in &context.participants {
+                    let presence = if participant.is_present { "present" } else { "not present" };
+                    markdown.push_str(&format!("- {} ({}, {})\n", participant.name, participant.role, presence));
+                }
+            }
+            if !context.goals.is_empty() {
+                markdown.push_str("\n## Goals\n");
+                for goal in &context.goals {
+                    markdown.push_str(&format!("- {} ({:?})\n", goal.description, goal.status));
+                }
+            }
+        }
+        None => {
+            markdown.push_str("# Meeting\n\n");
+            markdown.push_str(&format!("**Date:** {}\n", data.exported_at.format("%Y-%m-%d %H:%M")));
+        }
+    }
+
+    // The summary's own title is already in the header, and its sections go one level down.
+    // Its action items are left for the section below, which shows which ones are done.
+    if let Some(summary) = &data.summary {
+        markdown.push_str("\n## Summary\n");
+        let mut in_action_items = false;
+        let mut summary_markdown = String::new();
+        for line in summary.markdown.lines().skip_while(|line| line.starts_with("# ")) {
+            if line.starts_with("## ") {
+                in_action_items = line == "## Action Items";
+            }
+            if in_action_items {
+                continue;
+            }
+            if line.starts_with("## ") {
+                summary_markdown.push('#');
+            }
+            summary_markdown.push_str(line);
+            summary_markdown.push('\n');
+        }
+        markdown.push_str(summary_markdown.trim_end());
+        markdown.push('\n');
+    } else if let Some(insights) = &data.insights {
+        if !insights.decisions.is_empty() {
+            markdown.push_str("\n## Decisions\n");
+            for decision in &insights.decisions {
+                markdown.push_str(&format!("- {}\n", decision.text));
+            }
+        }
+        if !insights.risks.is_empty() {
+            markdown.push_str("\n## Risks\n");
+            for risk in &insights.risks {
+                markdown.push_str(&format!("- {}\n", risk.text));
+            }
+        }
+    }
+
+    let action_items = action_items(data);
+    if !action_items.is_empty() {
+        markdown.push_str("\n## Action Items\n");
+        for item in action_items {
+            let done = if item.done { "x" } else { " " };
+            let owner = item.owner.as_deref().map(|owner| format!(" - {}", owner)).unwrap_or_default();
+            let due = item.due.as_deref().map(|due| format!(", due {}", due)).unwrap_or_default();
+            markdown.push_str(&format!("- [{}] {}{}{}\n", done, item.description, owner, due));
+        }
+    }
+
+    if let Some(assistant_summary) = &data.assistant_summary {
+        markdown.push_str("\n## Running Summary\n");
+        markdown.push_str(assistant_summary.trim());
+        markdown.push('\n');
+    }
+
+    if let Some(context) = data.context.as_ref().filter(|context| !context.key_points_to_cover.is_empty()) {
+        let coverage = context.coverage_report();
+        markdown.push_str(&format!("\n## Key Points Covered ({}/{})\n", coverage.covered, coverage.total));
+        for point in &coverage.points {
+            match point.first_mentioned_ms {
+                Some(ms) => markdown.push_str(&format!("- [x] {} (at {})\n", point.point, format_ms(ms))),
+                None => markdown.push_str(&format!("- [ ] {}\n", point.point)),
+            }
+        }
+    }
+
+    if let Some(talk_time) = data.talk_time.as_ref().filter(|talk_time| !talk_time.speakers.is_empty()) {
+        markdown.push_str("\n## Talk Time\n");
+        for speaker in &talk_time.speakers {
+            markdown.push_str(&format!(
+                "- {}: {} ({:.0}%), {} turn(s)\n",
+                speaker.speaker_label,
+                format_ms(speaker.talk_time_ms),
+                speaker.talk_share * 100.0,
+                speaker.turn_count
+            ));
+        }
+    }
+
+    markdown.push_str("\n## Transcript\n\n");
+    for segment in &data.segments {
+        let time = format_ms(segment.start_ms);
+        let text = segment.text.trim();
+        match &segment.speaker_label {
+            _ if segment.gap => markdown.push_str(&format!("[{}] *{}*\n\n", time, text)),
+            Some(speaker) => match &segment.overlapping_speaker_label {
+                Some(overlapping) => markdown.push_str(&format!(
+                    "[{}] **{}** (talking over {}): {}\n\n",
+                    time, speaker, overlapping, text
+                )),
+                None => markdown.push_str(&format!("[{}] **{}:** {}\n\n", time, speaker, text)),
+            },
+            None => markdown.push_str(&format!("[{}] {}\n\n", time, text)),
+        }
+    }
+    markdown
+}
+
+/// Where an export goes when no path is given: a file named after the time in the app data dir
+pub fn default_export_path(extension: &str) -> Result<PathBuf, String> {
+    let data_dir = dirs::data_local_dir()
+        .ok_or("Could not find local data directory")?;
+    let filename = format!("meeting-{}.{}", Local::now().format("%Y%m%d-%H%M%S"), extension);
+    Ok(data_dir.join("hypergranola").join("exports").join(filename))
+}
+
+/// Lay out a Markdown document as plain text in a PDF, with headings in bold.
+/// The built-in fonts only cover Latin characters.
+pub fn write_pdf(path: &Path, title: &str, markdown: &str) -> Result<(), String> {
+    let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH_MM), Mm(PAGE_HEIGHT_MM), "Layer 1");
+    let regular = doc.add_builtin_font(BuiltinFont::Helvetica).map_err(|e| e.to_string())?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold).map_err(|e| e.to_string())?;
+
+    let mut writer = PdfWriter {
+        layer: doc.get_page(page).get_layer(layer),
+        y: PAGE_HEIGHT_MM - MARGIN_MM,
+    };
+    for line in markdown.lines() {
+        let (text, size, font) = if let Some(heading) = line.strip_prefix("### ") {
+            (heading, 11.0, &bold)
+        } else if let Some(heading) = line.strip_prefix("## ") {
+            (heading, 13.0, &bold)
+        } else if let Some(heading) = line.strip_prefix("# ") {
+            (heading, 16.0, &bold)
+        } else {
+            (line, 10.0, &regular)
+        };
+
+        let text = text.replace("**", "").replace('*', "");
+        if text.trim().is_empty() {
+            writer.skip(&doc, size * PT_TO_MM * 0.5);
+            continue;
+        }
+        let max_chars = ((PAGE_WIDTH_MM - 2.0 * MARGIN_MM) / (size * PT_TO_MM * CHAR_WIDTH_EM)) as usize;
+        for wrapped in wrap(&text, max_chars) {
+            writer.line(&doc, &wrapped, size, font);
+        }
+    }
+
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    doc.save(&mut BufWriter::new(file))
+        .map_err(|e| format!("Failed to write PDF: {}", e))
+}
+
+/// Writes lines top to bottom, starting a new page when one fills up
+struct PdfWriter {
+    layer: PdfLayerReference,
+    /// Baseline of the last line written, from the bottom of the page
+    y: f32,
+}
+
+impl PdfWriter {
+    fn line(&mut self, doc: &PdfDocumentReference, text: &str, size: f32, font: &IndirectFontRef) {
+        self.skip(doc, size * PT_TO_MM * LINE_SPACING);
+        self.layer.use_text(text, size, Mm(MARGIN_MM), Mm(self.y), font);
+    }
+
+    fn skip(&mut self, doc: &PdfDocumentReference, height: f32) {
+        if self.y - height < MARGIN_MM {
+            let (page, layer) = doc.add_page(Mm(PAGE_WIDTH_MM), Mm(PAGE_HEIGHT_MM), "Layer 1");
+            self.layer = doc.get_page(page).get_layer(layer);
+            self.y = PAGE_HEIGHT_MM - MARGIN_MM;
+        }
+        self.y -= height;
+    }
+}
+
+/// Break text into lines of at most `width` characters, at spaces where possible
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        // Words longer than a line are cut
+        while word.len() > width {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            lines.push(word.drain(..width).collect());
+        }
+        let word: String = word.into_iter().collect();
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(&word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diarization::SpeakerStats;
+    use crate::insights::{Decision, Risk};
+    use crate::meeting_context::{GoalStatus, MeetingDomain};
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 3, 2, hour, minute, 0).unwrap()
+    }
+
+    fn segment(start_ms: u64, text: &str, speaker: Option<&str>) -> StoredSegment {
+        StoredSegment {
+            text: text.to_string(),
+            start_ms,
+            end_ms: start_ms + 2000,
+            received_at: at(9, 30),
+            language: Some("en".to_string()),
+            gap: false,
+            speaker_id: speaker.map(|speaker| speaker.to_lowercase()),
+            speaker_label: speaker.map(str::to_string),
+            overlapping_speaker_id: None,
+            overlapping_speaker_label: None,
+        }
+    }
+
+    fn action_item(description: &str, owner: Option<&str>, due: Option<&str>, done: bool) -> ActionItem {
+        ActionItem {
+            id: uuid::Uuid::new_v4(),
+            description: description.to_string(),
+            owner: owner.map(str::to_string),
+            due: due.map(str::to_string),
+            confidence: 0.9,
+            source_quote: None,
+            done,
+        }
+    }
+
+    fn talk_time(label: &str, talk_time_ms: u64, talk_share: f32, turn_count: usize) -> SpeakerStats {
+        SpeakerStats {
+            speaker_id: label.to_lowercase(),
+            speaker_label: label.to_string(),
+            talk_time_ms,
+            talk_share,
+            turn_count,
+            average_turn_ms: talk_time_ms / turn_count as u64,
+            interruptions: 0,
+        }
+    }
+
+    /// A planning meeting that ran for a while, with a summary generated
+    fn finished_meeting() -> SummaryData {
+        let mut context = MeetingContext {
+            title: "Launch planning".to_string(),
+            description: Some("Settle the beta launch".to_string()),
+            domain: MeetingDomain::Technical,
+            duration_estimate_minutes: 30,
+            key_points_to_cover: vec!["Launch date".to_string(), "Support rota".to_string()],
+            created_at: at(9, 30).with_timezone(&chrono::Utc),
+            ..MeetingContext::default()
+        };
+        context.add_participant("Ana".to_string(), "PM".to_string(), None);
+        context.add_participant("Ben".to_string(), "Engineer".to_string(), None);
+        context.participants[0].is_present = true;
+        context.add_goal("Pick a launch date".to_string(), 5);
+        context.add_goal("Staff the support rota".to_string(), 3);
+        context.update_goal_status(0, GoalStatus::Completed).unwrap();
+        context.track_coverage("What launch date works?", 65_000);
+        context.add_action_items(vec![
+            action_item("Book the venue", Some("Ana"), Some("Friday"), true),
+            action_item("Draft the invite", None, None, false),
+        ]);
+
+        let summary: MeetingSummary = serde_json::from_value(serde_json::json!({
+            "title": "Launch planning",
+            "markdown": "# Launch planning\n\nThe beta launches on the 14th.\n\n## Decisions\n- Launch on the 14th\n\n\
+                         ## Action Items\n- Book the venue (owner: Ana, due: Friday)\n- Draft the invite\n\n\
+                         ## Follow-ups\n- Support rota\n",
+        }))
+        .unwrap();
+        let mut overlapped = segment(4000, "Sorry, the 14th?", Some("Ben"));
+        overlapped.overlapping_speaker_label = Some("Ana".to_string());
+        let mut gap = segment(6000, "[audio missing for 12s]", None);
+        gap.gap = true;
+
+        SummaryData {
+            context: Some(context),
+            summary: Some(summary),
+            insights: None,
+            segments: vec![
+                segment(0, "What launch date works? ", Some("Ana")),
+                overlapped,
+                gap,
+                segment(65_000, "Let's go with the 14th.", None),
+            ],
+            talk_time: Some(SpeakerStatsReport {
+                total_talk_time_ms: 100_000,
+                speakers: vec![talk_time("Ana", 75_000, 0.75, 3), talk_time("Ben", 25_000, 0.25, 1)],
+            }),
+            assistant_summary: Some("  Agreed on the 14th.\n".to_string()),
+            exported_at: at(10, 5),
+        }
+    }
+
+    #[test]
+    fn finished_meeting_markdown() {
+        let expected = "\
+# Launch planning
+
+Settle the beta launch
+
+**Date:** 2026-03-02 09:30
+**Domain:** Technical
+**Planned duration:** 30 minutes
+
+## Participants
+- Ana (PM, present)
+- Ben (Engineer, not present)
+
+## Goals
+- Pick a launch date (Completed)
+- Staff the support rota (Pending)
+
+## Summary
+
+The beta launches on the 14th.
+
+### Decisions
+- Launch on the 14th
+
+### Follow-ups
+- Support rota
+
+## Action Items
+- [x] Book the venue - Ana, due Friday
+- [ ] Draft the invite
+
+## Running Summary
+Agreed on the 14th.
+
+## Key Points Covered (1/2)
+- [x] Launch date (at 01:05)
+- [ ] Support rota
+
+## Talk Time
+- Ana: 01:15 (75%), 3 turn(s)
+- Ben: 00:25 (25%), 1 turn(s)
+
+## Transcript
+
+[00:00] **Ana:** What launch date works?
+
+[00:04] **Ben** (talking over Ana): Sorry, the 14th?
+
+[00:06] *[audio missing for 12s]*
+
+[01:05] Let's go with the 14th.
+
+";
+        assert_eq!(meeting_markdown(&finished_meeting()), expected);
+    }
+
+    #[test]
+    fn meeting_without_context_or_summary_uses_the_insights() {
+        let insights = MeetingInsights {
+            action_items: vec![
+                action_item("Book the venue", Some("Ana"), Some("Friday"), false),
+                action_item("Draft the invite", None, None, false),
+            ],
+            decisions: vec![Decision {
+                text: "Hold it in person".to_string(),
+                reasoning: Some("Better turnout".to_string()),
+            }],
+            risks: vec![Risk {
+                text: "Venue may be booked".to_string(),
+                mitigation: None,
+            }],
+        };
+        let data = SummaryData {
+            insights: Some(insights),
+            segments: vec![segment(1000, "Let's book it.", None)],
+            exported_at: at(10, 5),
+            ..SummaryData::default()
+        };
+
+        let expected = "\
+# Meeting
+
+**Date:** 2026-03-02 10:05
+
+## Decisions
+- Hold it in person
+
+## Risks
+- Venue may be booked
+
+## Action Items
+- [ ] Book the venue - Ana, due Friday
+- [ ] Draft the invite
+
+## Transcript
+
+[00:01] Let's book it.
+
+";
+        assert_eq!(meeting_markdown(&data), expected);
+    }
+
+    #[test]
+    fn empty_sections_are_left_out() {
+        let mut data = finished_meeting();
+        data.summary = None;
+        data.assistant_summary = None;
+        data.talk_time = Some(SpeakerStatsReport {
+            total_talk_time_ms: 0,
+            speakers: Vec::new(),
+        });
+        data.segments.clear();
+        if let Some(context) = &mut data.context {
+            context.participants.clear();
+            context.goals.clear();
+            context.key_points_to_cover.clear();
+            context.action_items.clear();
+        }
+
+        assert_eq!(
+            meeting_markdown(&data),
+            "# Launch planning\n\nSettle the beta launch\n\n**Date:** 2026-03-02 09:30\n**Domain:** Technical\n\
+             **Planned duration:** 30 minutes\n\n## Transcript\n\n"
+        );
+    }
+
+    #[test]
+    fn lines_are_wrapped_at_spaces() {
+        assert_eq!(wrap("the quick brown fox jumps", 10), ["the quick", "brown fox", "jumps"]);
+        // Words longer than a line are cut
+        assert_eq!(wrap("a https://example.com/x b", 8), ["a", "https://", "example.", "com/x b"]);
+        assert!(wrap("   ", 10).is_empty());
+        assert_eq!(wrap("abc", 0), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn pdf_is_written() {
+        let path = std::env::temp_dir().join(format!("hypergranola-{}-export.pdf", std::process::id()));
+        let markdown = meeting_markdown(&finished_meeting());
+        write_pdf(&path, "Launch planning", &markdown).unwrap();
+
+        let written = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(written.starts_with(b"%PDF"));
+    }
+}
@@ -0,0 +1,285 @@
+//! HTTP helpers
+//! Retries requests that failed for reasons that may pass, for the LLM and search clients
+
+use serde::Serialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Attempts made for a request before giving up, unless configured otherwise
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// How a request is retried: exponential backoff with jitter, honoring Retry-After
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled for each one after
+    pub base_delay: Duration,
+    /// Longest wait between attempts, also caps a server's Retry-After
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A retry about to happen, the payload of the `llm_retry` event
+#[derive(Debug, Clone, Serialize)]
+pub struct RetryAttempt {
+    /// The attempt about to be made, 2 for the first retry
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub delay_ms: u64,
+    /// Status or error of the failed attempt
+    pub reason: String,
+}
+
+/// Rate limits, request timeouts and server errors are transient. Other statuses, like a
+/// refused key, won't change on retry.
+pub fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::REQUEST_TIMEOUT
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
+}
+
+/// Connection failures and timeouts may pass, errors in building the request won't
+fn is_retryable_error(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout() || e.is_request()
+}
+
+/// A Retry-After value, in seconds or as an HTTP date, as the wait from `now`
+pub fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (at.with_timezone(&chrono::Utc) - now).to_std().ok()
+}
+
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, chrono::Utc::now())
+}
+
+/// A number in [0, 1) that varies between calls, to spread out retries
+fn jitter() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as f64 / 1e9)
+        .unwrap_or(0.5)
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `retry` (0 for the first): the exponential delay scaled
+    /// between half and all of it by `jitter` (0-1), so concurrent requests don't retry in lockstep
+    pub fn backoff(&self, retry: u32, jitter: f64) -> Duration {
+        let delay = self.base_delay.saturating_mul(1 << retry.min(16)).min(self.max_delay);
+        delay.mul_f64(0.5 + jitter.clamp(0.0, 1.0) / 2.0)
+    }
+
+    /// Send `request`, retrying transient failures (network errors, timeouts, 408, 429 and 5xx).
+    /// `on_retry` is told before each wait. The last response or error is returned once
+    /// attempts run out.
+    pub async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        mut on_retry: impl FnMut(&RetryAttempt),
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let mut retry = 0;
+        loop {
+            // Bodies that can't be cloned (streams) can only be sent once
+            let result = match request.try_clone() {
+                Some(request) => request.send().await,
+                None => return request.send().await,
+            };
+
+            let (delay, reason) = match &result {
+                Ok(response) if is_retryable_status(response.status()) => (
+                    retry_after(response).unwrap_or_else(|| self.backoff(retry, jitter())),
+                    response.status().to_string(),
+                ),
+                Err(e) if is_retryable_error(e) => (self.backoff(retry, jitter()), e.to_string()),
+                _ => return result,
+            };
+
+            retry += 1;
+            if retry >= self.max_attempts {
+                return result;
+            }
+            let delay = delay.min(self.max_delay);
+            on_retry(&RetryAttempt {
+                attempt: retry + 1,
+                max_attempts: self.max_attempts,
+                delay_ms: delay.as_millis() as u64,
+                reason,
+            });
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Waits short enough for tests
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(20),
+        }
+    }
+
+    /// A local server answering each request with the next of `responses` (status line and
+    /// headers), closing the connection after each. Returns its URL and how many requests it got.
+    async fn mock_server(responses: &[&str]) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let responses: Vec<String> = responses.iter().map(|response| response.to_string()).collect();
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                // Requests are GETs without a body, so the headers end them
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match socket.read(&mut buffer).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response = format!("{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", response);
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        (url, requests)
+    }
+
+    /// Send a GET to `url` under `policy`, returning the status and the retries announced
+    async fn send(policy: RetryPolicy, url: &str) -> (Result<u16, String>, Vec<RetryAttempt>) {
+        let mut retries = Vec::new();
+        let result = policy
+            .send(reqwest::Client::new().get(url), |attempt| retries.push(attempt.clone()))
+            .await
+            .map(|response| response.status().as_u16())
+            .map_err(|e| e.to_string());
+        (result, retries)
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0, 1.0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1, 1.0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3, 1.0), Duration::from_secs(4));
+        assert_eq!(policy.backoff(10, 1.0), Duration::from_secs(30));
+        // Jitter takes off up to half
+        assert_eq!(policy.backoff(0, 0.0), Duration::from_millis(250));
+        assert_eq!(policy.backoff(1, 0.5), Duration::from_millis(750));
+        assert_eq!(policy.backoff(0, -3.0), Duration::from_millis(250));
+        assert_eq!(policy.backoff(0, 7.0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(u32::MAX, 1.0), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn retry_after_is_read_as_seconds_or_a_date() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-03-02T09:30:00Z").unwrap().with_timezone(&chrono::Utc);
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("Mon, 02 Mar 2026 09:30:45 GMT", now), Some(Duration::from_secs(45)));
+        // A date already passed, or something else
+        assert_eq!(parse_retry_after("Mon, 02 Mar 2026 09:29:00 GMT", now), None);
+        assert_eq!(parse_retry_after("soon", now), None);
+        assert_eq!(parse_retry_after("-5", now), None);
+    }
+
+    #[test]
+    fn only_transient_statuses_are_retried() {
+        for status in [408, 429, 500, 502, 503, 504] {
+            assert!(is_retryable_status(reqwest::StatusCode::from_u16(status).unwrap()), "{}", status);
+        }
+        for status in [200, 400, 401, 403, 404, 422] {
+            assert!(!is_retryable_status(reqwest::StatusCode::from_u16(status).unwrap()), "{}", status);
+        }
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_until_one_succeeds() {
+        let (url, requests) =
+            mock_server(&["HTTP/1.1 503 Service Unavailable", "HTTP/1.1 502 Bad Gateway", "HTTP/1.1 200 OK"]).await;
+        let (result, retries) = send(fast_policy(3), &url).await;
+
+        assert_eq!(result, Ok(200));
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        let announced: Vec<(u32, u32, &str)> = retries
+            .iter()
+            .map(|retry| (retry.attempt, retry.max_attempts, retry.reason.as_str()))
+            .collect();
+        assert_eq!(announced, [(2, 3, "503 Service Unavailable"), (3, 3, "502 Bad Gateway")]);
+    }
+
+    #[tokio::test]
+    async fn last_response_is_returned_once_attempts_run_out() {
+        let (url, requests) = mock_server(&["HTTP/1.1 500 Internal Server Error"; 3]).await;
+        let (result, retries) = send(fast_policy(2), &url).await;
+
+        assert_eq!(result, Ok(500));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert_eq!(retries.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn refused_key_is_not_retried() {
+        let (url, requests) = mock_server(&["HTTP/1.1 401 Unauthorized", "HTTP/1.1 200 OK"]).await;
+        let (result, retries) = send(fast_policy(3), &url).await;
+
+        assert_eq!(result, Ok(401));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert!(retries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn retry_after_is_honored_up_to_the_longest_wait() {
+        let (url, _) = mock_server(&[
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0",
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 120",
+            "HTTP/1.1 200 OK",
+        ])
+        .await;
+        let (result, retries) = send(fast_policy(3), &url).await;
+
+        assert_eq!(result, Ok(200));
+        let delays: Vec<u64> = retries.iter().map(|retry| retry.delay_ms).collect();
+        assert_eq!(delays, [0, 20]);
+    }
+
+    #[tokio::test]
+    async fn connection_failures_are_retried() {
+        // A port nothing listens on any more
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+
+        let (result, retries) = send(fast_policy(2), &url).await;
+        assert!(result.is_err());
+        assert_eq!(retries.len(), 1);
+        assert_eq!(retries[0].attempt, 2);
+    }
+}
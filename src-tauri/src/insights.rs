@@ -0,0 +1,750 @@
+//! Structured meeting insights
+//! Asks the LLM for action items, decisions and risks as JSON instead of markdown.
+//! Transcripts too long for one request are sent in chunks and the results merged.
+
+use crate::chunking;
+use crate::llm::{ChatMessage, CompletionOptions, LlmClient};
+use crate::meeting_context::{self, ActionItem, MeetingContext};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Something the meeting settled on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Decision {
+    pub text: String,
+    #[serde(default)]
+    pub reasoning: Option<String>,
+}
+
+/// An issue raised that could get in the way
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Risk {
+    pub text: String,
+    #[serde(default)]
+    pub mitigation: Option<String>,
+}
+
+/// Payload of the `meeting_insights` event. Its action items are also tracked on the meeting.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MeetingInsights {
+    #[serde(default)]
+    pub action_items: Vec<ActionItem>,
+    #[serde(default)]
+    pub decisions: Vec<Decision>,
+    #[serde(default)]
+    pub risks: Vec<Risk>,
+}
+
+/// The insights last extracted this session, kept for exports
+pub type SharedLatestInsights = Arc<Mutex<Option<MeetingInsights>>>;
+
+const INSIGHTS_INSTRUCTIONS: &str = r#"Extract the action items, decisions and risks from the meeting transcript above.
+
+Respond with ONLY a JSON object in exactly this shape, with no markdown or commentary:
+{
+  "action_items": [{"description": "...", "owner": "name or null", "due": "timeframe or null", "confidence": 0.0-1.0, "source_quote": "what was said, word for word"}],
+  "decisions": [{"text": "...", "reasoning": "... or null"}],
+  "risks": [{"text": "...", "mitigation": "... or null"}]
+}
+
+Use empty arrays when there is nothing to report. Never invent owners or dates that weren't mentioned."#;
+
+/// Tokens of transcript sent in one request, leaving the rest of the context window for the
+/// meeting context, the instructions and the reply
+fn chunk_tokens(llm: &LlmClient) -> usize {
+    llm.context_window() / 2
+}
+
+/// One prompt for each chunk of `text` of at most `chunk_tokens` tokens, under `heading` and
+/// with the meeting context and `instructions`
+fn chunk_prompts(
+    heading: &str,
+    text: &str,
+    meeting_context: Option<&MeetingContext>,
+    instructions: &str,
+    chunk_tokens: usize,
+) -> Vec<String> {
+    let chunks = chunking::chunk_text(text, chunk_tokens);
+    let count = chunks.len();
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut prompt_parts = Vec::new();
+            if let Some(context) = meeting_context {
+                prompt_parts.push(format!("Meeting Context:\n{}", context.get_context_summary()));
+            }
+            if count == 1 {
+                prompt_parts.push(format!("{}:\n{}", heading, chunk));
+            } else {
+                prompt_parts.push(format!("{} (part {} of {}):\n{}", heading, i + 1, count, chunk));
+            }
+            prompt_parts.push(instructions.to_string());
+            prompt_parts.join("\n\n")
+        })
+        .collect()
+}
+
+/// The JSON object in the model's reply, tolerating a markdown code fence or text around it.
+/// A reply that opens with an array instead, as some models answer when asked for an object
+/// holding one, gives the array.
+fn json_object(reply: &str) -> Result<&str, String> {
+    let start = reply.find(['{', '[']).ok_or("LLM reply contains no JSON object")?;
+    let close = if reply[start..].starts_with('{') { '}' } else { ']' };
+    let end = reply.rfind(close).filter(|end| *end > start).ok_or("LLM reply contains no JSON object")?;
+    Ok(&reply[start..=end])
+}
+
+/// Insights as the LLM reports them, before the action items are validated
+#[derive(Deserialize)]
+struct InsightsReply {
+    #[serde(default)]
+    action_items: Vec<ExtractedActionItem>,
+    #[serde(default)]
+    decisions: Vec<Decision>,
+    #[serde(default)]
+    risks: Vec<Risk>,
+}
+
+fn parse_insights(reply: &str, meeting_context: Option<&MeetingContext>) -> Result<MeetingInsights, String> {
+    let reply: InsightsReply =
+        serde_json::from_str(json_object(reply)?).map_err(|e| format!("Failed to parse meeting insights: {}", e))?;
+    Ok(MeetingInsights {
+        action_items: tracked_action_items(validate_action_items(reply.action_items), meeting_context),
+        decisions: reply.decisions,
+        risks: reply.risks,
+    })
+}
+
+/// Add the insights from one chunk of a transcript to those from the chunks before it
+fn merge_insights(insights: &mut MeetingInsights, chunk: MeetingInsights) {
+    meeting_context::merge_action_items(&mut insights.action_items, chunk.action_items);
+    for decision in chunk.decisions {
+        if !insights.decisions.iter().any(|existing| existing.text.eq_ignore_ascii_case(&decision.text)) {
+            insights.decisions.push(decision);
+        }
+    }
+    for risk in chunk.risks {
+        if !insights.risks.iter().any(|existing| existing.text.eq_ignore_ascii_case(&risk.text)) {
+            insights.risks.push(risk);
+        }
+    }
+}
+
+/// Extract structured insights from a transcript
+pub async fn extract_insights(
+    llm: &LlmClient,
+    transcript: &str,
+    meeting_context: Option<&MeetingContext>,
+) -> Result<MeetingInsights, String> {
+    println!("Extracting meeting insights via: {} (Model: {})", llm.api_url(), llm.model());
+
+    let options = CompletionOptions {
+        temperature: Some(0.2),
+        json: true,
+        ..Default::default()
+    };
+    let mut insights = MeetingInsights::default();
+    let chunk_tokens = chunk_tokens(llm);
+    for prompt in chunk_prompts("Meeting Transcript", transcript, meeting_context, INSIGHTS_INSTRUCTIONS, chunk_tokens) {
+        let reply = llm
+            .complete(&[ChatMessage::user(prompt)], options)
+            .await?
+            .ok_or("LLM returned no insights")?;
+        merge_insights(&mut insights, parse_insights(&reply, meeting_context)?);
+    }
+    Ok(insights)
+}
+
+/// Whether the transcript suggests a meeting goal was addressed, sent in the `goal_progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalProgress {
+    /// Index of the goal in the meeting context
+    pub index: usize,
+    #[serde(default)]
+    pub description: String,
+    pub addressed: bool,
+    /// What in the transcript shows it, if addressed
+    #[serde(default)]
+    pub evidence: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GoalProgressReply {
+    #[serde(default)]
+    goals: Vec<GoalProgress>,
+}
+
+const GOAL_PROGRESS_INSTRUCTIONS: &str = r#"For each meeting goal above, decide whether the transcript shows it has been addressed.
+
+Respond with ONLY a JSON object in exactly this shape, with no markdown or commentary:
+{"goals": [{"index": 0, "addressed": true, "evidence": "short quote or summary, or null"}]}
+
+Include every goal by its index. Only mark a goal addressed when the transcript clearly covers it."#;
+
+/// Ask the LLM which of the meeting's goals the transcript has addressed.
+/// Only suggests progress, goal statuses are left for the user to update.
+pub async fn check_goal_progress(
+    llm: &LlmClient,
+    transcript: &str,
+    meeting_context: &MeetingContext,
+) -> Result<Vec<GoalProgress>, String> {
+    if meeting_context.goals.is_empty() {
+        return Ok(Vec::new());
+    }
+    println!("Checking goal progress via: {} (Model: {})", llm.api_url(), llm.model());
+
+    let goals: Vec<String> = meeting_context
+        .goals
+        .iter()
+        .enumerate()
+        .map(|(index, goal)| format!("{}. {} (status: {:?})", index, goal.description, goal.status))
+        .collect();
+    let prompt = format!(
+        "Meeting Goals:\n{}\n\nMeeting Transcript:\n{}\n\n{}",
+        goals.join("\n"),
+        transcript,
+        GOAL_PROGRESS_INSTRUCTIONS
+    );
+
+    let options = CompletionOptions {
+        temperature: Some(0.2),
+        json: true,
+        ..Default::default()
+    };
+    let reply = llm
+        .complete(&[ChatMessage::user(prompt)], options)
+        .await?
+        .ok_or("LLM returned no goal progress")?;
+    let reply: GoalProgressReply =
+        serde_json::from_str(json_object(&reply)?).map_err(|e| format!("Failed to parse goal progress: {}", e))?;
+
+    // Drop goals the model made up, and fill in descriptions from the context
+    Ok(reply
+        .goals
+        .into_iter()
+        .filter_map(|mut progress| {
+            let goal = meeting_context.goals.get(progress.index)?;
+            progress.description = goal.description.clone();
+            Some(progress)
+        })
+        .collect())
+}
+
+/// A question suggested by the LLM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedQuestion {
+    pub question: String,
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub priority: u8,
+}
+
+#[derive(Deserialize)]
+struct GeneratedQuestionsReply {
+    #[serde(default)]
+    questions: Vec<GeneratedQuestion>,
+}
+
+const QUESTION_GENERATION_INSTRUCTIONS: &str = r#"Suggest questions worth asking in this meeting, so every goal gets covered.
+
+Respond with ONLY a JSON object in exactly this shape, with no markdown or commentary:
+{"questions": [{"question": "...", "category": "clarification|follow-up|technical|discovery|decision", "priority": 1-5}]}
+
+Suggest 3 to 8 questions, most important first. Don't repeat the questions already planned."#;
+
+/// Ask the LLM for questions to ask, based on the meeting's context and goals
+pub async fn generate_questions(llm: &LlmClient, meeting_context: &MeetingContext) -> Result<Vec<GeneratedQuestion>, String> {
+    println!("Generating meeting questions via: {} (Model: {})", llm.api_url(), llm.model());
+
+    let planned: Vec<&str> = meeting_context
+        .pre_generated_questions
+        .iter()
+        .map(|question| question.question.as_str())
+        .collect();
+    let prompt = format!(
+        "Meeting Context:\n{}\nPlanned Questions:\n{}\n\n{}",
+        meeting_context.get_context_summary(),
+        if planned.is_empty() { "None".to_string() } else { planned.join("\n") },
+        QUESTION_GENERATION_INSTRUCTIONS
+    );
+
+    let options = CompletionOptions {
+        temperature: Some(0.5),
+        json: true,
+        ..Default::default()
+    };
+    let reply = llm
+        .complete(&[ChatMessage::user(prompt)], options)
+        .await?
+        .ok_or("LLM returned no questions")?;
+    let reply: GeneratedQuestionsReply =
+        serde_json::from_str(json_object(&reply)?).map_err(|e| format!("Failed to parse questions: {}", e))?;
+    Ok(reply
+        .questions
+        .into_iter()
+        .map(|mut question| {
+            question.priority = question.priority.clamp(1, 5);
+            question
+        })
+        .collect())
+}
+
+/// Final summary of a meeting, for sharing once it's over
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeetingSummary {
+    #[serde(default)]
+    pub title: String,
+    /// A few sentences on what the meeting was about and how it went
+    #[serde(default)]
+    pub overview: String,
+    #[serde(default)]
+    pub attendees: Vec<String>,
+    #[serde(default)]
+    pub key_points: Vec<String>,
+    #[serde(default)]
+    pub decisions: Vec<Decision>,
+    #[serde(default)]
+    pub action_items: Vec<ActionItem>,
+    /// Topics to pick up in a later meeting
+    #[serde(default)]
+    pub follow_ups: Vec<String>,
+    #[serde(default)]
+    pub open_questions: Vec<String>,
+    /// All of the above as markdown, ready to paste into an email or document
+    #[serde(default)]
+    pub markdown: String,
+}
+
+impl MeetingSummary {
+    fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\n{}\n", self.title, self.overview);
+        let mut section = |heading: &str, items: Vec<String>| {
+            if !items.is_empty() {
+                markdown.push_str(&format!("\n## {}\n", heading));
+                for item in items {
+                    markdown.push_str(&format!("- {}\n", item));
+                }
+            }
+        };
+        section("Attendees", self.attendees.clone());
+        section("Key Points", self.key_points.clone());
+        section(
+            "Decisions",
+            self.decisions
+                .iter()
+                .map(|decision| match &decision.reasoning {
+                    Some(reasoning) => format!("{} ({})", decision.text, reasoning),
+                    None => decision.text.clone(),
+                })
+                .collect(),
+        );
+        section(
+            "Action Items",
+            self.action_items
+                .iter()
+                .map(|item| {
+                    let mut line = item.description.clone();
+                    if let Some(owner) = &item.owner {
+                        line.push_str(&format!(" - {}", owner));
+                    }
+                    if let Some(due) = &item.due {
+                        line.push_str(&format!(", due {}", due));
+                    }
+                    line
+                })
+                .collect(),
+        );
+        section("Follow-ups", self.follow_ups.clone());
+        section("Open Questions", self.open_questions.clone());
+        markdown
+    }
+}
+
+const SUMMARY_INSTRUCTIONS: &str = r#"Write the final summary of the meeting above, for sharing with the attendees.
+
+Respond with ONLY a JSON object in exactly this shape, with no markdown or commentary:
+{
+  "title": "short title of the meeting",
+  "overview": "2-4 sentences on what was discussed and the outcome",
+  "attendees": ["names of the people who spoke or were present"],
+  "key_points": ["..."],
+  "decisions": [{"text": "...", "reasoning": "... or null"}],
+  "action_items": [{"description": "...", "owner": "name or null", "due": "timeframe or null", "confidence": 0.0-1.0, "source_quote": "what was said, word for word"}],
+  "follow_ups": ["topics to pick up in a later meeting"],
+  "open_questions": ["questions raised but not answered"]
+}
+
+Use empty arrays when there is nothing to report. Never invent names, owners or dates that weren't mentioned."#;
+
+/// A summary as the LLM reports it, before the action items are validated
+#[derive(Deserialize)]
+struct SummaryReply {
+    #[serde(default)]
+    action_items: Vec<ExtractedActionItem>,
+    #[serde(flatten)]
+    summary: MeetingSummary,
+}
+
+const SUMMARY_NOTES_INSTRUCTIONS: &str = "Take notes on this part of the meeting: topics discussed, decisions, action items with owners and due dates, follow-ups and open questions. Return ONLY the notes as short bullet points.";
+
+/// Condense a transcript too long for one request into notes on each chunk, in rounds until the
+/// notes fit. Each chunk's notes are held to a quarter of its length so every round shrinks.
+/// Returns the heading and text to summarize.
+async fn summary_source(
+    llm: &LlmClient,
+    transcript: &str,
+    meeting_context: Option<&MeetingContext>,
+    chunk_tokens: usize,
+) -> Result<(&'static str, String), String> {
+    let mut heading = "Meeting Transcript";
+    let mut text = transcript.trim().to_string();
+    while chunking::estimate_tokens(&text) > chunk_tokens {
+        let prompts = chunk_prompts(heading, &text, meeting_context, SUMMARY_NOTES_INSTRUCTIONS, chunk_tokens);
+        println!("Meeting too long to summarize at once, taking notes on {} parts", prompts.len());
+        let options = CompletionOptions {
+            max_tokens: Some((chunk_tokens / 4).max(1) as u32),
+            temperature: Some(0.2),
+            ..Default::default()
+        };
+        let mut notes = Vec::new();
+        for prompt in prompts {
+            if let Some(content) = llm.complete(&[ChatMessage::user(prompt)], options).await? {
+                notes.push(content.trim().to_string());
+            }
+        }
+        heading = "Notes on the Meeting, in order";
+        text = notes.join("\n\n");
+    }
+    Ok((heading, text))
+}
+
+/// Summarize a whole meeting, once it's over
+pub async fn generate_summary(
+    llm: &LlmClient,
+    transcript: &str,
+    meeting_context: Option<&MeetingContext>,
+) -> Result<MeetingSummary, String> {
+    println!("Generating meeting summary via: {} (Model: {})", llm.api_url(), llm.model());
+
+    let chunk_tokens = chunk_tokens(llm);
+    let (heading, text) = summary_source(llm, transcript, meeting_context, chunk_tokens).await?;
+    let prompt = chunk_prompts(heading, &text, meeting_context, SUMMARY_INSTRUCTIONS, chunk_tokens)
+        .pop()
+        .ok_or("No transcript to summarize")?;
+
+    let options = CompletionOptions {
+        temperature: Some(0.3),
+        json: true,
+        ..Default::default()
+    };
+    let reply = llm
+        .complete(&[ChatMessage::user(prompt)], options)
+        .await?
+        .ok_or("LLM returned no summary")?;
+    let reply: SummaryReply =
+        serde_json::from_str(json_object(&reply)?).map_err(|e| format!("Failed to parse meeting summary: {}", e))?;
+    let mut summary = reply.summary;
+    summary.action_items = tracked_action_items(validate_action_items(reply.action_items), meeting_context);
+
+    // The meeting's own title and roster are more reliable than the model's guess
+    if let Some(context) = meeting_context {
+        summary.title = context.title.clone();
+        if summary.attendees.is_empty() {
+            summary.attendees = context
+                .participants
+                .iter()
+                .filter(|participant| participant.is_present)
+                .map(|participant| participant.name.clone())
+                .collect();
+        }
+    }
+    summary.markdown = summary.to_markdown();
+    Ok(summary)
+}
+
+/// An action item as the LLM reports it, before it's validated
+#[derive(Debug, Deserialize)]
+struct ExtractedActionItem {
+    #[serde(default, alias = "text")]
+    description: String,
+    #[serde(default)]
+    owner: Option<String>,
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    confidence: Option<f32>,
+    #[serde(default)]
+    source_quote: Option<String>,
+}
+
+/// The reply as asked for, or the bare array some models answer with
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ActionItemsReply {
+    Object { action_items: Vec<ExtractedActionItem> },
+    List(Vec<ExtractedActionItem>),
+}
+
+/// Confidence given to action items the model didn't rate
+const DEFAULT_ACTION_ITEM_CONFIDENCE: f32 = 0.5;
+
+const ACTION_ITEMS_SCHEMA: &str = r#"{"action_items": [{"description": "what will be done", "owner": "name or null", "due": "timeframe or null", "confidence": 0.0-1.0, "source_quote": "what was said, word for word"}]}"#;
+
+const ACTION_ITEMS_INSTRUCTIONS: &str = r#"List the action items in the meeting transcript above: tasks someone committed to or was asked to do.
+
+Respond with ONLY a JSON object in exactly this shape, with no markdown or commentary:"#;
+
+const ACTION_ITEMS_RULES: &str = r#"Use an empty array when there are none. Use the participants' names for owners where they match. Never invent owners or dates that weren't mentioned. Confidence is how clearly the task was agreed on."#;
+
+/// A text field, None when blank or a spelled out null
+fn clean_field(field: Option<String>) -> Option<String> {
+    let field = field?.trim().to_string();
+    let empty = field.is_empty() || field.eq_ignore_ascii_case("null") || field.eq_ignore_ascii_case("none");
+    (!empty).then_some(field)
+}
+
+/// Parse and validate the action items in a reply
+fn parse_action_items(reply: &str) -> Result<Vec<ExtractedActionItem>, String> {
+    let reply: ActionItemsReply =
+        serde_json::from_str(json_object(reply)?).map_err(|e| format!("Failed to parse action items: {}", e))?;
+    let items = match reply {
+        ActionItemsReply::Object { action_items } => action_items,
+        ActionItemsReply::List(items) => items,
+    };
+    Ok(validate_action_items(items))
+}
+
+/// Drop items without a description, clear blank fields and keep confidence between 0 and 1
+fn validate_action_items(items: Vec<ExtractedActionItem>) -> Vec<ExtractedActionItem> {
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let description = item.description.trim().to_string();
+            if description.is_empty() {
+                return None;
+            }
+            let confidence = item
+                .confidence
+                .filter(|confidence| confidence.is_finite())
+                .unwrap_or(DEFAULT_ACTION_ITEM_CONFIDENCE)
+                .clamp(0.0, 1.0);
+            Some(ExtractedActionItem {
+                description,
+                owner: clean_field(item.owner),
+                due: clean_field(item.due),
+                confidence: Some(confidence),
+                source_quote: clean_field(item.source_quote),
+            })
+        })
+        .collect()
+}
+
+/// Validated items as action items to track, with owners matched to the meeting's participants
+fn tracked_action_items(items: Vec<ExtractedActionItem>, meeting_context: Option<&MeetingContext>) -> Vec<ActionItem> {
+    items
+        .into_iter()
+        .map(|item| {
+            let owner = item.owner.map(|owner| {
+                match meeting_context.and_then(|context| context.match_participant(&owner)) {
+                    Some(participant) => participant.name.clone(),
+                    None => owner,
+                }
+            });
+            ActionItem {
+                id: Uuid::new_v4(),
+                description: item.description,
+                owner,
+                due: item.due,
+                confidence: item.confidence.unwrap_or(DEFAULT_ACTION_ITEM_CONFIDENCE),
+                source_quote: item.source_quote,
+                done: false,
+            }
+        })
+        .collect()
+}
+
+/// Ask the model to fix a reply that didn't parse
+fn repair_prompt(reply: &str, error: &str) -> String {
+    format!(
+        "This reply was meant to be JSON in exactly this shape:\n{}\n\nIt failed to parse: {}\n\nReply:\n{}\n\nRespond with ONLY the corrected JSON object, keeping its content, with no markdown or commentary.",
+        ACTION_ITEMS_SCHEMA, error, reply
+    )
+}
+
+/// Extract the action items in a transcript, with owners matched to the meeting's participants.
+/// A reply that doesn't parse is sent back once to be repaired.
+pub async fn extract_action_items(
+    llm: &LlmClient,
+    transcript: &str,
+    meeting_context: Option<&MeetingContext>,
+) -> Result<Vec<ActionItem>, String> {
+    println!("Extracting action items via: {} (Model: {})", llm.api_url(), llm.model());
+
+    let options = CompletionOptions {
+        temperature: Some(0.2),
+        json: true,
+        ..Default::default()
+    };
+    let instructions = format!("{}\n{}\n\n{}", ACTION_ITEMS_INSTRUCTIONS, ACTION_ITEMS_SCHEMA, ACTION_ITEMS_RULES);
+    let mut action_items = Vec::new();
+    for prompt in chunk_prompts("Meeting Transcript", transcript, meeting_context, &instructions, chunk_tokens(llm)) {
+        let reply = llm
+            .complete(&[ChatMessage::user(prompt)], options)
+            .await?
+            .ok_or("LLM returned no action items")?;
+        let items = match parse_action_items(&reply) {
+            Ok(items) => items,
+            Err(e) => {
+                println!("{}, asking the LLM to repair its reply", e);
+                let repaired = llm
+                    .complete(&[ChatMessage::user(repair_prompt(&reply, &e))], options)
+                    .await?
+                    .ok_or("LLM returned no action items")?;
+                parse_action_items(&repaired)?
+            }
+        };
+        meeting_context::merge_action_items(&mut action_items, tracked_action_items(items, meeting_context));
+    }
+    Ok(action_items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEMS: &str = r#"{"action_items": [
+        {"description": "Send the deck", "owner": "Ana", "due": "Friday", "source_quote": "I'll send the deck by Friday"}
+    ]}"#;
+
+    fn descriptions(items: &[ExtractedActionItem]) -> Vec<&str> {
+        items.iter().map(|item| item.description.as_str()).collect()
+    }
+
+    #[test]
+    fn json_is_found_around_fences_and_commentary() {
+        let replies = [
+            ITEMS.to_string(),
+            format!("```json\n{}\n```", ITEMS),
+            format!("Here are the action items:\n\n{}\n\nLet me know if you need more.", ITEMS),
+            // Some models answer with the bare array
+            r#"[{"description": "Send the deck", "owner": "Ana"}]"#.to_string(),
+        ];
+        for reply in &replies {
+            let items = parse_action_items(reply).unwrap_or_else(|e| panic!("{}: {}", e, reply));
+            assert_eq!(descriptions(&items), ["Send the deck"]);
+            assert_eq!(items[0].owner.as_deref(), Some("Ana"));
+        }
+        assert!(parse_action_items(r#"{"action_items": []}"#).unwrap().is_empty());
+    }
+
+    #[test]
+    fn long_transcript_is_sent_in_bounded_chunks() {
+        let transcript = (0..400).map(|i| format!("Ana: Item {} is on track.", i)).collect::<Vec<_>>().join("\n");
+        let prompts = chunk_prompts("Meeting Transcript", &transcript, None, INSIGHTS_INSTRUCTIONS, 200);
+
+        assert!(prompts.len() > 1);
+        let limit = 200 + chunking::estimate_tokens(INSIGHTS_INSTRUCTIONS) + 20;
+        for (i, prompt) in prompts.iter().enumerate() {
+            assert!(prompt.starts_with(&format!("Meeting Transcript (part {} of {}):\n", i + 1, prompts.len())));
+            assert!(prompt.ends_with(INSIGHTS_INSTRUCTIONS));
+            assert!(chunking::estimate_tokens(prompt) <= limit, "{} tokens", chunking::estimate_tokens(prompt));
+        }
+        // Every line is sent once
+        let sent: usize = prompts.iter().map(|prompt| prompt.matches("is on track").count()).sum();
+        assert_eq!(sent, 400);
+
+        let prompts = chunk_prompts("Meeting Transcript", "Ana: Short one.", None, INSIGHTS_INSTRUCTIONS, 200);
+        assert_eq!(prompts.len(), 1);
+        assert!(prompts[0].starts_with("Meeting Transcript:\nAna: Short one.\n\n"));
+    }
+
+    #[test]
+    fn insights_from_each_chunk_are_merged() {
+        let mut insights = parse_insights(
+            r#"{"action_items": [{"description": "Book the venue"}], "decisions": [{"text": "Meet in person"}]}"#,
+            None,
+        )
+        .unwrap();
+        let chunk = parse_insights(
+            r#"{"action_items": [{"description": "Book the venue"}, {"description": "Draft the invite"}],
+                "decisions": [{"text": "meet in person"}], "risks": [{"text": "Venue may be booked"}]}"#,
+            None,
+        )
+        .unwrap();
+        merge_insights(&mut insights, chunk);
+
+        let items: Vec<_> = insights.action_items.iter().map(|item| item.description.as_str()).collect();
+        assert_eq!(items, ["Book the venue", "Draft the invite"]);
+        assert_eq!(insights.decisions.len(), 1);
+        assert_eq!(insights.risks.len(), 1);
+    }
+
+    #[test]
+    fn insights_give_action_items_to_track() {
+        let reply = r#"{
+            "action_items": [{"text": "Book the venue", "owner": "Ana", "confidence": 0.9}, {"description": " "}],
+            "decisions": [{"text": "Go with the spring date"}]
+        }"#;
+        let insights = parse_insights(reply, None).unwrap();
+
+        assert_eq!(insights.action_items.len(), 1);
+        let item = &insights.action_items[0];
+        assert_eq!((item.description.as_str(), item.owner.as_deref()), ("Book the venue", Some("Ana")));
+        assert_eq!(item.confidence, 0.9);
+        assert!(!item.done);
+        assert_eq!(insights.decisions[0].text, "Go with the spring date");
+    }
+
+    #[test]
+    fn malformed_replies_are_errors_to_repair() {
+        let malformed = [
+            ("There were no action items.", "LLM reply contains no JSON"),
+            // Cut off by the token limit
+            (r#"{"action_items": [{"description": "Send the deck", "owner": "Ana"#, "LLM reply contains no JSON"),
+            (r#"{"action_items": [{"description": "Send the deck"},]}"#, "Failed to parse action items"),
+            ("{'action_items': [{'description': 'Send the deck'}]}", "Failed to parse action items"),
+            (r#"{"action_items": "none"}"#, "Failed to parse action items"),
+            (r#"{"action_items": [{"description": "Send the deck", "confidence": "high"}]}"#, "Failed to parse action items"),
+            (r#"{"items": [{"description": "Send the deck"}]}"#, "Failed to parse action items"),
+        ];
+        for (reply, error) in malformed {
+            let e = parse_action_items(reply).err().unwrap_or_else(|| panic!("{} parsed", reply));
+            assert!(e.starts_with(error), "{:?} gave {:?}", reply, e);
+
+            // The repair prompt carries what's needed to fix it
+            let prompt = repair_prompt(reply, &e);
+            assert!(prompt.contains(ACTION_ITEMS_SCHEMA));
+            assert!(prompt.contains(&e));
+            assert!(prompt.contains(reply));
+        }
+    }
+
+    #[test]
+    fn items_are_validated() {
+        let reply = r#"{"action_items": [
+            {"description": "  Book the venue  ", "owner": "null", "due": " ", "confidence": 1.7},
+            {"description": "", "owner": "Ana"},
+            {"description": "Draft the invite", "owner": "None", "due": "next week", "confidence": -0.2, "source_quote": "NULL"},
+            {"owner": "Ben"},
+            {"description": "Check the budget", "extra": true}
+        ]}"#;
+        let items = parse_action_items(reply).unwrap();
+
+        assert_eq!(descriptions(&items), ["Book the venue", "Draft the invite", "Check the budget"]);
+        let fields: Vec<_> = items
+            .iter()
+            .map(|item| (item.owner.as_deref(), item.due.as_deref(), item.confidence, item.source_quote.as_deref()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                (None, None, Some(1.0), None),
+                (None, Some("next week"), Some(0.0), None),
+                (None, None, Some(DEFAULT_ACTION_ITEM_CONFIDENCE), None),
+            ]
+        );
+    }
+}
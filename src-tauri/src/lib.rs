@@ -1,170 +1,89 @@
-use tauri::Emitter;
+use tauri::Manager;
 use dotenv::dotenv;
-use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use reqwest::Client;
-use scraper::{Html, Selector};
+use uuid::Uuid;
 
 mod audio;
+mod audio_file;
+// Public only for the benches, which build with the `bench` feature
+#[cfg(feature = "bench")]
+pub mod whisper;
+#[cfg(not(feature = "bench"))]
 mod whisper;
 mod stt;
+mod vad;
+mod recording;
+mod transcript;
 mod diarization;
 mod meeting_context;
-
-use stt::{SharedSttState, SttState, SttStatus};
-use whisper::{ModelSize, get_model_dir, get_model_path};
-use diarization::{initialize_diarization_engine, process_audio_diarization, get_example_speakers};
-use meeting_context::{MeetingContext, MeetingContextManager};
-
-async fn perform_search(query: &str) -> Result<String, String> {
-    println!("Scraping DuckDuckGo for: {}", query);
-    let client = Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36")
-        .build()
-        .map_err(|e| e.to_string())?;
-
-    let res = client
-        .post("https://html.duckduckgo.com/html/")
-        .form(&[("q", query)])
-        .send()
-        .await
-        .map_err(|e| format!("DDG Request failed: {}", e))?;
-
-    let html_content = res.text().await.map_err(|e| e.to_string())?;
-    let document = Html::parse_document(&html_content);
-    
-    // Selectors
-    let result_selector = Selector::parse(".result").unwrap();
-    let title_selector = Selector::parse(".result__title .result__a").unwrap();
-    let snippet_selector = Selector::parse(".result__snippet").unwrap();
-
-    let mut results = Vec::new();
-
-    for element in document.select(&result_selector).take(3) {
-        let title = element.select(&title_selector).next().map(|e| e.text().collect::<String>()).unwrap_or("No Title".into());
-        let link = element.select(&title_selector).next().and_then(|e| e.value().attr("href")).unwrap_or("#").to_string();
-        let snippet = element.select(&snippet_selector).next().map(|e| e.text().collect::<String>()).unwrap_or("".into());
-        
-        if !title.is_empty() {
-             results.push(format!("[{}]({}) - {}", title.trim(), link, snippet.trim()));
-        }
+mod meeting_templates;
+mod llm;
+mod chunking;
+mod http_util;
+mod insights;
+mod export;
+mod correction;
+mod assistant;
+mod search;
+mod speaker_profiles;
+mod settings;
+mod storage;
+mod events;
+
+use audio::AudioDeviceInfo;
+use stt::{SharedSttState, SttConfig, SttSensitivity, SttState, SttStatus};
+use recording::RecordingSettings;
+use transcript::{SharedTranscriptStore, StoredSegment, TranscriptStore};
+use vad::VadConfig;
+use whisper::{ModelIntegrity, ModelSize, get_model_dir, get_model_path};
+use diarization::{initialize_diarization_engine, process_audio_diarization, get_example_speakers, get_current_speakers, get_speaker_stats, set_expected_speaker_count, update_diarization_config, SharedDiarizationState, SpeakerInfo};
+use meeting_context::{ActionItem, ActionItemUpdate, CoverageReport, GoalStatus, HistoryEntrySummary, IndexedQuestion, MeetingContext, MeetingContextManager, SavedMeeting};
+use meeting_templates::{MeetingTemplate, SharedTemplateStore, TemplateStore};
+use llm::{ChatMessage, CompletionOptions, LlmClient, LlmConfig, LlmConfigCheck, LlmConnectionTest, LlmUsage};
+use export::{ExportFormat, SummaryData};
+use insights::{GeneratedQuestion, GoalProgress, MeetingInsights, MeetingSummary, SharedLatestInsights};
+use assistant::{ask_meeting_assistant, AutoAnswer, SharedAssistantMemory};
+use search::{SearchBackend, SearchPlan, SharedSearchCache};
+use speaker_profiles::{KnownSpeakerInfo, SharedSpeakerProfiles, SpeakerProfiles};
+use settings::{AppSettings, SharedSettings};
+use events::{emit_event, report_error, ErrorSource};
+
+async fn perform_search(app_handle: &tauri::AppHandle, search: &SearchBackend, query: &str) -> Result<String, String> {
+    if settings::privacy_mode(app_handle) {
+        return Err("Web search is off in privacy mode".to_string());
     }
+    println!("Searching {} for: {}", search.name(), query);
+    let client = search::http_client()?;
+    let results = search.search(&client, query).await?;
 
     if results.is_empty() {
-        Ok("No results found on DuckDuckGo (scraping might be blocked or parsing failed).".to_string())
+        Ok(format!("No results found on {} (the search might be blocked or parsing failed).", search.name()))
     } else {
-        Ok(results.join("\n\n"))
+        Ok(results
+            .iter()
+            .map(|result| format!("[{}]({}) - {}", result.title, result.url, result.snippet))
+            .collect::<Vec<_>>()
+            .join("\n\n"))
     }
 }
 
-async fn ask_meeting_assistant(transcript: &str, search_context: &str, meeting_context: Option<&MeetingContext>) -> Result<String, String> {
-    // Configuration from ENV
-    let api_key = env::var("LLM_API_KEY").unwrap_or_default();
-    let api_url = env::var("LLM_API_URL").unwrap_or("https://openrouter.ai/api/v1/chat/completions".to_string());
-    let model = env::var("LLM_MODEL").unwrap_or("openrouter/google/gemini-2.0-flash-001".to_string());
-
-    println!("Asking Meeting Assistant via: {} (Model: {})", api_url, model);
-
-    let client = Client::new();
-
-    // Build context-aware prompt
-    let mut prompt_parts = Vec::new();
-
-    // Add domain-specific role
-    if let Some(context) = meeting_context {
-        prompt_parts.push(context.get_ai_prompt_prefix());
-        prompt_parts.push(format!("\n\nMeeting Context:\n{}", context.get_context_summary()));
-    } else {
-        prompt_parts.push("You are an expert AI Meeting Assistant specializing in productive meetings, clear communication, and effective decision-making.".to_string());
-    }
-
-    // Add search context if available
-    if !search_context.is_empty() {
-        prompt_parts.push(format!("Context from Live Search:\n{}", search_context));
-    }
-
-    // Add transcript
-    prompt_parts.push(format!("Current Meeting Transcript:\n{}", transcript));
-
-    // Add meeting assistance instructions
-    prompt_parts.push(r#"
-IMPORTANT: You are a MEETING FACILITATOR, not a chatbot. Provide STRUCTURED, ACTIONABLE HELP only.
-
-Format your response using MARKDOWN with clear sections:
-- Use ## for main sections
-- Use - for bullet points
-- Use **bold** for emphasis
-- Include numbers for prioritized lists
-
-NEVER:
-- Make small talk or casual chat
-- Ask conversational follow-ups like "How does that sound?"
-- Give generic advice
-- Respond with opinion or chat
-
-ALWAYS:
-- Extract concrete ACTION ITEMS with ownership
-- List KEY DECISIONS made
-- Highlight RISKS or CONCERNS
-- Provide WEB SEARCH context when relevant (clearly labeled)
-- Use domain-specific terminology for this meeting type
-- Focus on what NEEDS TO HAPPEN NEXT
-
-Structure your response exactly like this:
-
-## Action Items
-- [Clear action] - Owner: [person], Due: [timeframe]
-
-## Key Decisions
-- Decision and reasoning
-
-## Discussion Summary
-- Main points covered
-
-## Risks/Concerns
-- Potential issues to address
-
-## Search Context (if relevant)
-- [Only if needed based on transcript]
-
-Keep each section CONCISE and ACTIONABLE. No fluff."#.to_string());
-
-    let prompt = prompt_parts.join("\n\n");
-
-    let mut request = client
-        .post(&api_url)
-        .header("Content-Type", "application/json")
-        .json(&serde_json::json!({
-            "model": model,
-            "messages": [{"role": "user", "content": prompt}]
-        }));
-
-    // Only add Bearer token if API Key is present (Ollama might not need it)
-    if !api_key.is_empty() {
-        request = request.bearer_auth(api_key);
-    }
-    
-    // Add OpenRouter specific headers just in case
-    if api_url.contains("openrouter.ai") {
-        request = request
-            .header("HTTP-Referer", "https://hypergranola.app")
-            .header("X-Title", "HyperGranola");
-    }
-
-    let res = request
-        .send()
-        .await
-        .map_err(|e| format!("LLM Request Failed: {}", e))?;
-
-    let json: serde_json::Value = res.json().await.map_err(|e| format!("Failed to parse LLM JSON: {}", e))?;
-    
-    // Robust parsing for different providers (OpenAI standard)
-    if let Some(content) = json["choices"][0]["message"]["content"].as_str() {
-        Ok(content.to_string())
-    } else {
-        // Fallback for debugging errors
-        Err(format!("Unexpected LLM Response: {:?}", json))
-    }
+/// Save the meetings once they've gone `SAVE_DELAY` without another change
+fn schedule_meeting_save(state: &Arc<Mutex<MeetingContextManager>>, manager: &mut MeetingContextManager) {
+    let revision = manager.mark_changed();
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(meeting_context::SAVE_DELAY).await;
+        let Ok(manager) = state.lock() else {
+            return;
+        };
+        if manager.revision() == revision {
+            if let Err(e) = manager.save_to_disk() {
+                eprintln!("{}", e);
+            }
+        }
+    });
 }
 
 #[tauri::command]
@@ -172,8 +91,10 @@ fn set_meeting_context(
     context: MeetingContext,
     state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
 ) -> Result<(), String> {
+    context.check_custom_prompt()?;
     let mut manager = state.lock().map_err(|e| e.to_string())?;
     manager.set_context(context);
+    schedule_meeting_save(&state, &mut manager);
     Ok(())
 }
 
@@ -190,295 +111,1841 @@ fn add_meeting_participant(
     name: String,
     role: String,
     email: Option<String>,
+    id: Option<Uuid>,
     state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
 ) -> Result<(), String> {
     let mut manager = state.lock().map_err(|e| e.to_string())?;
-    if let Some(context) = manager.get_current_context_mut() {
-        context.add_participant(name, role, email);
-        Ok(())
-    } else {
-        Err("No active meeting context".to_string())
-    }
+    manager.context_mut(id)?.add_participant(name, role, email);
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
+
+/// Mark a participant as in the room or not. Participants sharing a name are told apart
+/// by `index` in the participant list, `email` or `role`.
+#[tauri::command]
+fn set_participant_presence(
+    name: String,
+    present: bool,
+    index: Option<usize>,
+    email: Option<String>,
+    role: Option<String>,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    let context = manager.context_mut(id)?;
+    context.set_participant_presence(&name, index, email.as_deref(), role.as_deref(), present)?;
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
+
+/// Remove a participant, matched like in `set_participant_presence`
+#[tauri::command]
+fn remove_participant(
+    name: String,
+    index: Option<usize>,
+    email: Option<String>,
+    role: Option<String>,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    let context = manager.context_mut(id)?;
+    context.remove_participant(&name, index, email.as_deref(), role.as_deref())?;
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
 }
 
 #[tauri::command]
 fn add_meeting_goal(
     description: String,
     priority: u8,
+    id: Option<Uuid>,
     state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
 ) -> Result<(), String> {
     let mut manager = state.lock().map_err(|e| e.to_string())?;
-    if let Some(context) = manager.get_current_context_mut() {
-        context.add_goal(description, priority);
-        Ok(())
-    } else {
-        Err("No active meeting context".to_string())
-    }
+    manager.context_mut(id)?.add_goal(description, priority);
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
 }
 
 #[tauri::command]
-fn clear_meeting_context(
+fn update_goal_status(
+    index: usize,
+    status: GoalStatus,
+    id: Option<Uuid>,
     state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
 ) -> Result<(), String> {
     let mut manager = state.lock().map_err(|e| e.to_string())?;
-    manager.clear_context();
+    let context = manager.context_mut(id)?;
+    context.update_goal_status(index, status)?;
+    schedule_meeting_save(&state, &mut manager);
     Ok(())
 }
 
 #[tauri::command]
-async fn process_transcript(
-    app_handle: tauri::AppHandle,
-    text: String,
-    meeting_state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+fn remove_goal(
+    index: usize,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
 ) -> Result<(), String> {
-    // Load .env
-    dotenv().ok();
-    
-    // 1. Keyword Extraction (Simple Regex replacement for now, or small LLM)
-    let query = if text.len() > 10 {
-        // Simple heuristic: search for the last sentence
-        Some(text.clone()) 
-    } else {
-        None
-    };
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    let context = manager.context_mut(id)?;
+    context.remove_goal(index)?;
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
 
-    if let Some(q) = query {
-        app_handle.emit("search_results", format!("Searching: {}", q)).unwrap();
-        
-        let search_res = perform_search(&q).await?;
-        app_handle.emit("search_results", &search_res).unwrap();
+#[tauri::command]
+fn add_agenda_item(
+    title: String,
+    planned_minutes: u32,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    manager.context_mut(id)?.add_agenda_item(title, planned_minutes)?;
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
 
-        // Get current meeting context for AI assistance
-        let meeting_context = {
-            let manager = meeting_state.lock().map_err(|e| e.to_string())?;
-            manager.get_current_context().cloned()
-        };
-    
-        let assistant_res = ask_meeting_assistant(&text, &search_res, meeting_context.as_ref()).await?;
-        app_handle.emit("meeting_assistant_response", &assistant_res).unwrap();
-    }
+/// Move the agenda item at `from` to position `to`
+#[tauri::command]
+fn move_agenda_item(
+    from: usize,
+    to: usize,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    manager.context_mut(id)?.move_agenda_item(from, to)?;
+    schedule_meeting_save(&state, &mut manager);
     Ok(())
 }
 
 #[tauri::command]
-async fn revise_transcript(full_transcript: String) -> Result<String, String> {
-    // Configuration from ENV
-    let api_key = env::var("LLM_API_KEY").unwrap_or_default();
-    let api_url = env::var("LLM_API_URL").unwrap_or("https://openrouter.ai/api/v1/chat/completions".to_string());
-    let model = env::var("LLM_MODEL").unwrap_or("google/gemini-2.0-flash-001".to_string());
+fn remove_agenda_item(
+    index: usize,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    manager.context_mut(id)?.remove_agenda_item(index)?;
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
 
-    println!("Revising full transcript via: {} (Model: {})", api_url, model);
+/// Start discussing the agenda item at `index`, completing the one that was active
+#[tauri::command]
+fn start_agenda_item(
+    index: usize,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    manager.context_mut(id)?.start_agenda_item(index)?;
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
 
-    let client = Client::new();
-    let prompt = format!(
-        "You are revising a conversation transcript with the benefit of full context. Review the entire conversation and improve the accuracy of earlier transcriptions.
+#[tauri::command]
+fn complete_agenda_item(
+    index: usize,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    manager.context_mut(id)?.complete_agenda_item(index)?;
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
 
-Full conversation transcript:
-{}
+/// How far past its time box, in percent, an agenda item may run before `agenda_overrun` is emitted
+#[tauri::command]
+fn set_agenda_overrun_threshold(
+    percent: u32,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    manager.set_agenda_overrun_percent(percent);
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
 
-Return ONLY the corrected, flowing text of the entire conversation. Do not include timestamps, speaker labels, explanations, or any formatting. Just the natural conversation text:",
-        full_transcript
-    );
+/// Update the meeting's goals from a stretch of transcript, emitting `goal_status_changed`.
+/// Goals the LLM finds addressed are completed, and Pending goals that come up are in progress.
+async fn track_goals(
+    app_handle: &tauri::AppHandle,
+    llm: &LlmClient,
+    transcript: &str,
+    meeting_state: &Arc<Mutex<MeetingContextManager>>,
+) -> Result<(), String> {
+    let meeting_context = {
+        let manager = meeting_state.lock().map_err(|e| e.to_string())?;
+        match manager.get_current_context() {
+            Some(context) if context.has_open_goals() => context.clone(),
+            _ => return Ok(()),
+        }
+    };
 
-    let mut request = client
-        .post(&api_url)
-        .header("Content-Type", "application/json")
-        .json(&serde_json::json!({
-            "model": model,
-            "messages": [{"role": "user", "content": prompt}],
-            "max_tokens": 1000,
-            "temperature": 0.2
-        }));
+    // Without the LLM's verdict, mentions can still mark goals in progress
+    let addressed: Vec<usize> = match insights::check_goal_progress(llm, transcript, &meeting_context).await {
+        Ok(progress) => progress
+            .iter()
+            .filter(|progress| progress.addressed)
+            .map(|progress| progress.index)
+            .collect(),
+        Err(e) => {
+            report_error(app_handle, ErrorSource::Llm, format!("Goal progress check failed: {}", e));
+            Vec::new()
+        }
+    };
 
-    // Only add Bearer token if API Key is present
-    if !api_key.is_empty() {
-        request = request.bearer_auth(api_key);
+    let mut manager = meeting_state.lock().map_err(|e| e.to_string())?;
+    let Some(context) = manager.get_current_context_mut() else {
+        return Ok(());
+    };
+    let changes = context.track_goal_progress(transcript, &addressed);
+    if !changes.is_empty() {
+        schedule_meeting_save(meeting_state, &mut manager);
+        emit_event(app_handle, "goal_status_changed", &changes);
     }
+    Ok(())
+}
 
-    // Add OpenRouter specific headers just in case
-    if api_url.contains("openrouter.ai") {
-        request = request
-            .header("HTTP-Referer", "https://hypergranola.app")
-            .header("X-Title", "HyperGranola");
+/// Ask the LLM which goals the transcript has addressed, also emitted as `goal_progress`.
+/// Defaults to the transcript accumulated this session.
+#[tauri::command]
+async fn check_goal_progress(
+    app_handle: tauri::AppHandle,
+    transcript: Option<String>,
+    llm: tauri::State<'_, LlmClient>,
+    meeting_state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+    transcript_state: tauri::State<'_, SharedTranscriptStore>,
+) -> Result<Vec<GoalProgress>, String> {
+    let transcript = match transcript {
+        Some(text) => text,
+        None => transcript_state.lock().map_err(|e| e.to_string())?.full_text(),
+    };
+    if transcript.trim().is_empty() {
+        return Err("No transcript to analyze".to_string());
     }
+    let meeting_context = {
+        let manager = meeting_state.lock().map_err(|e| e.to_string())?;
+        manager.get_current_context().cloned().ok_or("No active meeting context")?
+    };
 
-    let res = request
-        .send()
-        .await
-        .map_err(|e| format!("Revision Request Failed: {}", e))?;
-
-    let json: serde_json::Value = res.json().await.map_err(|e| format!("Failed to parse revision JSON: {}", e))?;
-
-    // Robust parsing for different providers (OpenAI standard)
-    if let Some(content) = json["choices"][0]["message"]["content"].as_str() {
-        Ok(content.trim().to_string())
-    } else {
-        // Fallback - return original transcript if revision fails
-        println!("Revision failed, returning original transcript");
-        Ok(full_transcript)
-    }
+    let progress = insights::check_goal_progress(&llm, &transcript, &meeting_context).await?;
+    emit_event(&app_handle, "goal_progress", &progress);
+    Ok(progress)
 }
 
-// ============ STT Commands ============
-
+/// Give meeting `id`, or the active one, its own assistant prompt prefix and instructions,
+/// None or empty to inherit the app-wide ones. With `replace_domain_prefix` the prefix is used
+/// instead of the domain's rather than after it.
 #[tauri::command]
-async fn start_listening(
-    app_handle: tauri::AppHandle,
-    state: tauri::State<'_, SharedSttState>,
+fn set_custom_prompt(
+    prefix: Option<String>,
+    instructions: Option<String>,
+    replace_domain_prefix: Option<bool>,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
 ) -> Result<(), String> {
-    stt::start_stt(app_handle, state.inner().clone()).await
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    manager
+        .context_mut(id)?
+        .set_custom_prompt(prefix, instructions, replace_domain_prefix.unwrap_or(false))?;
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
 }
 
 #[tauri::command]
-fn stop_listening(state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
-    stt::stop_stt(state.inner())
+fn get_settings(settings: tauri::State<'_, SharedSettings>) -> Result<AppSettings, String> {
+    let mut settings = settings.lock().map_err(|e| e.to_string())?.clone();
+    settings.llm = settings.llm.map(|llm| llm.redacted());
+    Ok(settings)
 }
 
+/// Set the assistant prompt prefix and instructions every meeting inherits unless it has its own
 #[tauri::command]
-fn get_stt_status(state: tauri::State<'_, SharedSttState>) -> SttStatus {
-    stt::get_stt_status(state.inner())
+fn set_default_prompt(
+    prefix: Option<String>,
+    instructions: Option<String>,
+    settings: tauri::State<'_, SharedSettings>,
+) -> Result<(), String> {
+    let mut settings = settings.lock().map_err(|e| e.to_string())?;
+    settings.set_default_prompt(prefix, instructions)?;
+    settings.save()
 }
 
+/// Keep transcripts on this machine: turns off web search and refuses LLM endpoints that
+/// aren't on localhost, so a local LLM like Ollama has to be configured
 #[tauri::command]
-async fn download_model(app_handle: tauri::AppHandle) -> Result<(), String> {
-    let model_size = ModelSize::Base;
-    let model_dir = get_model_dir()?;
-    let model_path = get_model_path(model_size)?;
+fn set_privacy_mode(enabled: bool, settings: tauri::State<'_, SharedSettings>) -> Result<(), String> {
+    let mut settings = settings.lock().map_err(|e| e.to_string())?;
+    settings.privacy_mode = enabled;
+    settings.save()
+}
 
-    // Create directory if it doesn't exist
-    std::fs::create_dir_all(&model_dir)
-        .map_err(|e| format!("Failed to create model directory: {}", e))?;
+/// The LLM endpoint, model and defaults in use, with the API key masked
+#[tauri::command]
+fn get_llm_config(llm: tauri::State<'_, LlmClient>) -> LlmConfig {
+    llm.config().redacted()
+}
 
-    if model_path.exists() {
-        return Ok(());
+/// Switch the LLM endpoint, model or defaults and save them, no restart needed.
+/// The masked key from `get_llm_config` can be sent back unchanged to keep the current key.
+#[tauri::command]
+fn set_llm_config(
+    mut config: LlmConfig,
+    llm: tauri::State<'_, LlmClient>,
+    settings: tauri::State<'_, SharedSettings>,
+) -> Result<(), String> {
+    let current = llm.config();
+    if config.api_key == llm::redact_key(&current.api_key) {
+        config.api_key = current.api_key;
     }
+    llm.set_config(config.clone())?;
+    println!("LLM config changed: {:?}", config);
+    let mut settings = settings.lock().map_err(|e| e.to_string())?;
+    settings.llm = Some(config);
+    settings.save()
+}
 
-    app_handle.emit("model_download_progress", "Starting download...").unwrap();
-    println!("Downloading model from: {}", model_size.download_url());
+/// Send a tiny prompt to the configured LLM, returning the latency or why it failed
+#[tauri::command]
+async fn test_llm_connection(llm: tauri::State<'_, LlmClient>) -> Result<LlmConnectionTest, String> {
+    Ok(llm.test_connection().await)
+}
 
-    let client = Client::new();
-    let response = client
-        .get(model_size.download_url())
-        .send()
-        .await
-        .map_err(|e| format!("Download request failed: {}", e))?;
+/// Check the LLM is usable before a meeting: whether a key is missing or refused, the model
+/// exists, and the endpoint is reachable
+#[tauri::command]
+async fn validate_llm_config(llm: tauri::State<'_, LlmClient>) -> Result<LlmConfigCheck, String> {
+    Ok(llm.validate_config().await)
+}
 
-    let _total_size = response.content_length().unwrap_or(0);
-    let _downloaded: u64 = 0;
+/// Tokens the LLM calls of this session have used, against the budget
+#[tauri::command]
+fn get_llm_usage(llm: tauri::State<'_, LlmClient>) -> LlmUsage {
+    llm.usage()
+}
 
-    let mut file = std::fs::File::create(&model_path)
-        .map_err(|e| format!("Failed to create model file: {}", e))?;
+/// Tokens a session may use before automatic assistant calls pause, None for no limit
+#[tauri::command]
+fn set_llm_token_budget(budget: Option<u64>, settings: tauri::State<'_, SharedSettings>) -> Result<(), String> {
+    let mut settings = settings.lock().map_err(|e| e.to_string())?;
+    settings.set_llm_token_budget(budget)?;
+    settings.save()
+}
 
-    use std::io::Write;
-    let bytes = response.bytes().await
-        .map_err(|e| format!("Failed to download: {}", e))?;
+/// Which of the meeting's key points have come up so far
+#[tauri::command]
+fn get_coverage_report(
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<CoverageReport, String> {
+    let manager = state.lock().map_err(|e| e.to_string())?;
+    let context = manager.context(id)?;
+    Ok(context.coverage_report())
+}
 
-    file.write_all(&bytes)
-        .map_err(|e| format!("Failed to write model: {}", e))?;
+#[tauri::command]
+fn add_question(
+    question: String,
+    category: String,
+    priority: u8,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    let context = manager.context_mut(id)?;
+    if !context.add_question(question, category, priority) {
+        return Err("The question is empty or already planned".to_string());
+    }
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
 
-    app_handle.emit("model_download_progress", "Download complete!").unwrap();
-    println!("Model downloaded to: {:?}", model_path);
+/// Planned questions with their index, only those in `category` if given
+#[tauri::command]
+fn list_questions(
+    category: Option<String>,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<Vec<IndexedQuestion>, String> {
+    let manager = state.lock().map_err(|e| e.to_string())?;
+    let context = manager.context(id)?;
+    Ok(context.list_questions(category.as_deref()))
+}
 
+/// Mark a planned question as asked, or as not asked with `asked` false
+#[tauri::command]
+fn mark_question_asked(
+    index: usize,
+    asked: Option<bool>,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    let context = manager.context_mut(id)?;
+    context.mark_question_asked(index, asked.unwrap_or(true))?;
+    schedule_meeting_save(&state, &mut manager);
     Ok(())
 }
 
+/// How similar (0-1) transcript text must be to a planned question for it to be marked asked
 #[tauri::command]
-fn check_model_exists() -> bool {
-    whisper::model_exists(ModelSize::Base)
+fn set_question_match_threshold(
+    threshold: f32,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    manager.set_question_match_threshold(threshold)?;
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
+
+/// Mark planned questions the text asks as asked, emitting `question_asked` for each
+fn track_questions_asked(
+    app_handle: &tauri::AppHandle,
+    text: &str,
+    state: &Arc<Mutex<MeetingContextManager>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    let threshold = manager.question_match_threshold();
+    let Some(context) = manager.get_current_context_mut() else {
+        return Ok(());
+    };
+    let asked = context.track_questions_asked(text, threshold);
+    if !asked.is_empty() {
+        schedule_meeting_save(state, &mut manager);
+        for question in &asked {
+            emit_event(app_handle, "question_asked", question);
+        }
+    }
+    Ok(())
 }
 
+/// Ask the LLM for questions covering the meeting's goals and add them to the plan.
+/// Returns the questions added, leaving out any already planned.
 #[tauri::command]
-async fn correct_transcript(text: String, context: Option<String>) -> Result<String, String> {
-    // Configuration from ENV
-    let api_key = env::var("LLM_API_KEY").unwrap_or_default();
-    let api_url = env::var("LLM_API_URL").unwrap_or("https://openrouter.ai/api/v1/chat/completions".to_string());
-    let model = env::var("LLM_MODEL").unwrap_or("google/gemini-2.0-flash-001".to_string());
+async fn generate_meeting_questions(
+    llm: tauri::State<'_, LlmClient>,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<Vec<GeneratedQuestion>, String> {
+    let meeting_context = {
+        let manager = state.lock().map_err(|e| e.to_string())?;
+        manager.context(id)?.clone()
+    };
 
-    println!("Correcting transcript with context via: {} (Model: {})", api_url, model);
+    let generated = insights::generate_questions(&llm, &meeting_context).await?;
 
-    let client = Client::new();
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    let context = manager.context_mut(id)?;
+    let added: Vec<GeneratedQuestion> = generated
+        .into_iter()
+        .filter(|question| context.add_question(question.question.clone(), question.category.clone(), question.priority))
+        .collect();
+    if !added.is_empty() {
+        schedule_meeting_save(&state, &mut manager);
+    }
+    Ok(added)
+}
 
-    let prompt = if let Some(ctx) = context {
-        format!(
-            "You are correcting speech-to-text transcriptions in real-time. Use the conversation context to improve accuracy.
+/// Searches `prepare_meeting` runs when not told otherwise
+const DEFAULT_PREPARATION_SEARCHES: usize = 5;
+/// Planned questions above which `prepare_meeting` doesn't ask the LLM for more
+const PREPARED_QUESTION_COUNT: usize = 5;
+
+/// Payload of the `meeting_preparation` event, sent as each step of `prepare_meeting` starts
+#[derive(Clone, serde::Serialize)]
+struct PreparationProgress {
+    step: String,
+    completed: usize,
+    total: usize,
+}
 
-Previous conversation context:
-{}
+/// What `prepare_meeting` added to the meeting
+#[derive(serde::Serialize)]
+struct PreparationReport {
+    questions_added: usize,
+    topics_researched: Vec<String>,
+    /// Topics left out to stay within `max_searches`
+    topics_skipped: Vec<String>,
+}
 
-Current spoken text to correct: \"{}\"
+/// Prepare meeting `id`, or the active one: generate questions for its goals with the LLM, and search the
+/// web for its title and each goal, keeping the results as background info.
+/// Steps already done are skipped, so it can be run again after a failure. Progress is
+/// emitted as `meeting_preparation`.
+#[tauri::command]
+async fn prepare_meeting(
+    app_handle: tauri::AppHandle,
+    max_searches: Option<usize>,
+    llm: tauri::State<'_, LlmClient>,
+    search: tauri::State<'_, SearchBackend>,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<PreparationReport, String> {
+    let meeting_context = {
+        let manager = state.lock().map_err(|e| e.to_string())?;
+        manager.context(id)?.clone()
+    };
 
-Return ONLY the corrected version of the spoken text. Do not include any explanations, coaching tips, or additional formatting. Just the corrected text:",
-            ctx, text
-        )
+    // The title matters most, then the goals by priority
+    let mut goals = meeting_context.goals.clone();
+    goals.sort_by_key(|goal| std::cmp::Reverse(goal.priority));
+    let topics: Vec<(String, f32)> = std::iter::once((meeting_context.title.clone(), 1.0))
+        .chain(goals.iter().map(|goal| (goal.description.clone(), goal.priority.clamp(1, 5) as f32 / 5.0)))
+        .filter(|(topic, _)| !meeting_context.background_info.contains_key(topic))
+        .collect();
+    // Nothing is looked up in privacy mode, so every topic is skipped
+    let max_searches = if settings::privacy_mode(&app_handle) {
+        0
     } else {
-        format!(
-            "Correct this spoken text to make it more coherent and grammatically correct. Return ONLY the corrected text, nothing else: \"{}\"",
-            text
-        )
+        max_searches.unwrap_or(DEFAULT_PREPARATION_SEARCHES)
+    };
+    let topics_skipped: Vec<String> = topics.iter().skip(max_searches).map(|(topic, _)| topic.clone()).collect();
+    let topics: Vec<(String, f32)> = topics.into_iter().take(max_searches).collect();
+
+    let needs_questions = meeting_context.pre_generated_questions.len() < PREPARED_QUESTION_COUNT;
+    let total = topics.len() + usize::from(needs_questions);
+    let mut completed = 0;
+    let progress = |step: String, completed: usize| {
+        emit_event(&app_handle, "meeting_preparation", PreparationProgress { step, completed, total });
     };
 
-    let mut request = client
-        .post(&api_url)
-        .header("Content-Type", "application/json")
-        .json(&serde_json::json!({
-            "model": model,
-            "messages": [{"role": "user", "content": prompt}],
-            "max_tokens": 200,
-            "temperature": 0.3
-        }));
-
-    // Only add Bearer token if API Key is present
-    if !api_key.is_empty() {
-        request = request.bearer_auth(api_key);
+    let mut questions_added = 0;
+    if needs_questions {
+        progress("Generating questions".to_string(), completed);
+        let generated = insights::generate_questions(&llm, &meeting_context).await?;
+        let mut manager = state.lock().map_err(|e| e.to_string())?;
+        let context = manager.context_mut(id)?;
+        questions_added = generated
+            .into_iter()
+            .filter(|question| context.add_question(question.question.clone(), question.category.clone(), question.priority))
+            .count();
+        schedule_meeting_save(&state, &mut manager);
+        completed += 1;
     }
 
-    // Add OpenRouter specific headers just in case
-    if api_url.contains("openrouter.ai") {
-        request = request
-            .header("HTTP-Referer", "https://hypergranola.app")
-            .header("X-Title", "HyperGranola");
+    let mut topics_researched = Vec::new();
+    for (topic, relevance) in topics {
+        progress(format!("Researching: {}", topic), completed);
+        let results = perform_search(&app_handle, &search, &topic).await?;
+        let mut manager = state.lock().map_err(|e| e.to_string())?;
+        let context = manager.context_mut(id)?;
+        context.add_background_info(topic.clone(), results, search.name().to_string(), relevance);
+        schedule_meeting_save(&state, &mut manager);
+        topics_researched.push(topic);
+        completed += 1;
     }
+    progress("Done".to_string(), completed);
 
-    let res = request
-        .send()
-        .await
-        .map_err(|e| format!("Correction Request Failed: {}", e))?;
+    Ok(PreparationReport {
+        questions_added,
+        topics_researched,
+        topics_skipped,
+    })
+}
 
-    let json: serde_json::Value = res.json().await.map_err(|e| format!("Failed to parse correction JSON: {}", e))?;
+#[tauri::command]
+fn clear_meeting_context(
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    manager.clear_context();
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
 
-    // Robust parsing for different providers (OpenAI standard)
-    if let Some(content) = json["choices"][0]["message"]["content"].as_str() {
-        Ok(content.trim().to_string())
-    } else {
-        // Fallback - return original text if correction fails
-        println!("Correction failed, returning original text");
-        Ok(text)
+/// Built-in templates followed by the ones saved by the user
+#[tauri::command]
+fn list_templates(templates: tauri::State<'_, SharedTemplateStore>) -> Result<Vec<MeetingTemplate>, String> {
+    Ok(templates.lock().map_err(|e| e.to_string())?.list())
+}
+
+/// Fill in meeting `id`, or the active one, from a template, setting up a new meeting if none is active
+#[tauri::command]
+fn apply_template(
+    name: String,
+    templates: tauri::State<'_, SharedTemplateStore>,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<MeetingContext, String> {
+    let template = find_template(&templates, &name)?;
+
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    if id.is_none() && manager.get_current_context().is_none() {
+        manager.set_context(MeetingContext::default());
     }
+    let context = manager.context_mut(id)?;
+    context.apply_template(&template);
+    let context = context.clone();
+    schedule_meeting_save(&state, &mut manager);
+    Ok(context)
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
+/// Start a new meeting set up from a template, moving the current one to the history
+#[tauri::command]
+fn create_context_from_template(
+    name: String,
+    templates: tauri::State<'_, SharedTemplateStore>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<MeetingContext, String> {
+    let context = find_template(&templates, &name)?.to_context();
+
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    manager.set_context(context.clone());
+    schedule_meeting_save(&state, &mut manager);
+    Ok(context)
+}
+
+/// Save the setup of meeting `id`, or the active one, as a template, replacing a saved template of the same name
+#[tauri::command]
+fn save_context_as_template(
+    name: String,
+    templates: tauri::State<'_, SharedTemplateStore>,
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<MeetingTemplate, String> {
+    let template = {
+        let manager = state.lock().map_err(|e| e.to_string())?;
+        let context = manager.context(id)?;
+        MeetingTemplate::from_context(&name, context)
+    };
+
+    let mut templates = templates.lock().map_err(|e| e.to_string())?;
+    templates.upsert(template.clone())?;
+    templates.save()?;
+    Ok(template)
+}
+
+/// Delete a template saved by the user
+#[tauri::command]
+fn delete_template(name: String, templates: tauri::State<'_, SharedTemplateStore>) -> Result<(), String> {
+    let mut templates = templates.lock().map_err(|e| e.to_string())?;
+    templates.remove(&name)?;
+    templates.save()
+}
+
+fn find_template(templates: &SharedTemplateStore, name: &str) -> Result<MeetingTemplate, String> {
+    templates
+        .lock()
+        .map_err(|e| e.to_string())?
+        .find(name)
+        .ok_or_else(|| format!("Unknown template: {}", name))
+}
+
+/// Ids, titles and timestamps of the saved meetings, the open ones first
+#[tauri::command]
+fn list_saved_meetings(
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<Vec<SavedMeeting>, String> {
+    let manager = state.lock().map_err(|e| e.to_string())?;
+    Ok(manager.list_saved_meetings())
+}
+
+/// Make a saved meeting the active one, by its id from `list_saved_meetings`
+#[tauri::command]
+fn load_meeting_context(
+    id: Uuid,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<MeetingContext, String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    let context = manager.activate(id)?.clone();
+    schedule_meeting_save(&state, &mut manager);
+    Ok(context)
+}
+
+/// Set up a meeting alongside the open ones without touching the active one, e.g. to prepare
+/// tomorrow's meeting during today's. Returns its id, which commands taking an `id` accept.
+/// It only becomes active if no meeting is.
+#[tauri::command]
+fn create_meeting_context(
+    context: MeetingContext,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<Uuid, String> {
+    context.check_custom_prompt()?;
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    let id = manager.create_context(context);
+    schedule_meeting_save(&state, &mut manager);
+    Ok(id)
+}
+
+/// Make a meeting the active one, the one the live session and commands without an `id` use
+#[tauri::command]
+fn activate_meeting_context(
+    id: Uuid,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<MeetingContext, String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    let context = manager.activate(id)?.clone();
+    schedule_meeting_save(&state, &mut manager);
+    Ok(context)
+}
+
+/// A meeting by id, open or from the history
+#[tauri::command]
+fn get_meeting_context(
+    id: Uuid,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<MeetingContext, String> {
+    let manager = state.lock().map_err(|e| e.to_string())?;
+    manager.get_meeting(id).cloned().ok_or_else(|| format!("No saved meeting with id {}", id))
+}
+
+/// The open meetings, newest first, with the active one marked `is_current`
+#[tauri::command]
+fn list_meeting_contexts(
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<Vec<SavedMeeting>, String> {
+    let manager = state.lock().map_err(|e| e.to_string())?;
+    Ok(manager.list_contexts())
+}
+
+/// Past meetings, oldest first, with their goal completion and how long they ran
+#[tauri::command]
+fn get_context_history(
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<Vec<HistoryEntrySummary>, String> {
+    let manager = state.lock().map_err(|e| e.to_string())?;
+    Ok(manager.get_context_history())
+}
+
+/// A past meeting in full, by its index from `get_context_history`
+#[tauri::command]
+fn get_history_entry(
+    index: usize,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<MeetingContext, String> {
+    let manager = state.lock().map_err(|e| e.to_string())?;
+    manager.get_history_entry(index).cloned()
+}
+
+#[tauri::command]
+fn delete_history_entry(
+    index: usize,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    manager.delete_history_entry(index)?;
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
+
+/// How many past meetings to keep, dropping the oldest beyond that
+#[tauri::command]
+fn set_context_history_limit(
+    limit: usize,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    manager.set_history_limit(limit)?;
+    schedule_meeting_save(&state, &mut manager);
+    Ok(())
+}
+
+#[tauri::command]
+async fn process_transcript(
+    app_handle: tauri::AppHandle,
+    text: Option<String>,
+    llm: tauri::State<'_, LlmClient>,
+    search: tauri::State<'_, SearchBackend>,
+    search_cache: tauri::State<'_, SharedSearchCache>,
+    meeting_state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+    transcript_state: tauri::State<'_, SharedTranscriptStore>,
+) -> Result<(), String> {
+    // Without text, the transcript the assistant hasn't read yet is processed. What it read
+    // before is in its summary.
+    let memory = app_handle.state::<SharedAssistantMemory>();
+    let Some(input) = assistant::next_input(&llm, &memory, &transcript_state, text.as_deref()).await? else {
+        return Ok(());
+    };
+    let text = input.delta;
+
+    track_questions_asked(&app_handle, &text, &meeting_state)?;
+
+    // Search for keyphrases of the text, reusing recent results for queries already looked up.
+    // In privacy mode, or when the text has no keyphrases, nothing is searched and the assistant
+    // works from the transcript alone.
+    let plan = if settings::privacy_mode(&app_handle) {
+        SearchPlan::default()
+    } else {
+        let queries = search::extract_queries(&text, search::MAX_QUERIES);
+        search_cache.lock().map_err(|e| e.to_string())?.plan(queries)
+    };
+    if let Some(skipped) = plan.skipped {
+        emit_event(&app_handle, "search_skipped", skipped);
+    }
+    let mut search_sections = plan.sections;
+    let new_queries = plan.new_queries;
+
+    for q in &new_queries {
+        emit_event(&app_handle, "search_results", format!("Searching: {}", q));
+
+        // A failed search leaves the assistant without its results rather than without an answer
+        let search_res = match perform_search(&app_handle, &search, q).await {
+            Ok(results) => results,
+            Err(e) => {
+                report_error(&app_handle, ErrorSource::Search, format!("Search for \"{}\" failed: {}", q, e));
+                continue;
+            }
+        };
+        emit_event(&app_handle, "search_results", &search_res);
+        search_cache.lock().map_err(|e| e.to_string())?.insert(q, search_res.clone());
+        search_sections.push(format!("Results for \"{}\":\n{}", q, search_res));
+    }
+
+    // Get current meeting context for AI assistance
+    let meeting_context = {
+        let manager = meeting_state.lock().map_err(|e| e.to_string())?;
+        manager.get_current_context().cloned()
+    };
+
+    let search_context = search_sections.join("\n\n");
+    llm.check_budget()?;
+    let assistant_res = ask_meeting_assistant(
+        &app_handle,
+        &llm,
+        &input.discussion,
+        input.summary.as_deref(),
+        &search_context,
+        meeting_context.as_ref(),
+    )
+    .await?;
+    emit_event(&app_handle, "meeting_assistant_response", &assistant_res);
+
+    track_goals(&app_handle, &llm, &text, &meeting_state).await
+}
+
+/// Extract action items, decisions and risks as structured data, also emitted as `meeting_insights`.
+/// The action items are tracked on the meeting. Defaults to the transcript accumulated this
+/// session, with speaker labels.
+#[tauri::command]
+async fn get_meeting_insights(
+    app_handle: tauri::AppHandle,
+    transcript: Option<String>,
+    llm: tauri::State<'_, LlmClient>,
+    meeting_state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+    transcript_state: tauri::State<'_, SharedTranscriptStore>,
+    latest_insights: tauri::State<'_, SharedLatestInsights>,
+) -> Result<MeetingInsights, String> {
+    let transcript = match transcript {
+        Some(text) => text,
+        None => transcript_state.lock().map_err(|e| e.to_string())?.attributed_text(),
+    };
+    if transcript.trim().is_empty() {
+        return Err("No transcript to analyze".to_string());
+    }
+    let meeting_context = {
+        let manager = meeting_state.lock().map_err(|e| e.to_string())?;
+        manager.get_current_context().cloned()
+    };
+
+    let insights = insights::extract_insights(&llm, &transcript, meeting_context.as_ref()).await?;
+    if let Some(context) = &meeting_context {
+        track_action_items(&meeting_state, context.id, insights.action_items.clone())?;
+    }
+    *latest_insights.lock().map_err(|e| e.to_string())? = Some(insights.clone());
+    emit_event(&app_handle, "meeting_insights", &insights);
+    Ok(insights)
+}
+
+/// Extract action items as typed data and track them on the meeting, returning the new ones.
+/// Owners are matched to participants. Defaults to the transcript accumulated this session.
+#[tauri::command]
+async fn extract_action_items(
+    transcript: Option<String>,
+    id: Option<Uuid>,
+    llm: tauri::State<'_, LlmClient>,
+    meeting_state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+    transcript_state: tauri::State<'_, SharedTranscriptStore>,
+) -> Result<Vec<ActionItem>, String> {
+    let transcript = match transcript {
+        Some(text) => text,
+        None => transcript_state.lock().map_err(|e| e.to_string())?.attributed_text(),
+    };
+    if transcript.trim().is_empty() {
+        return Err("No transcript to extract action items from".to_string());
+    }
+    let meeting_context = meeting_state.lock().map_err(|e| e.to_string())?.context(id)?.clone();
+
+    let items = insights::extract_action_items(&llm, &transcript, Some(&meeting_context)).await?;
+    track_action_items(&meeting_state, meeting_context.id, items)
+}
+
+/// Track action items on the meeting they were found in, returning the ones it didn't have yet
+fn track_action_items(
+    meeting_state: &Arc<Mutex<MeetingContextManager>>,
+    id: Uuid,
+    items: Vec<ActionItem>,
+) -> Result<Vec<ActionItem>, String> {
+    let mut manager = meeting_state.lock().map_err(|e| e.to_string())?;
+    let added = manager.context_mut(Some(id))?.add_action_items(items);
+    if !added.is_empty() {
+        schedule_meeting_save(meeting_state, &mut manager);
+    }
+    Ok(added)
+}
+
+/// The action items tracked on the meeting
+#[tauri::command]
+fn get_action_items(
+    id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<Vec<ActionItem>, String> {
+    let manager = state.lock().map_err(|e| e.to_string())?;
+    Ok(manager.context(id)?.action_items.clone())
+}
+
+/// Edit an action item, e.g. to fix its owner or mark it done. Fields not given are kept,
+/// an empty owner or due date clears it.
+#[tauri::command]
+fn update_action_item(
+    id: Uuid,
+    description: Option<String>,
+    owner: Option<String>,
+    due: Option<String>,
+    done: Option<bool>,
+    meeting_id: Option<Uuid>,
+    state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<ActionItem, String> {
+    let mut manager = state.lock().map_err(|e| e.to_string())?;
+    let update = ActionItemUpdate {
+        description,
+        owner,
+        due,
+        done,
+    };
+    let item = manager.context_mut(meeting_id)?.update_action_item(id, update)?;
+    schedule_meeting_save(&state, &mut manager);
+    Ok(item)
+}
+
+/// Summarize the whole meeting for sharing, meant to be called once it's over. Its action items
+/// are tracked on the meeting. Defaults to the transcript accumulated this session, with speaker labels.
+#[tauri::command]
+async fn generate_meeting_summary(
+    full_transcript: Option<String>,
+    llm: tauri::State<'_, LlmClient>,
+    meeting_state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+    transcript_state: tauri::State<'_, SharedTranscriptStore>,
+) -> Result<MeetingSummary, String> {
+    let full_transcript = match full_transcript {
+        Some(text) => text,
+        None => transcript_state.lock().map_err(|e| e.to_string())?.attributed_text(),
+    };
+    if full_transcript.trim().is_empty() {
+        return Err("No transcript to summarize".to_string());
+    }
+    let meeting_context = {
+        let manager = meeting_state.lock().map_err(|e| e.to_string())?;
+        manager.get_current_context().cloned()
+    };
+
+    let summary = insights::generate_summary(&llm, &full_transcript, meeting_context.as_ref()).await?;
+    if let Some(context) = &meeting_context {
+        track_action_items(&meeting_state, context.id, summary.action_items.clone())?;
+    }
+    Ok(summary)
+}
+
+/// Gather what goes into an export from the session's state
+fn summary_data(app_handle: &tauri::AppHandle, summary: Option<MeetingSummary>) -> Result<SummaryData, String> {
+    let meeting_state = app_handle.state::<Arc<Mutex<MeetingContextManager>>>();
+    let context = meeting_state.lock().map_err(|e| e.to_string())?.get_current_context().cloned();
+    let transcript_state = app_handle.state::<SharedTranscriptStore>();
+    let segments = transcript_state.lock().map_err(|e| e.to_string())?.segments();
+    let latest_insights = app_handle.state::<SharedLatestInsights>();
+    let insights = latest_insights.lock().map_err(|e| e.to_string())?.clone();
+    let diarization = app_handle.state::<SharedDiarizationState>();
+    let talk_time = diarization.lock().map_err(|e| e.to_string())?.as_ref().map(|engine| engine.speaker_stats());
+    let memory = app_handle.state::<SharedAssistantMemory>();
+    let assistant_summary = memory.lock().map_err(|e| e.to_string())?.summary();
+    Ok(SummaryData {
+        context,
+        summary,
+        insights,
+        segments,
+        talk_time,
+        assistant_summary,
+        exported_at: chrono::Local::now(),
+    })
+}
+
+/// The path to export to, or a timestamped file in the app data dir if none was given
+fn export_path(path: Option<String>, extension: &str) -> Result<std::path::PathBuf, String> {
+    let path = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => export::default_export_path(extension)?,
+    };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    Ok(path)
+}
+
+/// Write the meeting's details, summary and transcript as Markdown or PDF, returning the path.
+/// `summary` is the one from `generate_meeting_summary`, the latest insights are used if not given.
+/// Defaults to a timestamped file in the app data dir.
+#[tauri::command]
+fn export_meeting(
+    app_handle: tauri::AppHandle,
+    format: ExportFormat,
+    path: Option<String>,
+    summary: Option<MeetingSummary>,
+) -> Result<String, String> {
+    let data = summary_data(&app_handle, summary)?;
+    let markdown = export::meeting_markdown(&data);
+
+    let file = match format {
+        ExportFormat::Markdown => {
+            let file = export_path(path, "md")?;
+            std::fs::write(&file, markdown).map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+            file
+        }
+        ExportFormat::Pdf => {
+            let file = export_path(path, "pdf")?;
+            let title = data.context.as_ref().map_or("Meeting", |context| context.title.as_str());
+            export::write_pdf(&file, title, &markdown)?;
+            file
+        }
+    };
+    Ok(file.display().to_string())
+}
+
+/// Write the whole meeting to Markdown for sharing: its details and goals, the latest action items
+/// and decisions, key point coverage, talk time and the transcript. Returns the path written.
+#[tauri::command]
+fn export_meeting_summary(app_handle: tauri::AppHandle, path: Option<String>) -> Result<String, String> {
+    let data = summary_data(&app_handle, None)?;
+    let file = export_path(path, "md")?;
+    std::fs::write(&file, export::meeting_markdown(&data))
+        .map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+    Ok(file.display().to_string())
+}
+
+/// Most tokens of transcript revised in one call. The revision is about as long, so both fit
+/// with room to spare, and a smaller context window shrinks it further.
+const REVISION_CHUNK_TOKENS: usize = 2_000;
+
+/// Revise a long transcript in chunks that fit the model, stitching the revised chunks together
+#[tauri::command]
+async fn revise_transcript(
+    full_transcript: Option<String>,
+    llm: tauri::State<'_, LlmClient>,
+    transcript_state: tauri::State<'_, SharedTranscriptStore>,
+) -> Result<String, String> {
+    // Default to the transcript accumulated this session
+    let full_transcript = match full_transcript {
+        Some(text) => text,
+        None => transcript_state.lock().map_err(|e| e.to_string())?.full_text(),
+    };
+
+    let chunk_tokens = REVISION_CHUNK_TOKENS.min(llm.context_window() / 3);
+    let chunks = chunking::chunk_text(&full_transcript, chunk_tokens);
+    println!(
+        "Revising full transcript in {} chunk(s) via: {} (Model: {})",
+        chunks.len(),
+        llm.api_url(),
+        llm.model()
+    );
+
+    let mut revised = Vec::with_capacity(chunks.len());
+    for (i, chunk) in chunks.iter().enumerate() {
+        let part = if chunks.len() > 1 {
+            format!(" (part {} of {})", i + 1, chunks.len())
+        } else {
+            String::new()
+        };
+        let prompt = format!(
+            "You are revising a conversation transcript with the benefit of full context. Review the conversation and improve the accuracy of earlier transcriptions.
+
+Conversation transcript{}:
+{}
+
+Return ONLY the corrected, flowing text of this part of the conversation, all of it. Do not include timestamps, speaker labels, explanations, or any formatting. Just the natural conversation text:",
+            part, chunk
+        );
+
+        // The revision is about as long as the chunk, leave room for it to run a little longer
+        let options = CompletionOptions {
+            max_tokens: Some((chunking::estimate_tokens(chunk) * 3 / 2 + 100) as u32),
+            temperature: Some(0.2),
+            ..Default::default()
+        };
+        match llm.complete(&[ChatMessage::user(prompt)], options).await? {
+            Some(content) => revised.push(content.trim().to_string()),
+            None => {
+                // Fallback - keep the original chunk if its revision fails
+                println!("Revision of chunk {} failed, keeping the original", i + 1);
+                revised.push(chunk.clone());
+            }
+        }
+    }
+    Ok(revised.join(" "))
+}
+
+/// Forget what the assistant has read and its rolling summary, so the next call starts over
+#[tauri::command]
+fn reset_assistant_memory(memory: tauri::State<'_, SharedAssistantMemory>) -> Result<(), String> {
+    memory.lock().map_err(|e| e.to_string())?.reset();
+    Ok(())
+}
+
+#[tauri::command]
+fn get_full_transcript(state: tauri::State<'_, SharedTranscriptStore>) -> Result<Vec<StoredSegment>, String> {
+    let store = state.lock().map_err(|e| e.to_string())?;
+    Ok(store.segments())
+}
+
+#[tauri::command]
+fn clear_transcript(state: tauri::State<'_, SharedTranscriptStore>) -> Result<(), String> {
+    let mut store = state.lock().map_err(|e| e.to_string())?;
+    store.clear();
+    Ok(())
+}
+
+#[tauri::command]
+fn set_transcript_limit(max_segments: usize, state: tauri::State<'_, SharedTranscriptStore>) -> Result<(), String> {
+    let mut store = state.lock().map_err(|e| e.to_string())?;
+    store.set_max_segments(max_segments);
+    Ok(())
+}
+
+#[tauri::command]
+async fn start_listening(
+    app_handle: tauri::AppHandle,
+    device_name: Option<String>,
+    state: tauri::State<'_, SharedSttState>,
+) -> Result<(), String> {
+    if let Some(name) = device_name {
+        stt::set_input_device(state.inner(), name)?;
+    }
+    stt::start_stt(app_handle, state.inner().clone()).await
+}
+
+#[tauri::command]
+fn stop_listening(app_handle: tauri::AppHandle, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::stop_stt(&app_handle, state.inner())
+}
+
+#[tauri::command]
+fn pause_listening(app_handle: tauri::AppHandle, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::pause_stt(&app_handle, state.inner())
+}
+
+#[tauri::command]
+fn resume_listening(app_handle: tauri::AppHandle, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::resume_stt(&app_handle, state.inner())
+}
+
+#[tauri::command]
+fn get_stt_status(state: tauri::State<'_, SharedSttState>) -> SttStatus {
+    stt::get_stt_status(state.inner())
+}
+
+#[tauri::command]
+fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
+    audio::list_devices()
+}
+
+#[tauri::command]
+fn list_input_devices() -> Result<Vec<AudioDeviceInfo>, String> {
+    audio::list_input_devices()
+}
+
+#[tauri::command]
+fn set_input_device(name: String, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::set_input_device(state.inner(), name)
+}
+
+#[tauri::command]
+fn set_capture_source(
+    source: String,
+    microphone_gain: Option<f32>,
+    system_gain: Option<f32>,
+    state: tauri::State<'_, SharedSttState>,
+) -> Result<(), String> {
+    stt::set_capture_source(state.inner(), source.parse()?, microphone_gain, system_gain)
+}
+
+#[tauri::command]
+fn set_audio_buffer_duration(seconds: usize, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::set_buffer_duration(state.inner(), seconds)
+}
+
+#[tauri::command]
+async fn transcribe_file(
+    app_handle: tauri::AppHandle,
+    path: String,
+    state: tauri::State<'_, SharedSttState>,
+) -> Result<String, String> {
+    stt::transcribe_file(app_handle, state.inner(), path).await
+}
+
+#[tauri::command]
+fn set_import_limit(seconds: u64, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::set_import_limit(state.inner(), seconds)
+}
+
+#[tauri::command]
+fn get_audio_stats(state: tauri::State<'_, SharedSttState>) -> Result<Option<audio::CaptureStats>, String> {
+    stt::get_capture_stats(state.inner())
+}
+
+#[tauri::command]
+fn set_whisper_threads(threads: Option<usize>, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::set_whisper_threads(state.inner(), threads)
+}
+
+#[tauri::command]
+fn set_transcription_overlap(overlap_ms: u32, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::set_transcription_overlap(state.inner(), overlap_ms)
+}
+
+#[tauri::command]
+fn set_gpu_enabled(enabled: bool, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::set_gpu_enabled(state.inner(), enabled)
+}
+
+#[tauri::command]
+async fn benchmark_model(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, SharedSttState>,
+) -> Result<whisper::BenchmarkResult, String> {
+    let clip = app_handle
+        .path()
+        .resolve(whisper::BENCHMARK_CLIP, tauri::path::BaseDirectory::Resource)
+        .ok();
+    stt::benchmark_model(state.inner(), clip).await
+}
+
+#[tauri::command]
+fn set_transcription_language(
+    lang: Option<String>,
+    state: tauri::State<'_, SharedSttState>,
+) -> Result<(), String> {
+    stt::set_transcription_language(state.inner(), lang)
+}
+
+#[tauri::command]
+fn set_vad_config(
+    silence_threshold: f32,
+    hangover_ms: u32,
+    state: tauri::State<'_, SharedSttState>,
+) -> Result<(), String> {
+    stt::set_vad_config(state.inner(), VadConfig { silence_threshold, hangover_ms })
+}
+
+#[tauri::command]
+fn set_stt_sensitivity(
+    min_level_dbfs: f32,
+    max_no_speech_prob: f32,
+    state: tauri::State<'_, SharedSttState>,
+) -> Result<(), String> {
+    stt::set_sensitivity(state.inner(), SttSensitivity { min_level_dbfs, max_no_speech_prob })
+}
+
+/// Tune how often audio is processed and how long utterances may be, trading latency for accuracy
+#[tauri::command]
+fn configure_stt(config: SttConfig, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::configure_stt(state.inner(), config)
+}
+
+#[tauri::command]
+fn get_stt_config(state: tauri::State<'_, SharedSttState>) -> Result<SttConfig, String> {
+    stt::get_stt_config(state.inner())
+}
+
+#[tauri::command]
+fn set_diarization_enabled(enabled: bool, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::set_diarization_enabled(state.inner(), enabled)
+}
+
+/// Answer questions detected in the live transcript without waiting for `process_transcript`.
+/// Needs diarization, which is what flags questions.
+#[tauri::command]
+fn set_auto_answer_questions(enabled: bool, auto_answer: tauri::State<'_, AutoAnswer>) {
+    auto_answer.set_enabled(enabled);
+}
+
+/// Correct each finalized segment with the LLM as it's transcribed, emitting `corrected_transcript`
+#[tauri::command]
+fn set_auto_correct_transcript(enabled: bool, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::set_auto_correct(state.inner(), enabled)
+}
+
+#[tauri::command]
+fn set_hallucination_filter(enabled: bool, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::set_hallucination_filter(state.inner(), enabled)
+}
+
+#[tauri::command]
+fn set_audio_recording(
+    app_handle: tauri::AppHandle,
+    enabled: bool,
+    path: Option<std::path::PathBuf>,
+    overwrite: Option<bool>,
+    state: tauri::State<'_, SharedSttState>,
+) -> Result<(), String> {
+    let settings = RecordingSettings {
+        enabled,
+        path,
+        overwrite: overwrite.unwrap_or(false),
+    };
+    stt::set_audio_recording(&app_handle, state.inner(), settings)
+}
+
+#[tauri::command]
+fn start_recording(
+    app_handle: tauri::AppHandle,
+    path: Option<std::path::PathBuf>,
+    overwrite: Option<bool>,
+    state: tauri::State<'_, SharedSttState>,
+) -> Result<(), String> {
+    set_audio_recording(app_handle, true, path, overwrite, state)
+}
+
+#[tauri::command]
+fn stop_recording(app_handle: tauri::AppHandle, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    set_audio_recording(app_handle, false, None, None, state)
+}
+
+/// Progress payload for the `model_download_progress` event.
+/// `total` and `percent` are absent when the server omits Content-Length.
+#[derive(Clone, serde::Serialize)]
+struct DownloadProgress {
+    model: String,
+    downloaded: u64,
+    total: Option<u64>,
+    percent: Option<f64>,
+}
+
+/// Minimum number of bytes between two progress events
+const PROGRESS_EMIT_INTERVAL: u64 = 1024 * 1024;
+/// Longest wait for a connection to the model download server
+const DOWNLOAD_CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+/// Longest wait for the checksum of a model to download
+const CHECKSUM_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Cancellation flag for the in-flight model download
+#[derive(Default)]
+struct DownloadCancel(AtomicBool);
+
+/// Stream a response body into a file, emitting progress as chunks arrive.
+/// `offset` is the number of bytes already on disk when resuming.
+async fn stream_to_file(
+    app_handle: &tauri::AppHandle,
+    model: &str,
+    response: reqwest::Response,
+    file: &mut std::fs::File,
+    offset: u64,
+    cancel: &AtomicBool,
+) -> Result<(), String> {
+    use futures_util::StreamExt;
+    use std::io::Write;
+
+    let total = response.content_length().map(|len| len + offset);
+    let mut downloaded: u64 = offset;
+    let mut last_emitted: u64 = offset;
+    let mut stream = response.bytes_stream();
+
+    while let Some(chunk) = stream.next().await {
+        if cancel.load(Ordering::SeqCst) {
+            file.flush().map_err(|e| format!("Failed to write model: {}", e))?;
+            return Err("Download cancelled".to_string());
+        }
+
+        let chunk = chunk.map_err(|e| format!("Failed to download: {}", e))?;
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write model: {}", e))?;
+
+        downloaded += chunk.len() as u64;
+        if downloaded - last_emitted >= PROGRESS_EMIT_INTERVAL || Some(downloaded) == total {
+            last_emitted = downloaded;
+            emit_event(
+                app_handle,
+                "model_download_progress",
+                DownloadProgress {
+                    model: model.to_string(),
+                    downloaded,
+                    total,
+                    percent: total.filter(|t| *t > 0).map(|t| downloaded as f64 / t as f64 * 100.0),
+                },
+            );
+        }
+    }
+
+    file.flush().map_err(|e| format!("Failed to write model: {}", e))?;
+
+    if let Some(total) = total {
+        if downloaded != total {
+            return Err(format!("Download incomplete: received {} of {} bytes", downloaded, total));
+        }
+    }
+    Ok(())
+}
+
+/// SHA256 Hugging Face publishes for a file kept in Git LFS. It's sent as X-Linked-Etag on the
+/// redirect a `resolve` URL answers with, so the redirect isn't followed.
+async fn published_sha256(url: &str) -> Result<String, String> {
+    let client = Client::builder()
+        .connect_timeout(DOWNLOAD_CONNECT_TIMEOUT)
+        .timeout(CHECKSUM_REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| format!("Failed to create download client: {}", e))?;
+    let response = client
+        .head(url)
+        .send()
+        .await
+        .map_err(|e| format!("Checksum request failed: {}", e))?;
+    response
+        .headers()
+        .get("x-linked-etag")
+        .and_then(|value| value.to_str().ok())
+        .and_then(whisper::parse_sha256)
+        .ok_or_else(|| format!("No checksum published for {}", url))
+}
+
+/// Download `url` into `part_path`, resuming from a previous partial download if there is one
+async fn download_resumable(
+    app_handle: &tauri::AppHandle,
+    model: &str,
+    url: &str,
+    part_path: &std::path::Path,
+    cancel: &AtomicBool,
+) -> Result<(), String> {
+    let existing_len = std::fs::metadata(part_path).map(|m| m.len()).unwrap_or(0);
+    println!("Downloading model from: {} (resuming at {} bytes)", url, existing_len);
+
+    // No overall timeout, a large model takes a while, but a dead server shouldn't hang the download
+    let client = Client::builder()
+        .connect_timeout(DOWNLOAD_CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create download client: {}", e))?;
+    let mut request = client.get(url);
+    if existing_len > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing_len));
+    }
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| format!("Download request failed: {}", e))?;
+
+    // A stale partial file the server can't resume from: start over
+    if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        let _ = std::fs::remove_file(part_path);
+        response = client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| format!("Download request failed: {}", e))?;
+    }
+
+    if !response.status().is_success() {
+        return Err(format!("Download failed with status {}", response.status()));
+    }
+
+    // 206 appends to the partial file, anything else is a full download
+    let resuming = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut file = if resuming {
+        std::fs::OpenOptions::new().append(true).open(part_path)
+    } else {
+        std::fs::File::create(part_path)
+    }
+    .map_err(|e| format!("Failed to create model file: {}", e))?;
+
+    // Keep the partial file on failure so the next attempt can resume
+    let offset = if resuming { existing_len } else { 0 };
+    stream_to_file(app_handle, model, response, &mut file, offset, cancel).await
+}
+
+#[tauri::command]
+async fn download_model(
+    app_handle: tauri::AppHandle,
+    size: Option<String>,
+    state: tauri::State<'_, SharedSttState>,
+    cancel: tauri::State<'_, DownloadCancel>,
+) -> Result<(), String> {
+    let model_size = resolve_model_size(size, state.inner())?;
+    cancel.0.store(false, Ordering::SeqCst);
+    let model_dir = get_model_dir()?;
+    let model_path = get_model_path(model_size)?;
+    let part_path = whisper::get_partial_model_path(model_size)?;
+
+    // Create directory if it doesn't exist
+    std::fs::create_dir_all(&model_dir)
+        .map_err(|e| format!("Failed to create model directory: {}", e))?;
+
+    // A model that can't be verified, e.g. from before its checksum was recorded, is downloaded again
+    if model_path.exists() && whisper::model_sha256(model_size).is_some() {
+        return Ok(());
+    }
+
+    // Models without a pinned checksum are checked against the one the server publishes
+    if model_size.expected_sha256().is_none() {
+        let sha256 = published_sha256(&model_size.download_url()).await?;
+        whisper::record_sha256(&whisper::get_model_checksum_path(model_size)?, &sha256)?;
+    }
+
+    download_resumable(
+        &app_handle,
+        model_size.name(),
+        &model_size.download_url(),
+        &part_path,
+        &cancel.0,
+    )
+    .await?;
+
+    // Catch truncated downloads and HTML error pages before they reach whisper
+    match whisper::verify_model(model_size, &part_path)? {
+        ModelIntegrity::Verified => {}
+        ModelIntegrity::Corrupted => {
+            let _ = std::fs::remove_file(&part_path);
+            return Err("Model checksum mismatch, download corrupted".to_string());
+        }
+        ModelIntegrity::Unverified => {
+            let _ = std::fs::remove_file(&part_path);
+            return Err(format!("No checksum known for model '{}', download not verified", model_size.name()));
+        }
+    }
+
+    std::fs::rename(&part_path, &model_path)
+        .map_err(|e| format!("Failed to finalize model file: {}", e))?;
+    stt::mark_model_verified(state.inner(), model_size)?;
+
+    println!("Model downloaded to: {:?}", model_path);
+
+    Ok(())
+}
+
+/// Abort the running model download, keeping the partial file for a later resume
+#[tauri::command]
+fn cancel_model_download(cancel: tauri::State<'_, DownloadCancel>) {
+    cancel.0.store(true, Ordering::SeqCst);
+}
+
+/// Relabel a speaker in the diarization engine and the stored transcript, then emit `speakers_updated`
+fn relabel_speaker(
+    app_handle: &tauri::AppHandle,
+    diarization: &SharedDiarizationState,
+    transcript: &SharedTranscriptStore,
+    speaker_id: &str,
+    label: &str,
+    participant: Option<&str>,
+) -> Result<Vec<SpeakerInfo>, String> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err("Speaker label can't be empty".to_string());
+    }
+
+    let (speakers, unlinked) = {
+        let mut engine = diarization.lock().map_err(|e| e.to_string())?;
+        let engine = engine.as_mut().ok_or("Diarization engine not initialized")?;
+        let unlinked = engine.relabel_speaker(speaker_id, label, participant)?;
+        (engine.speakers(), unlinked)
+    };
+    diarization::relabel_transcript(&mut *transcript.lock().map_err(|e| e.to_string())?, speaker_id, label, &unlinked);
+
+    emit_event(app_handle, "speakers_updated", &speakers);
+    Ok(speakers)
+}
+
+/// Give a detected speaker a name, also applied to their earlier transcript segments
+#[tauri::command]
+fn rename_speaker(
+    app_handle: tauri::AppHandle,
+    speaker_id: String,
+    label: String,
+    diarization: tauri::State<'_, SharedDiarizationState>,
+    transcript_state: tauri::State<'_, SharedTranscriptStore>,
+) -> Result<Vec<SpeakerInfo>, String> {
+    relabel_speaker(&app_handle, &diarization, &transcript_state, &speaker_id, &label, None)
+}
+
+/// Assign a detected speaker to a participant of the current meeting, naming the speaker after them
+/// and marking them present. A speaker assigned to the participant before goes back to its numbered label.
+#[tauri::command]
+fn assign_speaker_to_participant(
+    app_handle: tauri::AppHandle,
+    speaker_id: String,
+    participant_name: String,
+    diarization: tauri::State<'_, SharedDiarizationState>,
+    transcript_state: tauri::State<'_, SharedTranscriptStore>,
+    meeting_state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<Vec<SpeakerInfo>, String> {
+    let name = {
+        let manager = meeting_state.lock().map_err(|e| e.to_string())?;
+        let context = manager.get_current_context().ok_or("No active meeting context")?;
+        context
+            .find_participant(&participant_name)
+            .map(|participant| participant.name.clone())
+            .ok_or_else(|| format!("Unknown participant: {}", participant_name))?
+    };
+
+    let speakers = relabel_speaker(&app_handle, &diarization, &transcript_state, &speaker_id, &name, Some(&name))?;
+
+    let mut manager = meeting_state.lock().map_err(|e| e.to_string())?;
+    if let Some(context) = manager.get_current_context_mut() {
+        if let Some(joined) = context.mark_participant_present(&name) {
+            emit_event(&app_handle, "participant_joined", &joined);
+        }
+        schedule_meeting_save(&meeting_state, &mut manager);
+    }
+    Ok(speakers)
+}
+
+/// The name `assign_speaker_to_participant` had before, kept for callers that still use it
+#[tauri::command]
+fn link_speaker_to_participant(
+    app_handle: tauri::AppHandle,
+    speaker_id: String,
+    participant_name: String,
+    diarization: tauri::State<'_, SharedDiarizationState>,
+    transcript_state: tauri::State<'_, SharedTranscriptStore>,
+    meeting_state: tauri::State<'_, Arc<Mutex<MeetingContextManager>>>,
+) -> Result<Vec<SpeakerInfo>, String> {
+    assign_speaker_to_participant(app_handle, speaker_id, participant_name, diarization, transcript_state, meeting_state)
+}
+
+/// Microphone audio recorded to enroll a speaker
+const ENROLLMENT_SECONDS: u64 = 10;
+
+/// Voices saved for recognizing speakers across meetings
+#[tauri::command]
+fn list_known_speakers(profiles: tauri::State<'_, SharedSpeakerProfiles>) -> Result<Vec<KnownSpeakerInfo>, String> {
+    Ok(profiles.lock().map_err(|e| e.to_string())?.list())
+}
+
+#[tauri::command]
+fn delete_known_speaker(name: String, profiles: tauri::State<'_, SharedSpeakerProfiles>) -> Result<(), String> {
+    let mut profiles = profiles.lock().map_err(|e| e.to_string())?;
+    if !profiles.remove(&name) {
+        return Err(format!("Unknown speaker: {}", name));
+    }
+    profiles.save()
+}
+
+/// Record the microphone for ENROLLMENT_SECONDS and save the voice as a known speaker
+#[tauri::command]
+async fn enroll_speaker(
+    name: String,
+    stt_state: tauri::State<'_, SharedSttState>,
+    profiles: tauri::State<'_, SharedSpeakerProfiles>,
+) -> Result<KnownSpeakerInfo, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Speaker name can't be empty".to_string());
+    }
+
+    let samples = stt::record_microphone(stt_state.inner(), ENROLLMENT_SECONDS).await?;
+    let embedding = speaker_profiles::voice_embedding(&samples, audio::WHISPER_SAMPLE_RATE)
+        .ok_or("Not enough speech heard to enroll, keep talking for the whole recording")?;
+
+    let mut profiles = profiles.lock().map_err(|e| e.to_string())?;
+    profiles.upsert(&name, &embedding, ENROLLMENT_SECONDS as f32);
+    profiles.save()?;
+    profiles
+        .list()
+        .into_iter()
+        .find(|known| known.name == name)
+        .ok_or_else(|| format!("Failed to enroll {}", name))
+}
+
+#[tauri::command]
+fn check_model_exists(size: Option<String>, state: tauri::State<'_, SharedSttState>) -> Result<bool, String> {
+    Ok(whisper::model_exists(resolve_model_size(size, state.inner())?))
+}
+
+/// Check a downloaded model against its published checksum, "unverified" when none is known,
+/// in which case it isn't loaded until downloaded again
+#[tauri::command]
+fn verify_model(size: Option<String>, state: tauri::State<'_, SharedSttState>) -> Result<ModelIntegrity, String> {
+    stt::verify_model(state.inner(), resolve_model_size(size, state.inner())?)
+}
+
+#[tauri::command]
+fn set_active_model(size: String, state: tauri::State<'_, SharedSttState>) -> Result<(), String> {
+    stt::set_active_model(state.inner(), size.parse()?)
+}
+
+/// Use the requested model size, or the active model when none is given
+fn resolve_model_size(size: Option<String>, state: &SharedSttState) -> Result<ModelSize, String> {
+    match size {
+        Some(size) => size.parse(),
+        None => stt::get_active_model(state),
+    }
+}
+
+/// Correct speech-to-text mistakes in `text`. Without a context, the last few
+/// finalized segments of the current session are used.
+#[tauri::command]
+async fn correct_transcript(
+    text: String,
+    context: Option<String>,
+    llm: tauri::State<'_, LlmClient>,
+    state: tauri::State<'_, SharedSttState>,
+) -> Result<String, String> {
+    llm.check_budget()?;
+    let context = match context {
+        Some(context) => context,
+        None => stt::correction_context(state.inner())?,
+    };
+    correction::correct_text(&llm, &text, Some(&context)).await
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    // Load .env before anything reads its settings
+    dotenv().ok();
+    recording::repair_unfinished_recording();
+    // Unreadable files are set aside and start over empty, the user is told once the app is up
+    let mut load_errors = Vec::new();
+    let meetings = storage::loaded_or_default(MeetingContextManager::load_from_disk(), &mut load_errors);
+    let speaker_profiles = storage::loaded_or_default(SpeakerProfiles::load(), &mut load_errors);
+    let templates = storage::loaded_or_default(TemplateStore::load(), &mut load_errors);
+    // LLM settings saved from the app take over from the environment
+    let settings = storage::loaded_or_default(AppSettings::load(), &mut load_errors);
+    let llm_config = settings.llm.clone().unwrap_or_else(LlmConfig::from_env);
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .manage(Arc::new(Mutex::new(SttState::default())) as SharedSttState)
-        .manage(Arc::new(Mutex::new(MeetingContextManager::default())))
+        .manage(Arc::new(Mutex::new(meetings)))
+        .manage(DownloadCancel::default())
+        .manage(LlmClient::new(llm_config))
+        .manage(AutoAnswer::default())
+        .manage(SharedAssistantMemory::default())
+        .manage(SearchBackend::from_env())
+        .manage(SharedSearchCache::default())
+        .manage(SharedDiarizationState::default())
+        .manage(Arc::new(Mutex::new(speaker_profiles)) as SharedSpeakerProfiles)
+        .manage(Arc::new(Mutex::new(templates)) as SharedTemplateStore)
+        .manage(Arc::new(Mutex::new(settings)) as SharedSettings)
+        .manage(Arc::new(Mutex::new(TranscriptStore::default())) as SharedTranscriptStore)
+        .manage(SharedLatestInsights::default())
+        .setup(move |app| {
+            app.state::<LlmClient>().attach(app.handle().clone());
+            for error in load_errors {
+                report_error(app.handle(), ErrorSource::Storage, error);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             process_transcript,
+            reset_assistant_memory,
             correct_transcript,
             revise_transcript,
+            generate_meeting_summary,
+            export_meeting,
+            export_meeting_summary,
+            get_meeting_insights,
+            extract_action_items,
+            get_action_items,
+            update_action_item,
             start_listening,
             stop_listening,
+            pause_listening,
+            resume_listening,
             get_stt_status,
+            list_audio_devices,
+            list_input_devices,
+            set_input_device,
+            set_capture_source,
+            set_vad_config,
+            set_stt_sensitivity,
+            configure_stt,
+            get_stt_config,
+            set_hallucination_filter,
+            set_transcription_language,
+            set_gpu_enabled,
+            set_transcription_overlap,
+            set_whisper_threads,
+            get_full_transcript,
+            clear_transcript,
+            set_transcript_limit,
+            set_audio_buffer_duration,
+            get_audio_stats,
+            transcribe_file,
+            set_import_limit,
+            benchmark_model,
+            set_audio_recording,
+            start_recording,
+            stop_recording,
             download_model,
+            cancel_model_download,
             check_model_exists,
+            rename_speaker,
+            assign_speaker_to_participant,
+            link_speaker_to_participant,
+            list_known_speakers,
+            delete_known_speaker,
+            enroll_speaker,
+            verify_model,
+            set_active_model,
             initialize_diarization_engine,
+            update_diarization_config,
             process_audio_diarization,
             get_example_speakers,
+            get_current_speakers,
+            get_speaker_stats,
+            set_expected_speaker_count,
+            set_diarization_enabled,
+            set_auto_answer_questions,
+            set_auto_correct_transcript,
             set_meeting_context,
             get_current_meeting_context,
             add_meeting_participant,
+            set_participant_presence,
+            remove_participant,
             add_meeting_goal,
+            update_goal_status,
+            remove_goal,
+            add_agenda_item,
+            move_agenda_item,
+            remove_agenda_item,
+            start_agenda_item,
+            complete_agenda_item,
+            set_agenda_overrun_threshold,
+            check_goal_progress,
+            get_coverage_report,
+            add_question,
+            list_questions,
+            mark_question_asked,
+            set_question_match_threshold,
+            generate_meeting_questions,
+            prepare_meeting,
             clear_meeting_context,
+            list_saved_meetings,
+            load_meeting_context,
+            create_meeting_context,
+            activate_meeting_context,
+            get_meeting_context,
+            list_meeting_contexts,
+            set_custom_prompt,
+            get_settings,
+            set_default_prompt,
+            set_privacy_mode,
+            get_llm_config,
+            set_llm_config,
+            test_llm_connection,
+            validate_llm_config,
+            get_llm_usage,
+            set_llm_token_budget,
+            get_context_history,
+            get_history_entry,
+            delete_history_entry,
+            set_context_history_limit,
+            list_templates,
+            apply_template,
+            create_context_from_template,
+            save_context_as_template,
+            delete_template,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,1115 @@
+//! LLM client
+//! Sends chat completions to the endpoint configured in the settings or the environment
+
+use crate::events::emit_event;
+use crate::http_util::{RetryAttempt, RetryPolicy, DEFAULT_MAX_ATTEMPTS};
+use crate::settings::SharedSettings;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// Endpoint used when LLM_API_URL isn't set
+const DEFAULT_API_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+/// Model used when LLM_MODEL isn't set
+const DEFAULT_MODEL: &str = "google/gemini-2.0-flash-001";
+/// API version sent to Anthropic's Messages API
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// Anthropic requires `max_tokens`, this is used when a call doesn't set one
+const ANTHROPIC_DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Request and response format spoken by the LLM endpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LlmProvider {
+    /// OpenAI chat completions, also used by OpenRouter and Ollama
+    OpenAi,
+    /// Anthropic Messages API
+    Anthropic,
+}
+
+impl LlmProvider {
+    /// Use the configured provider when set, otherwise recognize Anthropic by its URL
+    fn detect(explicit: Option<&str>, api_url: &str) -> Self {
+        match explicit.map(|p| p.trim().to_lowercase()).as_deref() {
+            Some("anthropic") => return Self::Anthropic,
+            Some("openai") => return Self::OpenAi,
+            Some(other) => eprintln!("Unknown LLM provider '{}', detecting from the URL", other),
+            None => {}
+        }
+        if api_url.contains("api.anthropic.com") || api_url.trim_end_matches('/').ends_with("/v1/messages") {
+            Self::Anthropic
+        } else {
+            Self::OpenAi
+        }
+    }
+
+    /// Reply text of a non-streamed response
+    fn response_text(self, json: &serde_json::Value) -> Option<String> {
+        match self {
+            Self::OpenAi => json["choices"][0]["message"]["content"].as_str().map(str::to_string),
+            Self::Anthropic => {
+                let blocks = json["content"].as_array()?;
+                let text: String = blocks
+                    .iter()
+                    .filter(|block| block["type"] == "text")
+                    .filter_map(|block| block["text"].as_str())
+                    .collect();
+                Some(text)
+            }
+        }
+    }
+}
+
+/// Tokens a call used, as reported by the provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl TokenUsage {
+    /// The `usage` of a response or stream event, in OpenAI's or Anthropic's field names.
+    /// Anthropic's stream reports the prompt's in `message.usage` at the start.
+    fn from_json(json: &serde_json::Value) -> Option<Self> {
+        let usage = json
+            .get("usage")
+            .filter(|usage| usage.is_object())
+            .or_else(|| json["message"].get("usage").filter(|usage| usage.is_object()))?;
+        let prompt_tokens = usage["prompt_tokens"].as_u64().or_else(|| usage["input_tokens"].as_u64());
+        let completion_tokens = usage["completion_tokens"].as_u64().or_else(|| usage["output_tokens"].as_u64());
+        if prompt_tokens.is_none() && completion_tokens.is_none() {
+            return None;
+        }
+        Some(Self {
+            prompt_tokens: prompt_tokens.unwrap_or(0),
+            completion_tokens: completion_tokens.unwrap_or(0),
+        })
+    }
+
+    /// Combine usage reported in pieces over a stream, where each count is a running total
+    fn merge(&mut self, other: Self) {
+        self.prompt_tokens = self.prompt_tokens.max(other.prompt_tokens);
+        self.completion_tokens = self.completion_tokens.max(other.completion_tokens);
+    }
+}
+
+/// Payload of the `llm_usage` and `llm_budget_exceeded` events: what this session's calls used
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct LlmUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub calls: u32,
+    /// From the settings, None for no limit
+    pub budget: Option<u64>,
+    pub budget_exceeded: bool,
+}
+
+/// Longest wait for a connection to the LLM endpoint
+const LLM_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest a completion may take, or a streamed one may take to start, unless configured
+const LLM_REQUEST_TIMEOUT: Duration = Duration::from_secs(90);
+/// Longest silence in a streamed completion before it's given up on
+const LLM_READ_TIMEOUT: Duration = Duration::from_secs(30);
+/// Highest sampling temperature accepted as a default
+const MAX_TEMPERATURE: f32 = 2.0;
+/// Context window assumed when not configured, small enough for most hosted and local models
+const DEFAULT_CONTEXT_WINDOW: u32 = 32_000;
+/// Smallest context window accepted, below it prompts alone wouldn't fit
+const MIN_CONTEXT_WINDOW: u32 = 2_000;
+
+/// Describe a failed LLM request, telling a timeout apart from other failures
+fn request_error(e: reqwest::Error) -> String {
+    if e.is_timeout() {
+        format!("LLM request timed out, the endpoint didn't respond in time: {}", e)
+    } else {
+        format!("LLM Request Failed: {}", e)
+    }
+}
+
+/// Whether a failed response says the configured model doesn't exist. Most providers answer
+/// 404, OpenRouter a 400 saying the model ID isn't valid.
+fn is_model_not_found(status: reqwest::StatusCode, body: &str) -> bool {
+    let body = body.to_lowercase();
+    status == reqwest::StatusCode::NOT_FOUND
+        || (status == reqwest::StatusCode::BAD_REQUEST
+            && body.contains("model")
+            && (body.contains("not found") || body.contains("not a valid") || body.contains("does not exist")))
+}
+
+/// Describe a failed response, calling out a refused key or an unknown model
+fn status_error(status: reqwest::StatusCode, body: &str, model: &str) -> String {
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        format!("LLM API key was refused ({}), check LLM_API_KEY: {}", status, body)
+    } else if is_model_not_found(status, body) {
+        format!("LLM model '{}' was not found ({}): {}", model, status, body)
+    } else {
+        format!("LLM Request Failed: {} {}", status, body)
+    }
+}
+
+/// One line of a streamed (SSE) chat completion
+#[derive(Debug, PartialEq)]
+enum StreamLine {
+    /// Text generated since the previous line
+    Delta(String),
+    /// Tokens used so far, sent by some providers near the end
+    Usage(TokenUsage),
+    /// The provider signalled the end of the stream
+    Done,
+    /// Blank lines, comments, event names and deltas without text
+    Skip,
+}
+
+/// Parse one line of an SSE chat completion stream.
+/// Providers differ in `data:` spacing, keep-alive comments and the final `[DONE]` sentinel,
+/// so anything that isn't a recognizable delta is skipped rather than treated as an error.
+fn parse_stream_line(line: &str) -> Result<StreamLine, String> {
+    let line = line.trim();
+    let data = match line.strip_prefix("data:") {
+        Some(data) => data.trim_start(),
+        None => return Ok(StreamLine::Skip),
+    };
+    if data == "[DONE]" {
+        return Ok(StreamLine::Done);
+    }
+
+    let json: serde_json::Value = match serde_json::from_str(data) {
+        Ok(json) => json,
+        Err(_) => return Ok(StreamLine::Skip),
+    };
+    if let Some(error) = json.get("error") {
+        return Err(format!("LLM stream error: {}", error));
+    }
+
+    // OpenAI-style deltas, with the older completion, Ollama and Anthropic shapes as fallbacks
+    let choice = &json["choices"][0];
+    let text = choice["delta"]["content"]
+        .as_str()
+        .or_else(|| choice["text"].as_str())
+        .or_else(|| json["message"]["content"].as_str())
+        .or_else(|| json["delta"]["text"].as_str());
+    // OpenAI reports usage after the chunk with the finish reason, so only explicit ends stop reading
+    let done = json["done"].as_bool() == Some(true) || json["type"] == "message_stop";
+
+    match text {
+        Some(text) if !text.is_empty() => Ok(StreamLine::Delta(text.to_string())),
+        _ => match TokenUsage::from_json(&json) {
+            Some(usage) => Ok(StreamLine::Usage(usage)),
+            None if done => Ok(StreamLine::Done),
+            None => Ok(StreamLine::Skip),
+        },
+    }
+}
+
+/// Splits a streamed response into lines as its chunks arrive. Events can be split anywhere,
+/// even inside a character, so bytes are kept until their line's newline arrives.
+#[derive(Default)]
+struct StreamParser {
+    pending: Vec<u8>,
+}
+
+impl StreamParser {
+    /// The lines `chunk` completes, parsed
+    fn push(&mut self, chunk: &[u8]) -> Vec<Result<StreamLine, String>> {
+        self.pending.extend_from_slice(chunk);
+        let mut lines = Vec::new();
+        while let Some(newline) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=newline).collect();
+            lines.push(parse_stream_line(&String::from_utf8_lossy(&line)));
+        }
+        lines
+    }
+
+    /// A final line the stream ended without a newline after
+    fn finish(&mut self) -> Result<StreamLine, String> {
+        let line = std::mem::take(&mut self.pending);
+        parse_stream_line(&String::from_utf8_lossy(&line))
+    }
+}
+
+/// Why a streamed completion failed, with the text received before it did
+#[derive(Debug)]
+pub struct StreamError {
+    pub partial: String,
+    pub message: String,
+}
+
+/// Failures before any text arrived
+impl From<String> for StreamError {
+    fn from(message: String) -> Self {
+        Self {
+            partial: String::new(),
+            message,
+        }
+    }
+}
+
+/// A message of the chat sent to the model
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+/// Sampling settings for a completion, provider defaults when unset
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompletionOptions {
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    /// Ask for a JSON object reply, where the provider supports it
+    pub json: bool,
+}
+
+/// Whether `url` points at this machine, like a local Ollama
+pub fn is_local_url(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    // IPv6 hosts come in brackets
+    let host = host.trim_start_matches('[').trim_end_matches(']').to_lowercase();
+    match host.parse::<std::net::IpAddr>() {
+        Ok(ip) => ip.is_loopback(),
+        Err(_) => host == "localhost" || host.ends_with(".localhost"),
+    }
+}
+
+/// Where LLM calls go and their defaults. Saved in the settings once changed from the app,
+/// until then read from LLM_API_URL, LLM_API_KEY, LLM_MODEL and LLM_PROVIDER.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LlmConfig {
+    pub api_url: String,
+    pub api_key: String,
+    pub model: String,
+    /// "openai" or "anthropic", None to detect it from the URL
+    pub provider: Option<String>,
+    /// For calls that don't set their own, None for the provider's default
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// Longest a completion may take, in seconds
+    pub timeout_secs: u64,
+    /// Attempts at a request that fails transiently, including the first
+    pub max_attempts: u32,
+    /// Tokens the model takes in and writes in one call, long transcripts are split to fit
+    pub context_window: u32,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            api_url: DEFAULT_API_URL.to_string(),
+            api_key: String::new(),
+            model: DEFAULT_MODEL.to_string(),
+            provider: None,
+            temperature: None,
+            max_tokens: None,
+            timeout_secs: LLM_REQUEST_TIMEOUT.as_secs(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            context_window: DEFAULT_CONTEXT_WINDOW,
+        }
+    }
+}
+
+/// Keeps the API key out of logs
+impl std::fmt::Debug for LlmConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LlmConfig")
+            .field("api_url", &self.api_url)
+            .field("api_key", &redact_key(&self.api_key))
+            .field("model", &self.model)
+            .field("provider", &self.provider)
+            .field("temperature", &self.temperature)
+            .field("max_tokens", &self.max_tokens)
+            .field("timeout_secs", &self.timeout_secs)
+            .field("max_attempts", &self.max_attempts)
+            .field("context_window", &self.context_window)
+            .finish()
+    }
+}
+
+/// An API key with all but its last four characters masked, empty if there's none
+pub fn redact_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    match chars.len() {
+        0 => String::new(),
+        len if len <= 8 => "****".to_string(),
+        len => format!("****{}", chars[len - 4..].iter().collect::<String>()),
+    }
+}
+
+impl LlmConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            api_url: env::var("LLM_API_URL").unwrap_or(defaults.api_url),
+            api_key: env::var("LLM_API_KEY").unwrap_or_default(),
+            model: env::var("LLM_MODEL").unwrap_or(defaults.model),
+            provider: env::var("LLM_PROVIDER").ok(),
+            ..defaults
+        }
+    }
+
+    /// The same configuration with the API key masked, for showing in the app
+    pub fn redacted(&self) -> Self {
+        Self {
+            api_key: redact_key(&self.api_key),
+            ..self.clone()
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts,
+            ..RetryPolicy::default()
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !self.api_url.starts_with("http://") && !self.api_url.starts_with("https://") {
+            return Err("LLM API URL must start with http:// or https://".to_string());
+        }
+        if self.model.trim().is_empty() {
+            return Err("LLM model cannot be empty".to_string());
+        }
+        if let Some(provider) = &self.provider {
+            if !matches!(provider.trim().to_lowercase().as_str(), "openai" | "anthropic") {
+                return Err(format!("Unknown LLM provider '{}', use openai or anthropic", provider));
+            }
+        }
+        if self.temperature.is_some_and(|temperature| !(0.0..=MAX_TEMPERATURE).contains(&temperature)) {
+            return Err(format!("Temperature must be between 0 and {}", MAX_TEMPERATURE));
+        }
+        if self.max_tokens == Some(0) {
+            return Err("Max tokens must be at least 1".to_string());
+        }
+        if self.timeout_secs == 0 {
+            return Err("Timeout must be at least 1 second".to_string());
+        }
+        if self.max_attempts == 0 {
+            return Err("Max attempts must be at least 1".to_string());
+        }
+        if self.context_window < MIN_CONTEXT_WINDOW {
+            return Err(format!("Context window must be at least {} tokens", MIN_CONTEXT_WINDOW));
+        }
+        Ok(())
+    }
+}
+
+/// The configuration in use, with the request format it resolves to
+#[derive(Clone)]
+struct Endpoint {
+    config: LlmConfig,
+    provider: LlmProvider,
+}
+
+impl Endpoint {
+    fn new(config: LlmConfig) -> Self {
+        let provider = LlmProvider::detect(config.provider.as_deref(), &config.api_url);
+        Self { config, provider }
+    }
+}
+
+/// Why `test_llm_connection` failed
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LlmConnectionError {
+    /// The endpoint couldn't be reached
+    Connection { message: String },
+    /// No API key is set and the endpoint isn't local
+    MissingKey { message: String },
+    /// Privacy mode is on and the endpoint isn't local
+    PrivacyMode { message: String },
+    Timeout { message: String },
+    /// The key was refused
+    Unauthorized { status: u16, message: String },
+    ModelNotFound { status: u16, message: String },
+    Http { status: u16, message: String },
+    /// The endpoint answered with something that isn't a completion
+    InvalidResponse { message: String },
+}
+
+/// Result of `test_llm_connection`
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmConnectionTest {
+    pub ok: bool,
+    pub latency_ms: u64,
+    pub model: String,
+    /// What the model replied, when it did
+    pub reply: Option<String>,
+    pub error: Option<LlmConnectionError>,
+}
+
+/// Outcome of `validate_llm_config`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmConfigStatus {
+    Reachable,
+    MissingKey,
+    InvalidKey,
+    ModelNotFound,
+    /// The endpoint couldn't be reached or didn't answer in time
+    Unreachable,
+    PrivacyMode,
+    /// Any other failure, like a server error or a response that isn't a completion
+    Error,
+}
+
+/// Result of `validate_llm_config`, a diagnostic to show before a meeting starts
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmConfigCheck {
+    pub status: LlmConfigStatus,
+    /// What to tell the user
+    pub message: String,
+    pub api_url: String,
+    pub model: String,
+    pub latency_ms: u64,
+}
+
+/// Tokens used by LLM calls since the session started
+#[derive(Debug, Default)]
+struct SessionUsage {
+    tokens: TokenUsage,
+    calls: u32,
+}
+
+/// Chat completion client, reconfigurable while the app runs
+pub struct LlmClient {
+    client: Client,
+    endpoint: Mutex<Endpoint>,
+    usage: Mutex<SessionUsage>,
+    /// For usage events and the token budget, set once the app is running
+    app_handle: OnceLock<AppHandle>,
+}
+
+impl LlmClient {
+    pub fn new(config: LlmConfig) -> Self {
+        Self {
+            client: Client::builder()
+                .connect_timeout(LLM_CONNECT_TIMEOUT)
+                .build()
+                .unwrap_or_else(|_| Client::new()),
+            endpoint: Mutex::new(Endpoint::new(config)),
+            usage: Mutex::new(SessionUsage::default()),
+            app_handle: OnceLock::new(),
+        }
+    }
+
+    /// Report usage to the app and read the token budget from its settings
+    pub fn attach(&self, app_handle: AppHandle) {
+        let _ = self.app_handle.set(app_handle);
+    }
+
+    fn token_budget(&self) -> Option<u64> {
+        let settings = self.app_handle.get()?.try_state::<SharedSettings>()?;
+        let budget = settings.lock().ok()?.llm_token_budget;
+        budget
+    }
+
+    /// Tokens used this session, against the budget
+    pub fn usage(&self) -> LlmUsage {
+        let (tokens, calls) = match self.usage.lock() {
+            Ok(usage) => (usage.tokens, usage.calls),
+            Err(_) => (TokenUsage::default(), 0),
+        };
+        let total_tokens = tokens.prompt_tokens + tokens.completion_tokens;
+        let budget = self.token_budget();
+        LlmUsage {
+            prompt_tokens: tokens.prompt_tokens,
+            completion_tokens: tokens.completion_tokens,
+            total_tokens,
+            calls,
+            budget,
+            budget_exceeded: budget.is_some_and(|budget| total_tokens > budget),
+        }
+    }
+
+    /// Start counting a new session, which also lifts a pause from an exceeded budget
+    pub fn reset_usage(&self) {
+        if let Ok(mut usage) = self.usage.lock() {
+            *usage = SessionUsage::default();
+        }
+    }
+
+    /// Refuse calls the app makes on its own once this session is over its token budget.
+    /// Calls the user asks for directly aren't checked.
+    pub fn check_budget(&self) -> Result<(), String> {
+        let usage = self.usage();
+        match usage.budget {
+            Some(budget) if usage.budget_exceeded => Err(format!(
+                "This session used {} tokens, over the budget of {}. Automatic assistant calls are paused.",
+                usage.total_tokens, budget
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Add a call's tokens to the session and emit `llm_usage`, plus `llm_budget_exceeded`
+    /// when this call took the session over its budget
+    fn record_usage(&self, tokens: Option<TokenUsage>) {
+        let before = match self.usage.lock() {
+            Ok(mut usage) => {
+                let before = usage.tokens.prompt_tokens + usage.tokens.completion_tokens;
+                if let Some(tokens) = tokens {
+                    usage.tokens.prompt_tokens += tokens.prompt_tokens;
+                    usage.tokens.completion_tokens += tokens.completion_tokens;
+                }
+                usage.calls += 1;
+                before
+            }
+            Err(_) => return,
+        };
+        let Some(app_handle) = self.app_handle.get() else {
+            return;
+        };
+        let usage = self.usage();
+        emit_event(app_handle, "llm_usage", usage);
+        if let Some(budget) = usage.budget {
+            if usage.budget_exceeded && before <= budget {
+                eprintln!("LLM token budget of {} exceeded, pausing automatic assistant calls", budget);
+                emit_event(app_handle, "llm_budget_exceeded", usage);
+            }
+        }
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        match self.endpoint.lock() {
+            Ok(endpoint) => endpoint.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// The configuration in use, API key included
+    pub fn config(&self) -> LlmConfig {
+        self.endpoint().config
+    }
+
+    /// Switch to `config` for the calls that follow
+    pub fn set_config(&self, config: LlmConfig) -> Result<(), String> {
+        config.validate()?;
+        self.check_privacy(&config)?;
+        let mut endpoint = self.endpoint.lock().map_err(|e| e.to_string())?;
+        *endpoint = Endpoint::new(config);
+        Ok(())
+    }
+
+    /// In privacy mode, refuse endpoints that aren't on this machine so no transcript leaves it
+    fn check_privacy(&self, config: &LlmConfig) -> Result<(), String> {
+        let privacy_mode = self.app_handle.get().is_some_and(crate::settings::privacy_mode);
+        if privacy_mode && !is_local_url(&config.api_url) {
+            return Err(format!(
+                "Privacy mode is on and {} isn't on this machine. Use a local LLM such as Ollama (http://localhost:11434/v1/chat/completions).",
+                config.api_url
+            ));
+        }
+        Ok(())
+    }
+
+    /// Refuse to send an unauthenticated request to a hosted endpoint, which would only be rejected.
+    /// Local endpoints like Ollama don't need a key.
+    fn check_key(config: &LlmConfig) -> Result<(), String> {
+        if config.api_key.trim().is_empty() && !is_local_url(&config.api_url) {
+            return Err(format!(
+                "No LLM API key is set for {}. Add LLM_API_KEY to .env or set a key in the LLM settings.",
+                config.api_url
+            ));
+        }
+        Ok(())
+    }
+
+    /// Send a request with the configured retries, emitting `llm_retry` before each one
+    async fn send(&self, endpoint: &Endpoint, request: reqwest::RequestBuilder) -> Result<reqwest::Response, reqwest::Error> {
+        endpoint
+            .config
+            .retry_policy()
+            .send(request, |retry: &RetryAttempt| {
+                eprintln!(
+                    "LLM request failed ({}), retrying in {:.1}s ({}/{})",
+                    retry.reason,
+                    retry.delay_ms as f64 / 1000.0,
+                    retry.attempt,
+                    retry.max_attempts
+                );
+                if let Some(app_handle) = self.app_handle.get() {
+                    emit_event(app_handle, "llm_retry", retry);
+                }
+            })
+            .await
+    }
+
+    pub fn api_url(&self) -> String {
+        self.endpoint().config.api_url
+    }
+
+    pub fn model(&self) -> String {
+        self.endpoint().config.model
+    }
+
+    /// Tokens the model takes in and writes in one call
+    pub fn context_window(&self) -> usize {
+        self.endpoint().config.context_window as usize
+    }
+
+    fn request(
+        &self,
+        endpoint: &Endpoint,
+        messages: &[ChatMessage],
+        options: CompletionOptions,
+        stream: bool,
+    ) -> reqwest::RequestBuilder {
+        let Endpoint { config, provider } = endpoint;
+        let provider = *provider;
+        let mut body = match provider {
+            LlmProvider::OpenAi => serde_json::json!({
+                "model": config.model,
+                "messages": messages,
+            }),
+            // Anthropic takes the system prompt apart from the conversation
+            LlmProvider::Anthropic => {
+                let (system, conversation): (Vec<&ChatMessage>, Vec<&ChatMessage>) =
+                    messages.iter().partition(|message| message.role == "system");
+                let mut body = serde_json::json!({
+                    "model": config.model,
+                    "messages": conversation,
+                    "max_tokens": ANTHROPIC_DEFAULT_MAX_TOKENS,
+                });
+                if !system.is_empty() {
+                    let system: Vec<&str> = system.iter().map(|message| message.content.as_str()).collect();
+                    body["system"] = system.join("\n\n").into();
+                }
+                body
+            }
+        };
+        if let Some(max_tokens) = options.max_tokens.or(config.max_tokens) {
+            body["max_tokens"] = max_tokens.into();
+        }
+        if let Some(temperature) = options.temperature.or(config.temperature) {
+            body["temperature"] = temperature.into();
+        }
+        if stream {
+            body["stream"] = true.into();
+            // Anthropic always reports usage in the stream, OpenAI only when asked
+            if provider == LlmProvider::OpenAi {
+                body["stream_options"] = serde_json::json!({ "include_usage": true });
+            }
+        }
+        // Anthropic has no JSON mode, the prompt has to ask for it
+        if options.json && provider == LlmProvider::OpenAi {
+            body["response_format"] = serde_json::json!({ "type": "json_object" });
+        }
+
+        let mut request = self
+            .client
+            .post(&config.api_url)
+            .header("Content-Type", "application/json")
+            .json(&body);
+
+        match provider {
+            LlmProvider::Anthropic => {
+                request = request
+                    .header("x-api-key", &config.api_key)
+                    .header("anthropic-version", ANTHROPIC_VERSION);
+            }
+            // Only add Bearer token if API Key is present (Ollama might not need it)
+            LlmProvider::OpenAi if !config.api_key.is_empty() => {
+                request = request.bearer_auth(&config.api_key);
+            }
+            LlmProvider::OpenAi => {}
+        }
+
+        // Add OpenRouter specific headers just in case
+        if config.api_url.contains("openrouter.ai") {
+            request = request
+                .header("HTTP-Referer", "https://hypergranola.app")
+                .header("X-Title", "HyperGranola");
+        }
+        request
+    }
+
+    /// Send `messages` and return the reply, or None when the response carries no message
+    pub async fn complete(&self, messages: &[ChatMessage], options: CompletionOptions) -> Result<Option<String>, String> {
+        let endpoint = self.endpoint();
+        self.check_privacy(&endpoint.config)?;
+        Self::check_key(&endpoint.config)?;
+        let request = self.request(&endpoint, messages, options, false);
+        let res = self
+            .send(&endpoint, request.timeout(endpoint.config.timeout()))
+            .await
+            .map_err(request_error)?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            return Err(status_error(status, &body, &endpoint.config.model));
+        }
+
+        let json: serde_json::Value = res.json().await.map_err(|e| {
+            if e.is_timeout() {
+                request_error(e)
+            } else {
+                format!("Failed to parse LLM JSON: {}", e)
+            }
+        })?;
+        self.record_usage(TokenUsage::from_json(&json));
+
+        match endpoint.provider.response_text(&json) {
+            Some(content) => Ok(Some(content)),
+            None => {
+                println!("Unexpected LLM Response: {:?}", json);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Send `messages` and stream the reply, passing each piece of text to `on_delta` as it
+    /// arrives. Returns the whole reply once the stream ends, or what arrived if it breaks off.
+    pub async fn complete_stream(
+        &self,
+        messages: &[ChatMessage],
+        options: CompletionOptions,
+        mut on_delta: impl FnMut(&str),
+    ) -> Result<String, StreamError> {
+        use futures_util::StreamExt;
+
+        // The whole stream can take longer than a completion, so only its start is timed out
+        let endpoint = self.endpoint();
+        self.check_privacy(&endpoint.config)?;
+        Self::check_key(&endpoint.config)?;
+        let timeout = endpoint.config.timeout();
+        let response = tokio::time::timeout(timeout, self.send(&endpoint, self.request(&endpoint, messages, options, true)))
+            .await
+            .map_err(|_| format!("LLM request timed out, no response after {}s", timeout.as_secs()))?
+            .map_err(request_error)?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(status_error(status, &body, &endpoint.config.model).into());
+        }
+
+        // Providers that ignore `stream` answer with a plain completion
+        let is_event_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+        if !is_event_stream {
+            let json: serde_json::Value = tokio::time::timeout(timeout, response.json())
+                .await
+                .map_err(|_| format!("LLM request timed out, no response after {}s", timeout.as_secs()))?
+                .map_err(|e| format!("Failed to parse LLM JSON: {}", e))?;
+            self.record_usage(TokenUsage::from_json(&json));
+            return match endpoint.provider.response_text(&json) {
+                Some(content) => {
+                    on_delta(&content);
+                    Ok(content)
+                }
+                None => Err(format!("Unexpected LLM Response: {:?}", json).into()),
+            };
+        }
+
+        let mut content = String::new();
+        let mut usage: Option<TokenUsage> = None;
+        let mut parser = StreamParser::default();
+        let mut stream = response.bytes_stream();
+
+        'stream: loop {
+            let chunk = match tokio::time::timeout(LLM_READ_TIMEOUT, stream.next()).await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(_) => {
+                    self.record_usage(usage);
+                    return Err(StreamError {
+                        partial: content,
+                        message: format!("LLM stream timed out, nothing received for {}s", LLM_READ_TIMEOUT.as_secs()),
+                    });
+                }
+            };
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    self.record_usage(usage);
+                    return Err(StreamError {
+                        partial: content,
+                        message: format!("LLM stream interrupted: {}", e),
+                    });
+                }
+            };
+
+            for line in parser.push(&chunk) {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(message) => {
+                        self.record_usage(usage);
+                        return Err(StreamError { partial: content, message });
+                    }
+                };
+                match line {
+                    StreamLine::Delta(text) => {
+                        on_delta(&text);
+                        content.push_str(&text);
+                    }
+                    StreamLine::Usage(tokens) => usage.get_or_insert_default().merge(tokens),
+                    StreamLine::Done => break 'stream,
+                    StreamLine::Skip => {}
+                }
+            }
+        }
+
+        match parser.finish() {
+            Ok(StreamLine::Delta(text)) => {
+                on_delta(&text);
+                content.push_str(&text);
+            }
+            Ok(StreamLine::Usage(tokens)) => usage.get_or_insert_default().merge(tokens),
+            _ => {}
+        }
+        self.record_usage(usage);
+
+        if content.is_empty() {
+            return Err("LLM stream ended without a response".to_string().into());
+        }
+        Ok(content)
+    }
+
+    /// Send a tiny prompt once, without retrying, and report how long the reply took or
+    /// what went wrong
+    pub async fn test_connection(&self) -> LlmConnectionTest {
+        let endpoint = self.endpoint();
+        if let Err(message) = self.check_privacy(&endpoint.config) {
+            return LlmConnectionTest {
+                ok: false,
+                latency_ms: 0,
+                model: endpoint.config.model,
+                reply: None,
+                error: Some(LlmConnectionError::PrivacyMode { message }),
+            };
+        }
+        if let Err(message) = Self::check_key(&endpoint.config) {
+            return LlmConnectionTest {
+                ok: false,
+                latency_ms: 0,
+                model: endpoint.config.model,
+                reply: None,
+                error: Some(LlmConnectionError::MissingKey { message }),
+            };
+        }
+        let options = CompletionOptions {
+            max_tokens: Some(5),
+            temperature: Some(0.0),
+            ..Default::default()
+        };
+        let request = self
+            .request(&endpoint, &[ChatMessage::user("Reply with OK.")], options, false)
+            .timeout(endpoint.config.timeout());
+
+        let started = Instant::now();
+        let result = async {
+            let response = request.send().await.map_err(|e| {
+                let message = e.to_string();
+                if e.is_timeout() {
+                    LlmConnectionError::Timeout { message }
+                } else {
+                    LlmConnectionError::Connection { message }
+                }
+            })?;
+            let status = response.status();
+            if !status.is_success() {
+                let message = response.text().await.unwrap_or_default();
+                return Err(match status {
+                    reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+                        LlmConnectionError::Unauthorized { status: status.as_u16(), message }
+                    }
+                    _ if is_model_not_found(status, &message) => {
+                        LlmConnectionError::ModelNotFound { status: status.as_u16(), message }
+                    }
+                    _ => LlmConnectionError::Http { status: status.as_u16(), message },
+                });
+            }
+            let json: serde_json::Value = response.json().await.map_err(|e| LlmConnectionError::InvalidResponse {
+                message: e.to_string(),
+            })?;
+            endpoint.provider.response_text(&json).ok_or_else(|| LlmConnectionError::InvalidResponse {
+                message: format!("No completion in the response: {}", json),
+            })
+        }
+        .await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let (reply, error) = match result {
+            Ok(reply) => (Some(reply.trim().to_string()), None),
+            Err(error) => (None, Some(error)),
+        };
+        LlmConnectionTest {
+            ok: error.is_none(),
+            latency_ms,
+            model: endpoint.config.model,
+            reply,
+            error,
+        }
+    }
+
+    /// Check the configuration with a tiny request, for a diagnostic to show before a meeting
+    pub async fn validate_config(&self) -> LlmConfigCheck {
+        let api_url = self.api_url();
+        let test = self.test_connection().await;
+        let (status, message) = match &test.error {
+            None => (LlmConfigStatus::Reachable, format!("{} is reachable with model {}", api_url, test.model)),
+            Some(LlmConnectionError::MissingKey { message }) => (LlmConfigStatus::MissingKey, message.clone()),
+            Some(LlmConnectionError::PrivacyMode { message }) => (LlmConfigStatus::PrivacyMode, message.clone()),
+            Some(LlmConnectionError::Unauthorized { status, .. }) => (
+                LlmConfigStatus::InvalidKey,
+                format!("The LLM API key was refused ({}), check LLM_API_KEY", status),
+            ),
+            Some(LlmConnectionError::ModelNotFound { status, .. }) => (
+                LlmConfigStatus::ModelNotFound,
+                format!("Model {} was not found at {} ({}), check LLM_MODEL", test.model, api_url, status),
+            ),
+            Some(LlmConnectionError::Connection { message } | LlmConnectionError::Timeout { message }) => (
+                LlmConfigStatus::Unreachable,
+                format!("Couldn't reach {}: {}", api_url, message),
+            ),
+            Some(LlmConnectionError::Http { status, message }) => (
+                LlmConfigStatus::Error,
+                format!("{} answered {}: {}", api_url, status, message),
+            ),
+            Some(LlmConnectionError::InvalidResponse { message }) => (LlmConfigStatus::Error, message.clone()),
+        };
+        LlmConfigCheck {
+            status,
+            message,
+            api_url,
+            model: test.model,
+            latency_ms: test.latency_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// A stream captured from a provider
+    fn fixture(name: &str) -> Vec<u8> {
+        std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)).unwrap()
+    }
+
+    /// What reading a stream gave, as `complete_stream` would see it
+    #[derive(Debug, Default, PartialEq)]
+    struct StreamRead {
+        text: String,
+        usage: Option<TokenUsage>,
+        done: bool,
+        error: Option<String>,
+    }
+
+    impl StreamRead {
+        /// Take in one line, returning whether reading stops
+        fn line(&mut self, line: Result<StreamLine, String>) -> bool {
+            match line {
+                Ok(StreamLine::Delta(text)) => self.text.push_str(&text),
+                Ok(StreamLine::Usage(tokens)) => self.usage.get_or_insert_default().merge(tokens),
+                Ok(StreamLine::Done) => self.done = true,
+                Ok(StreamLine::Skip) => {}
+                Err(message) => self.error = Some(message),
+            }
+            self.done || self.error.is_some()
+        }
+    }
+
+    /// Read `stream` arriving `chunk_size` bytes at a time
+    fn read_stream(stream: &[u8], chunk_size: usize) -> StreamRead {
+        let mut parser = StreamParser::default();
+        let mut read = StreamRead::default();
+        for chunk in stream.chunks(chunk_size) {
+            for line in parser.push(chunk) {
+                if read.line(line) {
+                    return read;
+                }
+            }
+        }
+        read.line(parser.finish());
+        read
+    }
+
+    #[test]
+    fn openai_stream_is_read_however_it_is_split() {
+        let stream = fixture("openai-stream.sse");
+        // One byte at a time splits every event, and the dash and accent inside their bytes
+        for chunk_size in [1, 2, 3, 7, 64, stream.len()] {
+            let read = read_stream(&stream, chunk_size);
+            assert_eq!(read.text, "The team agreed to ship on Friday — after the café demo.", "in {} byte chunks", chunk_size);
+            assert_eq!(read.usage, Some(TokenUsage { prompt_tokens: 412, completion_tokens: 14 }));
+            assert!(read.done);
+            assert_eq!(read.error, None);
+        }
+    }
+
+    #[test]
+    fn anthropic_stream_is_read_however_it_is_split() {
+        let stream = fixture("anthropic-stream.sse");
+        for chunk_size in [1, 5, 100, stream.len()] {
+            let read = read_stream(&stream, chunk_size);
+            assert_eq!(read.text, "Ship on Friday.");
+            // The prompt's tokens come at the start, the completion's near the end
+            assert_eq!(read.usage, Some(TokenUsage { prompt_tokens: 380, completion_tokens: 6 }));
+            assert!(read.done);
+        }
+    }
+
+    #[test]
+    fn error_mid_stream_keeps_the_text_before_it() {
+        let read = read_stream(&fixture("openai-stream-error.sse"), 16);
+        assert_eq!(read.text, "The budget is");
+        assert!(read.error.unwrap().contains("Provider returned error"));
+        assert!(!read.done);
+    }
+
+    #[test]
+    fn last_line_without_a_newline_is_read() {
+        let read = read_stream(br#"data: {"choices":[{"delta":{"content":"Hi"}}]}"#, 10);
+        assert_eq!(read.text, "Hi");
+        assert!(!read.done);
+        assert!(StreamParser::default().push(b"data: [DO").is_empty());
+    }
+
+    #[test]
+    fn stream_lines_of_each_provider() {
+        let delta = |text: &str| Ok(StreamLine::Delta(text.to_string()));
+        let cases = [
+            (r#"data:{"choices":[{"delta":{"content":"no space"}}]}"#, delta("no space")),
+            (r#"data: {"choices":[{"text":"legacy"}]}"#, delta("legacy")),
+            (r#"data: {"message":{"content":"ollama"},"done":false}"#, delta("ollama")),
+            (r#"data: {"type":"content_block_delta","delta":{"text":"anthropic"}}"#, delta("anthropic")),
+            (r#"data: {"done":true}"#, Ok(StreamLine::Done)),
+            ("data: [DONE]\r", Ok(StreamLine::Done)),
+            (r#"data: {"choices":[{"delta":{"content":""}}]}"#, Ok(StreamLine::Skip)),
+            (": keep-alive", Ok(StreamLine::Skip)),
+            ("event: ping", Ok(StreamLine::Skip)),
+            ("data: not json", Ok(StreamLine::Skip)),
+            ("", Ok(StreamLine::Skip)),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(parse_stream_line(line), expected, "{:?}", line);
+        }
+        assert!(parse_stream_line(r#"data: {"error":{"message":"overloaded"}}"#).is_err());
+    }
+}
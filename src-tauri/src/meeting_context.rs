@@ -3,6 +3,14 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::settings::{check_custom_prompt, AppSettings};
+use crate::storage;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Quiet time after a change before the meetings are saved, so a burst of edits is written once
+pub const SAVE_DELAY: Duration = Duration::from_secs(2);
 
 /// Meeting domain types for specialized AI prompts and behavior
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,8 +34,11 @@ impl Default for MeetingDomain {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeetingParticipant {
     pub name: String,
+    #[serde(default)]
     pub role: String,
+    #[serde(default)]
     pub email: Option<String>,
+    #[serde(default)]
     pub is_present: bool,
 }
 
@@ -36,26 +47,307 @@ pub struct MeetingParticipant {
 pub struct MeetingGoal {
     pub description: String,
     pub priority: u8, // 1-5, higher is more important
+    #[serde(default)]
     pub status: GoalStatus,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub enum GoalStatus {
+    #[default]
     Pending,
     InProgress,
     Completed,
     Cancelled,
 }
 
+/// Payload of the `goal_status_changed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalStatusChange {
+    pub index: usize,
+    pub description: String,
+    pub previous: GoalStatus,
+    pub status: GoalStatus,
+}
+
+/// Where an agenda item is in the meeting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum AgendaStatus {
+    #[default]
+    Pending,
+    Active,
+    Completed,
+}
+
+/// A topic on the agenda with its time box
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgendaItem {
+    pub title: String,
+    pub planned_minutes: u32,
+    #[serde(default)]
+    pub status: AgendaStatus,
+    #[serde(default)]
+    pub actual_started_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub actual_ended_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Start of the current or last stint on the item, a reopened item has more than one
+    #[serde(default)]
+    pub stint_started_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Time spent on the item in stints before the current one
+    #[serde(default)]
+    pub earlier_stints_ms: i64,
+}
+
+impl AgendaItem {
+    fn new(title: String, planned_minutes: u32) -> Self {
+        Self {
+            title,
+            planned_minutes,
+            status: AgendaStatus::Pending,
+            actual_started_at: None,
+            actual_ended_at: None,
+            stint_started_at: None,
+            earlier_stints_ms: 0,
+        }
+    }
+
+    /// Start of the current or last stint on the item, None if it was never started.
+    /// Items saved before stints were tracked had one, from their start.
+    pub fn stint_start(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.stint_started_at.or(self.actual_started_at)
+    }
+
+    /// Time spent on the item so far, adding up every stint, or in total once it's completed
+    pub fn elapsed(&self, now: chrono::DateTime<chrono::Utc>) -> Option<chrono::Duration> {
+        let started = self.stint_start()?;
+        let ended = self.actual_ended_at.unwrap_or(now);
+        let stint = (ended - started).max(chrono::Duration::zero());
+        Some(chrono::Duration::milliseconds(self.earlier_stints_ms) + stint)
+    }
+
+    /// Make the item active. Reopening a completed item starts a new stint, so time spent on
+    /// other topics in between doesn't count towards it.
+    fn start(&mut self, now: chrono::DateTime<chrono::Utc>) {
+        if self.status == AgendaStatus::Active {
+            return;
+        }
+        if let (Some(started), Some(ended)) = (self.stint_start(), self.actual_ended_at) {
+            self.earlier_stints_ms += (ended - started).num_milliseconds().max(0);
+        }
+        self.status = AgendaStatus::Active;
+        self.actual_started_at.get_or_insert(now);
+        self.stint_started_at = Some(now);
+        self.actual_ended_at = None;
+    }
+
+    /// Mark the item completed, ending its stint
+    fn complete(&mut self, now: chrono::DateTime<chrono::Utc>) {
+        if self.status != AgendaStatus::Completed {
+            self.status = AgendaStatus::Completed;
+            self.actual_ended_at = Some(now);
+        }
+    }
+
+    /// How far the active item is past its budget plus `threshold_percent` of it, None if it isn't
+    pub fn overrun(&self, now: chrono::DateTime<chrono::Utc>, threshold_percent: u32) -> Option<chrono::Duration> {
+        if self.status != AgendaStatus::Active {
+            return None;
+        }
+        let elapsed = self.elapsed(now)?;
+        let budget_ms = self.planned_minutes as i64 * 60_000;
+        let allowed = chrono::Duration::milliseconds(budget_ms + budget_ms * threshold_percent as i64 / 100);
+        (elapsed > allowed).then(|| elapsed - allowed)
+    }
+}
+
+/// Payload of the `agenda_overrun` event
+#[derive(Debug, Clone, Serialize)]
+pub struct AgendaOverrun {
+    pub index: usize,
+    pub title: String,
+    pub planned_minutes: u32,
+    pub elapsed_ms: i64,
+    /// Time past the budget plus the allowed overrun
+    pub overrun_ms: i64,
+}
+
+/// How far past its time box an agenda item may run before `agenda_overrun` is emitted
+pub const DEFAULT_AGENDA_OVERRUN_PERCENT: u32 = 10;
+
+/// Share of a phrase's keywords that must come up in the transcript for it to count as mentioned
+const MENTION_SHARE: f32 = 0.5;
+
+/// Whether `text` mentions most of the longer words of `phrase`. A word also matches longer forms
+/// of itself, so "deadlines" mentions "deadline". Phrases without longer words, like "Q&A",
+/// must appear as they are.
+pub fn mentions_phrase(phrase: &str, text: &str) -> bool {
+    let words = |text: &str| -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| word.chars().count() >= 4)
+            .map(|word| word.to_lowercase())
+            .collect()
+    };
+    let keywords = words(phrase);
+    if keywords.is_empty() {
+        let phrase = phrase.trim().to_lowercase();
+        return !phrase.is_empty() && text.to_lowercase().contains(&phrase);
+    }
+    let spoken = words(text);
+    let mentioned = keywords
+        .iter()
+        .filter(|keyword| spoken.iter().any(|word| word.starts_with(keyword.as_str())))
+        .count();
+    mentioned as f32 / keywords.len() as f32 >= MENTION_SHARE
+}
+
+/// Share of a question's words a sentence must contain to count as asking it
+pub const DEFAULT_QUESTION_MATCH_THRESHOLD: f32 = 0.6;
+
+/// Words too common in questions to tell them apart
+const QUESTION_FILLER_WORDS: &[&str] = &[
+    "the", "and", "are", "you", "what's", "how's", "your", "our", "for", "with", "this", "that", "what", "how", "why",
+    "when", "where", "who", "which", "does", "did", "can", "could", "would", "should", "will", "any",
+    "there", "have", "has", "been", "about", "from", "into", "they", "them", "their", "its", "was",
+];
+
+/// Content words of a question or sentence, lowercased with plural endings dropped
+fn question_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|word| word.trim_matches('\'').to_lowercase())
+        .filter(|word| word.chars().count() >= 3 && !QUESTION_FILLER_WORDS.contains(&word.as_str()))
+        .map(|word| match word.strip_suffix('s') {
+            Some(stem) if stem.chars().count() >= 3 && !stem.ends_with('s') => stem.to_string(),
+            _ => word,
+        })
+        .collect()
+}
+
+/// Whether two words look like forms of the same word: one starts with the other, or they share
+/// their first five letters, like "allergies" and "allergic"
+fn same_stem(a: &str, b: &str) -> bool {
+    let common = a.chars().zip(b.chars()).take_while(|(a, b)| a == b).count();
+    common >= 5 || common == a.chars().count().min(b.chars().count())
+}
+
+/// How closely any sentence of `text` asks `question`, from 0 to 1: the largest share of the
+/// question's words found in one sentence. Rewordings that keep the key words still match,
+/// e.g. "What's the budget for this project?" and "how big is the project budget".
+pub fn question_similarity(question: &str, text: &str) -> f32 {
+    let keywords = question_words(question);
+    if keywords.is_empty() {
+        return 0.0;
+    }
+    text.split(['.', '?', '!'])
+        .map(|sentence| {
+            let words = question_words(sentence);
+            let found = keywords
+                .iter()
+                .filter(|keyword| words.iter().any(|word| same_stem(keyword, word)))
+                .count();
+            found as f32 / keywords.len() as f32
+        })
+        .fold(0.0, f32::max)
+}
+
+/// Whether a key point has come up, in `get_coverage_report`
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyPointCoverage {
+    pub point: String,
+    pub covered: bool,
+    /// When it was first mentioned, from the start of the session
+    pub first_mentioned_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoverageReport {
+    pub points: Vec<KeyPointCoverage>,
+    pub covered: usize,
+    pub total: usize,
+}
+
+/// Payload of the `coverage_warning` event
+#[derive(Debug, Clone, Serialize)]
+pub struct CoverageWarning {
+    pub uncovered: Vec<String>,
+    pub elapsed_ms: u64,
+    pub planned_ms: u64,
+}
+
+/// Share of the planned duration after which undiscussed key points are warned about
+pub const COVERAGE_WARNING_SHARE: f64 = 0.8;
+
 /// Pre-generated questions for the meeting
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PreGeneratedQuestion {
     pub question: String,
+    #[serde(default)]
     pub category: String, // e.g., "clarification", "follow-up", "technical"
     pub priority: u8,
+    #[serde(default)]
     pub asked: bool,
 }
 
+/// A question listed with its position, for `mark_question_asked`
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexedQuestion {
+    pub index: usize,
+    #[serde(flatten)]
+    pub question: PreGeneratedQuestion,
+}
+
+/// Unasked questions listed in the context summary, highest priority first
+const SUMMARY_QUESTION_COUNT: usize = 5;
+
+/// An action item tracked on the meeting, from `extract_action_items`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionItem {
+    pub id: Uuid,
+    pub description: String,
+    /// A participant's name when it matched one, otherwise as said
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Due date or timeframe as said in the meeting, e.g. "Friday"
+    #[serde(default)]
+    pub due: Option<String>,
+    /// How sure the LLM was that it was agreed on, from 0 to 1
+    #[serde(default)]
+    pub confidence: f32,
+    /// What was said that it came from
+    #[serde(default)]
+    pub source_quote: Option<String>,
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// Changes to an action item, fields left None stay as they are
+#[derive(Debug, Clone, Default)]
+pub struct ActionItemUpdate {
+    pub description: Option<String>,
+    /// Empty to clear the owner
+    pub owner: Option<String>,
+    /// Empty to clear the due date
+    pub due: Option<String>,
+    pub done: Option<bool>,
+}
+
+/// How similar a new action item's description must be to a tracked one to count as the same
+const DUPLICATE_ACTION_ITEM_SIMILARITY: f32 = 0.8;
+
+/// Add the `new` action items to `items`, skipping ones with a description like one already there.
+/// Returns the items added.
+pub fn merge_action_items(items: &mut Vec<ActionItem>, new: Vec<ActionItem>) -> Vec<ActionItem> {
+    let mut added = Vec::new();
+    for item in new {
+        let duplicate = items.iter().any(|existing| {
+            question_similarity(&existing.description, &item.description) >= DUPLICATE_ACTION_ITEM_SIMILARITY
+        });
+        if !duplicate {
+            items.push(item.clone());
+            added.push(item);
+        }
+    }
+    added
+}
+
 /// Background information and research
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackgroundInfo {
@@ -65,9 +357,14 @@ pub struct BackgroundInfo {
     pub relevance_score: f32, // 0.0 to 1.0
 }
 
-/// Complete meeting context structure
+/// Complete meeting context structure.
+/// Fields missing from meetings saved by older versions take their defaults.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MeetingContext {
+    /// Stays the same for the life of the meeting, including in the history
+    pub id: Uuid,
+
     // Basic meeting information
     pub title: String,
     pub description: Option<String>,
@@ -79,50 +376,60 @@ pub struct MeetingContext {
     // Meeting structure
     pub goals: Vec<MeetingGoal>,
     pub duration_estimate_minutes: u32,
+    pub agenda: Vec<AgendaItem>,
     pub pre_generated_questions: Vec<PreGeneratedQuestion>,
+    pub action_items: Vec<ActionItem>,
 
     // Background and preparation
     pub background_info: HashMap<String, BackgroundInfo>,
     pub key_points_to_cover: Vec<String>,
     pub potential_challenges: Vec<String>,
+    /// Key points mentioned so far, with when they first were, from the start of the session
+    pub covered_key_points: HashMap<String, u64>,
+
+    // Assistant prompt, falling back to the app settings' defaults when None
+    pub custom_prompt_prefix: Option<String>,
+    pub custom_instructions: Option<String>,
+    /// Use the custom prefix instead of the domain's rather than after it
+    pub replace_domain_prefix: bool,
 
     // Meeting metadata
     pub template_name: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub last_modified: chrono::DateTime<chrono::Utc>,
+    /// When the meeting was replaced or cleared and moved to the history
+    pub ended_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl Default for MeetingContext {
     fn default() -> Self {
         Self {
+            id: Uuid::new_v4(),
             title: "New Meeting".to_string(),
             description: None,
             domain: MeetingDomain::General,
             participants: Vec::new(),
             goals: Vec::new(),
             duration_estimate_minutes: 60,
+            agenda: Vec::new(),
             pre_generated_questions: Vec::new(),
+            action_items: Vec::new(),
             background_info: HashMap::new(),
             key_points_to_cover: Vec::new(),
             potential_challenges: Vec::new(),
+            covered_key_points: HashMap::new(),
+            custom_prompt_prefix: None,
+            custom_instructions: None,
+            replace_domain_prefix: false,
             template_name: None,
             created_at: chrono::Utc::now(),
             last_modified: chrono::Utc::now(),
+            ended_at: None,
         }
     }
 }
 
 impl MeetingContext {
-    /// Create a new meeting context with basic information
-    #[allow(dead_code)]
-    pub fn new(title: String, domain: MeetingDomain) -> Self {
-        Self {
-            title,
-            domain,
-            ..Default::default()
-        }
-    }
-
     /// Add a participant to the meeting
     pub fn add_participant(&mut self, name: String, role: String, email: Option<String>) {
         self.participants.push(MeetingParticipant {
@@ -134,6 +441,117 @@ impl MeetingContext {
         self.last_modified = chrono::Utc::now();
     }
 
+    /// Find a participant by name, ignoring case
+    pub fn find_participant(&self, name: &str) -> Option<&MeetingParticipant> {
+        self.participants
+            .iter()
+            .find(|participant| participant.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// The participant a name said in the meeting refers to: the one with that name ignoring
+    /// case, or else the only one with that first name, e.g. "Sam" for "Sam Lee"
+    pub fn match_participant(&self, name: &str) -> Option<&MeetingParticipant> {
+        if let Some(participant) = self.find_participant(name) {
+            return Some(participant);
+        }
+        let first_name = |name: &str| name.split_whitespace().next().map(str::to_lowercase);
+        let wanted = first_name(name)?;
+        let mut matches = self
+            .participants
+            .iter()
+            .filter(|participant| first_name(&participant.name).as_deref() == Some(wanted.as_str()));
+        match (matches.next(), matches.next()) {
+            (Some(participant), None) => Some(participant),
+            _ => None,
+        }
+    }
+
+    /// Mark a participant, matched by name ignoring case, as present.
+    /// Returns the participant if they weren't present before.
+    pub fn mark_participant_present(&mut self, name: &str) -> Option<MeetingParticipant> {
+        let participant = self
+            .participants
+            .iter_mut()
+            .find(|participant| participant.name.eq_ignore_ascii_case(name.trim()))?;
+        if participant.is_present {
+            return None;
+        }
+        participant.is_present = true;
+        let joined = participant.clone();
+        self.last_modified = chrono::Utc::now();
+        Some(joined)
+    }
+
+    /// Position of a participant. `index` picks one directly, and `email` or `role` tell apart
+    /// people with the same name, which is otherwise an error.
+    fn participant_index(
+        &self,
+        name: &str,
+        index: Option<usize>,
+        email: Option<&str>,
+        role: Option<&str>,
+    ) -> Result<usize, String> {
+        let name = name.trim();
+        if let Some(index) = index {
+            return match self.participants.get(index) {
+                Some(participant) if participant.name.eq_ignore_ascii_case(name) => Ok(index),
+                Some(participant) => Err(format!("Participant {} is {}, not {}", index, participant.name, name)),
+                None => Err(format!(
+                    "Participant index {} is out of range, the meeting has {} participant(s)",
+                    index,
+                    self.participants.len()
+                )),
+            };
+        }
+
+        let matches: Vec<usize> = self
+            .participants
+            .iter()
+            .enumerate()
+            .filter(|(_, participant)| participant.name.eq_ignore_ascii_case(name))
+            .filter(|(_, participant)| {
+                email.is_none_or(|email| {
+                    participant.email.as_deref().is_some_and(|known| known.eq_ignore_ascii_case(email.trim()))
+                })
+            })
+            .filter(|(_, participant)| role.is_none_or(|role| participant.role.eq_ignore_ascii_case(role.trim())))
+            .map(|(index, _)| index)
+            .collect();
+        match matches.as_slice() {
+            [index] => Ok(*index),
+            [] => Err(format!("No participant named {}", name)),
+            _ => Err(format!("Several participants are named {}, give their index, email or role", name)),
+        }
+    }
+
+    /// Mark a participant as in the room or not
+    pub fn set_participant_presence(
+        &mut self,
+        name: &str,
+        index: Option<usize>,
+        email: Option<&str>,
+        role: Option<&str>,
+        present: bool,
+    ) -> Result<(), String> {
+        let index = self.participant_index(name, index, email, role)?;
+        self.participants[index].is_present = present;
+        self.last_modified = chrono::Utc::now();
+        Ok(())
+    }
+
+    /// Take a participant off the meeting, returning them
+    pub fn remove_participant(
+        &mut self,
+        name: &str,
+        index: Option<usize>,
+        email: Option<&str>,
+        role: Option<&str>,
+    ) -> Result<MeetingParticipant, String> {
+        let index = self.participant_index(name, index, email, role)?;
+        self.last_modified = chrono::Utc::now();
+        Ok(self.participants.remove(index))
+    }
+
     /// Add a meeting goal
     pub fn add_goal(&mut self, description: String, priority: u8) {
         self.goals.push(MeetingGoal {
@@ -144,8 +562,288 @@ impl MeetingContext {
         self.last_modified = chrono::Utc::now();
     }
 
+    /// Change the status of the goal at `index`
+    pub fn update_goal_status(&mut self, index: usize, status: GoalStatus) -> Result<(), String> {
+        let goal_count = self.goals.len();
+        let goal = self.goals.get_mut(index).ok_or_else(|| {
+            format!("Goal index {} is out of range, the meeting has {} goal(s)", index, goal_count)
+        })?;
+        goal.status = status;
+        self.last_modified = chrono::Utc::now();
+        Ok(())
+    }
+
+    /// Add an item to the end of the agenda
+    pub fn add_agenda_item(&mut self, title: String, planned_minutes: u32) -> Result<(), String> {
+        let title = title.trim().to_string();
+        if title.is_empty() {
+            return Err("Agenda item title cannot be empty".to_string());
+        }
+        self.agenda.push(AgendaItem::new(title, planned_minutes));
+        self.last_modified = chrono::Utc::now();
+        Ok(())
+    }
+
+    fn agenda_index(&self, index: usize) -> Result<usize, String> {
+        if index >= self.agenda.len() {
+            return Err(format!("Agenda index {} is out of range, the agenda has {} item(s)", index, self.agenda.len()));
+        }
+        Ok(index)
+    }
+
+    /// Move the agenda item at `from` to position `to`
+    pub fn move_agenda_item(&mut self, from: usize, to: usize) -> Result<(), String> {
+        let from = self.agenda_index(from)?;
+        let to = self.agenda_index(to)?;
+        let item = self.agenda.remove(from);
+        self.agenda.insert(to, item);
+        self.last_modified = chrono::Utc::now();
+        Ok(())
+    }
+
+    /// Remove the agenda item at `index`, returning it
+    pub fn remove_agenda_item(&mut self, index: usize) -> Result<AgendaItem, String> {
+        let index = self.agenda_index(index)?;
+        self.last_modified = chrono::Utc::now();
+        Ok(self.agenda.remove(index))
+    }
+
+    /// Start the agenda item at `index`, completing the one that was active.
+    /// Restarting a completed item adds to the time already spent on it.
+    pub fn start_agenda_item(&mut self, index: usize) -> Result<(), String> {
+        let index = self.agenda_index(index)?;
+        let now = chrono::Utc::now();
+        for (i, item) in self.agenda.iter_mut().enumerate() {
+            if i != index && item.status == AgendaStatus::Active {
+                item.complete(now);
+            }
+        }
+        self.agenda[index].start(now);
+        self.last_modified = now;
+        Ok(())
+    }
+
+    /// Mark the agenda item at `index` as completed
+    pub fn complete_agenda_item(&mut self, index: usize) -> Result<(), String> {
+        let index = self.agenda_index(index)?;
+        let now = chrono::Utc::now();
+        self.agenda[index].complete(now);
+        self.last_modified = now;
+        Ok(())
+    }
+
+    /// The agenda item being discussed, with its index
+    pub fn active_agenda_item(&self) -> Option<(usize, &AgendaItem)> {
+        self.agenda
+            .iter()
+            .enumerate()
+            .find(|(_, item)| item.status == AgendaStatus::Active)
+    }
+
+    /// The active agenda item if it's past its budget plus `threshold_percent` of it
+    pub fn agenda_overrun(&self, now: chrono::DateTime<chrono::Utc>, threshold_percent: u32) -> Option<AgendaOverrun> {
+        let (index, item) = self.active_agenda_item()?;
+        let overrun = item.overrun(now, threshold_percent)?;
+        Some(AgendaOverrun {
+            index,
+            title: item.title.clone(),
+            planned_minutes: item.planned_minutes,
+            elapsed_ms: item.elapsed(now)?.num_milliseconds(),
+            overrun_ms: overrun.num_milliseconds(),
+        })
+    }
+
+    /// Add a question to ask during the meeting, unless it's already there.
+    /// Returns whether it was added.
+    pub fn add_question(&mut self, question: String, category: String, priority: u8) -> bool {
+        let question = question.trim().to_string();
+        if question.is_empty()
+            || self
+                .pre_generated_questions
+                .iter()
+                .any(|existing| existing.question.eq_ignore_ascii_case(&question))
+        {
+            return false;
+        }
+        self.pre_generated_questions.push(PreGeneratedQuestion {
+            question,
+            category,
+            priority,
+            asked: false,
+        });
+        self.last_modified = chrono::Utc::now();
+        true
+    }
+
+    /// Questions with their index, only those in `category` (ignoring case) if given
+    pub fn list_questions(&self, category: Option<&str>) -> Vec<IndexedQuestion> {
+        self.pre_generated_questions
+            .iter()
+            .enumerate()
+            .filter(|(_, question)| category.is_none_or(|category| question.category.eq_ignore_ascii_case(category.trim())))
+            .map(|(index, question)| IndexedQuestion {
+                index,
+                question: question.clone(),
+            })
+            .collect()
+    }
+
+    /// Mark the question at `index` as asked or not
+    pub fn mark_question_asked(&mut self, index: usize, asked: bool) -> Result<(), String> {
+        let question_count = self.pre_generated_questions.len();
+        let question = self.pre_generated_questions.get_mut(index).ok_or_else(|| {
+            format!("Question index {} is out of range, the meeting has {} question(s)", index, question_count)
+        })?;
+        question.asked = asked;
+        self.last_modified = chrono::Utc::now();
+        Ok(())
+    }
+
+    /// Mark the unasked questions `text` asks, at least `threshold` similar, as asked.
+    /// Returns the questions marked.
+    pub fn track_questions_asked(&mut self, text: &str, threshold: f32) -> Vec<IndexedQuestion> {
+        let mut marked = Vec::new();
+        for (index, question) in self.pre_generated_questions.iter_mut().enumerate() {
+            if !question.asked && question_similarity(&question.question, text) >= threshold {
+                question.asked = true;
+                marked.push(IndexedQuestion {
+                    index,
+                    question: question.clone(),
+                });
+            }
+        }
+        if !marked.is_empty() {
+            self.last_modified = chrono::Utc::now();
+        }
+        marked
+    }
+
+    /// Track new action items, skipping ones already tracked so edits to them are kept.
+    /// Returns the items added.
+    pub fn add_action_items(&mut self, items: Vec<ActionItem>) -> Vec<ActionItem> {
+        let added = merge_action_items(&mut self.action_items, items);
+        if !added.is_empty() {
+            self.last_modified = chrono::Utc::now();
+        }
+        added
+    }
+
+    /// Edit the action item with `id`, returning it as updated
+    pub fn update_action_item(&mut self, id: Uuid, update: ActionItemUpdate) -> Result<ActionItem, String> {
+        let item = self
+            .action_items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| format!("No action item with id {}", id))?;
+        if let Some(description) = update.description {
+            let description = description.trim().to_string();
+            if description.is_empty() {
+                return Err("Action item description cannot be empty".to_string());
+            }
+            item.description = description;
+        }
+        if let Some(owner) = update.owner {
+            item.owner = Some(owner.trim().to_string()).filter(|owner| !owner.is_empty());
+        }
+        if let Some(due) = update.due {
+            item.due = Some(due.trim().to_string()).filter(|due| !due.is_empty());
+        }
+        if let Some(done) = update.done {
+            item.done = done;
+        }
+        let item = item.clone();
+        self.last_modified = chrono::Utc::now();
+        Ok(item)
+    }
+
+    /// Remove the goal at `index`, returning it
+    pub fn remove_goal(&mut self, index: usize) -> Result<MeetingGoal, String> {
+        if index >= self.goals.len() {
+            return Err(format!("Goal index {} is out of range, the meeting has {} goal(s)", index, self.goals.len()));
+        }
+        self.last_modified = chrono::Utc::now();
+        Ok(self.goals.remove(index))
+    }
+
+    /// Move goals forward from what was said: the `addressed` goals to Completed, and Pending
+    /// goals the transcript mentions to InProgress. Returns the goals that changed.
+    pub fn track_goal_progress(&mut self, transcript: &str, addressed: &[usize]) -> Vec<GoalStatusChange> {
+        let mut changes = Vec::new();
+        for (index, goal) in self.goals.iter_mut().enumerate() {
+            let status = match goal.status {
+                GoalStatus::Completed | GoalStatus::Cancelled => continue,
+                _ if addressed.contains(&index) => GoalStatus::Completed,
+                GoalStatus::Pending if mentions_phrase(&goal.description, transcript) => GoalStatus::InProgress,
+                _ => continue,
+            };
+            changes.push(GoalStatusChange {
+                index,
+                description: goal.description.clone(),
+                previous: std::mem::replace(&mut goal.status, status.clone()),
+                status,
+            });
+        }
+        if !changes.is_empty() {
+            self.last_modified = chrono::Utc::now();
+        }
+        changes
+    }
+
+    /// Whether any goal is still to be worked on
+    pub fn has_open_goals(&self) -> bool {
+        self.goals
+            .iter()
+            .any(|goal| matches!(goal.status, GoalStatus::Pending | GoalStatus::InProgress))
+    }
+
+    /// Mark the key points `text` mentions as covered, at `at_ms` into the session.
+    /// Returns the points covered for the first time.
+    pub fn track_coverage(&mut self, text: &str, at_ms: u64) -> Vec<String> {
+        let newly_covered: Vec<String> = self
+            .key_points_to_cover
+            .iter()
+            .filter(|point| !self.covered_key_points.contains_key(*point) && mentions_phrase(point, text))
+            .cloned()
+            .collect();
+        for point in &newly_covered {
+            self.covered_key_points.insert(point.clone(), at_ms);
+        }
+        if !newly_covered.is_empty() {
+            self.last_modified = chrono::Utc::now();
+        }
+        newly_covered
+    }
+
+    /// Key points not mentioned yet
+    pub fn uncovered_key_points(&self) -> Vec<String> {
+        self.key_points_to_cover
+            .iter()
+            .filter(|point| !self.covered_key_points.contains_key(*point))
+            .cloned()
+            .collect()
+    }
+
+    pub fn coverage_report(&self) -> CoverageReport {
+        let points: Vec<KeyPointCoverage> = self
+            .key_points_to_cover
+            .iter()
+            .map(|point| {
+                let first_mentioned_ms = self.covered_key_points.get(point).copied();
+                KeyPointCoverage {
+                    point: point.clone(),
+                    covered: first_mentioned_ms.is_some(),
+                    first_mentioned_ms,
+                }
+            })
+            .collect();
+        CoverageReport {
+            covered: points.iter().filter(|point| point.covered).count(),
+            total: points.len(),
+            points,
+        }
+    }
+
     /// Add background information
-    #[allow(dead_code)]
     pub fn add_background_info(&mut self, topic: String, content: String, source: String, relevance: f32) {
         self.background_info.insert(topic.clone(), BackgroundInfo {
             topic,
@@ -156,6 +854,46 @@ impl MeetingContext {
         self.last_modified = chrono::Utc::now();
     }
 
+    /// Set the assistant prompt prefix and instructions of this meeting, None or empty to
+    /// inherit the app-wide ones
+    pub fn set_custom_prompt(
+        &mut self,
+        prefix: Option<String>,
+        instructions: Option<String>,
+        replace_domain_prefix: bool,
+    ) -> Result<(), String> {
+        let prefix = check_custom_prompt("Prompt prefix", prefix)?;
+        let instructions = check_custom_prompt("Instructions", instructions)?;
+        self.custom_prompt_prefix = prefix;
+        self.custom_instructions = instructions;
+        self.replace_domain_prefix = replace_domain_prefix;
+        self.last_modified = chrono::Utc::now();
+        Ok(())
+    }
+
+    /// Check the custom prompt texts of a meeting set up elsewhere, e.g. sent whole by the frontend
+    pub fn check_custom_prompt(&self) -> Result<(), String> {
+        check_custom_prompt("Prompt prefix", self.custom_prompt_prefix.clone())?;
+        check_custom_prompt("Instructions", self.custom_instructions.clone())?;
+        Ok(())
+    }
+
+    /// The assistant's role for this meeting: the domain prefix with the custom prefix after it,
+    /// or instead of it if `replace_domain_prefix`, then the custom instructions.
+    /// The meeting's own prefix and instructions take precedence over the app-wide ones.
+    pub fn prompt_prefix(&self, settings: &AppSettings) -> String {
+        let custom = self.custom_prompt_prefix.as_ref().or(settings.default_prompt_prefix.as_ref());
+        let mut prefix = match custom {
+            Some(custom) if self.replace_domain_prefix => custom.clone(),
+            Some(custom) => format!("{}\n\n{}", self.get_ai_prompt_prefix(), custom),
+            None => self.get_ai_prompt_prefix(),
+        };
+        if let Some(instructions) = self.custom_instructions.as_ref().or(settings.default_instructions.as_ref()) {
+            prefix.push_str(&format!("\n\nInstructions from the organizer, always follow these:\n{}", instructions));
+        }
+        prefix
+    }
+
     /// Generate domain-specific AI prompt prefix
     pub fn get_ai_prompt_prefix(&self) -> String {
         match &self.domain {
@@ -183,6 +921,20 @@ impl MeetingContext {
         }
     }
 
+    /// Names and terms likely to come up, as a hint for speech recognition
+    pub fn get_vocabulary_hint(&self) -> Option<String> {
+        let mut terms: Vec<&str> = vec![self.title.as_str()];
+        terms.extend(self.participants.iter().map(|p| p.name.as_str()));
+        terms.extend(self.key_points_to_cover.iter().map(|k| k.as_str()));
+        terms.retain(|term| !term.trim().is_empty());
+
+        if terms.is_empty() {
+            None
+        } else {
+            Some(format!("{}.", terms.join(", ")))
+        }
+    }
+
     /// Get context summary for AI prompts
     pub fn get_context_summary(&self) -> String {
         let mut summary = format!("Meeting: {}\n", self.title);
@@ -195,18 +947,62 @@ impl MeetingContext {
         summary.push_str(&format!("Duration: {} minutes\n", self.duration_estimate_minutes));
 
         if !self.participants.is_empty() {
-            summary.push_str(&format!("Participants ({}): ", self.participants.len()));
-            let participant_names: Vec<String> = self.participants.iter()
-                .map(|p| format!("{} ({})", p.name, p.role))
-                .collect();
-            summary.push_str(&participant_names.join(", "));
-            summary.push_str("\n");
+            let (present, absent): (Vec<&MeetingParticipant>, Vec<&MeetingParticipant>) =
+                self.participants.iter().partition(|p| p.is_present);
+            let names = |participants: &[&MeetingParticipant]| -> String {
+                participants.iter().map(|p| format!("{} ({})", p.name, p.role)).collect::<Vec<_>>().join(", ")
+            };
+            // Until anyone is marked present, attendance isn't being tracked
+            if present.is_empty() {
+                summary.push_str(&format!("Participants ({}): {}\n", absent.len(), names(&absent)));
+            } else {
+                summary.push_str(&format!("Participants present ({}): {}\n", present.len(), names(&present)));
+                if !absent.is_empty() {
+                    summary.push_str(&format!("Expected but absent ({}): {}\n", absent.len(), names(&absent)));
+                }
+            }
         }
 
         if !self.goals.is_empty() {
             summary.push_str("Goals:\n");
             for goal in &self.goals {
-                summary.push_str(&format!("  - {} (Priority: {})\n", goal.description, goal.priority));
+                summary.push_str(&format!("  - {} (Priority: {}, Status: {:?})\n", goal.description, goal.priority, goal.status));
+            }
+        }
+
+        if !self.agenda.is_empty() {
+            let now = chrono::Utc::now();
+            summary.push_str("Agenda:\n");
+            for item in &self.agenda {
+                let progress = match (item.status, item.elapsed(now)) {
+                    (AgendaStatus::Active, Some(elapsed)) if elapsed.num_minutes() > item.planned_minutes as i64 => {
+                        format!("IN PROGRESS, {} min so far - OVER TIME", elapsed.num_minutes())
+                    }
+                    (AgendaStatus::Active, Some(elapsed)) => format!("IN PROGRESS, {} min so far", elapsed.num_minutes()),
+                    (AgendaStatus::Completed, Some(elapsed)) => format!("done, took {} min", elapsed.num_minutes()),
+                    (AgendaStatus::Completed, None) => "done".to_string(),
+                    _ => "not started".to_string(),
+                };
+                summary.push_str(&format!("  - {} ({} min planned, {})\n", item.title, item.planned_minutes, progress));
+            }
+        }
+
+        if !self.key_points_to_cover.is_empty() {
+            summary.push_str("Key points to cover:\n");
+            for point in &self.key_points_to_cover {
+                let status = if self.covered_key_points.contains_key(point) { "discussed" } else { "NOT YET DISCUSSED" };
+                summary.push_str(&format!("  - {} ({})\n", point, status));
+            }
+        }
+
+        // So the facilitator can nudge towards what hasn't been asked yet
+        let mut unasked: Vec<&PreGeneratedQuestion> =
+            self.pre_generated_questions.iter().filter(|q| !q.asked).collect();
+        if !unasked.is_empty() {
+            unasked.sort_by_key(|q| std::cmp::Reverse(q.priority));
+            summary.push_str("Suggested questions still open:\n");
+            for question in unasked.iter().take(SUMMARY_QUESTION_COUNT) {
+                summary.push_str(&format!("  - {} ({}, Priority: {})\n", question.question, question.category, question.priority));
             }
         }
 
@@ -214,50 +1010,789 @@ impl MeetingContext {
     }
 }
 
+/// A meeting as listed by `list_meeting_contexts` and `list_saved_meetings`
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedMeeting {
+    pub id: Uuid,
+    pub title: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_modified: chrono::DateTime<chrono::Utc>,
+    /// The active meeting, the one commands apply to when not given an id
+    pub is_current: bool,
+}
+
+impl SavedMeeting {
+    fn new(context: &MeetingContext, is_current: bool) -> Self {
+        Self {
+            id: context.id,
+            title: context.title.clone(),
+            created_at: context.created_at,
+            last_modified: context.last_modified,
+            is_current,
+        }
+    }
+}
+
+/// A meeting from the history as listed by `get_context_history`, without its details
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntrySummary {
+    /// Position in the history, oldest first, for `get_history_entry` and `delete_history_entry`
+    pub index: usize,
+    pub title: String,
+    pub domain: MeetingDomain,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub ended_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// From creation to when it ended, if it did
+    pub duration_minutes: Option<i64>,
+    pub goals_completed: usize,
+    pub goals_total: usize,
+}
+
+impl HistoryEntrySummary {
+    fn new(index: usize, context: &MeetingContext) -> Self {
+        Self {
+            index,
+            title: context.title.clone(),
+            domain: context.domain.clone(),
+            created_at: context.created_at,
+            ended_at: context.ended_at,
+            duration_minutes: context.ended_at.map(|ended_at| (ended_at - context.created_at).num_minutes()),
+            goals_completed: context.goals.iter().filter(|goal| goal.status == GoalStatus::Completed).count(),
+            goals_total: context.goals.len(),
+        }
+    }
+}
+
+/// Meetings kept in the history before the oldest are dropped
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// File the meetings are saved to
+pub fn get_meetings_path() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_local_dir()
+        .ok_or("Could not find local data directory")?;
+    Ok(data_dir.join("hypergranola").join("meetings.json"))
+}
+
 /// Meeting context manager for handling multiple meetings
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MeetingContextManager {
+    /// Meetings set up and not yet moved to the history, e.g. today's and tomorrow's
+    contexts: HashMap<Uuid, MeetingContext>,
+    /// The meeting commands apply to when not given an id, and the live session feeds
+    active_id: Option<Uuid>,
+    /// The single meeting saved by older versions, moved into `contexts` on load
+    #[serde(skip_serializing)]
     current_context: Option<MeetingContext>,
     context_history: Vec<MeetingContext>,
+    /// Bumped on every change, so a pending save can tell newer changes are coming
+    #[serde(skip)]
+    revision: u64,
+    /// How similar transcript text must be to a planned question to mark it asked
+    question_match_threshold: f32,
+    /// Most meetings kept in the history, the oldest are dropped first
+    history_limit: usize,
+    /// How far past its time box, in percent, the active agenda item may run before it's flagged
+    agenda_overrun_percent: u32,
 }
 
 impl Default for MeetingContextManager {
     fn default() -> Self {
         Self {
+            contexts: HashMap::new(),
+            active_id: None,
             current_context: None,
             context_history: Vec::new(),
+            revision: 0,
+            question_match_threshold: DEFAULT_QUESTION_MATCH_THRESHOLD,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            agenda_overrun_percent: DEFAULT_AGENDA_OVERRUN_PERCENT,
         }
     }
 }
 
 impl MeetingContextManager {
-    /// Set the current meeting context
+    /// Load the saved meetings, starting empty if there are none.
+    /// An unreadable file is set aside rather than left for the next save to overwrite.
+    pub fn load_from_disk() -> Result<Self, String> {
+        let loaded = storage::read_json_with(&get_meetings_path()?, Self::from_json)?;
+        Ok(loaded.unwrap_or_default())
+    }
+
+    /// Parse saved meetings, moving the single meeting of older versions in with the others
+    fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let mut manager = serde_json::from_str::<Self>(json)?;
+        if let Some(context) = manager.current_context.take() {
+            manager.active_id.get_or_insert(context.id);
+            manager.contexts.insert(context.id, context);
+        }
+        Ok(manager)
+    }
+
+    /// Write the current meeting and history to the app data dir
+    pub fn save_to_disk(&self) -> Result<(), String> {
+        storage::atomic_write_json(&get_meetings_path()?, self)
+    }
+
+    /// Record a change that needs saving, returning its revision
+    pub fn mark_changed(&mut self) -> u64 {
+        self.revision += 1;
+        self.revision
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn question_match_threshold(&self) -> f32 {
+        self.question_match_threshold
+    }
+
+    pub fn set_question_match_threshold(&mut self, threshold: f32) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err("Question match threshold must be between 0 and 1".to_string());
+        }
+        self.question_match_threshold = threshold;
+        Ok(())
+    }
+
+    pub fn agenda_overrun_percent(&self) -> u32 {
+        self.agenda_overrun_percent
+    }
+
+    pub fn set_agenda_overrun_percent(&mut self, percent: u32) {
+        self.agenda_overrun_percent = percent;
+    }
+
+    pub fn set_history_limit(&mut self, limit: usize) -> Result<(), String> {
+        if limit == 0 {
+            return Err("History limit must be at least 1".to_string());
+        }
+        self.history_limit = limit;
+        self.trim_history();
+        Ok(())
+    }
+
+    /// Move a meeting to the history, marking when it ended
+    fn archive(&mut self, mut context: MeetingContext) {
+        context.ended_at = Some(chrono::Utc::now());
+        self.context_history.push(context);
+        self.trim_history();
+    }
+
+    fn trim_history(&mut self) {
+        let excess = self.context_history.len().saturating_sub(self.history_limit);
+        self.context_history.drain(..excess);
+    }
+
+    /// The meetings set up and not yet moved to the history, newest first
+    pub fn list_contexts(&self) -> Vec<SavedMeeting> {
+        let mut contexts: Vec<&MeetingContext> = self.contexts.values().collect();
+        contexts.sort_by_key(|context| std::cmp::Reverse(context.created_at));
+        contexts
+            .into_iter()
+            .map(|context| SavedMeeting::new(context, Some(context.id) == self.active_id))
+            .collect()
+    }
+
+    /// Ids, titles and timestamps of the open meetings and then the history, newest first
+    pub fn list_saved_meetings(&self) -> Vec<SavedMeeting> {
+        let history = self.context_history.iter().rev().map(|context| SavedMeeting::new(context, false));
+        self.list_contexts().into_iter().chain(history).collect()
+    }
+
+    /// Set the active meeting context, moving the one active before to the history
     pub fn set_context(&mut self, context: MeetingContext) {
-        if let Some(old_context) = self.current_context.take() {
-            self.context_history.push(old_context);
+        if let Some(old_context) = self.active_id.take().and_then(|id| self.contexts.remove(&id)) {
+            self.archive(old_context);
+        }
+        let id = self.create_context(context);
+        self.active_id = Some(id);
+    }
+
+    /// Add a meeting alongside the others, returning its id. It becomes the active one
+    /// if none is, otherwise the active meeting stays as it is.
+    pub fn create_context(&mut self, mut context: MeetingContext) -> Uuid {
+        if self.contexts.contains_key(&context.id) || self.context_history.iter().any(|old| old.id == context.id) {
+            context.id = Uuid::new_v4();
+        }
+        let id = context.id;
+        self.contexts.insert(id, context);
+        self.active_id.get_or_insert(id);
+        id
+    }
+
+    /// Make a meeting the active one, bringing it back from the history if it's there
+    pub fn activate(&mut self, id: Uuid) -> Result<&MeetingContext, String> {
+        if !self.contexts.contains_key(&id) {
+            let index = self
+                .context_history
+                .iter()
+                .position(|context| context.id == id)
+                .ok_or_else(|| format!("No saved meeting with id {}", id))?;
+            let mut context = self.context_history.remove(index);
+            context.ended_at = None;
+            self.contexts.insert(id, context);
+        }
+        self.active_id = Some(id);
+        self.get_meeting(id).ok_or_else(|| format!("No saved meeting with id {}", id))
+    }
+
+    /// A meeting by id, whether open or in the history
+    pub fn get_meeting(&self, id: Uuid) -> Option<&MeetingContext> {
+        self.contexts
+            .get(&id)
+            .or_else(|| self.context_history.iter().find(|context| context.id == id))
+    }
+
+    /// The open meeting with `id`, or the active one if None
+    pub fn context(&self, id: Option<Uuid>) -> Result<&MeetingContext, String> {
+        match id {
+            Some(id) => self.contexts.get(&id).ok_or_else(|| format!("No open meeting with id {}", id)),
+            None => self.get_current_context().ok_or_else(|| "No active meeting context".to_string()),
+        }
+    }
+
+    /// The open meeting with `id` to change, or the active one if None
+    pub fn context_mut(&mut self, id: Option<Uuid>) -> Result<&mut MeetingContext, String> {
+        match id {
+            Some(id) => self.contexts.get_mut(&id).ok_or_else(|| format!("No open meeting with id {}", id)),
+            None => self.get_current_context_mut().ok_or_else(|| "No active meeting context".to_string()),
         }
-        self.current_context = Some(context);
     }
 
-    /// Get the current meeting context
+    /// Get the active meeting context
     pub fn get_current_context(&self) -> Option<&MeetingContext> {
-        self.current_context.as_ref()
+        self.contexts.get(self.active_id.as_ref()?)
     }
 
-    /// Get mutable reference to current context
+    /// Get mutable reference to the active context
     pub fn get_current_context_mut(&mut self) -> Option<&mut MeetingContext> {
-        self.current_context.as_mut()
+        self.contexts.get_mut(self.active_id.as_ref()?)
     }
 
-    /// Clear current context
+    /// Move the active meeting to the history, leaving none active
     pub fn clear_context(&mut self) {
-        if let Some(context) = self.current_context.take() {
-            self.context_history.push(context);
+        if let Some(context) = self.active_id.take().and_then(|id| self.contexts.remove(&id)) {
+            self.archive(context);
+        }
+    }
+
+    /// Summaries of the meetings in the history, oldest first
+    pub fn get_context_history(&self) -> Vec<HistoryEntrySummary> {
+        self.context_history
+            .iter()
+            .enumerate()
+            .map(|(index, context)| HistoryEntrySummary::new(index, context))
+            .collect()
+    }
+
+    pub fn get_history_entry(&self, index: usize) -> Result<&MeetingContext, String> {
+        self.context_history
+            .get(index)
+            .ok_or_else(|| format!("No history entry at index {}", index))
+    }
+
+    pub fn delete_history_entry(&mut self, index: usize) -> Result<MeetingContext, String> {
+        if index >= self.context_history.len() {
+            return Err(format!("No history entry at index {}", index));
+        }
+        Ok(self.context_history.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A meeting with something in every kind of field
+    fn planned_meeting(title: &str) -> MeetingContext {
+        let mut context = MeetingContext {
+            title: title.to_string(),
+            domain: MeetingDomain::Custom("Design review".to_string()),
+            description: Some("Pick the onboarding flow".to_string()),
+            key_points_to_cover: vec!["Signup drop-off".to_string()],
+            ..MeetingContext::default()
+        };
+        context.add_participant("Ana".to_string(), "PM".to_string(), Some("ana@example.com".to_string()));
+        context.add_goal("Choose a flow".to_string(), 5);
+        context.add_agenda_item("Metrics".to_string(), 10).unwrap();
+        context.add_question("What did users say?".to_string(), "research".to_string(), 3);
+        context.add_background_info("Funnel".to_string(), "40% drop".to_string(), "dashboard".to_string(), 0.8);
+        context.track_coverage("The signup drop-off is at the email step", 12_000);
+        context
+    }
+
+    #[test]
+    fn saved_meetings_round_trip() {
+        let mut manager = MeetingContextManager::default();
+        manager.set_context(planned_meeting("Last week"));
+        manager.set_context(planned_meeting("Onboarding review"));
+        manager.create_context(planned_meeting("Tomorrow"));
+
+        let json = serde_json::to_string_pretty(&manager).unwrap();
+        let restored = MeetingContextManager::from_json(&json).unwrap();
+
+        assert_eq!(serde_json::to_value(&restored).unwrap(), serde_json::to_value(&manager).unwrap());
+        assert_eq!(restored.get_current_context().unwrap().title, "Onboarding review");
+        assert_eq!(restored.get_context_history()[0].title, "Last week");
+        assert_eq!(restored.list_contexts().len(), 2);
+    }
+
+    #[test]
+    fn unknown_fields_are_ignored_and_missing_ones_defaulted() {
+        let context: MeetingContext = serde_json::from_value(serde_json::json!({
+            "title": "Standup",
+            "participants": [{ "name": "Ana" }],
+            "added_by_a_later_version": true,
+        }))
+        .unwrap();
+
+        assert_eq!(context.title, "Standup");
+        assert!(matches!(context.domain, MeetingDomain::General));
+        assert_eq!(context.duration_estimate_minutes, 60);
+        assert_eq!(context.participants[0].role, "");
+        assert!(!context.participants[0].is_present);
+        assert!(context.goals.is_empty());
+        assert_eq!(context.ended_at, None);
+    }
+
+    #[test]
+    fn single_meeting_of_older_versions_becomes_the_active_one() {
+        let json = serde_json::json!({
+            "current_context": { "title": "Saved before meetings were listed" },
+            "context_history": [{ "title": "Older" }],
+        });
+        let manager = MeetingContextManager::from_json(&json.to_string()).unwrap();
+
+        assert_eq!(manager.get_current_context().unwrap().title, "Saved before meetings were listed");
+        assert_eq!(manager.list_saved_meetings().len(), 2);
+        // Written back without the old field
+        let saved = serde_json::to_value(&manager).unwrap();
+        assert!(saved.get("current_context").is_none());
+    }
+
+    #[test]
+    fn history_summary_payload() {
+        let at = |time: &str| chrono::DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&chrono::Utc);
+        let mut context = planned_meeting("Onboarding review");
+        context.add_goal("Agree on a launch date".to_string(), 3);
+        context.update_goal_status(0, GoalStatus::Completed).unwrap();
+        context.created_at = at("2026-03-02T09:00:00Z");
+        context.ended_at = Some(at("2026-03-02T09:45:30Z"));
+
+        let json = serde_json::to_value(HistoryEntrySummary::new(4, &context)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "index": 4,
+                "title": "Onboarding review",
+                "domain": { "Custom": "Design review" },
+                "created_at": "2026-03-02T09:00:00Z",
+                "ended_at": "2026-03-02T09:45:30Z",
+                "duration_minutes": 45,
+                "goals_completed": 1,
+                "goals_total": 2,
+            })
+        );
+
+        context.ended_at = None;
+        let json = serde_json::to_value(HistoryEntrySummary::new(0, &context)).unwrap();
+        assert_eq!(json["ended_at"], serde_json::Value::Null);
+        assert_eq!(json["duration_minutes"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn archived_meetings_are_marked_ended_and_capped() {
+        let mut manager = MeetingContextManager::default();
+        manager.set_history_limit(2).unwrap();
+        for title in ["First", "Second", "Third"] {
+            manager.set_context(planned_meeting(title));
+        }
+        manager.clear_context();
+
+        let history = manager.get_context_history();
+        let titles: Vec<&str> = history.iter().map(|entry| entry.title.as_str()).collect();
+        assert_eq!(titles, ["Second", "Third"], "the oldest is dropped first");
+        assert!(history.iter().all(|entry| entry.ended_at.is_some()));
+        assert_eq!(history[1].index, 1);
+
+        assert_eq!(manager.delete_history_entry(0).unwrap().title, "Second");
+        assert!(manager.delete_history_entry(5).is_err());
+        assert_eq!(manager.get_history_entry(0).unwrap().title, "Third");
+    }
+
+    #[test]
+    fn custom_prompt_round_trips() {
+        let mut context = planned_meeting("OKR check-in");
+        context
+            .set_custom_prompt(
+                Some("  Always reference the OKR codes.  ".to_string()),
+                Some("Never suggest deadlines.".to_string()),
+                true,
+            )
+            .unwrap();
+
+        let json = serde_json::to_value(&context).unwrap();
+        assert_eq!(json["custom_prompt_prefix"], "Always reference the OKR codes.");
+        assert_eq!(json["custom_instructions"], "Never suggest deadlines.");
+        assert_eq!(json["replace_domain_prefix"], true);
+
+        let restored: MeetingContext = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.custom_prompt_prefix.as_deref(), Some("Always reference the OKR codes."));
+        assert_eq!(restored.custom_instructions.as_deref(), Some("Never suggest deadlines."));
+        assert!(restored.replace_domain_prefix);
+    }
+
+    #[test]
+    fn custom_prompt_length_is_limited() {
+        let mut context = MeetingContext::default();
+        let too_long = "x".repeat(crate::settings::MAX_CUSTOM_PROMPT_CHARS + 1);
+        assert!(context.set_custom_prompt(Some(too_long), None, false).is_err());
+        assert_eq!(context.custom_prompt_prefix, None);
+
+        let longest = "x".repeat(crate::settings::MAX_CUSTOM_PROMPT_CHARS);
+        context.set_custom_prompt(None, Some(longest), false).unwrap();
+        // Blank text inherits the app-wide prompt
+        context.set_custom_prompt(Some("   ".to_string()), None, false).unwrap();
+        assert_eq!(context.custom_prompt_prefix, None);
+    }
+
+    #[test]
+    fn custom_prefix_follows_or_replaces_the_domain_prefix() {
+        let settings = AppSettings {
+            default_prompt_prefix: Some("Org-wide prefix.".to_string()),
+            default_instructions: Some("Org-wide rules.".to_string()),
+            ..AppSettings::default()
+        };
+        let mut context = MeetingContext {
+            domain: MeetingDomain::Technical,
+            ..MeetingContext::default()
+        };
+        let domain_prefix = context.get_ai_prompt_prefix();
+
+        // Inherited from the settings, after the domain prefix
+        let prefix = context.prompt_prefix(&settings);
+        assert!(prefix.starts_with(&domain_prefix));
+        assert!(prefix.contains("Org-wide prefix."));
+        assert!(prefix.ends_with("Org-wide rules."));
+
+        // The meeting's own replace the settings', and can replace the domain prefix
+        context
+            .set_custom_prompt(Some("Meeting prefix.".to_string()), Some("Meeting rules.".to_string()), true)
+            .unwrap();
+        let prefix = context.prompt_prefix(&settings);
+        assert!(prefix.starts_with("Meeting prefix."));
+        assert!(!prefix.contains(&domain_prefix));
+        assert!(!prefix.contains("Org-wide"));
+        assert!(prefix.ends_with("Meeting rules."));
+    }
+
+    #[test]
+    fn goals_are_changed_and_removed_by_index() {
+        let mut context = planned_meeting("Planning");
+        context.add_goal("Agree on a launch date".to_string(), 3);
+
+        context.update_goal_status(1, GoalStatus::InProgress).unwrap();
+        assert_eq!(context.goals[1].status, GoalStatus::InProgress);
+        let error = context.update_goal_status(2, GoalStatus::Completed).unwrap_err();
+        assert_eq!(error, "Goal index 2 is out of range, the meeting has 2 goal(s)");
+
+        assert_eq!(context.remove_goal(0).unwrap().description, "Choose a flow");
+        assert_eq!(context.goals[0].description, "Agree on a launch date");
+        assert!(context.remove_goal(1).is_err());
+        assert_eq!(context.goals.len(), 1);
+    }
+
+    #[test]
+    fn goal_status_is_sent_by_name() {
+        let goal = MeetingGoal {
+            description: "Choose a flow".to_string(),
+            priority: 5,
+            status: GoalStatus::InProgress,
+        };
+        assert_eq!(
+            serde_json::to_value(&goal).unwrap(),
+            serde_json::json!({ "description": "Choose a flow", "priority": 5, "status": "InProgress" })
+        );
+        let status: GoalStatus = serde_json::from_value(serde_json::json!("Cancelled")).unwrap();
+        assert_eq!(status, GoalStatus::Cancelled);
+        // Goals saved before they had a status are pending
+        let goal: MeetingGoal = serde_json::from_value(serde_json::json!({ "description": "Old", "priority": 1 })).unwrap();
+        assert_eq!(goal.status, GoalStatus::Pending);
+
+        let change = GoalStatusChange {
+            index: 0,
+            description: "Choose a flow".to_string(),
+            previous: GoalStatus::Pending,
+            status: GoalStatus::Completed,
+        };
+        assert_eq!(
+            serde_json::to_value(&change).unwrap(),
+            serde_json::json!({ "index": 0, "description": "Choose a flow", "previous": "Pending", "status": "Completed" })
+        );
+    }
+
+    #[test]
+    fn goal_progress_is_tracked_from_the_transcript() {
+        let mut context = MeetingContext::default();
+        context.add_goal("Agree on a launch date".to_string(), 3);
+        context.add_goal("Review the budget".to_string(), 2);
+        context.add_goal("Hire a designer".to_string(), 1);
+        context.update_goal_status(2, GoalStatus::Cancelled).unwrap();
+
+        let transcript = "Can we agree on the launch? Any date works for me. Also the designer hire is off.";
+        let changes = context.track_goal_progress(transcript, &[1, 2]);
+        let changed: Vec<_> = changes
+            .iter()
+            .map(|change| (change.index, change.previous.clone(), change.status.clone()))
+            .collect();
+        assert_eq!(
+            changed,
+            [(0, GoalStatus::Pending, GoalStatus::InProgress), (1, GoalStatus::Pending, GoalStatus::Completed)]
+        );
+        assert!(context.has_open_goals());
+        assert!(context.get_context_summary().contains("Agree on a launch date (Priority: 3, Status: InProgress)"));
+
+        // Mentioning it again changes nothing, addressing it completes it
+        assert!(context.track_goal_progress(transcript, &[]).is_empty());
+        assert_eq!(context.track_goal_progress("", &[0])[0].status, GoalStatus::Completed);
+        assert!(!context.has_open_goals());
+    }
+
+    #[test]
+    fn key_points_are_matched_by_their_longer_words() {
+        let cases = [
+            ("Budget for Q3", "we should talk about the budget", true),
+            // Longer forms of a word match, shorter ones don't
+            ("Hiring deadline", "the deadlines for hiring slipped", true),
+            ("Deadlines", "what's the deadline", false),
+            // Half the keywords are enough
+            ("Migration plan and rollback", "the migration is planned for May", true),
+            ("Migration plan and rollback", "the rollback worked", false),
+            // Words too short to tell apart must appear as they are
+            ("Q&A", "let's leave time for q&a", true),
+            ("Q&A", "any questions", false),
+            ("", "anything at all", false),
+        ];
+        for (point, text, mentioned) in cases {
+            assert_eq!(mentions_phrase(point, text), mentioned, "{:?} in {:?}", point, text);
         }
     }
 
-    /// Get context history
-    #[allow(dead_code)]
-    pub fn get_context_history(&self) -> &[MeetingContext] {
-        &self.context_history
+    #[test]
+    fn key_points_keep_when_they_were_first_mentioned() {
+        let mut context = MeetingContext {
+            key_points_to_cover: vec!["Signup drop-off".to_string(), "Pricing page".to_string()],
+            ..MeetingContext::default()
+        };
+        assert_eq!(context.track_coverage("The signup drop-off is at the email step", 12_000), ["Signup drop-off"]);
+        assert!(context.track_coverage("Signup drop-off again", 30_000).is_empty());
+        assert_eq!(context.uncovered_key_points(), ["Pricing page"]);
+
+        let report = context.coverage_report();
+        assert_eq!((report.covered, report.total), (1, 2));
+        assert_eq!(report.points[0].first_mentioned_ms, Some(12_000));
+        assert!(!report.points[1].covered);
+        assert_eq!(report.points[1].first_mentioned_ms, None);
+
+        let summary = context.get_context_summary();
+        assert!(summary.contains("Signup drop-off (discussed)"));
+        assert!(summary.contains("Pricing page (NOT YET DISCUSSED)"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn paraphrased_questions_still_match() {
+        let paraphrases = [
+            ("What's the budget for this project?", "So how big is the project budget"),
+            ("When is the launch date?", "Do we have a date for launching yet?"),
+            ("Are there any allergies we should know about?", "Is anyone allergic to something I need to know"),
+            ("How many users signed up last month?", "I wonder how many signups we had last month."),
+            ("Who owns the migration?", "OK. Who is the owner of the database migration?"),
+        ];
+        for (question, said) in paraphrases {
+            let similarity = question_similarity(question, said);
+            assert!(similarity >= DEFAULT_QUESTION_MATCH_THRESHOLD, "{:?} / {:?}: {}", question, said, similarity);
+        }
+
+        let unrelated = [
+            ("What's the budget for this project?", "The project is on track"),
+            ("When is the launch date?", "Lunch is at noon"),
+            // Every word, but spread over sentences
+            ("Who owns the migration?", "The migration is done. Ask whoever owns it."),
+            ("What is it?", "anything at all"),
+        ];
+        for (question, said) in unrelated {
+            let similarity = question_similarity(question, said);
+            assert!(similarity < DEFAULT_QUESTION_MATCH_THRESHOLD, "{:?} / {:?}: {}", question, said, similarity);
+        }
+    }
+
+    #[test]
+    fn questions_are_marked_asked_once() {
+        let mut context = MeetingContext::default();
+        context.add_question("What's the budget for this project?".to_string(), "scope".to_string(), 3);
+        context.add_question("When is the launch date?".to_string(), "timeline".to_string(), 5);
+
+        let marked = context.track_questions_asked("So how big is the project budget?", DEFAULT_QUESTION_MATCH_THRESHOLD);
+        assert_eq!(marked.len(), 1);
+        assert_eq!(marked[0].index, 0);
+        assert!(marked[0].question.asked);
+        assert!(context.track_questions_asked("And the project budget?", DEFAULT_QUESTION_MATCH_THRESHOLD).is_empty());
+        let summary = context.get_context_summary();
+        assert!(summary.contains("Suggested questions still open:\n  - When is the launch date? (timeline, Priority: 5)"));
+        assert!(!summary.contains("budget"));
+        // Looser matching catches more, a threshold of 1 needs every word
+        assert!(context.track_questions_asked("What date?", 1.0).is_empty());
+        assert_eq!(context.track_questions_asked("What date?", 0.5)[0].index, 1);
+
+        context.mark_question_asked(1, false).unwrap();
+        assert!(!context.pre_generated_questions[1].asked);
+        let error = context.mark_question_asked(2, true).unwrap_err();
+        assert_eq!(error, "Question index 2 is out of range, the meeting has 2 question(s)");
+    }
+
+    #[test]
+    fn question_match_threshold_is_validated() {
+        let mut manager = MeetingContextManager::default();
+        assert_eq!(manager.question_match_threshold(), DEFAULT_QUESTION_MATCH_THRESHOLD);
+        manager.set_question_match_threshold(0.4).unwrap();
+        assert_eq!(manager.question_match_threshold(), 0.4);
+        for invalid in [-0.1, 1.5, f32::NAN] {
+            assert!(manager.set_question_match_threshold(invalid).is_err());
+        }
+        assert_eq!(manager.question_match_threshold(), 0.4);
+    }
+
+    fn minutes(minutes: i64) -> chrono::Duration {
+        chrono::Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn agenda_item_overruns_past_its_budget_and_allowance() {
+        let started = chrono::Utc::now();
+        let item = AgendaItem {
+            status: AgendaStatus::Active,
+            actual_started_at: Some(started),
+            ..AgendaItem::new("Metrics".to_string(), 10)
+        };
+        assert_eq!(item.elapsed(started + minutes(4)), Some(minutes(4)));
+        // 10% over a 10 minute budget is allowed
+        assert_eq!(item.overrun(started + minutes(10), 10), None);
+        assert_eq!(item.overrun(started + minutes(11), 10), None);
+        assert_eq!(item.overrun(started + minutes(13), 10), Some(minutes(2)));
+        assert_eq!(item.overrun(started + minutes(10) + chrono::Duration::seconds(1), 0), Some(chrono::Duration::seconds(1)));
+        // A clock that went back doesn't count as time spent
+        assert_eq!(item.elapsed(started - minutes(1)), Some(chrono::Duration::zero()));
+
+        // Only the active item can overrun
+        let pending = AgendaItem {
+            status: AgendaStatus::Pending,
+            ..item.clone()
+        };
+        assert_eq!(pending.overrun(started + minutes(30), 10), None);
+        let completed = AgendaItem {
+            status: AgendaStatus::Completed,
+            actual_ended_at: Some(started + minutes(12)),
+            ..item
+        };
+        assert_eq!(completed.elapsed(started + minutes(30)), Some(minutes(12)));
+        assert_eq!(completed.overrun(started + minutes(30), 10), None);
+    }
+
+    #[test]
+    fn reopened_agenda_item_counts_only_its_own_stints() {
+        let started = chrono::Utc::now();
+        let mut item = AgendaItem::new("Metrics".to_string(), 10);
+        item.start(started);
+        item.complete(started + minutes(8));
+        assert_eq!(item.elapsed(started + minutes(30)), Some(minutes(8)));
+
+        // Back to it after 30 minutes on other topics
+        let reopened = started + minutes(38);
+        item.start(reopened);
+        assert_eq!(item.actual_started_at, Some(started));
+        assert_eq!(item.stint_start(), Some(reopened));
+        assert_eq!(item.elapsed(reopened + minutes(2)), Some(minutes(10)));
+        assert_eq!(item.overrun(reopened + minutes(2), 10), None);
+        assert_eq!(item.overrun(reopened + minutes(5), 10), Some(minutes(2)));
+
+        item.complete(reopened + minutes(4));
+        assert_eq!(item.elapsed(reopened + minutes(60)), Some(minutes(12)));
+    }
+
+    #[test]
+    fn agenda_item_saved_before_stints_still_has_its_time() {
+        let started = chrono::Utc::now();
+        let json = serde_json::json!({
+            "title": "Metrics",
+            "planned_minutes": 10,
+            "status": "Completed",
+            "actual_started_at": started,
+            "actual_ended_at": started + minutes(7),
+        });
+        let item: AgendaItem = serde_json::from_value(json).unwrap();
+        assert_eq!(item.elapsed(started + minutes(30)), Some(minutes(7)));
+    }
+
+    #[test]
+    fn active_agenda_item_is_the_one_reported_overrunning() {
+        let mut context = MeetingContext::default();
+        context.add_agenda_item("Metrics".to_string(), 10).unwrap();
+        context.add_agenda_item("Roadmap".to_string(), 20).unwrap();
+        assert!(context.agenda_overrun(chrono::Utc::now(), 10).is_none());
+
+        context.start_agenda_item(0).unwrap();
+        let started = context.agenda[0].actual_started_at.unwrap();
+        assert!(context.agenda_overrun(started + minutes(10), 10).is_none());
+        let overrun = context.agenda_overrun(started + minutes(15), 10).unwrap();
+        assert_eq!((overrun.index, overrun.title.as_str(), overrun.planned_minutes), (0, "Metrics", 10));
+        assert_eq!((overrun.elapsed_ms, overrun.overrun_ms), (15 * 60_000, 4 * 60_000));
+
+        // Moving on completes the item
+        context.start_agenda_item(1).unwrap();
+        assert_eq!(context.agenda[0].status, AgendaStatus::Completed);
+        assert!(context.agenda[0].actual_ended_at.is_some());
+        assert_eq!(context.active_agenda_item().unwrap().0, 1);
+        assert!(context.agenda_overrun(chrono::Utc::now() + minutes(15), 10).is_none());
+        assert_eq!(context.agenda_overrun(chrono::Utc::now() + minutes(30), 10).unwrap().index, 1);
+        assert!(context.get_context_summary().contains("Roadmap (20 min planned, IN PROGRESS, 0 min so far)"));
+
+        context.complete_agenda_item(1).unwrap();
+        assert!(context.agenda_overrun(chrono::Utc::now() + minutes(30), 10).is_none());
+        assert!(context.start_agenda_item(2).is_err());
+    }
+
+    #[test]
+    fn agenda_items_are_reordered_by_index() {
+        let mut context = MeetingContext::default();
+        for title in ["Metrics", "Roadmap", "Hiring"] {
+            context.add_agenda_item(title.to_string(), 10).unwrap();
+        }
+        assert!(context.add_agenda_item("  ".to_string(), 10).is_err());
+
+        context.move_agenda_item(2, 0).unwrap();
+        let titles: Vec<&str> = context.agenda.iter().map(|item| item.title.as_str()).collect();
+        assert_eq!(titles, ["Hiring", "Metrics", "Roadmap"]);
+        let error = context.move_agenda_item(0, 3).unwrap_err();
+        assert_eq!(error, "Agenda index 3 is out of range, the agenda has 3 item(s)");
+        assert_eq!(context.remove_agenda_item(1).unwrap().title, "Metrics");
+        assert_eq!(context.agenda.len(), 2);
+    }
+
+    #[test]
+    fn owners_are_matched_to_participants() {
+        let mut context = MeetingContext::default();
+        for name in ["Sam Lee", "Ana Ruiz", "Ana Park"] {
+            context.add_participant(name.to_string(), String::new(), None);
+        }
+        let matched = |name: &str| context.match_participant(name).map(|participant| participant.name.as_str());
+        assert_eq!(matched("sam lee"), Some("Sam Lee"));
+        assert_eq!(matched(" Sam "), Some("Sam Lee"));
+        assert_eq!(matched("Ana Park"), Some("Ana Park"));
+        // Two Anas, so the first name alone doesn't say which
+        assert_eq!(matched("Ana"), None);
+        assert_eq!(matched("Ben"), None);
+        assert_eq!(matched(""), None);
+    }
+}
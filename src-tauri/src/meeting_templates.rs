@@ -0,0 +1,388 @@
+//! Meeting templates
+//! Pre-fill a meeting context for common kinds of meetings, built in or saved by the user
+
+use crate::storage;
+use crate::meeting_context::{GoalStatus, MeetingContext, MeetingDomain, MeetingGoal, MeetingParticipant, PreGeneratedQuestion};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// A starting point for a meeting context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeetingTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub domain: MeetingDomain,
+    pub duration_estimate_minutes: u32,
+    #[serde(default)]
+    pub participants: Vec<MeetingParticipant>,
+    #[serde(default)]
+    pub goals: Vec<MeetingGoal>,
+    #[serde(default)]
+    pub pre_generated_questions: Vec<PreGeneratedQuestion>,
+    #[serde(default)]
+    pub key_points_to_cover: Vec<String>,
+    #[serde(default)]
+    pub potential_challenges: Vec<String>,
+    /// Shipped with the app rather than saved by the user, so it can't be deleted
+    #[serde(default, skip_deserializing)]
+    pub built_in: bool,
+}
+
+impl MeetingTemplate {
+    /// Template of a meeting as set up, without the progress made in it
+    pub fn from_context(name: &str, context: &MeetingContext) -> Self {
+        let mut template = Self {
+            name: name.trim().to_string(),
+            description: context.description.clone(),
+            domain: context.domain.clone(),
+            duration_estimate_minutes: context.duration_estimate_minutes,
+            participants: context.participants.clone(),
+            goals: context.goals.clone(),
+            pre_generated_questions: context.pre_generated_questions.clone(),
+            key_points_to_cover: context.key_points_to_cover.clone(),
+            potential_challenges: context.potential_challenges.clone(),
+            built_in: false,
+        };
+        template.reset_progress();
+        template
+    }
+
+    /// A new meeting set up from the template, with nobody present and nothing done yet
+    pub fn to_context(&self) -> MeetingContext {
+        let mut template = self.clone();
+        template.reset_progress();
+        MeetingContext {
+            title: template.name.clone(),
+            description: template.description,
+            domain: template.domain,
+            participants: template.participants,
+            goals: template.goals,
+            duration_estimate_minutes: template.duration_estimate_minutes,
+            pre_generated_questions: template.pre_generated_questions,
+            key_points_to_cover: template.key_points_to_cover,
+            potential_challenges: template.potential_challenges,
+            template_name: Some(template.name),
+            ..MeetingContext::default()
+        }
+    }
+
+    fn reset_progress(&mut self) {
+        for participant in &mut self.participants {
+            participant.is_present = false;
+        }
+        for goal in &mut self.goals {
+            goal.status = GoalStatus::Pending;
+        }
+        for question in &mut self.pre_generated_questions {
+            question.asked = false;
+        }
+    }
+}
+
+/// A template shipped with the app, as static data
+struct BuiltInTemplate {
+    name: &'static str,
+    description: &'static str,
+    domain: MeetingDomain,
+    duration_estimate_minutes: u32,
+    /// Description and priority
+    goals: &'static [(&'static str, u8)],
+    /// Question, category and priority
+    questions: &'static [(&'static str, &'static str, u8)],
+    key_points: &'static [&'static str],
+    potential_challenges: &'static [&'static str],
+}
+
+impl BuiltInTemplate {
+    fn to_template(&self) -> MeetingTemplate {
+        MeetingTemplate {
+            name: self.name.to_string(),
+            description: Some(self.description.to_string()),
+            domain: self.domain.clone(),
+            duration_estimate_minutes: self.duration_estimate_minutes,
+            participants: Vec::new(),
+            goals: self
+                .goals
+                .iter()
+                .map(|&(description, priority)| MeetingGoal {
+                    description: description.to_string(),
+                    priority,
+                    status: GoalStatus::Pending,
+                })
+                .collect(),
+            pre_generated_questions: self
+                .questions
+                .iter()
+                .map(|&(question, category, priority)| PreGeneratedQuestion {
+                    question: question.to_string(),
+                    category: category.to_string(),
+                    priority,
+                    asked: false,
+                })
+                .collect(),
+            key_points_to_cover: self.key_points.iter().map(|point| point.to_string()).collect(),
+            potential_challenges: self.potential_challenges.iter().map(|challenge| challenge.to_string()).collect(),
+            built_in: true,
+        }
+    }
+}
+
+static BUILT_IN_TEMPLATES: &[BuiltInTemplate] = &[
+    BuiltInTemplate {
+        name: "Sprint Planning",
+        description: "Agree on the sprint goal and commit to a set of backlog items",
+        domain: MeetingDomain::Technical,
+        duration_estimate_minutes: 60,
+        goals: &[
+            ("Agree on a sprint goal", 5),
+            ("Commit to backlog items that fit the team's capacity", 4),
+            ("Surface dependencies and blockers", 3),
+        ],
+        questions: &[
+            ("What capacity does the team have this sprint?", "planning", 5),
+            ("What is the acceptance criteria for this item?", "clarification", 4),
+            ("Does anything depend on another team?", "technical", 3),
+        ],
+        key_points: &["Sprint goal", "Team capacity", "Carry-over from last sprint", "Estimates", "Definition of done"],
+        potential_challenges: &["Over-committing", "Unclear requirements", "Unestimated work"],
+    },
+    BuiltInTemplate {
+        name: "Sales Discovery Call",
+        description: "Understand a prospect's needs, budget and buying process",
+        domain: MeetingDomain::Sales,
+        duration_estimate_minutes: 30,
+        goals: &[
+            ("Understand the prospect's main pain points", 5),
+            ("Qualify budget, authority, need and timeline", 4),
+            ("Agree on a next step", 4),
+        ],
+        questions: &[
+            ("What prompted you to look for a solution now?", "discovery", 5),
+            ("How are you handling this today?", "discovery", 4),
+            ("Who else is involved in the decision?", "qualification", 4),
+            ("What does your timeline look like?", "qualification", 3),
+        ],
+        key_points: &["Pain points", "Current solution", "Decision makers", "Budget", "Timeline", "Next steps"],
+        potential_challenges: &["Price objections", "Missing decision maker", "Competing priorities"],
+    },
+    BuiltInTemplate {
+        name: "Patient Intake",
+        description: "Gather a new patient's history, symptoms and concerns",
+        domain: MeetingDomain::Medical,
+        duration_estimate_minutes: 30,
+        goals: &[
+            ("Record the chief complaint and symptoms", 5),
+            ("Review medical history, medications and allergies", 5),
+            ("Agree on a care plan and follow-up", 4),
+        ],
+        questions: &[
+            ("What brings you in today?", "history", 5),
+            ("When did the symptoms start?", "history", 4),
+            ("Are you taking any medications or supplements?", "medications", 5),
+            ("Do you have any allergies?", "medications", 5),
+        ],
+        key_points: &["Chief complaint", "Symptoms", "Medical history", "Medications", "Allergies", "Family history"],
+        potential_challenges: &["Incomplete history", "Patient anxiety", "Conflicting medications"],
+    },
+    BuiltInTemplate {
+        name: "Contract Review",
+        description: "Go through a contract's key terms and open issues",
+        domain: MeetingDomain::Legal,
+        duration_estimate_minutes: 60,
+        goals: &[
+            ("Review the key terms and obligations", 5),
+            ("List open issues and proposed changes", 4),
+            ("Agree on who redlines what, and by when", 3),
+        ],
+        questions: &[
+            ("What are the termination conditions?", "terms", 4),
+            ("How is liability limited?", "risk", 5),
+            ("Which terms are non-negotiable?", "negotiation", 3),
+        ],
+        key_points: &["Scope", "Payment terms", "Liability", "Termination", "Confidentiality", "Governing law"],
+        potential_challenges: &["Ambiguous wording", "Unbalanced liability", "Tight deadlines"],
+    },
+    BuiltInTemplate {
+        name: "Incident Review",
+        description: "Walk through an incident's timeline, causes and follow-up actions, without blame",
+        domain: MeetingDomain::Technical,
+        duration_estimate_minutes: 45,
+        goals: &[
+            ("Agree on the timeline of the incident", 5),
+            ("Identify the contributing causes", 5),
+            ("Assign follow-up actions with owners", 4),
+        ],
+        questions: &[
+            ("How was the incident detected?", "timeline", 5),
+            ("What made it worse or slowed the response?", "analysis", 4),
+            ("What would have caught this earlier?", "prevention", 4),
+        ],
+        key_points: &["Impact", "Timeline", "Detection", "Contributing causes", "Follow-up actions"],
+        potential_challenges: &["Assigning blame", "Stopping at the first cause", "Actions without owners"],
+    },
+    BuiltInTemplate {
+        name: "One-on-One",
+        description: "Regular check-in between a manager and a report",
+        domain: MeetingDomain::General,
+        duration_estimate_minutes: 30,
+        goals: &[
+            ("Check in on wellbeing and workload", 4),
+            ("Discuss progress and blockers", 4),
+            ("Talk about growth and feedback", 3),
+        ],
+        questions: &[
+            ("How are you feeling about your workload?", "wellbeing", 4),
+            ("Is anything blocking you?", "follow-up", 4),
+            ("What would you like more feedback on?", "growth", 3),
+        ],
+        key_points: &["Wellbeing", "Priorities", "Blockers", "Feedback", "Career growth"],
+        potential_challenges: &["Status-update drift", "Avoiding difficult topics"],
+    },
+];
+
+/// File the user's templates are saved to
+pub fn get_templates_path() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_local_dir()
+        .ok_or("Could not find local data directory")?;
+    Ok(data_dir.join("hypergranola").join("templates.json"))
+}
+
+/// Templates saved by the user, loaded at startup and saved whenever they change
+#[derive(Debug, Default)]
+pub struct TemplateStore {
+    templates: Vec<MeetingTemplate>,
+}
+
+impl TemplateStore {
+    /// Load the saved templates, starting empty if there are none.
+    /// An unreadable file is set aside rather than left for the next save to overwrite.
+    pub fn load() -> Result<Self, String> {
+        let templates = storage::read_json(&get_templates_path()?)?.unwrap_or_default();
+        Ok(Self { templates })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        storage::atomic_write_json(&get_templates_path()?, &self.templates)
+    }
+
+    /// Built-in templates followed by the user's
+    pub fn list(&self) -> Vec<MeetingTemplate> {
+        BUILT_IN_TEMPLATES
+            .iter()
+            .map(BuiltInTemplate::to_template)
+            .chain(self.templates.iter().cloned())
+            .collect()
+    }
+
+    /// Find a template by name, ignoring case
+    pub fn find(&self, name: &str) -> Option<MeetingTemplate> {
+        let name = name.trim();
+        BUILT_IN_TEMPLATES
+            .iter()
+            .find(|template| template.name.eq_ignore_ascii_case(name))
+            .map(BuiltInTemplate::to_template)
+            .or_else(|| {
+                self.templates
+                    .iter()
+                    .find(|template| template.name.eq_ignore_ascii_case(name))
+                    .cloned()
+            })
+    }
+
+    /// Add a template, replacing the user's template of the same name.
+    /// Built-in templates can't be replaced.
+    pub fn upsert(&mut self, template: MeetingTemplate) -> Result<(), String> {
+        if template.name.is_empty() {
+            return Err("Template name can't be empty".to_string());
+        }
+        if is_built_in(&template.name) {
+            return Err(format!("{} is a built-in template, choose another name", template.name));
+        }
+        match self
+            .templates
+            .iter_mut()
+            .find(|existing| existing.name.eq_ignore_ascii_case(&template.name))
+        {
+            Some(existing) => *existing = template,
+            None => self.templates.push(template),
+        }
+        Ok(())
+    }
+
+    /// Delete one of the user's templates
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        if is_built_in(name) {
+            return Err(format!("{} is a built-in template and can't be deleted", name.trim()));
+        }
+        let before = self.templates.len();
+        self.templates.retain(|template| !template.name.eq_ignore_ascii_case(name.trim()));
+        if self.templates.len() == before {
+            return Err(format!("Unknown template: {}", name));
+        }
+        Ok(())
+    }
+}
+
+fn is_built_in(name: &str) -> bool {
+    BUILT_IN_TEMPLATES
+        .iter()
+        .any(|template| template.name.eq_ignore_ascii_case(name.trim()))
+}
+
+pub type SharedTemplateStore = Arc<Mutex<TemplateStore>>;
+
+impl MeetingContext {
+    /// Fill in the context from a template, adding to the participants, goals, questions and points already set
+    pub fn apply_template(&mut self, template: &MeetingTemplate) {
+        if self.title == MeetingContext::default().title {
+            self.title = template.name.clone();
+        }
+        if self.description.is_none() {
+            self.description = template.description.clone();
+        }
+        self.domain = template.domain.clone();
+        self.duration_estimate_minutes = template.duration_estimate_minutes;
+
+        // Skip anything already there, so applying a template twice doesn't duplicate it
+        for participant in &template.participants {
+            if !self.participants.iter().any(|existing| existing.name.eq_ignore_ascii_case(&participant.name)) {
+                self.participants.push(MeetingParticipant {
+                    is_present: false,
+                    ..participant.clone()
+                });
+            }
+        }
+        for goal in &template.goals {
+            if !self.goals.iter().any(|existing| existing.description == goal.description) {
+                self.goals.push(MeetingGoal {
+                    status: GoalStatus::Pending,
+                    ..goal.clone()
+                });
+            }
+        }
+        for question in &template.pre_generated_questions {
+            if !self.pre_generated_questions.iter().any(|existing| existing.question == question.question) {
+                self.pre_generated_questions.push(PreGeneratedQuestion {
+                    asked: false,
+                    ..question.clone()
+                });
+            }
+        }
+        for point in &template.key_points_to_cover {
+            if !self.key_points_to_cover.contains(point) {
+                self.key_points_to_cover.push(point.clone());
+            }
+        }
+        for challenge in &template.potential_challenges {
+            if !self.potential_challenges.contains(challenge) {
+                self.potential_challenges.push(challenge.clone());
+            }
+        }
+
+        self.template_name = Some(template.name.clone());
+        self.last_modified = chrono::Utc::now();
+    }
+}
@@ -0,0 +1,225 @@
+//! Session audio recording
+//! Writes the captured 16kHz mono audio to a WAV file alongside transcription
+
+use crate::audio::WHISPER_SAMPLE_RATE;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// How often the WAV header is rewritten so a crash loses at most this much audio
+const FLUSH_INTERVAL_SAMPLES: u64 = WHISPER_SAMPLE_RATE as u64 * 5;
+/// Bytes per recorded sample (32-bit float)
+const BYTES_PER_SAMPLE: u64 = 4;
+
+/// Recording options chosen by the user
+#[derive(Debug, Clone, Default)]
+pub struct RecordingSettings {
+    pub enabled: bool,
+    pub path: Option<PathBuf>,
+    pub overwrite: bool,
+}
+
+/// Summary emitted when a recording is closed
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RecordingInfo {
+    pub path: String,
+    pub duration_secs: f64,
+}
+
+/// WAV file writer for captured audio.
+/// The header is finalized on `finish`, or on drop if the session ends abruptly.
+pub struct WavRecorder {
+    writer: Option<WavWriter<BufWriter<File>>>,
+    path: PathBuf,
+    samples_written: u64,
+    samples_flushed: u64,
+}
+
+impl WavRecorder {
+    /// Create a new recording, refusing to replace an existing file unless `overwrite` is set
+    pub fn create(path: &Path, overwrite: bool) -> Result<Self, String> {
+        if path.exists() && !overwrite {
+            return Err(format!("Recording file already exists: {:?}", path));
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create recording directory: {}", e))?;
+        }
+
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: WHISPER_SAMPLE_RATE,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+        let writer = WavWriter::create(path, spec)
+            .map_err(|e| format!("Failed to create recording file: {}", e))?;
+        mark_in_progress(path);
+
+        println!("Recording audio to: {:?}", path);
+        Ok(Self {
+            writer: Some(writer),
+            path: path.to_path_buf(),
+            samples_written: 0,
+            samples_flushed: 0,
+        })
+    }
+
+    /// Open a recording as configured, generating a timestamped path if none was given
+    pub fn from_settings(settings: &RecordingSettings) -> Result<Self, String> {
+        let path = match &settings.path {
+            Some(path) => path.clone(),
+            None => default_recording_path()?,
+        };
+        Self::create(&path, settings.overwrite)
+    }
+
+    /// Append samples to the recording
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        if let Some(writer) = &mut self.writer {
+            for &sample in samples {
+                writer
+                    .write_sample(sample)
+                    .map_err(|e| format!("Failed to write recording: {}", e))?;
+            }
+            self.samples_written += samples.len() as u64;
+
+            // Keep the header current so the file stays playable if the app dies
+            if self.samples_written - self.samples_flushed >= FLUSH_INTERVAL_SAMPLES {
+                writer
+                    .flush()
+                    .map_err(|e| format!("Failed to flush recording: {}", e))?;
+                self.samples_flushed = self.samples_written;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finalize the WAV header and return where the recording was saved
+    pub fn finish(mut self) -> Result<RecordingInfo, String> {
+        if let Some(writer) = self.writer.take() {
+            writer
+                .finalize()
+                .map_err(|e| format!("Failed to finalize recording: {}", e))?;
+            clear_in_progress();
+        }
+
+        println!("Recording saved to: {:?}", self.path);
+        Ok(RecordingInfo {
+            path: self.path.to_string_lossy().to_string(),
+            duration_secs: self.samples_written as f64 / WHISPER_SAMPLE_RATE as f64,
+        })
+    }
+}
+
+impl Drop for WavRecorder {
+    fn drop(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            if writer.finalize().is_ok() {
+                clear_in_progress();
+            }
+        }
+    }
+}
+
+/// Get the directory recordings are saved to by default
+pub fn get_recordings_dir() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_local_dir()
+        .ok_or("Could not find local data directory")?;
+    Ok(data_dir.join("hypergranola").join("recordings"))
+}
+
+/// Timestamped path for a new recording
+fn default_recording_path() -> Result<PathBuf, String> {
+    let filename = format!("meeting-{}.wav", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    Ok(get_recordings_dir()?.join(filename))
+}
+
+/// Marker naming the recording currently being written
+fn in_progress_marker() -> Result<PathBuf, String> {
+    Ok(get_recordings_dir()?.join(".in_progress"))
+}
+
+fn mark_in_progress(path: &Path) {
+    if let Ok(marker) = in_progress_marker() {
+        if let Some(dir) = marker.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let _ = std::fs::write(marker, path.to_string_lossy().as_bytes());
+    }
+}
+
+fn clear_in_progress() {
+    if let Ok(marker) = in_progress_marker() {
+        let _ = std::fs::remove_file(marker);
+    }
+}
+
+/// Fix up the header of a recording left unfinished by a crash.
+/// Call once on launch, before any new recording starts.
+pub fn repair_unfinished_recording() {
+    let marker = match in_progress_marker() {
+        Ok(marker) if marker.exists() => marker,
+        _ => return,
+    };
+
+    if let Ok(path) = std::fs::read_to_string(&marker) {
+        let path = PathBuf::from(path.trim());
+        match repair_wav_header(&path) {
+            Ok(true) => println!("Repaired unfinished recording: {:?}", path),
+            Ok(false) => {}
+            Err(e) => eprintln!("Failed to repair recording {:?}: {}", path, e),
+        }
+    }
+    let _ = std::fs::remove_file(marker);
+}
+
+/// Rewrite the RIFF and data chunk sizes to match the audio actually on disk.
+/// Returns whether the header needed fixing.
+fn repair_wav_header(path: &Path) -> Result<bool, String> {
+    let io_err = |e: std::io::Error| e.to_string();
+
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(io_err)?;
+    let file_len = file.metadata().map_err(io_err)?.len();
+
+    let mut riff = [0u8; 12];
+    file.read_exact(&mut riff).map_err(io_err)?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return Err("Not a WAV file".to_string());
+    }
+
+    // Walk the chunks to find where the audio data starts
+    let mut pos: u64 = 12;
+    while pos + 8 <= file_len {
+        let mut chunk = [0u8; 8];
+        file.seek(SeekFrom::Start(pos)).map_err(io_err)?;
+        file.read_exact(&mut chunk).map_err(io_err)?;
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+
+        if &chunk[0..4] == b"data" {
+            // Drop a trailing partial sample, if the crash left one
+            let data_len = (file_len - pos - 8) / BYTES_PER_SAMPLE * BYTES_PER_SAMPLE;
+            if data_len == size {
+                return Ok(false);
+            }
+
+            file.seek(SeekFrom::Start(pos + 4)).map_err(io_err)?;
+            file.write_all(&(data_len as u32).to_le_bytes()).map_err(io_err)?;
+            file.seek(SeekFrom::Start(4)).map_err(io_err)?;
+            file.write_all(&((pos + data_len) as u32).to_le_bytes()).map_err(io_err)?;
+            file.set_len(pos + 8 + data_len).map_err(io_err)?;
+            return Ok(true);
+        }
+
+        // Chunks are padded to an even length
+        pos += 8 + size + (size & 1);
+    }
+
+    Err("No data chunk found".to_string())
+}
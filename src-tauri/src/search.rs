@@ -0,0 +1,452 @@
+//! Web search
+//! Looks up context for the meeting assistant through a configurable search backend
+
+use crate::http_util::{RetryAttempt, RetryPolicy, DEFAULT_MAX_ATTEMPTS};
+use reqwest::Client;
+use scraper::{Html, Selector};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Results kept per search
+const MAX_RESULTS: usize = 3;
+/// Queries searched per transcript
+pub const MAX_QUERIES: usize = 3;
+/// Longest keyphrase used as a query
+const MAX_QUERY_WORDS: usize = 6;
+/// Searches kept in the cache
+const CACHE_CAPACITY: usize = 50;
+/// How long cached results are served instead of searching again
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+/// Least time between two rounds of web searches
+pub const SEARCH_DEBOUNCE: Duration = Duration::from_secs(10);
+/// Share of words in common above which a query counts as already searched
+const QUERY_SIMILARITY: f32 = 0.6;
+/// Words that split keyphrases, including filler common in speech
+const STOP_WORDS: &[&str] = &[
+    "a", "about", "actually", "after", "again", "all", "also", "am", "an", "and", "any", "are", "as", "at",
+    "be", "because", "been", "before", "being", "but", "by", "can", "could", "did", "do", "does", "doing",
+    "don't", "for", "from", "get", "go", "going", "gonna", "got", "had", "has", "have", "he", "her", "here",
+    "him", "his", "how", "i", "i'm", "if", "in", "into", "is", "it", "it's", "its", "just", "kind", "know",
+    "let", "let's", "like", "maybe", "me", "mean", "more", "my", "need", "no", "not", "now", "of", "okay",
+    "on", "one", "or", "our", "out", "really", "right", "say", "said", "see", "she", "should", "so", "some",
+    "sort", "that", "that's", "the", "their", "them", "then", "there", "there's", "these", "they", "think",
+    "this", "those", "to", "too", "uh", "um", "up", "us", "very", "want", "was", "we", "we're", "well",
+    "were", "what", "what's", "when", "where", "which", "who", "why", "will", "with", "would", "yeah", "yes",
+    "you", "you're", "your",
+];
+/// Longest wait for a connection to the search service
+const SEARCH_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest a search may take, including reading the results
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(15);
+/// Retries of a search that failed transiently, short since the assistant is waiting on it
+const SEARCH_RETRY: RetryPolicy = RetryPolicy {
+    max_attempts: DEFAULT_MAX_ATTEMPTS,
+    base_delay: Duration::from_millis(500),
+    max_delay: Duration::from_secs(5),
+};
+/// Browser user agent, DuckDuckGo refuses unknown clients
+const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36";
+
+/// One web search hit
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+/// HTTP client for searches, which gives up on a stalled service instead of waiting forever
+pub fn http_client() -> Result<Client, String> {
+    Client::builder()
+        .connect_timeout(SEARCH_CONNECT_TIMEOUT)
+        .timeout(SEARCH_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create search client: {}", e))
+}
+
+/// Describe a failed search request, telling a timeout apart from other failures
+fn request_error(provider: &str, e: reqwest::Error) -> String {
+    if e.is_timeout() {
+        format!("{} search timed out after {}s: {}", provider, SEARCH_TIMEOUT.as_secs(), e)
+    } else {
+        format!("{} request failed: {}", provider, e)
+    }
+}
+
+/// Log a search about to be retried
+fn log_retry(provider: &str) -> impl Fn(&RetryAttempt) + '_ {
+    move |retry: &RetryAttempt| {
+        eprintln!(
+            "{} request failed ({}), retrying in {:.1}s ({}/{})",
+            provider,
+            retry.reason,
+            retry.delay_ms as f64 / 1000.0,
+            retry.attempt,
+            retry.max_attempts
+        )
+    }
+}
+
+/// A web search service
+pub trait SearchProvider {
+    /// Name shown in logs and messages
+    fn name(&self) -> &'static str;
+
+    async fn search(&self, client: &Client, query: &str) -> Result<Vec<SearchResult>, String>;
+}
+
+/// Scrapes DuckDuckGo's HTML results page, needs no key but can be blocked
+pub struct DuckDuckGoHtml;
+
+impl SearchProvider for DuckDuckGoHtml {
+    fn name(&self) -> &'static str {
+        "DuckDuckGo"
+    }
+
+    async fn search(&self, client: &Client, query: &str) -> Result<Vec<SearchResult>, String> {
+        let request = client
+            .post("https://html.duckduckgo.com/html/")
+            .header(reqwest::header::USER_AGENT, BROWSER_USER_AGENT)
+            .form(&[("q", query)]);
+        let res = SEARCH_RETRY
+            .send(request, log_retry(self.name()))
+            .await
+            .map_err(|e| request_error(self.name(), e))?;
+
+        let html_content = res.text().await.map_err(|e| request_error(self.name(), e))?;
+        let document = Html::parse_document(&html_content);
+
+        // Selectors
+        let result_selector = Selector::parse(".result").unwrap();
+        let title_selector = Selector::parse(".result__title .result__a").unwrap();
+        let snippet_selector = Selector::parse(".result__snippet").unwrap();
+
+        let mut results = Vec::new();
+        for element in document.select(&result_selector).take(MAX_RESULTS) {
+            let title_element = element.select(&title_selector).next();
+            let title = title_element.map(|e| e.text().collect::<String>()).unwrap_or("No Title".into());
+            let url = title_element.and_then(|e| e.value().attr("href")).unwrap_or("#").to_string();
+            let snippet = element.select(&snippet_selector).next().map(|e| e.text().collect::<String>()).unwrap_or_default();
+
+            if !title.is_empty() {
+                results.push(SearchResult {
+                    title: title.trim().to_string(),
+                    url,
+                    snippet: snippet.trim().to_string(),
+                });
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// Brave Search API, needs BRAVE_API_KEY
+pub struct BraveSearch {
+    api_key: String,
+}
+
+impl SearchProvider for BraveSearch {
+    fn name(&self) -> &'static str {
+        "Brave Search"
+    }
+
+    async fn search(&self, client: &Client, query: &str) -> Result<Vec<SearchResult>, String> {
+        let count = MAX_RESULTS.to_string();
+        let request = client
+            .get("https://api.search.brave.com/res/v1/web/search")
+            .query(&[("q", query), ("count", count.as_str())])
+            .header(reqwest::header::ACCEPT, "application/json")
+            .header("X-Subscription-Token", &self.api_key);
+        let json: serde_json::Value = SEARCH_RETRY
+            .send(request, log_retry(self.name()))
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| request_error(self.name(), e))?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Brave Search JSON: {}", e))?;
+
+        Ok(json["web"]["results"]
+            .as_array()
+            .map(|results| results.iter().take(MAX_RESULTS).map(|r| json_result(r, "url", "description")).collect())
+            .unwrap_or_default())
+    }
+}
+
+/// SerpAPI (Google results), needs SERPAPI_API_KEY
+pub struct SerpApi {
+    api_key: String,
+}
+
+impl SearchProvider for SerpApi {
+    fn name(&self) -> &'static str {
+        "SerpAPI"
+    }
+
+    async fn search(&self, client: &Client, query: &str) -> Result<Vec<SearchResult>, String> {
+        let request = client
+            .get("https://serpapi.com/search.json")
+            .query(&[("engine", "google"), ("q", query), ("api_key", self.api_key.as_str())]);
+        let json: serde_json::Value = SEARCH_RETRY
+            .send(request, log_retry(self.name()))
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| request_error(self.name(), e))?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse SerpAPI JSON: {}", e))?;
+
+        Ok(json["organic_results"]
+            .as_array()
+            .map(|results| results.iter().take(MAX_RESULTS).map(|r| json_result(r, "link", "snippet")).collect())
+            .unwrap_or_default())
+    }
+}
+
+/// A word as compared for keyphrases: lowercase, without surrounding punctuation
+fn normalize_word(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase()
+}
+
+/// Extract up to `max` search queries from a transcript using RAKE: phrases are the
+/// runs of words between stop words, scored by how connected and frequent their words are
+pub fn extract_queries(text: &str, max: usize) -> Vec<String> {
+    let mut phrases: Vec<Vec<String>> = Vec::new();
+    for sentence in text.split(['.', ',', ';', ':', '?', '!', '\n']) {
+        let mut phrase = Vec::new();
+        for word in sentence.split_whitespace().map(normalize_word) {
+            if word.is_empty() || STOP_WORDS.contains(&word.as_str()) {
+                phrases.push(std::mem::take(&mut phrase));
+            } else {
+                phrase.push(word);
+            }
+        }
+        phrases.push(phrase);
+    }
+    // Single short words or numbers make poor queries
+    phrases.retain(|phrase| {
+        phrase.len() > 1 || phrase.first().is_some_and(|word| word.len() > 3 && !word.chars().all(|c| c.is_numeric()))
+    });
+    for phrase in &mut phrases {
+        phrase.truncate(MAX_QUERY_WORDS);
+    }
+
+    // Word score is degree / frequency, favouring words that appear in long phrases
+    let mut frequency: HashMap<&str, f32> = HashMap::new();
+    let mut degree: HashMap<&str, f32> = HashMap::new();
+    for phrase in &phrases {
+        for word in phrase {
+            *frequency.entry(word).or_default() += 1.0;
+            *degree.entry(word).or_default() += phrase.len() as f32;
+        }
+    }
+    let score = |phrase: &[String]| -> f32 {
+        phrase.iter().map(|word| degree[word.as_str()] / frequency[word.as_str()]).sum()
+    };
+
+    let mut scored: Vec<(f32, String)> = phrases.iter().map(|phrase| (score(phrase), phrase.join(" "))).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut queries: Vec<String> = Vec::new();
+    for (_, query) in scored {
+        if queries.len() == max {
+            break;
+        }
+        if !queries.iter().any(|existing| is_similar_query(existing, &query)) {
+            queries.push(query);
+        }
+    }
+    queries
+}
+
+/// Check whether two queries share most of their words
+fn is_similar_query(a: &str, b: &str) -> bool {
+    let a: Vec<String> = a.split_whitespace().map(normalize_word).collect();
+    let b: Vec<String> = b.split_whitespace().map(normalize_word).collect();
+    let shared = a.iter().filter(|word| b.contains(word)).count();
+    let total = a.len() + b.len() - shared;
+    total > 0 && shared as f32 / total as f32 >= QUERY_SIMILARITY
+}
+
+/// A search kept in the cache
+#[derive(Debug)]
+struct CachedSearch {
+    query: String,
+    results: String,
+    searched_at: Instant,
+}
+
+/// Recent search results, and when the last search went out.
+/// Keeps live meetings from searching on every transcript chunk.
+#[derive(Debug, Default)]
+pub struct SearchCache {
+    entries: VecDeque<CachedSearch>,
+    last_search: Option<Instant>,
+}
+
+impl SearchCache {
+    /// Results of an identical or near-identical query searched within the cache window
+    pub fn get(&mut self, query: &str) -> Option<String> {
+        self.entries.retain(|entry| entry.searched_at.elapsed() < CACHE_TTL);
+        self.entries
+            .iter()
+            .find(|entry| is_similar_query(&entry.query, query))
+            .map(|entry| entry.results.clone())
+    }
+
+    pub fn insert(&mut self, query: &str, results: String) {
+        self.entries.push_back(CachedSearch {
+            query: query.to_string(),
+            results,
+            searched_at: Instant::now(),
+        });
+        if self.entries.len() > CACHE_CAPACITY {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Start a round of searches unless the last one was under SEARCH_DEBOUNCE ago,
+    /// in which case return how long is left to wait
+    pub fn start_search(&mut self) -> Result<(), Duration> {
+        if let Some(last_search) = self.last_search {
+            let elapsed = last_search.elapsed();
+            if elapsed < SEARCH_DEBOUNCE {
+                return Err(SEARCH_DEBOUNCE - elapsed);
+            }
+        }
+        self.last_search = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Split `queries` into results already cached and queries to search now. New queries
+    /// are held back while the last round of searches is too recent.
+    pub fn plan(&mut self, queries: Vec<String>) -> SearchPlan {
+        let mut plan = SearchPlan::default();
+        for query in queries {
+            match self.get(&query) {
+                Some(results) => plan.sections.push(format!("Results for \"{}\":\n{}", query, results)),
+                None => plan.new_queries.push(query),
+            }
+        }
+        if !plan.new_queries.is_empty() {
+            if let Err(wait) = self.start_search() {
+                plan.skipped = Some(SearchSkipped {
+                    queries: std::mem::take(&mut plan.new_queries),
+                    retry_in_ms: wait.as_millis() as u64,
+                });
+            }
+        }
+        plan
+    }
+}
+
+/// What to look up for a piece of transcript, see `SearchCache::plan`
+#[derive(Debug, Default)]
+pub struct SearchPlan {
+    /// Cached results, as sections of the assistant's search context
+    pub sections: Vec<String>,
+    /// Queries to search now
+    pub new_queries: Vec<String>,
+    /// Queries held back by the debounce
+    pub skipped: Option<SearchSkipped>,
+}
+
+pub type SharedSearchCache = Arc<Mutex<SearchCache>>;
+
+/// Payload of the `search_skipped` event
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchSkipped {
+    pub queries: Vec<String>,
+    /// Time until searching is allowed again
+    pub retry_in_ms: u64,
+}
+
+/// Read a result from an API response, whose field names differ between services
+fn json_result(result: &serde_json::Value, url_key: &str, snippet_key: &str) -> SearchResult {
+    let field = |key: &str| result[key].as_str().unwrap_or_default().trim().to_string();
+    SearchResult {
+        title: field("title"),
+        url: field(url_key),
+        snippet: field(snippet_key),
+    }
+}
+
+/// The search provider chosen with SEARCH_PROVIDER (duckduckgo, brave or serpapi).
+/// Falls back to DuckDuckGo when the chosen provider's API key is missing.
+pub enum SearchBackend {
+    DuckDuckGo(DuckDuckGoHtml),
+    Brave(BraveSearch),
+    SerpApi(SerpApi),
+}
+
+impl SearchBackend {
+    pub fn from_env() -> Self {
+        let provider = env::var("SEARCH_PROVIDER").unwrap_or_default().trim().to_lowercase();
+        let key = |name: &str| env::var(name).ok().filter(|key| !key.trim().is_empty());
+
+        let backend = match provider.as_str() {
+            "" | "duckduckgo" | "ddg" => None,
+            "brave" => key("BRAVE_API_KEY").map(|api_key| Self::Brave(BraveSearch { api_key })),
+            "serpapi" => key("SERPAPI_API_KEY").map(|api_key| Self::SerpApi(SerpApi { api_key })),
+            other => {
+                eprintln!("Unknown SEARCH_PROVIDER '{}'", other);
+                None
+            }
+        };
+        backend.unwrap_or_else(|| {
+            if !matches!(provider.as_str(), "" | "duckduckgo" | "ddg") {
+                eprintln!("Search provider '{}' isn't configured, using DuckDuckGo", provider);
+            }
+            Self::DuckDuckGo(DuckDuckGoHtml)
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::DuckDuckGo(provider) => provider.name(),
+            Self::Brave(provider) => provider.name(),
+            Self::SerpApi(provider) => provider.name(),
+        }
+    }
+
+    pub async fn search(&self, client: &Client, query: &str) -> Result<Vec<SearchResult>, String> {
+        match self {
+            Self::DuckDuckGo(provider) => provider.search(client, query).await,
+            Self::Brave(provider) => provider.search(client, query).await,
+            Self::SerpApi(provider) => provider.search(client, query).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transcript_without_keyphrases_plans_no_search() {
+        let queries = extract_queries("Yes, I think so. Okay, let's do that then.", MAX_QUERIES);
+        assert!(queries.is_empty(), "{:?}", queries);
+
+        let mut cache = SearchCache::default();
+        let plan = cache.plan(queries);
+        assert!(plan.sections.is_empty() && plan.new_queries.is_empty() && plan.skipped.is_none());
+        // Nothing went out, so the next transcript can search straight away
+        assert!(cache.start_search().is_ok());
+    }
+
+    #[test]
+    fn cached_queries_are_reused_and_new_ones_debounced() {
+        let mut cache = SearchCache::default();
+        let plan = cache.plan(vec!["kubernetes migration plan".to_string()]);
+        assert_eq!(plan.new_queries, vec!["kubernetes migration plan"]);
+        cache.insert("kubernetes migration plan", "k8s docs".to_string());
+
+        let plan = cache.plan(vec!["kubernetes migration plan".to_string(), "postgres upgrade".to_string()]);
+        assert_eq!(plan.sections, vec!["Results for \"kubernetes migration plan\":\nk8s docs"]);
+        assert!(plan.new_queries.is_empty());
+        assert_eq!(plan.skipped.unwrap().queries, vec!["postgres upgrade"]);
+    }
+}
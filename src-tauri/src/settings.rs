@@ -0,0 +1,87 @@
+//! App-wide settings
+//! Defaults every meeting inherits, like the organization's instructions for the assistant
+
+use crate::llm::LlmConfig;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+
+/// Longest custom prompt text accepted, in characters
+pub const MAX_CUSTOM_PROMPT_CHARS: usize = 4000;
+
+/// Trim a custom prompt text, None if it's empty. Rejects text over MAX_CUSTOM_PROMPT_CHARS.
+pub fn check_custom_prompt(name: &str, text: Option<String>) -> Result<Option<String>, String> {
+    let Some(text) = text.map(|text| text.trim().to_string()).filter(|text| !text.is_empty()) else {
+        return Ok(None);
+    };
+    let length = text.chars().count();
+    if length > MAX_CUSTOM_PROMPT_CHARS {
+        return Err(format!("{} is {} characters, the limit is {}", name, length, MAX_CUSTOM_PROMPT_CHARS));
+    }
+    Ok(Some(text))
+}
+
+/// File the settings are saved to
+pub fn get_settings_path() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_local_dir()
+        .ok_or("Could not find local data directory")?;
+    Ok(data_dir.join("hypergranola").join("settings.json"))
+}
+
+/// Loaded at startup and saved whenever they change
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Added to the domain prompt of meetings without their own prefix
+    pub default_prompt_prefix: Option<String>,
+    /// Style rules for meetings without their own, e.g. "always reference the OKR codes"
+    pub default_instructions: Option<String>,
+    /// Tokens a session's LLM calls may use before automatic assistant calls pause, None for no limit
+    pub llm_token_budget: Option<u64>,
+    /// Set from the app, None to use the LLM_* environment variables
+    pub llm: Option<LlmConfig>,
+    /// Keep transcripts on this machine: no web search, and only an LLM on localhost
+    pub privacy_mode: bool,
+}
+
+impl AppSettings {
+    /// Load the saved settings, the defaults if there are none.
+    /// An unreadable file is set aside rather than left for the next save to overwrite.
+    pub fn load() -> Result<Self, String> {
+        Ok(storage::read_json(&get_settings_path()?)?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        storage::atomic_write_json(&get_settings_path()?, self)
+    }
+
+    /// Set the org-wide prompt prefix and instructions, None or empty to clear them
+    pub fn set_default_prompt(&mut self, prefix: Option<String>, instructions: Option<String>) -> Result<(), String> {
+        let prefix = check_custom_prompt("Prompt prefix", prefix)?;
+        let instructions = check_custom_prompt("Instructions", instructions)?;
+        self.default_prompt_prefix = prefix;
+        self.default_instructions = instructions;
+        Ok(())
+    }
+
+    /// Set the per-session LLM token budget, None to remove it
+    pub fn set_llm_token_budget(&mut self, budget: Option<u64>) -> Result<(), String> {
+        if budget == Some(0) {
+            return Err("Token budget must be at least 1".to_string());
+        }
+        self.llm_token_budget = budget;
+        Ok(())
+    }
+}
+
+pub type SharedSettings = Arc<Mutex<AppSettings>>;
+
+/// Whether privacy mode is on
+pub fn privacy_mode(app_handle: &AppHandle) -> bool {
+    app_handle
+        .try_state::<SharedSettings>()
+        .and_then(|settings| settings.lock().ok().map(|settings| settings.privacy_mode))
+        .unwrap_or(false)
+}
@@ -0,0 +1,235 @@
+//! Known speaker profiles
+//! Voice fingerprints of named speakers, persisted so they're recognized in later meetings
+
+use crate::storage;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Samples per analysis frame, 32ms at 16kHz
+const FRAME_SIZE: usize = 512;
+/// Frequency bands of the fingerprint, spread over the range of the voice
+const BAND_COUNT: usize = 24;
+const MIN_BAND_HZ: f32 = 100.0;
+const MAX_BAND_HZ: f32 = 4000.0;
+/// Frames quieter than this (mean square) are left out of the fingerprint
+const SILENT_FRAME_ENERGY: f32 = 1e-5;
+/// Least voiced audio a fingerprint is computed from
+const MIN_VOICED_FRAMES: usize = 15;
+/// Cosine similarity above which a voice matches a known speaker
+pub const DEFAULT_RECOGNITION_THRESHOLD: f32 = 0.9;
+
+/// A coarse spectral fingerprint of a voice: the average log energy in each band,
+/// centered and normalized so cosine similarity compares the shape rather than loudness.
+/// Returns None when there's too little speech to go on.
+pub fn voice_embedding(samples: &[f32], sample_rate: u32) -> Option<Vec<f32>> {
+    if sample_rate == 0 {
+        return None;
+    }
+    let nyquist = sample_rate as f32 / 2.0;
+    let max_hz = MAX_BAND_HZ.min(nyquist * 0.95);
+    let frequencies: Vec<f32> = (0..BAND_COUNT)
+        .map(|band| MIN_BAND_HZ * (max_hz / MIN_BAND_HZ).powf(band as f32 / (BAND_COUNT - 1) as f32))
+        .collect();
+
+    let mut totals = [0.0f32; BAND_COUNT];
+    let mut voiced_frames = 0;
+    for frame in samples.chunks_exact(FRAME_SIZE) {
+        let energy = frame.iter().map(|s| s * s).sum::<f32>() / FRAME_SIZE as f32;
+        if energy < SILENT_FRAME_ENERGY {
+            continue;
+        }
+        for (total, &frequency) in totals.iter_mut().zip(&frequencies) {
+            *total += (goertzel_power(frame, frequency, sample_rate) + 1e-10).ln();
+        }
+        voiced_frames += 1;
+    }
+    if voiced_frames < MIN_VOICED_FRAMES {
+        return None;
+    }
+
+    let mean = totals.iter().sum::<f32>() / BAND_COUNT as f32;
+    let centered: Vec<f32> = totals.iter().map(|total| total - mean).collect();
+    normalize(centered)
+}
+
+/// Power of one frequency in a frame
+fn goertzel_power(frame: &[f32], frequency: f32, sample_rate: u32) -> f32 {
+    let coefficient = 2.0 * (2.0 * std::f32::consts::PI * frequency / sample_rate as f32).cos();
+    let (mut previous, mut before_previous) = (0.0f32, 0.0f32);
+    for &sample in frame {
+        let current = sample + coefficient * previous - before_previous;
+        before_previous = previous;
+        previous = current;
+    }
+    (previous * previous + before_previous * before_previous - coefficient * previous * before_previous) / frame.len() as f32
+}
+
+/// Scale a vector to unit length, None if it's all zeros
+fn normalize(vector: Vec<f32>) -> Option<Vec<f32>> {
+    let length = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    (length > f32::EPSILON).then(|| vector.iter().map(|v| v / length).collect())
+}
+
+/// Cosine similarity of two fingerprints
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+    let length_a = a.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let length_b = b.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if length_a <= f32::EPSILON || length_b <= f32::EPSILON {
+        return 0.0;
+    }
+    dot / (length_a * length_b)
+}
+
+/// Running average of the fingerprints heard from one speaker
+#[derive(Debug, Clone, Default)]
+pub struct VoiceCentroid {
+    sum: Vec<f32>,
+    /// Seconds of audio the fingerprints came from
+    pub seconds: f32,
+}
+
+impl VoiceCentroid {
+    pub fn add(&mut self, embedding: &[f32], seconds: f32) {
+        if self.sum.len() != embedding.len() {
+            self.sum = vec![0.0; embedding.len()];
+            self.seconds = 0.0;
+        }
+        for (sum, value) in self.sum.iter_mut().zip(embedding) {
+            *sum += value * seconds;
+        }
+        self.seconds += seconds;
+    }
+
+    /// Fold in the voice of another speaker found to be the same person
+    pub fn merge(&mut self, other: &VoiceCentroid) {
+        if self.sum.is_empty() {
+            *self = other.clone();
+        } else if self.sum.len() == other.sum.len() {
+            for (sum, value) in self.sum.iter_mut().zip(&other.sum) {
+                *sum += value;
+            }
+            self.seconds += other.seconds;
+        }
+    }
+
+    /// The averaged fingerprint, None until something was added
+    pub fn embedding(&self) -> Option<Vec<f32>> {
+        normalize(self.sum.clone())
+    }
+}
+
+/// A named voice saved for recognizing in later meetings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownSpeaker {
+    pub name: String,
+    pub embedding: Vec<f32>,
+    /// Seconds of audio the fingerprint was built from
+    pub sample_seconds: f32,
+    pub updated_at: DateTime<Local>,
+}
+
+/// A known speaker as listed to the frontend, without the fingerprint
+#[derive(Debug, Clone, Serialize)]
+pub struct KnownSpeakerInfo {
+    pub name: String,
+    pub sample_seconds: f32,
+    pub updated_at: DateTime<Local>,
+}
+
+impl From<&KnownSpeaker> for KnownSpeakerInfo {
+    fn from(speaker: &KnownSpeaker) -> Self {
+        Self {
+            name: speaker.name.clone(),
+            sample_seconds: speaker.sample_seconds,
+            updated_at: speaker.updated_at,
+        }
+    }
+}
+
+/// File the known speakers are saved to
+pub fn get_speaker_profiles_path() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_local_dir()
+        .ok_or("Could not find local data directory")?;
+    Ok(data_dir.join("hypergranola").join("known_speakers.json"))
+}
+
+/// Known speakers, loaded at startup and saved whenever they change
+#[derive(Debug, Default)]
+pub struct SpeakerProfiles {
+    speakers: Vec<KnownSpeaker>,
+}
+
+impl SpeakerProfiles {
+    /// Load the saved speakers, starting empty if there are none.
+    /// An unreadable file is set aside rather than left for the next save to overwrite.
+    pub fn load() -> Result<Self, String> {
+        let speakers = storage::read_json(&get_speaker_profiles_path()?)?.unwrap_or_default();
+        Ok(Self { speakers })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        storage::atomic_write_json(&get_speaker_profiles_path()?, &self.speakers)
+    }
+
+    pub fn list(&self) -> Vec<KnownSpeakerInfo> {
+        self.speakers.iter().map(KnownSpeakerInfo::from).collect()
+    }
+
+    /// Save a voice under `name`, averaging it into the existing fingerprint if the name is known
+    pub fn upsert(&mut self, name: &str, embedding: &[f32], seconds: f32) {
+        let updated_at = Local::now();
+        if let Some(known) = self.speakers.iter_mut().find(|known| known.name.eq_ignore_ascii_case(name)) {
+            if known.embedding.len() == embedding.len() {
+                let total = known.sample_seconds + seconds;
+                let blended: Vec<f32> = known
+                    .embedding
+                    .iter()
+                    .zip(embedding)
+                    .map(|(old, new)| (old * known.sample_seconds + new * seconds) / total)
+                    .collect();
+                if let Some(blended) = normalize(blended) {
+                    known.embedding = blended;
+                    known.sample_seconds = total;
+                }
+            } else {
+                known.embedding = embedding.to_vec();
+                known.sample_seconds = seconds;
+            }
+            known.name = name.to_string();
+            known.updated_at = updated_at;
+            return;
+        }
+
+        self.speakers.push(KnownSpeaker {
+            name: name.to_string(),
+            embedding: embedding.to_vec(),
+            sample_seconds: seconds,
+            updated_at,
+        });
+    }
+
+    /// Forget a speaker, returning whether they were known
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.speakers.len();
+        self.speakers.retain(|known| !known.name.eq_ignore_ascii_case(name.trim()));
+        self.speakers.len() != before
+    }
+
+    /// The known speaker whose voice is most like `embedding`, if any is similar enough
+    pub fn recognize(&self, embedding: &[f32], threshold: f32) -> Option<&KnownSpeaker> {
+        self.speakers
+            .iter()
+            .map(|known| (cosine_similarity(&known.embedding, embedding), known))
+            .filter(|(similarity, _)| *similarity >= threshold)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, known)| known)
+    }
+}
+
+pub type SharedSpeakerProfiles = Arc<Mutex<SpeakerProfiles>>;
@@ -0,0 +1,149 @@
+//! Files in the app data dir
+//! Saves JSON without leaving it half-written, and sets aside files that can't be read
+//! so the next save doesn't overwrite what's in them
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// `path` with `suffix` added to its file name, e.g. meetings.json.tmp
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Write `value` as JSON to `path`. It goes to a `.tmp` file in the same directory first, which
+/// then replaces `path`, so a crash mid-write leaves the previous file intact.
+pub fn atomic_write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+
+    let tmp_path = with_suffix(path, ".tmp");
+    let write = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)
+    };
+    write().map_err(|e| {
+        let _ = std::fs::remove_file(&tmp_path);
+        format!("Failed to save {}: {}", path.display(), e)
+    })
+}
+
+/// Read JSON saved by `atomic_write_json` with `parse`, None when there's no file yet.
+/// A file `parse` fails on is renamed to `<name>.corrupt-<timestamp>` and the error says so.
+pub fn read_json_with<T>(
+    path: &Path,
+    parse: impl FnOnce(&str) -> Result<T, serde_json::Error>,
+) -> Result<Option<T>, String> {
+    let json = match std::fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    match parse(&json) {
+        Ok(value) => Ok(Some(value)),
+        Err(parse_error) => {
+            let corrupt_path = with_suffix(path, &format!(".corrupt-{}", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+            std::fs::rename(path, &corrupt_path).map_err(|e| {
+                format!("{} is unreadable ({}) and couldn't be set aside: {}", path.display(), parse_error, e)
+            })?;
+            Err(format!(
+                "{} was unreadable ({}), it was moved to {} and starts over empty",
+                path.display(),
+                parse_error,
+                corrupt_path.display()
+            ))
+        }
+    }
+}
+
+/// Read JSON saved by `atomic_write_json`, see `read_json_with`
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, String> {
+    read_json_with(path, |json| serde_json::from_str(json))
+}
+
+/// The loaded value, or the default with the error kept for reporting once the app is up
+pub fn loaded_or_default<T: Default>(loaded: Result<T, String>, errors: &mut Vec<String>) -> T {
+    loaded.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        errors.push(e);
+        T::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory of this test run in the temp dir
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hypergranola-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn written_json_reads_back_without_a_temp_file_left() {
+        let dir = test_dir("storage-write");
+        let path = dir.join("nested").join("meetings.json");
+        atomic_write_json(&path, &vec!["standup", "retro"]).unwrap();
+        atomic_write_json(&path, &vec!["planning"]).unwrap();
+
+        assert_eq!(read_json::<Vec<String>>(&path).unwrap(), Some(vec!["planning".to_string()]));
+        assert_eq!(entries(path.parent().unwrap()), vec!["meetings.json"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_file_reads_as_none() {
+        let dir = test_dir("storage-missing");
+        assert_eq!(read_json::<Vec<String>>(&dir.join("settings.json")).unwrap(), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_file_is_set_aside_instead_of_overwritten() {
+        let dir = test_dir("storage-corrupt");
+        let path = dir.join("meetings.json");
+        // What a crash during a plain write could leave behind
+        std::fs::write(&path, r#"{"contexts": {"a": {"title": "Board me"#).unwrap();
+
+        let error = read_json::<serde_json::Value>(&path).unwrap_err();
+        assert!(error.contains("corrupt-"), "{}", error);
+        assert!(!path.exists());
+        let names = entries(&dir);
+        assert_eq!(names.len(), 1);
+        assert!(names[0].starts_with("meetings.json.corrupt-"), "{:?}", names);
+        assert!(std::fs::read_to_string(dir.join(&names[0])).unwrap().contains("Board me"));
+
+        // A save afterwards starts a new file and leaves the set-aside one alone
+        atomic_write_json(&path, &serde_json::json!({})).unwrap();
+        assert_eq!(entries(&dir).len(), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_errors_fall_back_to_the_default() {
+        let mut errors = Vec::new();
+        assert_eq!(loaded_or_default(Ok(vec![1]), &mut errors), vec![1]);
+        assert!(errors.is_empty());
+        assert_eq!(loaded_or_default::<Vec<i32>>(Err("bad file".to_string()), &mut errors), Vec::<i32>::new());
+        assert_eq!(errors, vec!["bad file".to_string()]);
+    }
+}
@@ -1,36 +1,389 @@
 //! Speech-to-Text manager
 //! Coordinates audio capture and whisper transcription
 
-use crate::audio::AudioCapture;
-use crate::whisper::{ModelSize, WhisperEngine, get_model_path, model_exists};
-use ringbuf::HeapProd;
+use crate::audio::{AudioCapture, CaptureSettings, CaptureSource, CaptureStats, ErrorHandler, LevelMeter, ReconnectPolicy, DEFAULT_BUFFER_SECONDS, WHISPER_SAMPLE_RATE};
+use crate::audio_file;
+use crate::correction;
+use crate::diarization::{self, QuestionDetected, SharedDiarizationState, SpeakerChange, SpeakerTranscript};
+use crate::events::{emit_event, report_error, ErrorSource};
+use crate::llm::LlmClient;
+use crate::meeting_context::{CoverageWarning, MeetingContextManager, COVERAGE_WARNING_SHARE};
+use crate::recording::{RecordingSettings, WavRecorder};
+use crate::transcript::{self, SharedTranscriptStore};
+use crate::vad::{self, Utterance, UtteranceSegmenter, VadConfig};
+use crate::whisper::{self, ModelIntegrity, ModelSize, TranscriptSegment, Transcription, WhisperEngine, get_model_path, model_exists};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Manager};
 use tokio::sync::mpsc;
 
-/// Minimum audio duration to process (in samples at 16kHz)
-const MIN_AUDIO_SAMPLES: usize = 16000; // 1 second
-/// Maximum audio duration to process at once
-const MAX_AUDIO_SAMPLES: usize = 16000 * 10; // 10 seconds
+/// Minimum audio duration to process (in samples at 16kHz), unless configured
+const DEFAULT_MIN_AUDIO_SAMPLES: usize = 16000; // 1 second
+/// Maximum audio duration to process at once, unless configured
+const DEFAULT_MAX_AUDIO_SAMPLES: usize = 16000 * 10; // 10 seconds
+/// Longest window that can be configured, the most Whisper takes in at once
+const MAX_WINDOW_SAMPLES: usize = 16000 * 30; // 30 seconds
+/// How often captured audio is drained into the VAD, unless configured
+const DEFAULT_POLL_INTERVAL_MS: u64 = 100;
+/// How often the input level is sent to the UI (~20Hz)
+const LEVEL_INTERVAL_MS: u64 = 50;
+/// Audio from before a pause is kept on resume if the pause was shorter than this, unless configured
+const DEFAULT_STALE_AUDIO_MS: u64 = 5000;
+/// Longest stale-audio threshold that can be configured
+const MAX_STALE_AUDIO_MS: u64 = 60_000;
+/// Longest capture buffer that can be configured
+const MAX_BUFFER_SECONDS: usize = 300;
+/// Audio from the previous chunk re-transcribed at the start of the next
+const DEFAULT_OVERLAP_MS: u32 = 1000;
+/// Longest overlap that can be configured
+const MAX_OVERLAP_MS: u32 = 5000;
+/// Words of preceding transcript given to Whisper as a prompt
+const PROMPT_WORDS: usize = 50;
+/// Speech waiting to be transcribed before the UI is told transcription is falling behind
+const LAG_WARNING_SECONDS: f64 = 15.0;
+/// Longest audio file accepted for import by default
+const DEFAULT_MAX_IMPORT_SECONDS: u64 = 4 * 60 * 60;
+/// Least time between two `speaker_stats` events
+const SPEAKER_STATS_INTERVAL: Duration = Duration::from_secs(30);
+/// How often the active agenda item is checked against its time box
+const AGENDA_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// Finalized segments kept as context for transcript correction
+const CORRECTION_HISTORY_SEGMENTS: usize = 10;
+/// Dropped audio that accumulates before the UI is warned
+const DROP_WARNING_SAMPLES: u64 = WHISPER_SAMPLE_RATE as u64 / 2; // 0.5 seconds
+
+/// Pipeline state changes and errors, sent as the `stt_event` event
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SttEvent {
+    Started,
+    Stopped,
+    Paused,
+    Resumed,
+    ModelLoading { model: String },
+    ModelLoaded { model: String },
+    AudioError { msg: String },
+    TranscriptionError { msg: String },
+    Lagging { seconds_behind: f64 },
+    DeviceLost,
+    DeviceReconnected { device: Option<String> },
+}
+
+/// The most recent pipeline error
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SttError {
+    pub message: String,
+    pub at: chrono::DateTime<chrono::Local>,
+}
+
+/// Sends `stt_event`s and remembers the last error for the status.
+/// Cloned into the audio and transcription threads, so it never takes the STT lock.
+#[derive(Clone)]
+struct EventSink {
+    app_handle: AppHandle,
+    last_error: Arc<Mutex<Option<SttError>>>,
+}
+
+impl EventSink {
+    fn emit(&self, event: SttEvent) {
+        if let SttEvent::AudioError { msg } | SttEvent::TranscriptionError { msg } = &event {
+            if let Ok(mut last_error) = self.last_error.lock() {
+                *last_error = Some(SttError {
+                    message: msg.clone(),
+                    at: chrono::Local::now(),
+                });
+            }
+        }
+        emit_event(&self.app_handle, "stt_event", event);
+    }
+}
+
+/// Thresholds deciding which audio and results count as speech
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct SttSensitivity {
+    /// Utterances quieter than this (RMS, dBFS) aren't transcribed
+    pub min_level_dbfs: f32,
+    /// Segments Whisper rates more likely than this to be non-speech are dropped
+    pub max_no_speech_prob: f32,
+}
+
+impl Default for SttSensitivity {
+    fn default() -> Self {
+        Self {
+            min_level_dbfs: -45.0,
+            max_no_speech_prob: 0.6,
+        }
+    }
+}
+
+/// How often audio is processed and how long the utterances sent to Whisper may be. Shorter
+/// windows and intervals lower latency, longer windows give Whisper more context.
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct SttConfig {
+    /// How often captured audio is drained into the VAD
+    pub interval_ms: u64,
+    /// Shortest utterance transcribed, in samples at 16kHz
+    pub min_samples: usize,
+    /// Longest utterance transcribed at once, longer speech is split
+    pub max_samples: usize,
+    /// Audio buffered before a pause is dropped on resume once the pause lasted this long
+    pub stale_audio_ms: u64,
+}
+
+impl Default for SttConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: DEFAULT_POLL_INTERVAL_MS,
+            min_samples: DEFAULT_MIN_AUDIO_SAMPLES,
+            max_samples: DEFAULT_MAX_AUDIO_SAMPLES,
+            stale_audio_ms: DEFAULT_STALE_AUDIO_MS,
+        }
+    }
+}
+
+impl SttConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.interval_ms == 0 {
+            return Err("Interval must be at least 1ms".to_string());
+        }
+        if self.min_samples == 0 || self.min_samples >= self.max_samples {
+            return Err("Minimum samples must be above 0 and below the maximum".to_string());
+        }
+        if self.max_samples > MAX_WINDOW_SAMPLES {
+            return Err(format!("Maximum samples must be at most {} (30 seconds)", MAX_WINDOW_SAMPLES));
+        }
+        // Each poll drains at most one window, so a longer interval would fall behind the audio
+        let samples_per_interval = self.interval_ms as usize * WHISPER_SAMPLE_RATE as usize / 1000;
+        if samples_per_interval > self.max_samples {
+            return Err("Interval must be shorter than the maximum window".to_string());
+        }
+        if self.stale_audio_ms > MAX_STALE_AUDIO_MS {
+            return Err(format!("Stale audio threshold must be at most {}ms", MAX_STALE_AUDIO_MS));
+        }
+        Ok(())
+    }
+}
 
 /// Global STT state
 pub struct SttState {
-    audio_capture: Option<AudioCapture>,
-    audio_producer: Option<HeapProd<f32>>,
-    whisper: Option<WhisperEngine>,
+    audio_capture: Option<Box<dyn CaptureStream>>,
+    /// Opens the audio devices, a stand-in in tests
+    capture_opener: Arc<dyn CaptureOpener>,
+    whisper: Option<Arc<WhisperEngine>>,
+    loaded_model: Option<ModelSize>,
+    active_model: ModelSize,
+    corrupt_model: Option<ModelSize>,
+    /// Model files that passed verification, so loading them again skips the hash
+    verified_models: HashMap<ModelSize, FileStamp>,
     is_running: bool,
+    /// A start is loading the model, cleared by stop to cancel it
+    is_starting: bool,
+    is_paused: bool,
+    /// When the current pause started
+    paused_at: Option<Instant>,
     shutdown_tx: Option<mpsc::Sender<()>>,
+    capture: CaptureSettings,
+    vad_config: VadConfig,
+    sensitivity: SttSensitivity,
+    config: SttConfig,
+    filter_hallucinations: bool,
+    /// Attribute transcribed utterances to speakers
+    diarization_enabled: bool,
+    last_error: Arc<Mutex<Option<SttError>>>,
+    segmenter: UtteranceSegmenter,
+    recording: RecordingSettings,
+    recorder: Option<WavRecorder>,
+    /// Transcription language, None to auto-detect
+    language: Option<String>,
+    gpu_enabled: bool,
+    buffer_seconds: usize,
+    overlap_ms: u32,
+    /// Whisper CPU threads, None for the default
+    n_threads: Option<usize>,
+    /// GPU setting the loaded model was created with
+    loaded_gpu: bool,
+    /// Longest audio file `transcribe_file` accepts
+    max_import_seconds: u64,
+    /// Text of the last few finalized segments, oldest first, for correcting the next one
+    correction_history: VecDeque<String>,
+    /// Correct each finalized segment with the LLM and emit `corrected_transcript`
+    auto_correct: bool,
 }
 
 impl Default for SttState {
     fn default() -> Self {
         Self {
             audio_capture: None,
-            audio_producer: None,
+            capture_opener: Arc::new(DeviceCapture),
             whisper: None,
+            loaded_model: None,
+            active_model: whisper::load_active_model(),
+            corrupt_model: None,
+            verified_models: HashMap::new(),
             is_running: false,
+            is_starting: false,
+            is_paused: false,
+            paused_at: None,
             shutdown_tx: None,
+            capture: CaptureSettings::default(),
+            vad_config: VadConfig::default(),
+            sensitivity: SttSensitivity::default(),
+            config: SttConfig::default(),
+            filter_hallucinations: true,
+            diarization_enabled: false,
+            last_error: Arc::new(Mutex::new(None)),
+            segmenter: UtteranceSegmenter::new(VadConfig::default(), DEFAULT_MIN_AUDIO_SAMPLES, DEFAULT_MAX_AUDIO_SAMPLES),
+            recording: RecordingSettings::default(),
+            recorder: None,
+            language: Some("en".to_string()),
+            gpu_enabled: whisper::GPU_SUPPORTED,
+            buffer_seconds: DEFAULT_BUFFER_SECONDS,
+            overlap_ms: DEFAULT_OVERLAP_MS,
+            n_threads: None,
+            loaded_gpu: false,
+            max_import_seconds: DEFAULT_MAX_IMPORT_SECONDS,
+            correction_history: VecDeque::new(),
+            auto_correct: false,
+        }
+    }
+}
+
+/// Length and modification time of a model file, to tell whether it changed since it was verified
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Result<Self, String> {
+        let metadata = std::fs::metadata(path).map_err(|e| format!("Failed to read model file: {}", e))?;
+        Ok(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+/// A model to load, read from the settings under the lock so the slow part can run without it
+struct ModelLoad {
+    model: ModelSize,
+    path: PathBuf,
+    gpu: bool,
+    n_threads: Option<usize>,
+    stamp: FileStamp,
+    /// Checksum the file must match, None when none is known for the model
+    sha256: Option<String>,
+    /// The file is unchanged since it last passed verification
+    verified: bool,
+}
+
+impl SttState {
+    /// Stop capturing and end the capture loop, returning the recording to save if there is one.
+    /// Transcription already queued finishes on its own.
+    fn end_session(&mut self) -> Option<WavRecorder> {
+        if let Some(mut capture) = self.audio_capture.take() {
+            capture.stop();
+        }
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.try_send(());
+        }
+        self.is_running = false;
+        self.is_starting = false;
+        self.is_paused = false;
+        self.paused_at = None;
+        self.recorder.take()
+    }
+
+    fn events(&self, app_handle: &AppHandle) -> EventSink {
+        EventSink {
+            app_handle: app_handle.clone(),
+            last_error: self.last_error.clone(),
+        }
+    }
+
+    /// The model to transcribe with: the active model, or its multilingual
+    /// counterpart when a language other than English is selected or auto-detected
+    fn transcription_model(&self) -> ModelSize {
+        if self.language.as_deref() == Some("en") {
+            self.active_model
+        } else {
+            self.active_model.multilingual()
+        }
+    }
+
+    /// Whether audio from before the pause is too old to transcribe on resume.
+    /// It's at least as old as the pause, so that's what's compared to the threshold.
+    fn pause_is_stale(&self) -> bool {
+        self.paused_at
+            .is_none_or(|at| at.elapsed() >= Duration::from_millis(self.config.stale_audio_ms))
+    }
+
+    /// Claim the start, failing while STT is running or another start is loading the model
+    fn begin_start(&mut self) -> Result<(), String> {
+        if self.is_running || self.is_starting {
+            return Err("STT already running".to_string());
+        }
+        self.is_starting = true;
+        Ok(())
+    }
+
+    /// Open the capture stream for the current settings, paused if STT is.
+    /// Returns the microphone it opened.
+    fn open_stream(&mut self, on_error: ErrorHandler) -> Result<Option<String>, String> {
+        let (mut capture, used_device) = self.capture_opener.open(self.buffer_seconds, &self.capture, on_error)?;
+        if self.is_paused {
+            capture.pause();
+        }
+        self.audio_capture = Some(capture);
+        Ok(used_device)
+    }
+
+    /// Mark STT running once its capture is open, with a fresh segmenter
+    fn begin_session(&mut self) {
+        self.segmenter = UtteranceSegmenter::new(self.vad_config, self.config.min_samples, self.config.max_samples);
+        self.correction_history.clear();
+        self.is_running = true;
+    }
+
+    /// The loaded engine if it's the model and GPU setting currently wanted
+    fn current_engine(&self) -> Option<Arc<WhisperEngine>> {
+        let model = self.transcription_model();
+        self.whisper
+            .clone()
+            .filter(|_| self.loaded_model == Some(model) && self.loaded_gpu == self.gpu_enabled)
+    }
+
+    /// What to load for the selected language, failing if it isn't downloaded
+    fn model_load(&self) -> Result<ModelLoad, String> {
+        let model = self.transcription_model();
+        let path = get_model_path(model)?;
+        if !path.exists() {
+            return Err(format!(
+                "Model '{}' not downloaded. Please download the model first.",
+                model.name()
+            ));
+        }
+        let stamp = FileStamp::of(&path)?;
+        Ok(ModelLoad {
+            model,
+            path,
+            gpu: self.gpu_enabled,
+            n_threads: self.n_threads,
+            stamp,
+            sha256: whisper::model_sha256(model),
+            verified: self.verified_models.get(&model) == Some(&stamp),
+        })
+    }
+
+    /// Language to ask the loaded model for, English-only models can't be asked for another
+    fn transcription_language(&self) -> Option<String> {
+        match self.loaded_model {
+            Some(model) if model.is_multilingual() => self.language.clone(),
+            _ => Some("en".to_string()),
         }
     }
 }
@@ -40,18 +393,313 @@ pub type SharedSttState = Arc<Mutex<SttState>>;
 /// Check STT status
 pub fn get_stt_status(state: &SharedSttState) -> SttStatus {
     let state = state.lock().unwrap();
+    let model = state.transcription_model();
+    let model_available = model_exists(model);
     SttStatus {
         model_loaded: state.whisper.is_some(),
         is_listening: state.is_running,
-        model_available: model_exists(ModelSize::Base),
+        is_paused: state.is_paused,
+        model_available,
+        active_model: state.active_model.name().to_string(),
+        transcription_model: model.name().to_string(),
+        // Corrupted files are deleted, so a file present again means it was re-downloaded
+        model_corrupted: state.corrupt_model == Some(model) && !model_available,
+        input_device: state.capture.input_device.clone(),
+        capture_source: state.capture.source,
+        language: state.language.clone(),
+        gpu_enabled: state.gpu_enabled,
+        gpu_in_use: state.whisper.as_ref().is_some_and(|w| w.uses_gpu()),
+        backend: state.whisper.as_ref().map(|w| w.backend().to_string()),
+        last_error: state.last_error.lock().ok().and_then(|e| e.clone()),
+    }
+}
+
+/// Get the model start_listening will load, taking the language into account
+pub fn get_active_model(state: &SharedSttState) -> Result<ModelSize, String> {
+    let stt = state.lock().map_err(|e| e.to_string())?;
+    Ok(stt.transcription_model())
+}
+
+/// Select and persist the model loaded the next time STT starts
+pub fn set_active_model(state: &SharedSttState, size: ModelSize) -> Result<(), String> {
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    whisper::save_active_model(size)?;
+    stt.active_model = size;
+    Ok(())
+}
+
+/// Verify a downloaded model's checksum.
+/// A corrupted file is deleted and flagged in the status so the UI can offer a re-download,
+/// an unverified one is kept but not loaded until it's downloaded again.
+pub fn verify_model(state: &SharedSttState, size: ModelSize) -> Result<ModelIntegrity, String> {
+    let path = get_model_path(size)?;
+    if !path.exists() {
+        return Err(format!("Model '{}' not downloaded", size.name()));
+    }
+
+    // Hash without holding the lock, it takes a while for the larger models
+    let integrity = whisper::verify_model(size, &path)?;
+
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    if integrity == ModelIntegrity::Corrupted {
+        let _ = std::fs::remove_file(&path);
+        stt.corrupt_model = Some(size);
+        stt.verified_models.remove(&size);
+    } else {
+        if stt.corrupt_model == Some(size) {
+            stt.corrupt_model = None;
+        }
+        if integrity == ModelIntegrity::Verified {
+            stt.verified_models.insert(size, FileStamp::of(&path)?);
+        } else {
+            stt.verified_models.remove(&size);
+        }
+    }
+    Ok(integrity)
+}
+
+/// Record a model file as verified, so the first load doesn't hash it again
+pub fn mark_model_verified(state: &SharedSttState, size: ModelSize) -> Result<(), String> {
+    let stamp = FileStamp::of(&get_model_path(size)?)?;
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.verified_models.insert(size, stamp);
+    Ok(())
+}
+
+/// Enable or disable GPU acceleration, applied the next time the model is loaded
+pub fn set_gpu_enabled(state: &SharedSttState, enabled: bool) -> Result<(), String> {
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.gpu_enabled = enabled;
+    Ok(())
+}
+
+/// Measure how fast the active model transcribes `clip` with the current GPU setting
+pub async fn benchmark_model(state: &SharedSttState, clip: Option<PathBuf>) -> Result<whisper::BenchmarkResult, String> {
+    let (size, use_gpu, n_threads) = {
+        let stt = state.lock().map_err(|e| e.to_string())?;
+        (stt.transcription_model(), stt.gpu_enabled, stt.n_threads)
+    };
+    let model_path = get_model_path(size)?;
+    if !model_path.exists() {
+        return Err(format!("Model '{}' not downloaded", size.name()));
+    }
+
+    // Load a separate engine so a running session isn't blocked
+    tokio::task::spawn_blocking(move || {
+        let engine = WhisperEngine::new(&model_path, use_gpu)?;
+        if let Some(n_threads) = n_threads {
+            engine.set_n_threads(n_threads);
+        }
+        whisper::benchmark(size, &engine, clip.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Set how many seconds of audio the capture buffer holds, applied on the next start.
+/// A longer buffer rides out slow transcription without dropping audio.
+pub fn set_buffer_duration(state: &SharedSttState, seconds: usize) -> Result<(), String> {
+    if seconds == 0 || seconds > MAX_BUFFER_SECONDS {
+        return Err(format!("Buffer duration must be between 1 and {} seconds", MAX_BUFFER_SECONDS));
+    }
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.buffer_seconds = seconds;
+    Ok(())
+}
+
+/// Set how much audio from the end of a chunk is transcribed again with the next one
+pub fn set_transcription_overlap(state: &SharedSttState, overlap_ms: u32) -> Result<(), String> {
+    if overlap_ms > MAX_OVERLAP_MS {
+        return Err(format!("Overlap must be at most {}ms", MAX_OVERLAP_MS));
     }
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.overlap_ms = overlap_ms;
+    Ok(())
+}
+
+/// Set the number of CPU threads Whisper uses, or None for one per CPU up to 8.
+/// Applies immediately if a model is loaded.
+pub fn set_whisper_threads(state: &SharedSttState, n_threads: Option<usize>) -> Result<(), String> {
+    if n_threads == Some(0) {
+        return Err("Thread count must be at least 1".to_string());
+    }
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.n_threads = n_threads;
+    if let Some(engine) = &stt.whisper {
+        engine.set_n_threads(n_threads.unwrap_or_else(whisper::default_threads));
+    }
+    Ok(())
+}
+
+/// Get the capture buffer counters, None when STT isn't running
+pub fn get_capture_stats(state: &SharedSttState) -> Result<Option<CaptureStats>, String> {
+    let stt = state.lock().map_err(|e| e.to_string())?;
+    Ok(stt.audio_capture.as_ref().map(|capture| capture.get_capture_stats()))
+}
+
+/// Select the input device used the next time STT starts
+pub fn set_input_device(state: &SharedSttState, name: String) -> Result<(), String> {
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.capture.input_device = Some(name);
+    Ok(())
+}
+
+/// Set the transcription language, or None to let Whisper detect it.
+/// Anything but English switches to the multilingual model on the next start.
+pub fn set_transcription_language(state: &SharedSttState, language: Option<String>) -> Result<(), String> {
+    let language = language.map(|lang| whisper::validate_language(&lang)).transpose()?;
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.language = language;
+    Ok(())
+}
+
+/// Update the VAD parameters, applied immediately if STT is running
+pub fn set_vad_config(state: &SharedSttState, config: VadConfig) -> Result<(), String> {
+    config.validate()?;
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.vad_config = config;
+    stt.segmenter.set_config(config);
+    Ok(())
+}
+
+/// Update the speech gating thresholds, applied immediately if STT is running
+pub fn set_sensitivity(state: &SharedSttState, sensitivity: SttSensitivity) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&sensitivity.max_no_speech_prob) {
+        return Err("No-speech probability threshold must be between 0 and 1".to_string());
+    }
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.sensitivity = sensitivity;
+    Ok(())
+}
+
+/// Update the processing interval and utterance lengths. The lengths apply immediately if STT
+/// is running, the interval the next time it starts.
+pub fn configure_stt(state: &SharedSttState, config: SttConfig) -> Result<(), String> {
+    config.validate()?;
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.config = config;
+    stt.segmenter.set_limits(config.min_samples, config.max_samples);
+    Ok(())
+}
+
+/// The processing interval and utterance lengths in use
+pub fn get_stt_config(state: &SharedSttState) -> Result<SttConfig, String> {
+    let stt = state.lock().map_err(|e| e.to_string())?;
+    Ok(stt.config)
+}
+
+/// Enable or disable suppressing likely hallucinated segments, e.g. to debug missing text
+pub fn set_hallucination_filter(state: &SharedSttState, enabled: bool) -> Result<(), String> {
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.filter_hallucinations = enabled;
+    Ok(())
+}
+
+/// Enable or disable attributing live transcripts to speakers
+pub fn set_diarization_enabled(state: &SharedSttState, enabled: bool) -> Result<(), String> {
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.diarization_enabled = enabled;
+    Ok(())
+}
+
+/// Enable or disable correcting each finalized segment with the LLM as it's transcribed
+pub fn set_auto_correct(state: &SharedSttState, enabled: bool) -> Result<(), String> {
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.auto_correct = enabled;
+    Ok(())
+}
+
+/// The last few finalized segments of this session, one per line, as context for correction
+pub fn correction_context(state: &SharedSttState) -> Result<String, String> {
+    let stt = state.lock().map_err(|e| e.to_string())?;
+    Ok(stt.correction_history.iter().map(String::as_str).collect::<Vec<_>>().join("\n"))
+}
+
+/// Enable or disable saving the session audio to a WAV file.
+/// Takes effect immediately while STT is running, otherwise on the next start.
+pub fn set_audio_recording(
+    app_handle: &AppHandle,
+    state: &SharedSttState,
+    settings: RecordingSettings,
+) -> Result<(), String> {
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+
+    // A new path or disabling closes the current recording
+    if let Some(recorder) = stt.recorder.take() {
+        emit_recording_saved(app_handle, recorder);
+    }
+
+    if settings.enabled && stt.is_running {
+        stt.recorder = Some(WavRecorder::from_settings(&settings)?);
+    }
+    stt.recording = settings;
+    Ok(())
+}
+
+/// Finalize a recording and tell the frontend where it was saved
+fn emit_recording_saved(app_handle: &AppHandle, recorder: WavRecorder) {
+    match recorder.finish() {
+        Ok(info) => {
+            emit_event(app_handle, "audio_recording_saved", info);
+        }
+        Err(e) => report_error(app_handle, ErrorSource::Storage, e),
+    }
+}
+
+/// Select which audio sources the next start captures, optionally adjusting their gain
+pub fn set_capture_source(
+    state: &SharedSttState,
+    source: CaptureSource,
+    microphone_gain: Option<f32>,
+    system_gain: Option<f32>,
+) -> Result<(), String> {
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.capture.source = source;
+    if let Some(gain) = microphone_gain {
+        stt.capture.microphone_gain = gain.max(0.0);
+    }
+    if let Some(gain) = system_gain {
+        stt.capture.system_gain = gain.max(0.0);
+    }
+    Ok(())
+}
+
+/// Payload of the `transcript_segment` event
+#[derive(serde::Serialize, Clone)]
+pub struct TranscriptEvent {
+    /// Start of the transcribed utterance relative to the session start
+    pub offset_ms: u64,
+    /// Segments timed relative to `offset_ms`
+    pub segments: Vec<TranscriptSegment>,
+    /// Language Whisper detected, present only when auto-detecting
+    pub language: Option<String>,
+}
+
+/// Payload of the `transcribe_file_progress` event
+#[derive(serde::Serialize, Clone)]
+pub struct FileProgress {
+    pub processed_seconds: f64,
+    pub total_seconds: f64,
 }
 
 #[derive(serde::Serialize, Clone)]
 pub struct SttStatus {
     pub model_loaded: bool,
     pub is_listening: bool,
+    pub is_paused: bool,
     pub model_available: bool,
+    pub active_model: String,
+    /// Model actually loaded for the selected language
+    pub transcription_model: String,
+    pub model_corrupted: bool,
+    pub input_device: Option<String>,
+    pub capture_source: CaptureSource,
+    pub language: Option<String>,
+    pub gpu_enabled: bool,
+    pub gpu_in_use: bool,
+    /// Backend of the loaded model ("cpu", "cuda" or "metal")
+    pub backend: Option<String>,
+    pub last_error: Option<SttError>,
 }
 
 /// Initialize and start STT
@@ -59,38 +707,39 @@ pub async fn start_stt(
     app_handle: AppHandle,
     state: SharedSttState,
 ) -> Result<(), String> {
-    let mut stt = state.lock().map_err(|e| e.to_string())?;
-    
-    if stt.is_running {
-        return Err("STT already running".to_string());
-    }
+    let events = {
+        let mut stt = state.lock().map_err(|e| e.to_string())?;
+        stt.begin_start()?;
+        stt.events(&app_handle)
+    };
 
-    // Load whisper model if not loaded
-    if stt.whisper.is_none() {
-        let model_path = get_model_path(ModelSize::Small)?;
-        if !model_path.exists() {
-            return Err("Model not downloaded. Please download the model first.".to_string());
-        }
-        stt.whisper = Some(WhisperEngine::new(&model_path)?);
+    // Load the model for the selected language
+    let engine = load_engine(&state, &events).await;
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    if !stt.is_starting {
+        return Err("Stopped while the model was loading".to_string());
     }
+    stt.is_starting = false;
+    engine?;
 
     // Initialize audio capture
-    let (mut audio_capture, producer) = AudioCapture::new()?;
-    audio_capture.start(producer)?;
-    
-    // Recreate for the processing loop
-    let (audio_capture2, producer2) = AudioCapture::new()?;
-    stt.audio_capture = Some(audio_capture2);
-    stt.audio_producer = Some(producer2);
-    
-    // Start audio capture with the new producer
-    if let Some(producer) = stt.audio_producer.take() {
-        if let Some(ref mut capture) = stt.audio_capture {
-            capture.start(producer)?;
+    stt.is_paused = false;
+    open_capture(&mut stt, &events)?;
+
+    // Open the recording once capture is up, closing the capture again if the path is refused
+    if stt.recording.enabled {
+        match WavRecorder::from_settings(&stt.recording) {
+            Ok(recorder) => stt.recorder = Some(recorder),
+            Err(e) => {
+                stt.end_session();
+                return Err(e);
+            }
         }
     }
-
-    stt.is_running = true;
+    stt.begin_session();
+    if let Ok(mut last_error) = stt.last_error.lock() {
+        *last_error = None;
+    }
 
     // Create shutdown channel
     let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
@@ -98,19 +747,53 @@ pub async fn start_stt(
 
     // Clone what we need for the processing task
     let state_clone = state.clone();
+    let level_meter = stt.audio_capture.as_ref().map(|capture| capture.level_meter());
+    let engine = stt.whisper.clone();
+    let poll_interval = Duration::from_millis(stt.config.interval_ms);
     
     // Drop the lock before spawning
     drop(stt);
+    events.emit(SttEvent::Started);
+
+    // Token usage and its budget count per session
+    if let Some(llm) = app_handle.try_state::<LlmClient>() {
+        llm.reset_usage();
+    }
+
+    // Transcript offsets count from now, so diarization timing does too
+    if let Some(diarization) = app_handle.try_state::<SharedDiarizationState>() {
+        if let Ok(mut engine) = diarization.lock() {
+            if let Some(engine) = engine.as_mut() {
+                engine.start_session();
+            }
+        }
+    }
+
+    if let Some(meter) = level_meter {
+        spawn_level_meter(app_handle.clone(), meter);
+    }
+    spawn_agenda_timer(app_handle.clone(), state.clone());
+
+    // Transcription runs in its own task so the capture loop never waits on Whisper
+    let (job_tx, job_rx) = mpsc::unbounded_channel::<TranscriptionJob>();
+    let backlog = Arc::new(AtomicU64::new(0));
+    if let Some(engine) = engine {
+        // Speakers are attributed in yet another task, so diarization never delays the next chunk
+        let (diarization_tx, diarization_rx) = mpsc::unbounded_channel::<DiarizationJob>();
+        tokio::spawn(run_diarization(app_handle.clone(), diarization_rx));
+        tokio::spawn(run_transcription(events.clone(), engine, job_rx, diarization_tx, backlog.clone()));
+    }
 
-    // Spawn transcription loop
+    // Spawn the capture loop, which only holds the lock long enough to drain audio
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_millis(500));
+        let mut interval = tokio::time::interval(poll_interval);
+        let mut last_dropped: u64 = 0;
         
         loop {
             tokio::select! {
                 _ = interval.tick() => {
-                    // Get audio samples and transcribe
-                    let transcript = {
+                    // Feed audio to the VAD and queue completed utterances
+                    let (dropped, device_lost) = {
                         let mut stt = match state_clone.lock() {
                             Ok(s) => s,
                             Err(_) => continue,
@@ -120,34 +803,60 @@ pub async fn start_stt(
                             break;
                         }
 
-                        // Process audio and transcribe
-                        if let Some(capture) = &mut stt.audio_capture {
-                            let samples = capture.get_samples(MAX_AUDIO_SAMPLES);
-                            if samples.len() >= MIN_AUDIO_SAMPLES {
-                                if let Some(whisper) = &stt.whisper {
-                                    match whisper.transcribe(&samples) {
-                                        Ok(text) if !text.is_empty() => Some(text),
-                                        Ok(_) => None,
-                                        Err(e) => {
-                                            eprintln!("Transcription error: {}", e);
-                                            None
-                                        }
-                                    }
-                                } else {
-                                    None
+                        let stt = &mut *stt;
+                        let mut dropped = last_dropped;
+                        let mut device_lost = false;
+                        let utterances = match &mut stt.audio_capture {
+                            Some(capture) => {
+                                dropped = capture.get_capture_stats().dropped_samples;
+                                device_lost = capture.is_device_lost();
+                                let samples = capture.get_samples(stt.config.max_samples);
+                                // A failed write stops the recording, keeping what was written so far
+                                if let Some(Err(e)) = stt.recorder.as_mut().map(|recorder| recorder.write(&samples)) {
+                                    stt.recorder = None;
+                                    report_error(&app_handle, ErrorSource::Storage, format!("{}, audio recording stopped", e));
                                 }
-                            } else {
-                                None
+                                stt.segmenter.push(&samples)
+                            }
+                            None => Vec::new(),
+                        };
+
+                        let language = stt.transcription_language();
+
+                        let overlap_samples = (stt.overlap_ms as usize * WHISPER_SAMPLE_RATE as usize) / 1000;
+                        for utterance in utterances {
+                            // Only wake Whisper for audio that actually contains speech
+                            if passes_speech_gate(&utterance.samples, stt.vad_config, stt.sensitivity) {
+                                backlog.fetch_add(utterance.samples.len() as u64, Ordering::Relaxed);
+                                let _ = job_tx.send(TranscriptionJob {
+                                    utterance,
+                                    language: language.clone(),
+                                    overlap_samples,
+                                    max_no_speech_prob: stt.sensitivity.max_no_speech_prob,
+                                    filter_hallucinations: stt.filter_hallucinations,
+                                    diarize: stt.diarization_enabled,
+                                });
                             }
-                        } else {
-                            None
                         }
+                        (dropped, device_lost)
                     };
 
-                    // Emit transcript outside the lock
-                    if let Some(text) = transcript {
-                        println!("Transcript: {}", text);
-                        let _ = app_handle.emit("native_transcript", text);
+                    if device_lost {
+                        if !reconnect_capture(&state_clone, &events).await {
+                            let _ = stop_stt(&app_handle, &state_clone);
+                            break;
+                        }
+                        // The new capture counts drops from zero
+                        last_dropped = 0;
+                        continue;
+                    }
+
+                    // Warn once enough audio has been lost to a full buffer since the last warning
+                    if dropped - last_dropped >= DROP_WARNING_SAMPLES {
+                        let seconds = (dropped - last_dropped) as f64 / WHISPER_SAMPLE_RATE as f64;
+                        eprintln!("Audio buffer overflow, dropped {:.2}s of audio", seconds);
+                        emit_event(&app_handle, "audio_dropped", seconds);
+                        last_dropped = dropped;
                     }
                 }
                 _ = shutdown_rx.recv() => {
@@ -161,21 +870,1269 @@ pub async fn start_stt(
     Ok(())
 }
 
-/// Stop STT
-pub fn stop_stt(state: &SharedSttState) -> Result<(), String> {
+/// Load the model for the selected language unless it's the one already loaded.
+/// The lock is only held to read the settings and store the engine, status, pause and stop
+/// don't wait on the seconds it takes to hash and load a model.
+async fn load_engine(state: &SharedSttState, events: &EventSink) -> Result<Arc<WhisperEngine>, String> {
+    let load = {
+        let stt = state.lock().map_err(|e| e.to_string())?;
+        if let Some(engine) = stt.current_engine() {
+            return Ok(engine);
+        }
+        stt.model_load()?
+    };
+
+    let (model, gpu) = (load.model, load.gpu);
+    events.emit(SttEvent::ModelLoading { model: model.name().to_string() });
+    let engine = load_model_with(state, load, WhisperEngine::new).await.map_err(|message| {
+        report_error(&events.app_handle, ErrorSource::Model, message.clone());
+        message
+    })?;
+    if gpu && !engine.uses_gpu() {
+        let reason = if whisper::GPU_SUPPORTED {
+            "GPU initialization failed"
+        } else {
+            "This build has no GPU support"
+        };
+        emit_event(&events.app_handle, "gpu_warning", format!("{}, transcribing on the CPU", reason));
+    }
+    events.emit(SttEvent::ModelLoaded { model: model.name().to_string() });
+    Ok(engine)
+}
+
+/// Verify the model unless it passed before unchanged, then create the engine with `new_engine`,
+/// both on a blocking thread and without holding the lock
+async fn load_model_with<F>(state: &SharedSttState, load: ModelLoad, new_engine: F) -> Result<Arc<WhisperEngine>, String>
+where
+    F: FnOnce(&PathBuf, bool) -> Result<WhisperEngine, String> + Send + 'static,
+{
+    let model = load.model;
+    if !load.verified {
+        let (path, sha256) = (load.path.clone(), load.sha256.clone());
+        let integrity = tokio::task::spawn_blocking(move || whisper::verify_file(&path, sha256.as_deref()))
+            .await
+            .map_err(|e| e.to_string())??;
+        let mut stt = state.lock().map_err(|e| e.to_string())?;
+        match integrity {
+            ModelIntegrity::Verified => {}
+            ModelIntegrity::Corrupted => {
+                let _ = std::fs::remove_file(&load.path);
+                stt.corrupt_model = Some(model);
+                return Err(format!("Model '{}' corrupted, please re-download", model.name()));
+            }
+            ModelIntegrity::Unverified => {
+                return Err(format!("Model '{}' has no checksum to verify it against, please re-download", model.name()));
+            }
+        }
+        stt.verified_models.insert(model, load.stamp);
+    }
+
+    let (path, gpu) = (load.path, load.gpu);
+    let engine = tokio::task::spawn_blocking(move || new_engine(&path, gpu))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to load model '{}': {}", model.name(), e))?;
+    engine.set_n_threads(load.n_threads.unwrap_or_else(whisper::default_threads));
+    let engine = Arc::new(engine);
+
     let mut stt = state.lock().map_err(|e| e.to_string())?;
-    
+    stt.whisper = Some(engine.clone());
+    stt.loaded_model = Some(model);
+    stt.loaded_gpu = gpu;
+    Ok(engine)
+}
+
+/// Create and start the capture for the current settings, reporting stream errors as events.
+/// Returns the microphone it opened.
+fn open_capture(stt: &mut SttState, events: &EventSink) -> Result<Option<String>, String> {
+    let audio_events = events.clone();
+    let used_device = stt.open_stream(Arc::new(move |msg| audio_events.emit(SttEvent::AudioError { msg })))?;
+    if let (Some(requested), Some(used_device)) = (&stt.capture.input_device, &used_device) {
+        if requested != used_device {
+            emit_event(
+                &events.app_handle,
+                "audio_device_warning",
+                format!("Input device '{}' not found, using '{}' instead", requested, used_device),
+            );
+        }
+    }
+    Ok(used_device)
+}
+
+/// Send the input level to the UI until the capture stops.
+/// Runs apart from the STT lock so it stays smooth during transcription.
+fn spawn_level_meter(app_handle: AppHandle, meter: LevelMeter) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_millis(LEVEL_INTERVAL_MS));
+        while meter.is_active() {
+            interval.tick().await;
+            emit_event(&app_handle, "audio_level", meter.take_peak());
+        }
+    });
+}
+
+/// Until STT stops, emit `agenda_overrun` once each time the active agenda item runs past its
+/// time box by more than the configured percentage
+fn spawn_agenda_timer(app_handle: AppHandle, state: SharedSttState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(AGENDA_CHECK_INTERVAL);
+        // Start of the stint last warned about, so each stint is only flagged once and a
+        // reopened item can be flagged again
+        let mut warned_start = None;
+        loop {
+            interval.tick().await;
+            if !state.lock().map(|stt| stt.is_running).unwrap_or(false) {
+                break;
+            }
+            let Some(manager) = app_handle.try_state::<Arc<Mutex<MeetingContextManager>>>() else {
+                continue;
+            };
+            let overrun = {
+                let Ok(manager) = manager.lock() else {
+                    continue;
+                };
+                let Some(context) = manager.get_current_context() else {
+                    continue;
+                };
+                let started = context.active_agenda_item().and_then(|(_, item)| item.stint_start());
+                if started.is_none() || started == warned_start {
+                    continue;
+                }
+                match context.agenda_overrun(chrono::Utc::now(), manager.agenda_overrun_percent()) {
+                    Some(overrun) => {
+                        warned_start = started;
+                        overrun
+                    }
+                    None => continue,
+                }
+            };
+            emit_event(&app_handle, "agenda_overrun", overrun);
+        }
+    });
+}
+
+/// Reopen the capture after its device disappeared, backing off between attempts.
+/// Returns false once the retries run out or STT was stopped meanwhile.
+async fn reconnect_capture(state: &SharedSttState, events: &EventSink) -> bool {
+    events.emit(SttEvent::DeviceLost);
+    let lost_at = std::time::Instant::now();
+
+    // Release the dead stream right away
+    let position_ms = match state.lock() {
+        Ok(mut stt) => {
+            if let Some(mut capture) = stt.audio_capture.take() {
+                capture.stop();
+            }
+            stt.segmenter.reset();
+            stt.segmenter.position_ms()
+        }
+        Err(_) => return false,
+    };
+
+    let policy = ReconnectPolicy::default();
+    let mut attempt = 0;
+    let mut reconnected = None;
+    while let Some(delay) = policy.delay(attempt) {
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+
+        let mut stt = match state.lock() {
+            Ok(stt) => stt,
+            Err(_) => return false,
+        };
+        if !stt.is_running {
+            return false;
+        }
+        match open_capture(&mut stt, events) {
+            Ok(device) => {
+                let meter = stt.audio_capture.as_ref().map(|capture| capture.level_meter());
+                reconnected = Some((meter, device));
+                break;
+            }
+            Err(e) => eprintln!("Audio reconnect attempt {} failed: {}", attempt, e),
+        }
+    }
+
+    // Show in the transcript where audio is missing
+    if let Some(store) = events.app_handle.try_state::<SharedTranscriptStore>() {
+        if let Ok(mut store) = store.lock() {
+            store.mark_gap(position_ms, lost_at.elapsed().as_secs());
+        }
+    }
+
+    match reconnected {
+        Some((meter, device)) => {
+            if let Some(meter) = meter {
+                spawn_level_meter(events.app_handle.clone(), meter);
+            }
+            events.emit(SttEvent::DeviceReconnected { device });
+            true
+        }
+        None => {
+            events.emit(SttEvent::AudioError {
+                msg: format!("Audio device lost, gave up after {} reconnect attempts", attempt),
+            });
+            false
+        }
+    }
+}
+
+/// An utterance waiting to be transcribed
+struct TranscriptionJob {
+    utterance: Utterance,
+    language: Option<String>,
+    /// Audio from the end of the previous utterance to prepend
+    overlap_samples: usize,
+    max_no_speech_prob: f32,
+    filter_hallucinations: bool,
+    /// Also attribute the transcript to a speaker
+    diarize: bool,
+}
+
+/// Transcribed speech waiting to be attributed to a speaker
+struct DiarizationJob {
+    samples: Vec<f32>,
+    text: String,
+    /// Whisper's confidence in the text
+    confidence: f32,
+    start_ms: u64,
+    end_ms: u64,
+}
+
+/// Check an utterance is loud enough and has enough speech frames to be worth transcribing.
+/// Near-silence makes Whisper hallucinate phrases like "Thank you."
+fn passes_speech_gate(samples: &[f32], vad_config: VadConfig, sensitivity: SttSensitivity) -> bool {
+    vad::rms_dbfs(samples) >= sensitivity.min_level_dbfs
+        && vad::contains_speech(samples, vad_config.silence_threshold)
+}
+
+/// What the transcription task remembers of the previous chunk
+#[derive(Default)]
+struct PreviousChunk {
+    /// Trailing audio, for overlapping the next chunk
+    tail: Vec<f32>,
+    /// Session sample just past the end of the chunk
+    end_sample: u64,
+    /// Most recent transcript words, for the prompt and deduplication
+    words: Vec<String>,
+    /// Text of the last few segments, for spotting hallucinated repeats
+    recent_segments: Vec<String>,
+}
+
+/// An utterance ready to transcribe
+struct PreparedChunk {
+    /// Its audio, after any overlap from the previous chunk
+    samples: Vec<f32>,
+    /// Where `samples` starts relative to the session start, which its segments are timed from
+    offset_ms: u64,
+    overlap_len: usize,
+}
+
+impl PreviousChunk {
+    /// The audio to transcribe for `utterance`, starting with up to `overlap_samples` of the
+    /// previous chunk when the two are contiguous. Remembers the end of the utterance for the next.
+    fn prepare(&mut self, utterance: &Utterance, overlap_samples: usize) -> PreparedChunk {
+        // Only a forced split at max length cuts through speech, utterances after a pause don't need overlap
+        let contiguous = self.end_sample == utterance.start_sample && !self.tail.is_empty();
+        let overlap = if contiguous {
+            &self.tail[self.tail.len().saturating_sub(overlap_samples)..]
+        } else {
+            &[][..]
+        };
+        let overlap_len = overlap.len();
+        let mut samples = Vec::with_capacity(overlap_len + utterance.samples.len());
+        samples.extend_from_slice(overlap);
+        samples.extend_from_slice(&utterance.samples);
+        let offset_ms = (utterance.start_sample - overlap_len as u64) * 1000 / WHISPER_SAMPLE_RATE as u64;
+
+        let keep = (MAX_OVERLAP_MS as usize * WHISPER_SAMPLE_RATE as usize) / 1000;
+        self.end_sample = utterance.start_sample + utterance.samples.len() as u64;
+        self.tail = utterance.samples[utterance.samples.len().saturating_sub(keep)..].to_vec();
+        PreparedChunk {
+            samples,
+            offset_ms,
+            overlap_len,
+        }
+    }
+
+    /// Filter out non-speech, words repeated from the overlap and likely hallucinations, and
+    /// remember the words left for the next prompt. None when nothing is left.
+    fn accept(
+        &mut self,
+        mut result: Transcription,
+        overlap_len: usize,
+        max_no_speech_prob: f32,
+        filter_hallucinations: bool,
+    ) -> Option<Transcription> {
+        result.segments.retain(|segment| segment.no_speech_prob <= max_no_speech_prob);
+
+        // The overlapped audio transcribes to words already emitted with the previous chunk
+        if overlap_len > 0 {
+            let words = transcript::segment_words(&result.segments);
+            let repeated = transcript::overlapping_words(&self.words, &words);
+            transcript::drop_leading_words(&mut result.segments, repeated);
+        }
+
+        if filter_hallucinations {
+            let recent = &mut self.recent_segments;
+            result.segments.retain(|segment| {
+                if transcript::is_likely_hallucination(&segment.text, recent) {
+                    println!("Suppressed likely hallucination: {}", segment.text);
+                    return false;
+                }
+                recent.push(segment.text.clone());
+                let excess = recent.len().saturating_sub(transcript::REPEAT_WINDOW);
+                recent.drain(..excess);
+                true
+            });
+        }
+        if result.segments.is_empty() {
+            return None;
+        }
+
+        self.words.extend(transcript::segment_words(&result.segments));
+        let excess = self.words.len().saturating_sub(PROMPT_WORDS);
+        self.words.drain(..excess);
+        Some(result)
+    }
+}
+
+/// An open capture, read by the capture loop
+trait CaptureStream: Send {
+    fn stop(&mut self);
+    fn pause(&mut self);
+    fn resume(&mut self);
+    fn clear_buffer(&mut self);
+    fn get_samples(&mut self, max_samples: usize) -> Vec<f32>;
+    fn get_capture_stats(&self) -> CaptureStats;
+    fn is_device_lost(&self) -> bool;
+    fn level_meter(&self) -> LevelMeter;
+}
+
+impl CaptureStream for AudioCapture {
+    fn stop(&mut self) {
+        AudioCapture::stop(self)
+    }
+    fn pause(&mut self) {
+        AudioCapture::pause(self)
+    }
+    fn resume(&mut self) {
+        AudioCapture::resume(self)
+    }
+    fn clear_buffer(&mut self) {
+        AudioCapture::clear_buffer(self)
+    }
+    fn get_samples(&mut self, max_samples: usize) -> Vec<f32> {
+        AudioCapture::get_samples(self, max_samples)
+    }
+    fn get_capture_stats(&self) -> CaptureStats {
+        AudioCapture::get_capture_stats(self)
+    }
+    fn is_device_lost(&self) -> bool {
+        AudioCapture::is_device_lost(self)
+    }
+    fn level_meter(&self) -> LevelMeter {
+        AudioCapture::level_meter(self)
+    }
+}
+
+/// Opens capture streams: the audio devices, or a stand-in in tests
+trait CaptureOpener: Send + Sync {
+    /// Start capturing from `settings`, returning the stream and the microphone it opened
+    fn open(
+        &self,
+        buffer_seconds: usize,
+        settings: &CaptureSettings,
+        on_error: ErrorHandler,
+    ) -> Result<(Box<dyn CaptureStream>, Option<String>), String>;
+}
+
+/// Captures from the devices in the settings
+struct DeviceCapture;
+
+impl CaptureOpener for DeviceCapture {
+    fn open(
+        &self,
+        buffer_seconds: usize,
+        settings: &CaptureSettings,
+        on_error: ErrorHandler,
+    ) -> Result<(Box<dyn CaptureStream>, Option<String>), String> {
+        let mut capture = AudioCapture::new(buffer_seconds)?;
+        capture.set_error_handler(on_error);
+        let used_device = capture.start(settings)?;
+        Ok((Box::new(capture), used_device))
+    }
+}
+
+/// Turns speech into timed text: the loaded Whisper model, or a stand-in in tests
+trait Transcriber: Send + Sync + 'static {
+    fn transcribe(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+        prompt: Option<&str>,
+        vocabulary_hint: Option<&str>,
+    ) -> Result<Transcription, String>;
+}
+
+impl Transcriber for WhisperEngine {
+    fn transcribe(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+        prompt: Option<&str>,
+        vocabulary_hint: Option<&str>,
+    ) -> Result<Transcription, String> {
+        WhisperEngine::transcribe(self, samples, language, prompt, vocabulary_hint)
+    }
+}
+
+/// Names and jargon of the current meeting for Whisper to expect
+fn vocabulary_hint(app_handle: &AppHandle) -> Option<String> {
+    let manager = app_handle.try_state::<Arc<Mutex<MeetingContextManager>>>()?;
+    let manager = manager.lock().ok()?;
+    manager.get_current_context()?.get_vocabulary_hint()
+}
+
+/// Transcribe queued utterances on a blocking thread and emit the results.
+/// `backlog` counts queued samples. Ends once the capture loop stops and the queue is drained.
+async fn run_transcription<T: Transcriber>(
+    events: EventSink,
+    engine: Arc<T>,
+    mut jobs: mpsc::UnboundedReceiver<TranscriptionJob>,
+    diarization: mpsc::UnboundedSender<DiarizationJob>,
+    backlog: Arc<AtomicU64>,
+) {
+    let app_handle = &events.app_handle;
+    let mut previous = PreviousChunk::default();
+    let mut coverage_warned = false;
+
+    while let Some(job) = jobs.recv().await {
+        let utterance_len = job.utterance.samples.len() as u64;
+        let diarize_samples = job.diarize.then(|| job.utterance.samples.clone());
+        let result = transcribe_job(&engine, &mut previous, job, vocabulary_hint(app_handle)).await;
+
+        // Speech still queued behind this utterance
+        let remaining = backlog.fetch_sub(utterance_len, Ordering::Relaxed) - utterance_len;
+        let seconds_behind = remaining as f64 / WHISPER_SAMPLE_RATE as f64;
+        if seconds_behind > LAG_WARNING_SECONDS {
+            events.emit(SttEvent::Lagging { seconds_behind });
+        }
+
+        match result {
+            Ok(Some((offset_ms, result))) => {
+                if let Some(store) = app_handle.try_state::<SharedTranscriptStore>() {
+                    if let Ok(mut store) = store.lock() {
+                        store.append(offset_ms, &result.segments, result.detected_language.as_deref());
+                    }
+                }
+
+                let text = whisper::join_segments(&result.segments);
+                println!("Transcript: {}", text);
+                track_coverage(app_handle, &text, offset_ms, &mut coverage_warned);
+                if let (Some(samples), Some(first), Some(last)) =
+                    (diarize_samples, result.segments.first(), result.segments.last())
+                {
+                    let _ = diarization.send(DiarizationJob {
+                        samples,
+                        text: text.clone(),
+                        confidence: whisper::joined_confidence(&result.segments),
+                        start_ms: offset_ms + first.start_ms.max(0) as u64,
+                        end_ms: offset_ms + last.end_ms.max(0) as u64,
+                    });
+                }
+                if let Some(context) = remember_for_correction(app_handle, &text) {
+                    correction::spawn_correction(app_handle, offset_ms, text.clone(), context);
+                }
+                emit_event(app_handle, "native_transcript", text);
+                emit_event(
+                    app_handle,
+                    "transcript_segment",
+                    TranscriptEvent {
+                        offset_ms,
+                        segments: result.segments,
+                        language: result.detected_language,
+                    },
+                );
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("Transcription error: {}", e);
+                events.emit(SttEvent::TranscriptionError { msg: e });
+            }
+        }
+    }
+}
+
+/// Add a finalized segment to the correction history. When auto-correct is on, returns
+/// the history before it, to correct it with.
+fn remember_for_correction(app_handle: &AppHandle, text: &str) -> Option<String> {
+    let state = app_handle.try_state::<SharedSttState>()?;
+    let mut stt = state.lock().ok()?;
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let context = stt
+        .auto_correct
+        .then(|| stt.correction_history.iter().map(String::as_str).collect::<Vec<_>>().join("\n"));
+    stt.correction_history.push_back(text.to_string());
+    while stt.correction_history.len() > CORRECTION_HISTORY_SEGMENTS {
+        stt.correction_history.pop_front();
+    }
+    context
+}
+
+/// Mark the meeting's key points the text mentions as covered. Once per session, emit
+/// `coverage_warning` when most of the planned time is up and some points haven't come up.
+fn track_coverage(app_handle: &AppHandle, text: &str, at_ms: u64, warned: &mut bool) {
+    let Some(state) = app_handle.try_state::<Arc<Mutex<MeetingContextManager>>>() else {
+        return;
+    };
+    let Ok(mut manager) = state.lock() else {
+        return;
+    };
+    let Some(context) = manager.get_current_context_mut() else {
+        return;
+    };
+    let newly_covered = context.track_coverage(text, at_ms);
+
+    let planned_ms = context.duration_estimate_minutes as u64 * 60_000;
+    if !*warned && planned_ms > 0 && at_ms as f64 >= planned_ms as f64 * COVERAGE_WARNING_SHARE {
+        let uncovered = context.uncovered_key_points();
+        if !uncovered.is_empty() {
+            *warned = true;
+            emit_event(
+                app_handle,
+                "coverage_warning",
+                CoverageWarning {
+                    uncovered,
+                    elapsed_ms: at_ms,
+                    planned_ms,
+                },
+            );
+        }
+    }
+    if !newly_covered.is_empty() {
+        crate::schedule_meeting_save(&state, &mut manager);
+    }
+}
+
+/// Attribute transcribed utterances to speakers and emit them as `speaker_transcript` events,
+/// with `speaker_stats` at most every SPEAKER_STATS_INTERVAL.
+/// Without an initialized diarization engine the transcript stays unattributed and the UI is warned once.
+async fn run_diarization(app_handle: AppHandle, mut jobs: mpsc::UnboundedReceiver<DiarizationJob>) {
+    let mut warned = false;
+    let mut last_stats: Option<std::time::Instant> = None;
+    let mut last_speaker: Option<String> = None;
+
+    while let Some(job) = jobs.recv().await {
+        let Some(state) = app_handle
+            .try_state::<SharedDiarizationState>()
+            .map(|state| state.inner().clone())
+        else {
+            continue;
+        };
+
+        let result = tokio::task::spawn_blocking(move || {
+            let mut engine = state.lock().map_err(|e| e.to_string())?;
+            let Some(engine) = engine.as_mut() else {
+                return Ok(None);
+            };
+            engine
+                .attribute_text(
+                    &job.samples,
+                    WHISPER_SAMPLE_RATE,
+                    &job.text,
+                    job.confidence,
+                    Duration::from_millis(job.start_ms),
+                )
+                .map(|attributed| Some((job, attributed, engine.speaker_stats(), engine.take_merges(), engine.speakers())))
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+
+        match result {
+            Ok(Some((job, attributed, stats, merges, speakers))) => {
+                if last_stats.is_none_or(|at| at.elapsed() >= SPEAKER_STATS_INTERVAL) {
+                    last_stats = Some(std::time::Instant::now());
+                    emit_event(&app_handle, "speaker_stats", stats);
+                }
+
+                for merge in &merges {
+                    if last_speaker.as_deref() == Some(merge.from.as_str()) {
+                        last_speaker = Some(merge.into.clone());
+                    }
+                }
+                diarization::apply_speaker_merges(&app_handle, &merges, &speakers);
+
+                // Utterances aren't contiguous, so the timing comes from the transcript
+                if let Some(attributed) = attributed.first() {
+                    if let Some(store) = app_handle.try_state::<SharedTranscriptStore>() {
+                        if let Ok(mut store) = store.lock() {
+                            store.assign_speaker(job.start_ms, job.end_ms, &attributed.speaker, attributed.overlaps_with.as_ref());
+                        }
+                    }
+                    if last_speaker.as_deref() != Some(attributed.speaker.id.as_str()) {
+                        last_speaker = Some(attributed.speaker.id.clone());
+                        emit_event(
+                            &app_handle,
+                            "speaker_change",
+                            SpeakerChange {
+                                speaker_id: attributed.speaker.id.clone(),
+                                speaker_label: attributed.speaker.label.clone(),
+                                at_ms: job.start_ms,
+                            },
+                        );
+                    }
+                    if attributed.is_question {
+                        let question = QuestionDetected {
+                            speaker_id: attributed.speaker.id.clone(),
+                            speaker_label: attributed.speaker.label.clone(),
+                            question: attributed.text.trim().to_string(),
+                            at_ms: job.start_ms,
+                        };
+                        emit_event(&app_handle, "question_detected", &question);
+                        crate::assistant::answer_question(&app_handle, question);
+                    }
+                    emit_event(
+                        &app_handle,
+                        "speaker_transcript",
+                        SpeakerTranscript {
+                            start_ms: job.start_ms,
+                            end_ms: job.end_ms,
+                            ..SpeakerTranscript::from(attributed)
+                        },
+                    );
+                }
+            }
+            Ok(None) if !warned => {
+                warned = true;
+                emit_event(
+                    &app_handle,
+                    "diarization_warning",
+                    "Diarization engine not initialized, transcribing without speakers",
+                );
+            }
+            Ok(None) => {}
+            Err(e) => report_error(&app_handle, ErrorSource::Diarization, format!("Diarization failed: {}", e)),
+        }
+    }
+}
+
+/// Transcribe one utterance, overlapping it with the previous chunk and filtering out
+/// non-speech, repeated words and hallucinations. Returns the offset of the transcribed
+/// audio with the remaining segments, or None when nothing is left.
+async fn transcribe_job<T: Transcriber>(
+    engine: &Arc<T>,
+    previous: &mut PreviousChunk,
+    job: TranscriptionJob,
+    vocabulary_hint: Option<String>,
+) -> Result<Option<(u64, Transcription)>, String> {
+    let TranscriptionJob {
+        utterance,
+        language,
+        overlap_samples,
+        max_no_speech_prob,
+        filter_hallucinations,
+        ..
+    } = job;
+
+    let prompt = (!previous.words.is_empty()).then(|| previous.words.join(" "));
+    let PreparedChunk {
+        samples,
+        offset_ms,
+        overlap_len,
+    } = previous.prepare(&utterance, overlap_samples);
+
+    let engine = engine.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        engine.transcribe(&samples, language.as_deref(), prompt.as_deref(), vocabulary_hint.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Transcription task failed: {}", e))??;
+
+    Ok(previous
+        .accept(result, overlap_len, max_no_speech_prob, filter_hallucinations)
+        .map(|result| (offset_ms, result)))
+}
+
+/// Transcribe an audio file with the same settings and filtering as live STT, emitting
+/// `transcript_segment` and `transcribe_file_progress` events as it goes.
+/// Needs no microphone and works whether or not listening is running.
+/// Returns the whole transcript.
+pub async fn transcribe_file(app_handle: AppHandle, state: &SharedSttState, path: String) -> Result<String, String> {
+    let events = state.lock().map_err(|e| e.to_string())?.events(&app_handle);
+    let engine = load_engine(state, &events).await?;
+    let (language, overlap_samples, vad_config, sensitivity, filter_hallucinations, max_seconds, window) = {
+        let stt = state.lock().map_err(|e| e.to_string())?;
+        (
+            stt.transcription_language(),
+            (stt.overlap_ms as usize * WHISPER_SAMPLE_RATE as usize) / 1000,
+            stt.vad_config,
+            stt.sensitivity,
+            stt.filter_hallucinations,
+            stt.max_import_seconds,
+            stt.config.max_samples,
+        )
+    };
+
+    println!("Transcribing file: {}", path);
+    let samples = tokio::task::spawn_blocking(move || audio_file::decode_file(Path::new(&path), max_seconds))
+        .await
+        .map_err(|e| e.to_string())??;
+    let total_seconds = samples.len() as f64 / WHISPER_SAMPLE_RATE as f64;
+
+    let mut previous = PreviousChunk::default();
+    let mut texts = Vec::new();
+    for (i, chunk) in samples.chunks(window).enumerate() {
+        let start_sample = (i * window) as u64;
+        if passes_speech_gate(chunk, vad_config, sensitivity) {
+            let job = TranscriptionJob {
+                utterance: Utterance {
+                    samples: chunk.to_vec(),
+                    start_sample,
+                },
+                language: language.clone(),
+                overlap_samples,
+                max_no_speech_prob: sensitivity.max_no_speech_prob,
+                filter_hallucinations,
+                diarize: false,
+            };
+            if let Some((offset_ms, result)) = transcribe_job(&engine, &mut previous, job, vocabulary_hint(&app_handle)).await? {
+                texts.push(whisper::join_segments(&result.segments));
+                emit_event(
+                    &app_handle,
+                    "transcript_segment",
+                    TranscriptEvent {
+                        offset_ms,
+                        segments: result.segments,
+                        language: result.detected_language,
+                    },
+                );
+            }
+        }
+
+        let processed = start_sample + chunk.len() as u64;
+        emit_event(
+            &app_handle,
+            "transcribe_file_progress",
+            FileProgress {
+                processed_seconds: processed as f64 / WHISPER_SAMPLE_RATE as f64,
+                total_seconds,
+            },
+        );
+    }
+
+    Ok(texts.join(" "))
+}
+
+/// Set the longest audio file accepted by `transcribe_file`
+pub fn set_import_limit(state: &SharedSttState, seconds: u64) -> Result<(), String> {
+    if seconds == 0 {
+        return Err("Import limit must be at least 1 second".to_string());
+    }
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    stt.max_import_seconds = seconds;
+    Ok(())
+}
+
+/// Record `seconds` of microphone audio at 16kHz on a capture of its own,
+/// so it works whether or not transcription is running
+pub async fn record_microphone(state: &SharedSttState, seconds: u64) -> Result<Vec<f32>, String> {
+    let settings = {
+        let stt = state.lock().map_err(|e| e.to_string())?;
+        CaptureSettings {
+            source: CaptureSource::Microphone,
+            ..stt.capture.clone()
+        }
+    };
+
+    let wanted = (seconds * WHISPER_SAMPLE_RATE as u64) as usize;
+    let mut audio_capture = AudioCapture::new(seconds as usize + 1)?;
+    audio_capture.start(&settings)?;
+
+    // Give up if the device stalls rather than waiting forever
+    let deadline = std::time::Instant::now() + Duration::from_secs(seconds * 2);
+    let mut samples = Vec::with_capacity(wanted);
+    while samples.len() < wanted && std::time::Instant::now() < deadline && !audio_capture.is_device_lost() {
+        tokio::time::sleep(Duration::from_millis(DEFAULT_POLL_INTERVAL_MS)).await;
+        samples.extend(audio_capture.get_samples(wanted - samples.len()));
+    }
+    audio_capture.stop();
+
+    if samples.len() < wanted {
+        return Err(format!(
+            "Microphone recording stopped after {:.1}s of {}s",
+            samples.len() as f64 / WHISPER_SAMPLE_RATE as f64,
+            seconds
+        ));
+    }
+    Ok(samples)
+}
+
+/// Pause STT during a break, keeping the model and audio stream ready
+pub fn pause_stt(app_handle: &AppHandle, state: &SharedSttState) -> Result<(), String> {
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    if !stt.is_running {
+        return Err("STT is not running".to_string());
+    }
+
     if let Some(ref mut capture) = stt.audio_capture {
-        capture.stop();
+        capture.pause();
     }
-    
-    if let Some(tx) = stt.shutdown_tx.take() {
-        let _ = tx.try_send(());
+    stt.is_paused = true;
+    stt.paused_at = Some(Instant::now());
+    stt.events(app_handle).emit(SttEvent::Paused);
+    Ok(())
+}
+
+/// Resume STT after a pause, without replaying audio from before it once the pause outlasted
+/// the stale-audio threshold
+pub fn resume_stt(app_handle: &AppHandle, state: &SharedSttState) -> Result<(), String> {
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    if !stt.is_running {
+        return Err("STT is not running".to_string());
     }
-    
-    stt.is_running = false;
-    stt.audio_capture = None;
-    
+
+    let stale = stt.pause_is_stale();
+    if let Some(ref mut capture) = stt.audio_capture {
+        if stale {
+            capture.clear_buffer();
+        }
+        capture.resume();
+    }
+    // Drop the half-finished utterance from before the pause, or finish it if the pause was brief
+    if stale {
+        stt.segmenter.reset();
+    }
+    stt.is_paused = false;
+    stt.paused_at = None;
+    stt.events(app_handle).emit(SttEvent::Resumed);
+    Ok(())
+}
+
+/// Stop STT
+pub fn stop_stt(app_handle: &AppHandle, state: &SharedSttState) -> Result<(), String> {
+    let mut stt = state.lock().map_err(|e| e.to_string())?;
+    if let Some(recorder) = stt.end_session() {
+        emit_recording_saved(app_handle, recorder);
+    }
+    stt.events(app_handle).emit(SttEvent::Stopped);
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stand in for a model file of this test run in the temp dir, the content is no real model
+    fn fixture_model(model: ModelSize, name: &str, verified: bool) -> ModelLoad {
+        let path = std::env::temp_dir().join(format!("hypergranola-{}-{}", std::process::id(), name));
+        std::fs::write(&path, b"lmgg not a model").unwrap();
+        ModelLoad {
+            model,
+            stamp: FileStamp::of(&path).unwrap(),
+            path,
+            gpu: false,
+            n_threads: None,
+            sha256: model.expected_sha256().map(str::to_string),
+            verified,
+        }
+    }
+
+    /// The error of a load that can't produce an engine, WhisperEngine has no Debug for `unwrap_err`
+    fn load_error(result: Result<Arc<WhisperEngine>, String>) -> String {
+        match result {
+            Ok(_) => panic!("the fixture loaded as a model"),
+            Err(e) => e,
+        }
+    }
+
+    fn state() -> SharedSttState {
+        Arc::new(Mutex::new(SttState::default()))
+    }
+
+    #[tokio::test]
+    async fn status_is_answered_while_a_model_loads() {
+        let state = state();
+        let mut load = fixture_model(ModelSize::Medium, "slow.bin", false);
+        load.sha256 = Some(whisper::file_sha256(&load.path).unwrap());
+        let path = load.path.clone();
+        let loading = tokio::spawn({
+            let state = state.clone();
+            async move {
+                load_model_with(&state, load, |_, _| {
+                    std::thread::sleep(Duration::from_millis(500));
+                    Err("fixture is no model".to_string())
+                })
+                .await
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let asked = Instant::now();
+        let status = get_stt_status(&state);
+        assert!(asked.elapsed() < Duration::from_millis(50), "status waited {:?}", asked.elapsed());
+        assert!(!status.model_loaded);
+        assert!(!loading.is_finished());
+
+        let error = load_error(loading.await.unwrap());
+        assert!(error.contains("fixture is no model"), "{}", error);
+        // Verified before the load failed, so the next attempt doesn't hash it again
+        assert!(state.lock().unwrap().verified_models.contains_key(&ModelSize::Medium));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn corrupted_model_is_not_loaded() {
+        let state = state();
+        let load = fixture_model(ModelSize::Tiny, "corrupt.bin", false);
+        let path = load.path.clone();
+        let error = load_error(load_model_with(&state, load, |_, _| panic!("loaded a corrupted model")).await);
+        assert!(error.contains("corrupted"), "{}", error);
+        assert!(!path.exists());
+        let stt = state.lock().unwrap();
+        assert_eq!(stt.corrupt_model, Some(ModelSize::Tiny));
+        assert!(!stt.verified_models.contains_key(&ModelSize::Tiny));
+    }
+
+    #[tokio::test]
+    async fn model_without_a_checksum_is_not_loaded() {
+        let state = state();
+        let mut load = fixture_model(ModelSize::TinyQ5_1, "unchecked.bin", false);
+        load.sha256 = None;
+        let path = load.path.clone();
+        let error = load_error(load_model_with(&state, load, |_, _| panic!("loaded an unverified model")).await);
+        assert!(error.contains("no checksum"), "{}", error);
+        // Kept for a re-download to replace, but not remembered as verified
+        assert!(path.exists());
+        assert!(!state.lock().unwrap().verified_models.contains_key(&ModelSize::TinyQ5_1));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn verified_model_is_not_hashed_again() {
+        let state = state();
+        // The fixture doesn't match the Tiny checksum, it only gets to the loader unhashed
+        let load = fixture_model(ModelSize::Tiny, "verified.bin", true);
+        let path = load.path.clone();
+        let error = load_error(load_model_with(&state, load, |_, _| Err("loader reached".to_string())).await);
+        assert!(error.contains("loader reached"), "{}", error);
+        assert!(path.exists());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// `samples` of session audio from `start_sample`, each sample holding its own position
+    fn session_audio(start_sample: u64, samples: u64) -> Utterance {
+        Utterance {
+            samples: (start_sample..start_sample + samples).map(|i| i as f32).collect(),
+            start_sample,
+        }
+    }
+
+    /// Whisper's output for `text` spoken from `start_ms` into a chunk, a word every 500ms
+    fn transcribed(text: &str, start_ms: i64) -> Transcription {
+        let words: Vec<whisper::TranscriptToken> = text
+            .split_whitespace()
+            .enumerate()
+            .map(|(i, word)| whisper::TranscriptToken {
+                text: word.to_string(),
+                start_ms: start_ms + i as i64 * 500,
+                end_ms: start_ms + (i as i64 + 1) * 500,
+            })
+            .collect();
+        Transcription {
+            segments: vec![TranscriptSegment {
+                text: text.to_string(),
+                start_ms,
+                end_ms: words.last().map_or(start_ms, |word| word.end_ms),
+                words,
+                confidence: 0.9,
+                no_speech_prob: 0.1,
+            }],
+            detected_language: None,
+        }
+    }
+
+    #[test]
+    fn consecutive_chunks_are_timed_from_the_session_start() {
+        let rate = WHISPER_SAMPLE_RATE as u64;
+        let overlap = rate as usize / 2;
+        let mut previous = PreviousChunk::default();
+
+        // Long speech split at the max length, then more after a pause
+        let first = previous.prepare(&session_audio(0, 6 * rate), overlap);
+        assert_eq!((first.offset_ms, first.overlap_len), (0, 0));
+        let first = previous.accept(transcribed("we ship the beta on friday", 3000), 0, 0.6, true).unwrap();
+
+        let second = previous.prepare(&session_audio(6 * rate, 4 * rate), overlap);
+        assert_eq!((second.offset_ms, second.overlap_len), (5500, overlap));
+        // The chunk starts with the audio it overlaps, so it's timed from there
+        assert_eq!(second.samples.len(), 4 * rate as usize + overlap);
+        assert_eq!(second.samples[0], (6 * rate) as f32 - overlap as f32);
+        assert_eq!(second.samples[overlap], (6 * rate) as f32);
+        // Whisper hears the overlapped word again, which was already emitted with the first chunk
+        let result = previous
+            .accept(transcribed("friday then we review", 0), second.overlap_len, 0.6, true)
+            .unwrap();
+        assert_eq!(result.segments[0].text, "then we review");
+        let first_end = first.segments[0].end_ms as u64;
+        let second_start = second.offset_ms + result.segments[0].start_ms as u64;
+        assert_eq!((first_end, second_start), (6000, 6000));
+
+        let third = previous.prepare(&session_audio(12 * rate, 2 * rate), overlap);
+        assert_eq!((third.offset_ms, third.overlap_len), (12_000, 0));
+        assert_eq!(third.samples[0], (12 * rate) as f32);
+    }
+
+    /// Stands in for Whisper, holding each call until told to go on
+    struct BlockedTranscriber {
+        started: std::sync::mpsc::Sender<()>,
+        release: Mutex<std::sync::mpsc::Receiver<()>>,
+    }
+
+    impl Transcriber for BlockedTranscriber {
+        fn transcribe(&self, _: &[f32], _: Option<&str>, _: Option<&str>, _: Option<&str>) -> Result<Transcription, String> {
+            let _ = self.started.send(());
+            self.release.lock().unwrap().recv().map_err(|e| e.to_string())?;
+            Ok(transcribed("sounds good", 0))
+        }
+    }
+
+    #[tokio::test]
+    async fn status_and_stop_are_answered_while_transcribing() {
+        let state = state();
+        state.lock().unwrap().is_running = true;
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel();
+        let transcriber = Arc::new(BlockedTranscriber {
+            started: started_tx,
+            release: Mutex::new(release_rx),
+        });
+        let job = TranscriptionJob {
+            utterance: session_audio(WHISPER_SAMPLE_RATE as u64, WHISPER_SAMPLE_RATE as u64),
+            language: Some("en".to_string()),
+            overlap_samples: 0,
+            max_no_speech_prob: 0.6,
+            filter_hallucinations: true,
+            diarize: false,
+        };
+        let transcribing = tokio::spawn(async move {
+            let mut previous = PreviousChunk::default();
+            transcribe_job(&transcriber, &mut previous, job, None).await
+        });
+        tokio::task::spawn_blocking(move || started_rx.recv()).await.unwrap().unwrap();
+
+        let asked = Instant::now();
+        let status = get_stt_status(&state);
+        assert!(asked.elapsed() < Duration::from_millis(50), "status waited {:?}", asked.elapsed());
+        assert!(status.is_listening);
+
+        let asked = Instant::now();
+        let recorder = state.lock().unwrap().end_session();
+        assert!(asked.elapsed() < Duration::from_millis(50), "stop waited {:?}", asked.elapsed());
+        assert!(recorder.is_none());
+        assert!(!get_stt_status(&state).is_listening);
+        assert!(!transcribing.is_finished(), "the transcription is still blocked");
+
+        // The queued utterance still finishes once Whisper does
+        release_tx.send(()).unwrap();
+        let (offset_ms, result) = transcribing.await.unwrap().unwrap().unwrap();
+        assert_eq!(offset_ms, 1000);
+        assert_eq!(whisper::join_segments(&result.segments), "sounds good");
+    }
+
+    /// Transcribes a clip of speech in forced chunks, as long speech is split, and checks the
+    /// segments come out in order once timed from the session start. Needs a downloaded model:
+    /// the one at HYPERGRANOLA_TEST_MODEL, or the tiny English model.
+    /// The clip is synthesized, vowels and consonants on a falling pitch rather than words.
+    #[test]
+    #[ignore = "needs a Whisper model"]
+    fn speech_segments_are_in_order_across_chunks() {
+        let path = std::env::var_os("HYPERGRANOLA_TEST_MODEL")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| get_model_path(ModelSize::Tiny).unwrap());
+        let engine = WhisperEngine::new(&path, false).unwrap();
+        let clip = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/speech-16khz-mono.wav");
+        let audio = audio_file::decode_file(&clip, 60).unwrap();
+
+        let chunk = 2 * WHISPER_SAMPLE_RATE as usize;
+        let mut previous = PreviousChunk::default();
+        let mut times = Vec::new();
+        for (i, samples) in audio.chunks(chunk).enumerate() {
+            let utterance = Utterance {
+                samples: samples.to_vec(),
+                start_sample: (i * chunk) as u64,
+            };
+            let prepared = previous.prepare(&utterance, WHISPER_SAMPLE_RATE as usize / 2);
+            let result = engine.transcribe(&prepared.samples, Some("en"), None, None).unwrap();
+            if let Some(result) = previous.accept(result, prepared.overlap_len, 1.0, false) {
+                let offset_ms = prepared.offset_ms as i64;
+                times.extend(result.segments.iter().map(|segment| (offset_ms + segment.start_ms, offset_ms + segment.end_ms)));
+            }
+        }
+
+        assert!(!times.is_empty(), "nothing was transcribed");
+        for (start, end) in &times {
+            assert!(start <= end, "segment ends at {} before it starts at {}", end, start);
+        }
+        for pair in times.windows(2) {
+            assert!(pair[0].1 <= pair[1].0, "{:?} overlaps {:?}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn audio_from_before_a_long_pause_is_stale() {
+        let mut stt = SttState {
+            paused_at: Some(Instant::now()),
+            ..SttState::default()
+        };
+        assert!(!stt.pause_is_stale());
+
+        stt.paused_at = Instant::now().checked_sub(Duration::from_millis(DEFAULT_STALE_AUDIO_MS + 1000));
+        assert!(stt.pause_is_stale());
+
+        stt.paused_at = Some(Instant::now());
+        stt.config.stale_audio_ms = 0;
+        assert!(stt.pause_is_stale());
+    }
+
+    #[test]
+    fn stale_audio_threshold_is_validated() {
+        assert!(SttConfig::default().validate().is_ok());
+        assert!(SttConfig { stale_audio_ms: 0, ..SttConfig::default() }.validate().is_ok());
+        let too_long = SttConfig {
+            stale_audio_ms: MAX_STALE_AUDIO_MS + 1,
+            ..SttConfig::default()
+        };
+        assert!(too_long.validate().is_err());
+    }
+
+    /// Stand-in capture counting how many of its streams are open
+    struct CountedStream {
+        live: Arc<AtomicU64>,
+        open: bool,
+    }
+
+    impl CountedStream {
+        fn close(&mut self) {
+            if std::mem::take(&mut self.open) {
+                self.live.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    impl CaptureStream for CountedStream {
+        fn stop(&mut self) {
+            self.close();
+        }
+        fn pause(&mut self) {}
+        fn resume(&mut self) {}
+        fn clear_buffer(&mut self) {}
+        fn get_samples(&mut self, _: usize) -> Vec<f32> {
+            Vec::new()
+        }
+        fn get_capture_stats(&self) -> CaptureStats {
+            CaptureStats { dropped_samples: 0, buffered_samples: 0 }
+        }
+        fn is_device_lost(&self) -> bool {
+            false
+        }
+        fn level_meter(&self) -> LevelMeter {
+            LevelMeter::default()
+        }
+    }
+
+    impl Drop for CountedStream {
+        fn drop(&mut self) {
+            self.close();
+        }
+    }
+
+    struct CountedOpener {
+        live: Arc<AtomicU64>,
+    }
+
+    impl CaptureOpener for CountedOpener {
+        fn open(&self, _: usize, _: &CaptureSettings, _: ErrorHandler) -> Result<(Box<dyn CaptureStream>, Option<String>), String> {
+            self.live.fetch_add(1, Ordering::SeqCst);
+            Ok((Box::new(CountedStream { live: self.live.clone(), open: true }), None))
+        }
+    }
+
+    /// What `start_stt` does to the state once the model is loaded
+    fn start(stt: &mut SttState) -> Result<(), String> {
+        stt.begin_start()?;
+        stt.is_starting = false;
+        stt.open_stream(Arc::new(|_| {}))?;
+        stt.begin_session();
+        Ok(())
+    }
+
+    #[test]
+    fn second_start_is_rejected_while_one_is_active() {
+        let live = Arc::new(AtomicU64::new(0));
+        let mut stt = SttState {
+            capture_opener: Arc::new(CountedOpener { live: live.clone() }),
+            ..SttState::default()
+        };
+        stt.begin_start().unwrap();
+        // The first start is still loading the model
+        assert_eq!(stt.begin_start().unwrap_err(), "STT already running");
+        stt.is_starting = false;
+
+        start(&mut stt).unwrap();
+        assert_eq!(start(&mut stt).unwrap_err(), "STT already running");
+        assert_eq!(live.load(Ordering::SeqCst), 1);
+        assert!(!stt.is_starting);
+
+        stt.end_session();
+        assert_eq!(live.load(Ordering::SeqCst), 0);
+
+        // Once stopped, listening can start again
+        start(&mut stt).unwrap();
+        assert_eq!(live.load(Ordering::SeqCst), 1);
+        stt.end_session();
+        assert_eq!(live.load(Ordering::SeqCst), 0);
+    }
+
+    /// One second of a 200 Hz tone at `amplitude`, voiced throughout like speech
+    fn tone(amplitude: f32) -> Vec<f32> {
+        (0..WHISPER_SAMPLE_RATE)
+            .map(|i| amplitude * (std::f32::consts::TAU * 200.0 * i as f32 / WHISPER_SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn speech_gate_skips_silence_and_noise() {
+        let (vad, sensitivity) = (VadConfig::default(), SttSensitivity::default());
+
+        assert!(!passes_speech_gate(&vec![0.0; WHISPER_SAMPLE_RATE as usize], vad, sensitivity));
+        // Hiss well under the silence threshold
+        assert!(!passes_speech_gate(&tone(0.002), vad, sensitivity));
+        // A single 20ms click is loud but too short to be speech
+        let mut click = vec![0.0; WHISPER_SAMPLE_RATE as usize];
+        click[..320].fill(0.9);
+        assert!(!passes_speech_gate(&click, vad, sensitivity));
+    }
+
+    #[test]
+    fn speech_gate_passes_quiet_and_loud_speech() {
+        let (vad, sensitivity) = (VadConfig::default(), SttSensitivity::default());
+        // About -37 dBFS, a soft-spoken participant
+        let quiet = tone(0.02);
+        assert!(passes_speech_gate(&quiet, vad, sensitivity));
+        assert!(passes_speech_gate(&tone(0.3), vad, sensitivity));
+
+        // A stricter level threshold skips the quiet speech only
+        let strict = SttSensitivity {
+            min_level_dbfs: -30.0,
+            ..sensitivity
+        };
+        assert!(!passes_speech_gate(&quiet, vad, strict));
+        assert!(passes_speech_gate(&tone(0.3), vad, strict));
+    }
+}
@@ -0,0 +1,529 @@
+//! Transcript text handling
+//! Stitches transcribed chunks together and keeps the session transcript
+
+use crate::diarization::Speaker;
+use crate::whisper::TranscriptSegment;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Longest run of repeated words looked for where chunks overlap
+const MAX_OVERLAP_WORDS: usize = 20;
+/// Segments kept before the oldest are discarded
+const DEFAULT_MAX_SEGMENTS: usize = 10_000;
+/// Recent segments a new one is compared against for repeats
+pub const REPEAT_WINDOW: usize = 3;
+/// Share of words in common above which a segment counts as a repeat
+const REPEAT_SIMILARITY: f32 = 0.7;
+/// Shorter segments may legitimately repeat ("yes", "okay, thanks")
+const MIN_REPEAT_WORDS: usize = 4;
+/// Whole segments Whisper invents from silence or noise, normalized
+const HALLUCINATION_PHRASES: &[&str] = &[
+    "thanks for watching",
+    "thank you for watching",
+    "thank you for watching this video",
+    "please subscribe",
+    "please like and subscribe",
+    "subtitles by the amaraorg community",
+    "blankaudio",
+    "music",
+    "silence",
+];
+
+/// Compare words ignoring case and punctuation
+fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Normalized words of a text
+fn normalize_text(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(normalize_word)
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Share of distinct words two texts have in common
+fn word_similarity(a: &[String], b: &[String]) -> f32 {
+    let a: HashSet<&String> = a.iter().collect();
+    let b: HashSet<&String> = b.iter().collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f32 / union as f32
+}
+
+/// Check for a phrase of two or more words repeated back to back at least three times
+fn is_repetition_loop(words: &[String]) -> bool {
+    (2..=words.len() / 3).any(|period| words[period..].iter().zip(words).all(|(a, b)| a == b))
+}
+
+/// Check whether a segment looks like a Whisper hallucination rather than speech:
+/// a known phrase, a phrase stuck in a loop, or a near-copy of a `recent` segment
+pub fn is_likely_hallucination(text: &str, recent: &[String]) -> bool {
+    let words = normalize_text(text);
+    if words.is_empty() {
+        return false;
+    }
+
+    if HALLUCINATION_PHRASES.contains(&words.join(" ").as_str()) || is_repetition_loop(&words) {
+        return true;
+    }
+
+    words.len() >= MIN_REPEAT_WORDS
+        && recent
+            .iter()
+            .any(|previous| word_similarity(&words, &normalize_text(previous)) >= REPEAT_SIMILARITY)
+}
+
+/// Count how many leading words of `next` repeat the trailing words of `previous`
+pub fn overlapping_words(previous: &[String], next: &[String]) -> usize {
+    let max = previous.len().min(next.len()).min(MAX_OVERLAP_WORDS);
+    (1..=max)
+        .rev()
+        .find(|&count| {
+            previous[previous.len() - count..]
+                .iter()
+                .zip(&next[..count])
+                .all(|(a, b)| normalize_word(a) == normalize_word(b))
+        })
+        .unwrap_or(0)
+}
+
+/// Every word of the segments, in order
+pub fn segment_words(segments: &[TranscriptSegment]) -> Vec<String> {
+    segments
+        .iter()
+        .flat_map(|segment| segment.words.iter().map(|word| word.text.clone()))
+        .collect()
+}
+
+/// Drop the first `count` words from the segments, rebuilding their text and timing
+pub fn drop_leading_words(segments: &mut Vec<TranscriptSegment>, mut count: usize) {
+    for segment in segments.iter_mut() {
+        if count == 0 {
+            break;
+        }
+        if segment.words.is_empty() {
+            continue;
+        }
+
+        let dropped = count.min(segment.words.len());
+        segment.words.drain(..dropped);
+        count -= dropped;
+
+        segment.text = segment
+            .words
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Some(first) = segment.words.first() {
+            segment.start_ms = first.start_ms;
+        }
+    }
+    segments.retain(|segment| !segment.text.is_empty());
+}
+
+/// A transcribed segment as kept for the session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredSegment {
+    pub text: String,
+    /// Offset from the start of the listening session
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// Wall-clock time the segment was transcribed
+    pub received_at: DateTime<Local>,
+    pub language: Option<String>,
+    /// Marks a stretch where no audio was captured rather than speech
+    #[serde(default)]
+    pub gap: bool,
+    /// Speaker diarization attributed the segment to
+    #[serde(default)]
+    pub speaker_id: Option<String>,
+    #[serde(default)]
+    pub speaker_label: Option<String>,
+    /// Speaker talking over this segment, whose words may be mixed in
+    #[serde(default)]
+    pub overlapping_speaker_id: Option<String>,
+    #[serde(default)]
+    pub overlapping_speaker_label: Option<String>,
+}
+
+/// Running transcript of the session, so it survives webview reloads and missed events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptStore {
+    segments: VecDeque<StoredSegment>,
+    max_segments: usize,
+    /// Segments added over the session, discarded ones included, which gives each a position
+    #[serde(default)]
+    added: u64,
+}
+
+impl Default for TranscriptStore {
+    fn default() -> Self {
+        Self {
+            segments: VecDeque::new(),
+            max_segments: DEFAULT_MAX_SEGMENTS,
+            added: 0,
+        }
+    }
+}
+
+impl TranscriptStore {
+    /// Append segments timed relative to `offset_ms` into the session
+    pub fn append(&mut self, offset_ms: u64, segments: &[TranscriptSegment], language: Option<&str>) {
+        let received_at = Local::now();
+        for segment in segments {
+            self.segments.push_back(StoredSegment {
+                text: segment.text.clone(),
+                start_ms: offset_ms + segment.start_ms.max(0) as u64,
+                end_ms: offset_ms + segment.end_ms.max(0) as u64,
+                received_at,
+                language: language.map(str::to_string),
+                gap: false,
+                speaker_id: None,
+                speaker_label: None,
+                overlapping_speaker_id: None,
+                overlapping_speaker_label: None,
+            });
+            self.added += 1;
+        }
+        self.trim();
+    }
+
+    /// Record that audio was missing for `duration_secs` at `position_ms` into the session
+    pub fn mark_gap(&mut self, position_ms: u64, duration_secs: u64) {
+        self.segments.push_back(StoredSegment {
+            text: format!("[audio missing for {}s]", duration_secs),
+            start_ms: position_ms,
+            end_ms: position_ms,
+            received_at: Local::now(),
+            language: None,
+            gap: true,
+            speaker_id: None,
+            speaker_label: None,
+            overlapping_speaker_id: None,
+            overlapping_speaker_label: None,
+        });
+        self.added += 1;
+        self.trim();
+    }
+
+    /// Attribute the segments between `start_ms` and `end_ms` to a speaker, noting who talked over them
+    pub fn assign_speaker(&mut self, start_ms: u64, end_ms: u64, speaker: &Speaker, overlapping: Option<&Speaker>) {
+        for segment in self.segments.iter_mut() {
+            if !segment.gap && segment.start_ms >= start_ms && segment.end_ms <= end_ms {
+                segment.speaker_id = Some(speaker.id.clone());
+                segment.speaker_label = Some(speaker.label.clone());
+                segment.overlapping_speaker_id = overlapping.map(|speaker| speaker.id.clone());
+                segment.overlapping_speaker_label = overlapping.map(|speaker| speaker.label.clone());
+            }
+        }
+    }
+
+    /// Give every segment of a speaker a new label, returning how many changed
+    pub fn relabel_speaker(&mut self, speaker_id: &str, speaker_label: &str) -> usize {
+        let mut relabeled = 0;
+        for segment in self.segments.iter_mut() {
+            if segment.speaker_id.as_deref() == Some(speaker_id) {
+                segment.speaker_label = Some(speaker_label.to_string());
+                relabeled += 1;
+            }
+            if segment.overlapping_speaker_id.as_deref() == Some(speaker_id) {
+                segment.overlapping_speaker_label = Some(speaker_label.to_string());
+            }
+        }
+        relabeled
+    }
+
+    /// Move a merged speaker's segments to the speaker they were merged into, returning how many moved
+    pub fn merge_speaker(&mut self, from_id: &str, into_id: &str, into_label: &str) -> usize {
+        let mut moved = 0;
+        for segment in self.segments.iter_mut() {
+            if segment.speaker_id.as_deref() == Some(from_id) {
+                segment.speaker_id = Some(into_id.to_string());
+                segment.speaker_label = Some(into_label.to_string());
+                moved += 1;
+            }
+            if segment.overlapping_speaker_id.as_deref() == Some(from_id) {
+                segment.overlapping_speaker_id = Some(into_id.to_string());
+                segment.overlapping_speaker_label = Some(into_label.to_string());
+            }
+            // A speaker can't talk over themselves
+            if segment.overlapping_speaker_id.is_some() && segment.overlapping_speaker_id == segment.speaker_id {
+                segment.overlapping_speaker_id = None;
+                segment.overlapping_speaker_label = None;
+            }
+        }
+        moved
+    }
+
+    /// Change how many segments are kept, dropping the oldest if over the new limit
+    pub fn set_max_segments(&mut self, max_segments: usize) {
+        self.max_segments = max_segments.max(1);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        let excess = self.segments.len().saturating_sub(self.max_segments);
+        self.segments.drain(..excess);
+    }
+
+    /// All stored segments, oldest first
+    pub fn segments(&self) -> Vec<StoredSegment> {
+        self.segments.iter().cloned().collect()
+    }
+
+    /// The whole transcript as plain text
+    pub fn full_text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The transcript with each speaker's turns prefixed by their label, so action items and
+    /// decisions can be attributed to people. Segments without a speaker are left unprefixed.
+    pub fn attributed_text(&self) -> String {
+        let mut turns: Vec<(Option<&str>, Vec<String>)> = Vec::new();
+        for segment in self.segments.iter().filter(|segment| !segment.gap) {
+            let speaker = segment.speaker_label.as_deref();
+            let text = match &segment.overlapping_speaker_label {
+                Some(overlapping) => format!("[talking over {}] {}", overlapping, segment.text.trim()),
+                None => segment.text.trim().to_string(),
+            };
+            match turns.last_mut() {
+                Some((last, texts)) if *last == speaker => texts.push(text),
+                _ => turns.push((speaker, vec![text])),
+            }
+        }
+        turns
+            .into_iter()
+            .map(|(speaker, texts)| match speaker {
+                Some(speaker) => format!("{}: {}", speaker, texts.join(" ")),
+                None => texts.join(" "),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Text of the last `count` spoken segments
+    pub fn recent_text(&self, count: usize) -> String {
+        let mut recent: Vec<&str> = self
+            .segments
+            .iter()
+            .rev()
+            .filter(|segment| !segment.gap)
+            .take(count)
+            .map(|segment| segment.text.as_str())
+            .collect();
+        recent.reverse();
+        recent.join(" ")
+    }
+
+    /// Text spoken from `position` on, a count of segments added before it, along with the
+    /// position after the latest segment to pick up from next time
+    pub fn text_since(&self, position: u64) -> (String, u64) {
+        let first = self.added - self.segments.len() as u64;
+        let text = self
+            .segments
+            .iter()
+            .skip(position.saturating_sub(first) as usize)
+            .filter(|segment| !segment.gap)
+            .map(|segment| segment.text.trim())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        (text, self.added)
+    }
+
+    /// Discard the whole transcript. Positions keep counting, so later segments are still new.
+    pub fn clear(&mut self) {
+        self.segments.clear();
+    }
+}
+
+pub type SharedTranscriptStore = Arc<Mutex<TranscriptStore>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::whisper::TranscriptToken;
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    /// A segment whose words are 100ms apart from `start_ms`
+    fn segment(text: &str, start_ms: i64) -> TranscriptSegment {
+        let words: Vec<TranscriptToken> = text
+            .split_whitespace()
+            .enumerate()
+            .map(|(i, word)| TranscriptToken {
+                text: word.to_string(),
+                start_ms: start_ms + i as i64 * 100,
+                end_ms: start_ms + i as i64 * 100 + 100,
+            })
+            .collect();
+        TranscriptSegment {
+            text: text.to_string(),
+            start_ms,
+            end_ms: words.last().map_or(start_ms, |word| word.end_ms),
+            words,
+            confidence: 0.9,
+            no_speech_prob: 0.0,
+        }
+    }
+
+    #[test]
+    fn overlap_is_the_longest_repeated_run_of_words() {
+        let previous = words("so we agreed to ship the beta next week");
+        assert_eq!(overlapping_words(&previous, &words("ship the beta next week and then")), 5);
+        // Whisper re-punctuates and re-capitalizes the overlap
+        assert_eq!(overlapping_words(&previous, &words("Next week. And then review")), 2);
+        assert_eq!(overlapping_words(&previous, &words("completely different words")), 0);
+        assert_eq!(overlapping_words(&[], &words("anything")), 0);
+    }
+
+    #[test]
+    fn overlap_only_matches_at_the_boundary() {
+        // "the" appears in both, but not at the end of the previous chunk
+        let previous = words("the budget is fine");
+        assert_eq!(overlapping_words(&previous, &words("the plan is next")), 0);
+    }
+
+    #[test]
+    fn overlap_is_looked_for_over_a_bounded_run() {
+        let repeated = words(&"word ".repeat(MAX_OVERLAP_WORDS + 5));
+        assert_eq!(overlapping_words(&repeated, &repeated), MAX_OVERLAP_WORDS);
+    }
+
+    #[test]
+    fn dropping_overlap_spans_segments_and_retimes_them() {
+        let mut segments = vec![segment("next week.", 0), segment("And then review the plan", 500)];
+        drop_leading_words(&mut segments, 3);
+
+        assert_eq!(segments.len(), 1, "the emptied segment is removed");
+        assert_eq!(segments[0].text, "then review the plan");
+        assert_eq!(segments[0].start_ms, 600);
+        assert_eq!(segments[0].end_ms, 1000);
+    }
+
+    #[test]
+    fn dropping_no_words_keeps_segments() {
+        let mut segments = vec![segment("hello there", 0)];
+        drop_leading_words(&mut segments, 0);
+        assert_eq!(segments[0].text, "hello there");
+        assert_eq!(segments[0].start_ms, 0);
+    }
+
+    #[test]
+    fn hallucinations_are_told_apart_from_speech() {
+        let recent = [
+            "I'm going to go ahead and share my screen.".to_string(),
+            "Yes.".to_string(),
+        ];
+        let cases = [
+            // (text, suppressed, case)
+            ("I'm going to go ahead and share my screen.", true, "exact repeat"),
+            ("I'm going to go ahead and share the screen", true, "minor variation"),
+            ("Yes.", false, "short answers legitimately repeat"),
+            ("Okay, thanks.", false, "short new answer"),
+            ("Thanks for watching!", true, "known phrase"),
+            ("[BLANK_AUDIO]", true, "known marker"),
+            ("go ahead and go ahead and go ahead and", true, "repetition loop"),
+            ("Let's move on to the budget review.", false, "new sentence"),
+            ("", false, "empty"),
+        ];
+        for (text, suppressed, case) in cases {
+            assert_eq!(is_likely_hallucination(text, &recent), suppressed, "{}: {:?}", case, text);
+        }
+    }
+
+    #[test]
+    fn hallucination_check_without_history_only_uses_phrases_and_loops() {
+        assert!(!is_likely_hallucination("I'm going to go ahead and share my screen.", &[]));
+        assert!(is_likely_hallucination("Please like and subscribe.", &[]));
+    }
+
+    #[test]
+    fn gap_marks_missing_audio_without_counting_as_speech() {
+        let mut store = TranscriptStore::default();
+        store.append(0, &[segment("before the headset dropped", 0)], Some("en"));
+        store.mark_gap(2000, 12);
+        store.append(14_000, &[segment("back again", 0)], Some("en"));
+
+        let segments = store.segments();
+        assert!(segments[1].gap);
+        assert_eq!(segments[1].start_ms, 2000);
+        assert_eq!(segments[1].text, "[audio missing for 12s]");
+        assert_eq!(segments[2].start_ms, 14_000);
+
+        // The marker shows in the full transcript but isn't anyone's speech
+        assert!(store.full_text().contains("[audio missing for 12s]"));
+        assert_eq!(store.attributed_text(), "before the headset dropped back again");
+        assert_eq!(store.text_since(1), ("back again".to_string(), 3));
+        assert_eq!(store.recent_text(2), "before the headset dropped back again");
+    }
+
+    #[test]
+    fn text_since_picks_up_after_discarded_and_cleared_segments() {
+        let mut store = TranscriptStore::default();
+        store.set_max_segments(2);
+        store.append(0, &[segment("one", 0), segment("two", 1000), segment("three", 2000)], None);
+        // "one" was discarded, so reading from before it gets what's left
+        assert_eq!(store.text_since(0), ("two three".to_string(), 3));
+        assert_eq!(store.text_since(3), (String::new(), 3));
+
+        store.clear();
+        store.append(0, &[segment("four", 0)], None);
+        assert_eq!(store.text_since(3), ("four".to_string(), 4));
+    }
+
+    fn speaker(id: &str, label: &str) -> Speaker {
+        Speaker {
+            id: id.to_string(),
+            label: label.to_string(),
+            characteristics: Vec::new(),
+            first_detected: std::time::Duration::ZERO,
+            last_active: std::time::Duration::ZERO,
+            message_count: 0,
+            participant: None,
+        }
+    }
+
+    #[test]
+    fn merged_speaker_segments_move_to_the_one_merged_into() {
+        let mut store = TranscriptStore::default();
+        store.append(0, &[segment("so what's the plan", 0), segment("ship it friday", 1000)], None);
+        store.append(2000, &[segment("agreed", 0)], None);
+        let first = speaker("speaker_1", "Speaker 1");
+        let second = speaker("speaker_2", "Dana");
+        let third = speaker("speaker_3", "Speaker 3");
+        store.assign_speaker(0, 1000, &first, None);
+        store.assign_speaker(1000, 2000, &second, Some(&first));
+        store.assign_speaker(2000, 3000, &third, Some(&first));
+
+        assert_eq!(store.merge_speaker("speaker_1", "speaker_2", "Dana"), 1);
+        let segments = store.segments();
+        let speakers: Vec<_> = segments
+            .iter()
+            .map(|segment| (segment.speaker_label.as_deref(), segment.overlapping_speaker_label.as_deref()))
+            .collect();
+        // Dana no longer talks over herself, and the talked-over speaker is remapped too
+        assert_eq!(speakers, [(Some("Dana"), None), (Some("Dana"), None), (Some("Speaker 3"), Some("Dana"))]);
+        assert_eq!(segments[0].speaker_id.as_deref(), Some("speaker_2"));
+        assert_eq!(segments[2].overlapping_speaker_id.as_deref(), Some("speaker_2"));
+        assert_eq!(store.merge_speaker("speaker_1", "speaker_2", "Dana"), 0);
+
+        assert_eq!(store.relabel_speaker("speaker_2", "Dana R."), 2);
+        assert_eq!(store.segments()[2].overlapping_speaker_label.as_deref(), Some("Dana R."));
+    }
+}
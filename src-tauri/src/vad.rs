@@ -0,0 +1,229 @@
+//! Voice activity detection
+//! Splits the 16kHz audio stream into complete utterances for transcription
+
+use crate::audio::WHISPER_SAMPLE_RATE;
+use std::collections::VecDeque;
+
+/// Analysis frame length (30ms at 16kHz)
+const FRAME_SAMPLES: usize = WHISPER_SAMPLE_RATE as usize * 30 / 1000;
+/// Audio kept from before speech starts so word onsets aren't clipped
+const PRE_ROLL_FRAMES: usize = 7; // ~200ms
+/// Speech frames required before audio is worth transcribing
+const MIN_SPEECH_FRAMES: usize = 3; // ~90ms
+
+/// Tunable VAD parameters
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct VadConfig {
+    /// RMS level above which a frame counts as speech
+    pub silence_threshold: f32,
+    /// Silence after speech that ends an utterance, in milliseconds
+    pub hangover_ms: u32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            silence_threshold: 0.01,
+            hangover_ms: 600,
+        }
+    }
+}
+
+impl VadConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        // Full-scale audio has an RMS level of 1, a threshold outside (0, 1] gates everything or nothing
+        if !(self.silence_threshold > 0.0 && self.silence_threshold <= 1.0) {
+            return Err("Silence threshold must be above 0 and at most 1".to_string());
+        }
+        if self.hangover_ms == 0 {
+            return Err("Hangover must be at least 1ms".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Root mean square level of a block of samples
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|&s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// RMS level in dBFS, where 0 is full scale
+pub fn rms_dbfs(samples: &[f32]) -> f32 {
+    20.0 * rms(samples).max(1e-10).log10()
+}
+
+/// Check whether audio holds enough speech to transcribe.
+/// Filters out clicks and noise bursts that would make Whisper hallucinate.
+pub fn contains_speech(samples: &[f32], threshold: f32) -> bool {
+    samples
+        .chunks(FRAME_SAMPLES)
+        .filter(|frame| rms(frame) > threshold)
+        .count()
+        >= MIN_SPEECH_FRAMES
+}
+
+/// A complete utterance and where it starts in the session
+#[derive(Debug, Clone)]
+pub struct Utterance {
+    pub samples: Vec<f32>,
+    /// Offset of the first sample from the start of the session
+    pub start_sample: u64,
+}
+
+impl Utterance {
+    /// Offset from the start of the session in milliseconds
+    pub fn start_ms(&self) -> u64 {
+        self.start_sample * 1000 / WHISPER_SAMPLE_RATE as u64
+    }
+}
+
+/// Accumulates audio until an end of utterance is detected
+pub struct UtteranceSegmenter {
+    config: VadConfig,
+    min_samples: usize,
+    max_samples: usize,
+    pending: Vec<f32>,
+    pre_roll: VecDeque<Vec<f32>>,
+    utterance: Vec<f32>,
+    utterance_start: u64,
+    position: u64,
+    in_speech: bool,
+    silence_samples: usize,
+}
+
+impl UtteranceSegmenter {
+    /// Create a segmenter emitting utterances between `min_samples` and `max_samples` long
+    pub fn new(config: VadConfig, min_samples: usize, max_samples: usize) -> Self {
+        Self {
+            config,
+            min_samples,
+            max_samples,
+            pending: Vec::new(),
+            pre_roll: VecDeque::with_capacity(PRE_ROLL_FRAMES),
+            utterance: Vec::new(),
+            utterance_start: 0,
+            position: 0,
+            in_speech: false,
+            silence_samples: 0,
+        }
+    }
+
+    /// Update the VAD parameters, keeping any audio already buffered
+    pub fn set_config(&mut self, config: VadConfig) {
+        self.config = config;
+    }
+
+    /// Change the shortest and longest utterance, applied to the one in progress too
+    pub fn set_limits(&mut self, min_samples: usize, max_samples: usize) {
+        self.min_samples = min_samples;
+        self.max_samples = max_samples;
+    }
+
+    /// Discard buffered audio, e.g. after a pause, keeping the session position
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.pre_roll.clear();
+        self.utterance.clear();
+        self.in_speech = false;
+        self.silence_samples = 0;
+    }
+
+    /// Session time reached so far, in milliseconds
+    pub fn position_ms(&self) -> u64 {
+        self.position * 1000 / WHISPER_SAMPLE_RATE as u64
+    }
+
+    /// Feed audio and return every utterance completed by it
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Utterance> {
+        self.pending.extend_from_slice(samples);
+
+        let mut utterances = Vec::new();
+        let mut offset = 0;
+        while self.pending.len() - offset >= FRAME_SAMPLES {
+            let frame = self.pending[offset..offset + FRAME_SAMPLES].to_vec();
+            offset += FRAME_SAMPLES;
+            if let Some(utterance) = self.process_frame(frame) {
+                utterances.push(utterance);
+            }
+            self.position += FRAME_SAMPLES as u64;
+        }
+        self.pending.drain(..offset);
+
+        utterances
+    }
+
+    fn process_frame(&mut self, frame: Vec<f32>) -> Option<Utterance> {
+        let is_speech = rms(&frame) > self.config.silence_threshold;
+        let hangover_samples = (self.config.hangover_ms as usize * WHISPER_SAMPLE_RATE as usize) / 1000;
+
+        if !self.in_speech {
+            if !is_speech {
+                if self.pre_roll.len() == PRE_ROLL_FRAMES {
+                    self.pre_roll.pop_front();
+                }
+                self.pre_roll.push_back(frame);
+                return None;
+            }
+
+            // Speech onset: start the utterance with the buffered pre-roll
+            self.in_speech = true;
+            self.silence_samples = 0;
+            self.utterance_start = self.position - (self.pre_roll.len() * FRAME_SAMPLES) as u64;
+            for pre in self.pre_roll.drain(..) {
+                self.utterance.extend_from_slice(&pre);
+            }
+        }
+
+        self.utterance.extend_from_slice(&frame);
+        if is_speech {
+            self.silence_samples = 0;
+        } else {
+            self.silence_samples += frame.len();
+        }
+
+        // End of utterance after enough trailing silence, or forced flush at max length
+        if self.silence_samples >= hangover_samples || self.utterance.len() >= self.max_samples {
+            return self.finish_utterance();
+        }
+
+        None
+    }
+
+    fn finish_utterance(&mut self) -> Option<Utterance> {
+        self.in_speech = false;
+        self.silence_samples = 0;
+
+        let mut utterance = std::mem::take(&mut self.utterance);
+        if utterance.is_empty() {
+            return None;
+        }
+
+        // Whisper needs at least the minimum duration, so pad short utterances with silence
+        if utterance.len() < self.min_samples {
+            utterance.resize(self.min_samples, 0.0);
+        }
+        Some(Utterance {
+            samples: utterance,
+            start_sample: self.utterance_start,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_vad_config_is_rejected() {
+        assert!(VadConfig::default().validate().is_ok());
+        for silence_threshold in [-0.01, 0.0, 1.5, f32::NAN, f32::INFINITY] {
+            let config = VadConfig { silence_threshold, ..VadConfig::default() };
+            assert!(config.validate().is_err(), "threshold {}", silence_threshold);
+        }
+        let config = VadConfig { hangover_ms: 0, ..VadConfig::default() };
+        assert!(config.validate().is_err());
+    }
+}
@@ -1,75 +1,336 @@
 //! Whisper transcription module
 //! Handles loading the model and transcribing audio
 
-use std::path::PathBuf;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use crate::audio::WHISPER_SAMPLE_RATE;
+use crate::audio_file;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperSegment, WhisperState};
+
+/// Base URL the ggml models are downloaded from
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+/// Whether this build was compiled with a GPU backend
+pub const GPU_SUPPORTED: bool = cfg!(any(feature = "cuda", feature = "metal"));
+/// Most threads used by default, more rarely speeds up decoding
+const MAX_DEFAULT_THREADS: usize = 8;
+/// Length of the generated audio the benchmark falls back to
+const BENCHMARK_SECONDS: usize = 10;
+/// Speech clip the benchmark transcribes, relative to the app's resource dir
+pub const BENCHMARK_CLIP: &str = "resources/benchmark-speech.wav";
+/// Longest benchmark clip read, so a replaced file can't make the benchmark run for minutes
+const MAX_BENCHMARK_CLIP_SECONDS: u64 = 60;
 
 /// Whisper model sizes
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ModelSize {
-    #[allow(dead_code)]
-    Tiny,   // ~75MB, fastest, lowest quality
-    Base,   // ~142MB, good balance
-    Small,  // ~466MB, better quality
+    Tiny,       // ~75MB, fastest, lowest quality
+    Base,       // ~142MB, good balance
+    Small,      // ~466MB, better quality
+    Medium,     // ~1.5GB, best quality, slow on CPU
+    TinyQ5_1,   // ~31MB, quantized tiny
+    BaseQ5_1,   // ~57MB, quantized base
+    SmallQ5_1,  // ~181MB, quantized small
+    MediumQ5_0, // ~514MB, quantized medium
+    TinyMultilingual,   // ~75MB, any language
+    BaseMultilingual,   // ~142MB, any language
+    SmallMultilingual,  // ~466MB, any language
+    MediumMultilingual, // ~1.5GB, any language
 }
 
 impl ModelSize {
+    /// Every model the app knows how to download
+    pub const ALL: [ModelSize; 12] = [
+        ModelSize::Tiny,
+        ModelSize::Base,
+        ModelSize::Small,
+        ModelSize::Medium,
+        ModelSize::TinyQ5_1,
+        ModelSize::BaseQ5_1,
+        ModelSize::SmallQ5_1,
+        ModelSize::MediumQ5_0,
+        ModelSize::TinyMultilingual,
+        ModelSize::BaseMultilingual,
+        ModelSize::SmallMultilingual,
+        ModelSize::MediumMultilingual,
+    ];
+
+    /// Name used by the frontend and in persisted settings
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModelSize::Tiny => "tiny",
+            ModelSize::Base => "base",
+            ModelSize::Small => "small",
+            ModelSize::Medium => "medium",
+            ModelSize::TinyQ5_1 => "tiny-q5_1",
+            ModelSize::BaseQ5_1 => "base-q5_1",
+            ModelSize::SmallQ5_1 => "small-q5_1",
+            ModelSize::MediumQ5_0 => "medium-q5_0",
+            ModelSize::TinyMultilingual => "tiny-multilingual",
+            ModelSize::BaseMultilingual => "base-multilingual",
+            ModelSize::SmallMultilingual => "small-multilingual",
+            ModelSize::MediumMultilingual => "medium-multilingual",
+        }
+    }
+
     pub fn filename(&self) -> &'static str {
         match self {
             ModelSize::Tiny => "ggml-tiny.en.bin",
             ModelSize::Base => "ggml-base.en.bin",
             ModelSize::Small => "ggml-small.en.bin",
+            ModelSize::Medium => "ggml-medium.en.bin",
+            ModelSize::TinyQ5_1 => "ggml-tiny.en-q5_1.bin",
+            ModelSize::BaseQ5_1 => "ggml-base.en-q5_1.bin",
+            ModelSize::SmallQ5_1 => "ggml-small.en-q5_1.bin",
+            ModelSize::MediumQ5_0 => "ggml-medium.en-q5_0.bin",
+            ModelSize::TinyMultilingual => "ggml-tiny.bin",
+            ModelSize::BaseMultilingual => "ggml-base.bin",
+            ModelSize::SmallMultilingual => "ggml-small.bin",
+            ModelSize::MediumMultilingual => "ggml-medium.bin",
         }
     }
 
-    pub fn download_url(&self) -> &'static str {
+    /// The multilingual model of the same size
+    pub fn multilingual(&self) -> ModelSize {
         match self {
-            ModelSize::Tiny => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.en.bin",
-            ModelSize::Base => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.en.bin",
-            ModelSize::Small => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.en.bin",
+            ModelSize::Tiny | ModelSize::TinyQ5_1 | ModelSize::TinyMultilingual => ModelSize::TinyMultilingual,
+            ModelSize::Base | ModelSize::BaseQ5_1 | ModelSize::BaseMultilingual => ModelSize::BaseMultilingual,
+            ModelSize::Small | ModelSize::SmallQ5_1 | ModelSize::SmallMultilingual => ModelSize::SmallMultilingual,
+            ModelSize::Medium | ModelSize::MediumQ5_0 | ModelSize::MediumMultilingual => ModelSize::MediumMultilingual,
         }
     }
+
+    /// Whether the model can transcribe languages other than English
+    pub fn is_multilingual(&self) -> bool {
+        matches!(
+            self,
+            ModelSize::TinyMultilingual
+                | ModelSize::BaseMultilingual
+                | ModelSize::SmallMultilingual
+                | ModelSize::MediumMultilingual
+        )
+    }
+
+    pub fn download_url(&self) -> String {
+        format!("{}/{}", MODEL_BASE_URL, self.filename())
+    }
+
+    /// SHA256 of the published ggml model file. Hugging Face lists the quantized models' hashes
+    /// only in their LFS metadata, so theirs are read at download time and recorded next to the
+    /// model instead, see `model_sha256`.
+    pub fn expected_sha256(&self) -> Option<&'static str> {
+        match self {
+            ModelSize::Tiny => Some("921e4cf8686fdd993dcd081a5da5b6c365bfde1162e72b08d75ac75289920b1f"),
+            ModelSize::Base => Some("a03779c86df3323075f5e796cb2ce5029f00ec8869eee3fdfb897afe36c6d002"),
+            ModelSize::Small => Some("c6138d6d58ecc8322097e0f987c32f1be8bb0a18532a3f88f734d1bbf9c41e5d"),
+            ModelSize::Medium => Some("cc37e93478338ec7700281a7ac30a10128929eb8f427dda2e865faa8f6da4356"),
+            ModelSize::TinyMultilingual => Some("be07e048e1e599ad46341c8d2a135645097a538221678b7acdd1b1919c6e1b21"),
+            ModelSize::BaseMultilingual => Some("60ed5bc3dd14eea856493d334349b405782ddcaf0028d4b5df4088345fba2efe"),
+            ModelSize::SmallMultilingual => Some("1be3a9b2063867b937e64e2ec7483364a79917e157fa98c5d94b5c1fffea987b"),
+            ModelSize::MediumMultilingual => Some("6c14d5adee5f86394037b4e4e8b59f1673b6cee10e3cf0b11bbdbee79c156208"),
+            ModelSize::TinyQ5_1 | ModelSize::BaseQ5_1 | ModelSize::SmallQ5_1 | ModelSize::MediumQ5_0 => None,
+        }
+    }
+}
+
+impl std::str::FromStr for ModelSize {
+    type Err = String;
+
+    /// Parse a model size name as sent by the frontend (e.g. "small", "base-q5_1")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        ModelSize::ALL
+            .iter()
+            .copied()
+            .find(|size| size.name() == name)
+            .ok_or_else(|| {
+                let known: Vec<&str> = ModelSize::ALL.iter().map(|size| size.name()).collect();
+                format!("Unknown model size '{}', expected one of: {}", name, known.join(", "))
+            })
+    }
+}
+
+/// A transcribed span of audio, timed relative to the start of the transcribed chunk
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TranscriptSegment {
+    pub text: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// Word-level timings within the segment
+    pub words: Vec<TranscriptToken>,
+    /// Average token probability, 0.0 to 1.0
+    pub confidence: f32,
+    /// Probability Whisper gives the segment of being non-speech
+    pub no_speech_prob: f32,
+}
+
+/// A word made of one or more Whisper tokens, timed like its segment
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TranscriptToken {
+    pub text: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// Result of transcribing one chunk of audio
+#[derive(Debug, Clone)]
+pub struct Transcription {
+    pub segments: Vec<TranscriptSegment>,
+    /// Language Whisper detected, set only when auto-detection was used
+    pub detected_language: Option<String>,
+}
+
+/// Check a language code (e.g. "en", "de") is one Whisper knows
+pub fn validate_language(lang: &str) -> Result<String, String> {
+    let lang = lang.trim().to_lowercase();
+    if whisper_rs::get_lang_id(&lang).is_none() {
+        return Err(format!("Unsupported language '{}'", lang));
+    }
+    Ok(lang)
 }
 
 /// Whisper transcription engine
 pub struct WhisperEngine {
     ctx: WhisperContext,
+    /// Decoder state reused by every transcription, which also serializes concurrent callers
+    state: Mutex<WhisperState>,
+    gpu: bool,
+    n_threads: AtomicUsize,
+}
+
+/// Thread count used unless configured: one per CPU, up to 8
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(MAX_DEFAULT_THREADS)
 }
 
 impl WhisperEngine {
-    /// Load whisper model from path
-    pub fn new(model_path: &PathBuf) -> Result<Self, String> {
+    /// Load whisper model from path.
+    /// If `use_gpu` is set but the GPU can't be initialized, the model is loaded on the CPU instead.
+    pub fn new(model_path: &PathBuf, use_gpu: bool) -> Result<Self, String> {
         println!("Loading Whisper model from: {:?}", model_path);
-        
-        let ctx = WhisperContext::new_with_params(
-            model_path.to_str().ok_or("Invalid model path")?,
-            WhisperContextParameters::default(),
-        )
-        .map_err(|e| format!("Failed to load Whisper model: {}", e))?;
+        let path = model_path.to_str().ok_or("Invalid model path")?;
+
+        if use_gpu && GPU_SUPPORTED {
+            let mut params = WhisperContextParameters::default();
+            params.use_gpu(true);
+            match WhisperContext::new_with_params(path, params) {
+                Ok(ctx) => {
+                    println!("Whisper model loaded successfully (GPU)");
+                    return Self::from_context(ctx, true);
+                }
+                Err(e) => eprintln!("GPU initialization failed, falling back to CPU: {}", e),
+            }
+        }
+
+        let mut params = WhisperContextParameters::default();
+        params.use_gpu(false);
+        let ctx = WhisperContext::new_with_params(path, params)
+            .map_err(|e| format!("Failed to load Whisper model: {}", e))?;
 
         println!("Whisper model loaded successfully");
-        Ok(Self { ctx })
+        Self::from_context(ctx, false)
     }
 
-    /// Transcribe audio samples (expects 16kHz mono f32 samples)
-    pub fn transcribe(&self, samples: &[f32]) -> Result<String, String> {
-        if samples.is_empty() {
-            return Ok(String::new());
+    fn from_context(ctx: WhisperContext, gpu: bool) -> Result<Self, String> {
+        let state = ctx.create_state()
+            .map_err(|e| format!("Failed to create whisper state: {}", e))?;
+        Ok(Self {
+            ctx,
+            state: Mutex::new(state),
+            gpu,
+            n_threads: AtomicUsize::new(default_threads()),
+        })
+    }
+
+    /// Whether the model is running on the GPU
+    pub fn uses_gpu(&self) -> bool {
+        self.gpu
+    }
+
+    /// Set the number of CPU threads used by subsequent transcriptions
+    pub fn set_n_threads(&self, n_threads: usize) {
+        self.n_threads.store(n_threads.max(1), Ordering::Relaxed);
+    }
+
+    /// Name of the compute backend the model runs on
+    pub fn backend(&self) -> &'static str {
+        if !self.gpu {
+            "cpu"
+        } else if cfg!(feature = "cuda") {
+            "cuda"
+        } else {
+            "metal"
         }
+    }
 
-        // Create a new state for this transcription
+    /// Transcribe audio samples into timed segments (expects 16kHz mono f32 samples).
+    /// A `language` of None lets Whisper detect it, `prompt` is preceding transcript text
+    /// that keeps spelling and style consistent across chunks, and `vocabulary_hint` lists
+    /// names and jargon Whisper should expect.
+    pub fn transcribe(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+        prompt: Option<&str>,
+        vocabulary_hint: Option<&str>,
+    ) -> Result<Transcription, String> {
+        // Whisper resets the state at the start of every run, so one can be reused across chunks
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        self.transcribe_in(&mut state, samples, language, prompt, vocabulary_hint)
+    }
+
+    /// `transcribe` in a decoder state created for the call, as every transcription was before the
+    /// state was reused. Only built for the benchmark comparing the two.
+    #[cfg(feature = "bench")]
+    pub fn transcribe_in_new_state(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+        prompt: Option<&str>,
+        vocabulary_hint: Option<&str>,
+    ) -> Result<Transcription, String> {
         let mut state = self.ctx.create_state()
             .map_err(|e| format!("Failed to create whisper state: {}", e))?;
+        self.transcribe_in(&mut state, samples, language, prompt, vocabulary_hint)
+    }
+
+    fn transcribe_in(
+        &self,
+        state: &mut WhisperState,
+        samples: &[f32],
+        language: Option<&str>,
+        prompt: Option<&str>,
+        vocabulary_hint: Option<&str>,
+    ) -> Result<Transcription, String> {
+        if samples.is_empty() {
+            return Ok(Transcription {
+                segments: Vec::new(),
+                detected_language: None,
+            });
+        }
 
         // Configure transcription parameters
         let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
         
         // Optimize for real-time
-        params.set_n_threads(4);
-        params.set_language(Some("en"));
+        params.set_n_threads(self.n_threads.load(Ordering::Relaxed) as i32);
+        params.set_language(Some(language.unwrap_or("auto")));
         params.set_translate(false);
-        params.set_no_context(true);
-        params.set_single_segment(true);
+        params.set_no_context(false);
+        // Whisper keeps the end of an overlong prompt, so the recent transcript goes last
+        let initial_prompt = [vocabulary_hint, prompt]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !initial_prompt.is_empty() {
+            params.set_initial_prompt(&initial_prompt.replace('\0', ""));
+        }
+        params.set_token_timestamps(true);
         params.set_print_special(false);
         params.set_print_progress(false);
         params.set_print_realtime(false);
@@ -84,19 +345,175 @@ impl WhisperEngine {
             .full(params, samples)
             .map_err(|e| format!("Transcription failed: {}", e))?;
 
-        // Collect results
+        // Collect results, timestamps come back in centiseconds
         let num_segments = state.full_n_segments();
 
-        let mut result = String::new();
+        let mut segments = Vec::new();
         for i in 0..num_segments {
             if let Some(segment) = state.get_segment(i) {
-                result.push_str(&format!("{}", segment));
-                result.push(' ');
+                let text = format!("{}", segment).trim().to_string();
+                if text.is_empty() {
+                    continue;
+                }
+                segments.push(TranscriptSegment {
+                    text,
+                    start_ms: segment.start_timestamp() * 10,
+                    end_ms: segment.end_timestamp() * 10,
+                    words: self.segment_words(&segment),
+                    confidence: self.segment_confidence(&segment),
+                    no_speech_prob: segment.no_speech_probability(),
+                });
             }
         }
 
-        Ok(result.trim().to_string())
+        let detected_language = match language {
+            Some(_) => None,
+            None => whisper_rs::get_lang_str(state.full_lang_id_from_state()).map(str::to_string),
+        };
+
+        Ok(Transcription {
+            segments,
+            detected_language,
+        })
+    }
+
+    /// Average probability of a segment's text tokens
+    fn segment_confidence(&self, segment: &WhisperSegment) -> f32 {
+        let eot = self.ctx.token_eot();
+        let probabilities: Vec<f32> = (0..segment.n_tokens())
+            .filter_map(|i| segment.get_token(i))
+            .filter(|token| token.token_id() < eot)
+            .map(|token| token.token_probability())
+            .collect();
+
+        if probabilities.is_empty() {
+            return 0.0;
+        }
+        probabilities.iter().sum::<f32>() / probabilities.len() as f32
+    }
+
+    /// Group a segment's tokens into words, a token starting with a space begins a new word
+    fn segment_words(&self, segment: &WhisperSegment) -> Vec<TranscriptToken> {
+        let eot = self.ctx.token_eot();
+
+        // Collect bytes so characters split across tokens decode correctly
+        let mut words: Vec<(Vec<u8>, i64, i64)> = Vec::new();
+        for i in 0..segment.n_tokens() {
+            let token = match segment.get_token(i) {
+                Some(token) => token,
+                None => continue,
+            };
+            // Timestamp and other special tokens come after end-of-text
+            if token.token_id() >= eot {
+                continue;
+            }
+            let bytes = match token.to_bytes() {
+                Ok(bytes) => bytes,
+                Err(_) => continue,
+            };
+
+            let data = token.token_data();
+            match words.last_mut() {
+                Some((text, _, end)) if !bytes.starts_with(b" ") => {
+                    text.extend_from_slice(bytes);
+                    *end = data.t1;
+                }
+                _ => words.push((bytes.to_vec(), data.t0, data.t1)),
+            }
+        }
+
+        words
+            .into_iter()
+            .filter_map(|(text, start, end)| {
+                let text = String::from_utf8_lossy(&text).trim().to_string();
+                (!text.is_empty()).then_some(TranscriptToken {
+                    text,
+                    start_ms: start * 10,
+                    end_ms: end * 10,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Timing of a benchmark transcription
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BenchmarkResult {
+    pub model: String,
+    pub gpu: bool,
+    pub audio_secs: f64,
+    pub elapsed_secs: f64,
+    /// Processing time divided by audio duration, below 1.0 keeps up with live audio
+    pub realtime_factor: f64,
+}
+
+/// Time how long a model takes to transcribe the speech clip at `clip`
+pub fn benchmark(size: ModelSize, engine: &WhisperEngine, clip: Option<&Path>) -> Result<BenchmarkResult, String> {
+    let samples = benchmark_audio(clip);
+    let audio_secs = samples.len() as f64 / WHISPER_SAMPLE_RATE as f64;
+
+    let start = std::time::Instant::now();
+    engine.transcribe(&samples, Some("en"), None, None)?;
+    let elapsed_secs = start.elapsed().as_secs_f64();
+
+    Ok(BenchmarkResult {
+        model: size.name().to_string(),
+        gpu: engine.uses_gpu(),
+        audio_secs,
+        elapsed_secs,
+        realtime_factor: elapsed_secs / audio_secs,
+    })
+}
+
+/// The clip at `clip` as 16kHz mono, or generated audio when there's none or it can't be read
+pub fn benchmark_audio(clip: Option<&Path>) -> Vec<f32> {
+    match clip.map(|clip| audio_file::decode_file(clip, MAX_BENCHMARK_CLIP_SECONDS)) {
+        Some(Ok(samples)) if !samples.is_empty() => samples,
+        Some(Err(e)) => {
+            eprintln!("Benchmark clip unavailable, using generated audio: {}", e);
+            benchmark_samples()
+        }
+        _ => benchmark_samples(),
+    }
+}
+
+/// Generated speech-like test audio: a pitch-varying voiced tone in syllable-length bursts.
+/// Only a fallback, the decoder runs differently on a tone than on words.
+fn benchmark_samples() -> Vec<f32> {
+    let rate = WHISPER_SAMPLE_RATE as f32;
+    (0..BENCHMARK_SECONDS * WHISPER_SAMPLE_RATE as usize)
+        .map(|i| {
+            let t = i as f32 / rate;
+            let pitch = 140.0 + 30.0 * (t * 1.3 * std::f32::consts::TAU).sin();
+            let envelope = (t * 4.0 * std::f32::consts::PI).sin().abs();
+            let voice: f32 = (1..=5)
+                .map(|h| (t * pitch * h as f32 * std::f32::consts::TAU).sin() / h as f32)
+                .sum();
+            0.1 * envelope * voice
+        })
+        .collect()
+}
+
+/// Join segments into plain transcript text
+pub fn join_segments(segments: &[TranscriptSegment]) -> String {
+    segments
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Confidence of the text `join_segments` makes, each segment weighted by the length of its text
+pub fn joined_confidence(segments: &[TranscriptSegment]) -> f32 {
+    let chars: usize = segments.iter().map(|segment| segment.text.len()).sum();
+    if chars == 0 {
+        return 0.0;
     }
+    segments
+        .iter()
+        .map(|segment| segment.confidence * segment.text.len() as f32)
+        .sum::<f32>()
+        / chars as f32
 }
 
 /// Get the model directory path
@@ -112,6 +529,98 @@ pub fn get_model_path(size: ModelSize) -> Result<PathBuf, String> {
     Ok(get_model_dir()?.join(size.filename()))
 }
 
+/// Get the path a model is downloaded to before it's complete
+pub fn get_partial_model_path(size: ModelSize) -> Result<PathBuf, String> {
+    Ok(get_model_dir()?.join(format!("{}.part", size.filename())))
+}
+
+/// Get the path of the checksum recorded for a model without a pinned one
+pub fn get_model_checksum_path(size: ModelSize) -> Result<PathBuf, String> {
+    Ok(get_model_dir()?.join(format!("{}.sha256", size.filename())))
+}
+
+/// A SHA256 as lowercase hex, from a checksum file or an ETag header (quoted, maybe weak)
+pub fn parse_sha256(value: &str) -> Option<String> {
+    let value = value.trim().trim_start_matches("W/").trim_matches('"').to_lowercase();
+    (value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())).then_some(value)
+}
+
+/// Read a checksum file written by `record_sha256`
+pub fn read_sha256(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().as_deref().and_then(parse_sha256)
+}
+
+/// Write a checksum file, for checking the file it belongs to later
+pub fn record_sha256(path: &Path, sha256: &str) -> Result<(), String> {
+    std::fs::write(path, sha256).map_err(|e| format!("Failed to save checksum: {}", e))
+}
+
+/// The SHA256 a model file must match: the pinned one, or the one published when it was downloaded
+pub fn model_sha256(size: ModelSize) -> Option<String> {
+    match size.expected_sha256() {
+        Some(sha256) => Some(sha256.to_string()),
+        None => read_sha256(&get_model_checksum_path(size).ok()?),
+    }
+}
+
+/// Compute the SHA256 of a file as a lowercase hex string
+pub fn file_sha256(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open model file: {}", e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Failed to read model file: {}", e))?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Outcome of checking a model file against its published checksum
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelIntegrity {
+    /// The file matches the checksum
+    Verified,
+    /// The file differs from the checksum, e.g. a truncated download
+    Corrupted,
+    /// No checksum is known for the model, so the file wasn't checked and isn't trusted
+    Unverified,
+}
+
+/// Check a file against an expected SHA256, Unverified when there's none to check against
+pub fn verify_file(path: &Path, expected_sha256: Option<&str>) -> Result<ModelIntegrity, String> {
+    match expected_sha256 {
+        Some(expected) if file_sha256(path)? == expected => Ok(ModelIntegrity::Verified),
+        Some(_) => Ok(ModelIntegrity::Corrupted),
+        None => Ok(ModelIntegrity::Unverified),
+    }
+}
+
+/// Check a model file against its published checksum
+pub fn verify_model(size: ModelSize, path: &Path) -> Result<ModelIntegrity, String> {
+    verify_file(path, model_sha256(size).as_deref())
+}
+
+/// File recording which model start_listening loads
+fn active_model_file() -> Result<PathBuf, String> {
+    Ok(get_model_dir()?.join("active_model"))
+}
+
+/// Load the persisted active model, defaulting to Small
+pub fn load_active_model() -> ModelSize {
+    active_model_file()
+        .and_then(|path| std::fs::read_to_string(path).map_err(|e| e.to_string()))
+        .and_then(|name| name.parse())
+        .unwrap_or(ModelSize::Small)
+}
+
+/// Persist the active model so it survives restarts
+pub fn save_active_model(size: ModelSize) -> Result<(), String> {
+    let path = active_model_file()?;
+    std::fs::create_dir_all(get_model_dir()?)
+        .map_err(|e| format!("Failed to create model directory: {}", e))?;
+    std::fs::write(path, size.name())
+        .map_err(|e| format!("Failed to save active model: {}", e))
+}
+
 /// Check if a model exists
 pub fn model_exists(size: ModelSize) -> bool {
     if let Ok(path) = get_model_path(size) {
@@ -121,3 +630,165 @@ pub fn model_exists(size: ModelSize) -> bool {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn benchmark_uses_the_bundled_clip() {
+        let clip = Path::new(env!("CARGO_MANIFEST_DIR")).join(BENCHMARK_CLIP);
+        let samples = benchmark_audio(Some(&clip));
+        let seconds = samples.len() as f64 / WHISPER_SAMPLE_RATE as f64;
+        assert!((9.0..12.0).contains(&seconds), "{}s", seconds);
+        assert_ne!(samples, benchmark_samples());
+    }
+
+    #[test]
+    fn benchmark_falls_back_to_generated_audio() {
+        let missing = std::env::temp_dir().join(format!("hypergranola-{}-no-clip.wav", std::process::id()));
+        assert_eq!(benchmark_audio(Some(&missing)), benchmark_samples());
+        assert_eq!(benchmark_audio(None), benchmark_samples());
+    }
+
+    /// SHA256 of `fixture_bytes()`
+    const FIXTURE_SHA256: &str = "3f7840893e6191d52982c7f5cb02d77630980bf68789168e8826e5396133e196";
+
+    /// A small stand-in for a model file: the ggml magic followed by every byte value
+    fn fixture_bytes() -> Vec<u8> {
+        let mut bytes = b"lmgg".to_vec();
+        for _ in 0..4 {
+            bytes.extend(0..=255u8);
+        }
+        bytes
+    }
+
+    /// Write `bytes` to a file of this test run in the temp dir
+    fn write_fixture(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("hypergranola-{}-{}", std::process::id(), name));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn intact_file_is_verified() {
+        let path = write_fixture("intact.bin", &fixture_bytes());
+        assert_eq!(file_sha256(&path).unwrap(), FIXTURE_SHA256);
+        assert_eq!(verify_file(&path, Some(FIXTURE_SHA256)).unwrap(), ModelIntegrity::Verified);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn corrupted_file_fails_verification() {
+        let mut bytes = fixture_bytes();
+        bytes[100] ^= 0xff;
+        let flipped = write_fixture("flipped.bin", &bytes);
+        let truncated = write_fixture("truncated.bin", &fixture_bytes()[..512]);
+
+        assert_eq!(verify_file(&flipped, Some(FIXTURE_SHA256)).unwrap(), ModelIntegrity::Corrupted);
+        assert_eq!(verify_file(&truncated, Some(FIXTURE_SHA256)).unwrap(), ModelIntegrity::Corrupted);
+        std::fs::remove_file(flipped).unwrap();
+        std::fs::remove_file(truncated).unwrap();
+    }
+
+    #[test]
+    fn file_without_checksum_is_unverified() {
+        let path = write_fixture("unchecked.bin", &fixture_bytes());
+        assert_eq!(verify_file(&path, None).unwrap(), ModelIntegrity::Unverified);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        let path = std::env::temp_dir().join(format!("hypergranola-{}-missing.bin", std::process::id()));
+        assert!(verify_file(&path, Some(FIXTURE_SHA256)).is_err());
+    }
+
+    #[test]
+    fn published_checksums_are_sha256_hex() {
+        for size in ModelSize::ALL {
+            if let Some(sha256) = size.expected_sha256() {
+                assert_eq!(sha256.len(), 64, "{}", size.name());
+                assert!(sha256.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)), "{}", size.name());
+            }
+        }
+    }
+
+    #[test]
+    fn every_model_has_a_checksum_to_verify_against() {
+        for size in ModelSize::ALL {
+            let quantized = matches!(
+                size,
+                ModelSize::TinyQ5_1 | ModelSize::BaseQ5_1 | ModelSize::SmallQ5_1 | ModelSize::MediumQ5_0
+            );
+            // Quantized models are checked against the hash read from Hugging Face when downloaded
+            assert_eq!(size.expected_sha256().is_some(), !quantized, "{}", size.name());
+        }
+    }
+
+    #[test]
+    fn published_checksum_is_read_from_etags_and_files() {
+        assert_eq!(parse_sha256(&format!("\"{}\"", FIXTURE_SHA256)).as_deref(), Some(FIXTURE_SHA256));
+        assert_eq!(parse_sha256(&format!("W/\"{}\"", FIXTURE_SHA256.to_uppercase())).as_deref(), Some(FIXTURE_SHA256));
+        // A git blob id, what non-LFS files get as their ETag
+        assert_eq!(parse_sha256("\"c78c86eb1a8faa21b369bcd33207cc90d64ae9df\""), None);
+        assert_eq!(parse_sha256(""), None);
+
+        let model = write_fixture("recorded.bin", &fixture_bytes());
+        let checksum = write_fixture("recorded.bin.sha256", b"");
+        record_sha256(&checksum, FIXTURE_SHA256).unwrap();
+        let recorded = read_sha256(&checksum);
+        assert_eq!(verify_file(&model, recorded.as_deref()).unwrap(), ModelIntegrity::Verified);
+        std::fs::remove_file(model).unwrap();
+        std::fs::remove_file(checksum).unwrap();
+    }
+
+    fn segment(text: &str, confidence: f32) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.to_string(),
+            start_ms: 0,
+            end_ms: 1000,
+            words: Vec::new(),
+            confidence,
+            no_speech_prob: 0.0,
+        }
+    }
+
+    #[test]
+    fn joined_confidence_weighs_segments_by_length() {
+        let segments = [segment("Let's review the budget.", 0.9), segment("Uh.", 0.2)];
+        let expected = (0.9 * 24.0 + 0.2 * 3.0) / 27.0;
+        assert!((joined_confidence(&segments) - expected).abs() < 1e-6);
+        assert_eq!(joined_confidence(&[segment("Okay.", 0.7)]), 0.7);
+        assert_eq!(joined_confidence(&[]), 0.0);
+    }
+
+    #[test]
+    fn every_model_maps_to_its_file_and_url() {
+        let table = [
+            (ModelSize::Tiny, "tiny", "ggml-tiny.en.bin"),
+            (ModelSize::Base, "base", "ggml-base.en.bin"),
+            (ModelSize::Small, "small", "ggml-small.en.bin"),
+            (ModelSize::Medium, "medium", "ggml-medium.en.bin"),
+            (ModelSize::TinyQ5_1, "tiny-q5_1", "ggml-tiny.en-q5_1.bin"),
+            (ModelSize::BaseQ5_1, "base-q5_1", "ggml-base.en-q5_1.bin"),
+            (ModelSize::SmallQ5_1, "small-q5_1", "ggml-small.en-q5_1.bin"),
+            (ModelSize::MediumQ5_0, "medium-q5_0", "ggml-medium.en-q5_0.bin"),
+            (ModelSize::TinyMultilingual, "tiny-multilingual", "ggml-tiny.bin"),
+            (ModelSize::BaseMultilingual, "base-multilingual", "ggml-base.bin"),
+            (ModelSize::SmallMultilingual, "small-multilingual", "ggml-small.bin"),
+            (ModelSize::MediumMultilingual, "medium-multilingual", "ggml-medium.bin"),
+        ];
+        assert_eq!(table.len(), ModelSize::ALL.len());
+
+        for (size, name, filename) in table {
+            assert_eq!(size.name(), name);
+            assert_eq!(name.parse::<ModelSize>().unwrap(), size);
+            assert_eq!(size.filename(), filename, "{}", name);
+            assert_eq!(
+                size.download_url(),
+                format!("https://huggingface.co/ggerganov/whisper.cpp/resolve/main/{}", filename)
+            );
+        }
+    }
+}
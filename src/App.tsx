@@ -38,6 +38,8 @@ function App() {
   const [apiKey, setApiKey] = useState("");
   const [modelDownloading, setModelDownloading] = useState(false);
   const [downloadProgress, setDownloadProgress] = useState("");
+  const [modelSize, setModelSize] = useState("small");
+  const [language, setLanguage] = useState("en");
   const [nativeSttActive, setNativeSttActive] = useState(false);
   const [systemStatus, setSystemStatus] = useState<SystemStatus>({
     mic: { status: "warning", message: "Initializing..." },
@@ -152,6 +154,8 @@ function App() {
 
     try {
       if (isTauri()) {
+        // The response streams in through meeting_assistant_chunk events
+        setCoachResponse("");
         await invoke("process_transcript", { text });
       } else {
         // Browser mode - direct API calls
@@ -235,8 +239,13 @@ function App() {
     const env = isTauri() ? "tauri" : "browser";
     let unlistenSearch: (() => void) | null = null;
     let unlistenCoach: (() => void) | null = null;
+    let unlistenCoachChunk: (() => void) | null = null;
+    let unlistenCoachDone: (() => void) | null = null;
+    let unlistenLlmRetry: (() => void) | null = null;
     let unlistenTranscript: (() => void) | null = null;
+    let unlistenSpeakerChange: (() => void) | null = null;
     let unlistenDownload: (() => void) | null = null;
+    let unlistenAppError: (() => void) | null = null;
 
     // Update AI status based on environment
     if (env === "tauri") {
@@ -244,6 +253,18 @@ function App() {
         ...prev,
         ai: { status: "ok", message: "Tauri backend ready" },
       }));
+      // Check the LLM before a meeting starts, so a missing key doesn't leave the assistant silent
+      invoke<{ status: string; message: string }>("validate_llm_config")
+        .then(({ status, message }) => {
+          setSystemStatus((prev) => ({
+            ...prev,
+            ai: { status: status === "reachable" ? "ok" : "error", message },
+          }));
+          if (status !== "reachable") {
+            setCoachResponse(`⚠️ The assistant can't reach the LLM: ${message}`);
+          }
+        })
+        .catch((e) => console.error("LLM config check failed:", e));
     } else {
       const hasKey = hasBrowserAPIKey();
       setSystemStatus((prev) => ({
@@ -261,11 +282,40 @@ function App() {
         setSearchResults((prev) => [...prev, event.payload]);
       }).then((fn) => { unlistenSearch = fn; });
 
+      // Set from meeting_assistant_done, which comes just before the response
+      let offlineLabel = "";
+      // A "Retrying…" note is showing
+      let retrying = false;
+
       listen<string>("meeting_assistant_response", (event) => {
-        setCoachResponse(event.payload);
+        setCoachResponse(offlineLabel + event.payload);
         setIsProcessing(false);
       }).then((fn) => { unlistenCoach = fn; });
 
+      listen<string>("meeting_assistant_chunk", (event) => {
+        // The first chunk after a retry replaces the "Retrying…" note
+        const replace = retrying;
+        retrying = false;
+        setCoachResponse((prev) => (replace ? "" : prev) + event.payload);
+      }).then((fn) => { unlistenCoachChunk = fn; });
+
+      // Keep what arrived when the stream breaks off, and say so. Label answers written offline.
+      listen<{ message: string; interrupted: boolean; error: string | null; offline: boolean }>("meeting_assistant_done", (event) => {
+        const { message, interrupted, error, offline } = event.payload;
+        offlineLabel = offline ? "🔒 Offline assistant (privacy mode, no web search)\n\n" : "";
+        if (interrupted) {
+          setCoachResponse(`${offlineLabel}${message}\n\n⚠️ Response interrupted: ${error ?? "unknown error"}`);
+          setIsProcessing(false);
+        }
+      }).then((fn) => { unlistenCoachDone = fn; });
+
+      // Requests are retried before any of the response arrives
+      listen<{ attempt: number; max_attempts: number; delay_ms: number; reason: string }>("llm_retry", (event) => {
+        const { attempt, max_attempts, reason } = event.payload;
+        retrying = true;
+        setCoachResponse(`Retrying… (attempt ${attempt}/${max_attempts}, ${reason})`);
+      }).then((fn) => { unlistenLlmRetry = fn; });
+
       // Listen for native transcripts
       listen<string>("native_transcript", (event) => {
         console.log("Native transcript received:", event.payload);
@@ -274,13 +324,38 @@ function App() {
         }
       }).then((fn) => { unlistenTranscript = fn; });
 
+      // With diarization on, the live transcript is attributed to speakers
+      listen<{ speaker_id: string; speaker_label: string; at_ms: number }>("speaker_change", (event) => {
+        setCurrentSpeaker(event.payload.speaker_label);
+      }).then((fn) => { unlistenSpeakerChange = fn; });
+
       // Listen for download progress
-      listen<string>("model_download_progress", (event) => {
-        setDownloadProgress(event.payload);
+      listen<{ downloaded: number; total: number | null; percent: number | null }>("model_download_progress", (event) => {
+        const { downloaded, total, percent } = event.payload;
+        const mb = (bytes: number) => (bytes / (1024 * 1024)).toFixed(1);
+        setDownloadProgress(
+          total != null && percent != null
+            ? `${mb(downloaded)} / ${mb(total)} MB (${percent.toFixed(0)}%)`
+            : `${mb(downloaded)} MB downloaded`
+        );
       }).then((fn) => { unlistenDownload = fn; });
 
+      // Backend failures no command returns, shown on the status item they belong to
+      listen<{ source: string; message: string }>("app_error", (event) => {
+        const { source, message } = event.payload;
+        console.error(`Backend error (${source}):`, message);
+        const item = source === "model" || source === "diarization" ? "mic" : "ai";
+        setSystemStatus((prev) => ({
+          ...prev,
+          [item]: { status: "error", message },
+        }));
+      }).then((fn) => { unlistenAppError = fn; });
+
       // Check if Whisper model is available
       try {
+        const sttStatus = await invoke<{ active_model: string; language: string | null }>("get_stt_status");
+        setModelSize(sttStatus.active_model);
+        setLanguage(sttStatus.language ?? "auto");
         const modelExists = await invoke<boolean>("check_model_exists");
         if (modelExists) {
           setSystemStatus((prev) => ({
@@ -321,8 +396,13 @@ function App() {
     return () => {
       unlistenSearch?.();
       unlistenCoach?.();
+      unlistenCoachChunk?.();
+      unlistenCoachDone?.();
+      unlistenLlmRetry?.();
       unlistenTranscript?.();
+      unlistenSpeakerChange?.();
       unlistenDownload?.();
+      unlistenAppError?.();
       if (recognitionRef.current) {
         recognitionRef.current.onend = null;
         recognitionRef.current.abort();
@@ -364,6 +444,28 @@ function App() {
     }
   }
 
+  // Select the Whisper model used for listening
+  async function selectModel(size: string) {
+    setModelSize(size);
+    if (!isTauri()) return;
+    try {
+      await invoke("set_active_model", { size });
+    } catch (e) {
+      console.error("Failed to set model:", e);
+    }
+  }
+
+  // Select the transcription language, non-English languages use the multilingual model
+  async function selectLanguage(lang: string) {
+    setLanguage(lang);
+    if (!isTauri()) return;
+    try {
+      await invoke("set_transcription_language", { lang: lang === "auto" ? null : lang });
+    } catch (e) {
+      console.error("Failed to set language:", e);
+    }
+  }
+
   // Download Whisper model
   async function downloadModel() {
     if (!isTauri()) return;
@@ -372,6 +474,7 @@ function App() {
     setDownloadProgress("Starting download...");
 
     try {
+      // Without a size the backend picks the model matching the selected language
       await invoke("download_model");
       setSystemStatus((prev) => ({
         ...prev,
@@ -490,6 +593,10 @@ function App() {
     setLastRevisionTime(new Date());
     setSearchResults([]);
     setCoachResponse("🆕 New conversation session started!");
+    // The assistant starts over too, without the previous conversation's summary
+    if (isTauri()) {
+      invoke("reset_assistant_memory").catch((e) => console.error("Failed to reset assistant memory:", e));
+    }
   };
 
   const exportCurrentSession = (format: 'json' | 'txt' | 'md') => {
@@ -783,7 +890,33 @@ function App() {
           {systemStatus.environment === "tauri" && (
             <div className="settings-section">
               <h4>🎤 Voice Recognition (Whisper)</h4>
-              <p>Download the Whisper model for offline voice recognition (~142MB):</p>
+              <p>Download the Whisper model for offline voice recognition:</p>
+              <select value={modelSize} onChange={(e) => selectModel(e.target.value)}>
+                <option value="tiny-q5_1">Tiny, quantized (~31MB)</option>
+                <option value="tiny">Tiny (~75MB)</option>
+                <option value="base-q5_1">Base, quantized (~57MB)</option>
+                <option value="base">Base (~142MB)</option>
+                <option value="small-q5_1">Small, quantized (~181MB)</option>
+                <option value="small">Small (~466MB)</option>
+                <option value="medium-q5_0">Medium, quantized (~514MB)</option>
+                <option value="medium">Medium (~1.5GB)</option>
+                <option value="tiny-multilingual">Tiny, multilingual (~75MB)</option>
+                <option value="base-multilingual">Base, multilingual (~142MB)</option>
+                <option value="small-multilingual">Small, multilingual (~466MB)</option>
+                <option value="medium-multilingual">Medium, multilingual (~1.5GB)</option>
+              </select>
+              <select value={language} onChange={(e) => selectLanguage(e.target.value)}>
+                <option value="en">English</option>
+                <option value="auto">Detect automatically</option>
+                <option value="de">German</option>
+                <option value="es">Spanish</option>
+                <option value="fr">French</option>
+                <option value="it">Italian</option>
+                <option value="ja">Japanese</option>
+                <option value="nl">Dutch</option>
+                <option value="pt">Portuguese</option>
+                <option value="zh">Chinese</option>
+              </select>
               <div className="settings-actions">
                 <button
                   onClick={downloadModel}